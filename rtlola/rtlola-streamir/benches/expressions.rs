@@ -0,0 +1,196 @@
+//! Benchmarks for the deduplication of expressions and the optimization of large generated specifications.
+//!
+//! The deduplication counts the occurrences of all subexpressions, as common subexpression elimination does,
+//! once with the hashing of expressions of version 0.1.0 as the baseline, once with the structural hashing
+//! of [Expr] and once with the [ExprInterner].
+//! The baseline degrades to linear scans of the expressions with the same outermost kind and is therefore only run once.
+//!
+//! Run with `cargo bench --bench expressions`.
+
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    hash::{Hash, Hasher},
+    hint::black_box,
+    mem::discriminant,
+    time::{Duration, Instant},
+};
+
+use rtlola_streamir::{
+    ir::{
+        expressions::{Expr, ExprKind},
+        interner::ExprInterner,
+        text, Guard, Stmt,
+    },
+    optimize_all,
+};
+
+const INPUTS: usize = 8;
+
+/// Generates a stream expression of the given depth over the inputs and the previously defined streams.
+fn generate_expr(seed: &mut u64, depth: usize, stream: usize) -> String {
+    *seed = seed
+        .wrapping_mul(6364136223846793005)
+        .wrapping_add(1442695040888963407);
+    let choice = (*seed >> 33) as usize;
+    if depth == 0 {
+        return match choice % 5 {
+            0 => format!("i64({})", choice % 100),
+            1 | 2 if stream > 0 => format!("out[{}]", choice % stream),
+            _ => format!("in[{}]", choice % INPUTS),
+        };
+    }
+    let op = ["+", "-", "*"][choice % 3];
+    format!(
+        "({} {op} {})",
+        generate_expr(seed, depth - 1, stream),
+        generate_expr(seed, depth - 1, stream)
+    )
+}
+
+/// Generates the textual StreamIR of a specification with `streams` output streams, each defined by an expression
+/// of the given depth and guarded by the arrival of an input and a dynamic condition.
+///
+/// Every tenth stream repeats the guard and expression of its predecessor, such that the rewriting rules
+/// have structurally equal guards and expressions to find.
+fn generate_spec(streams: usize, depth: usize) -> String {
+    let mut spec = String::from("streamir 1\n\n");
+    for i in 0..INPUTS {
+        writeln!(spec, "stream in[{i}] \"i{i}\": Int64 = static single").unwrap();
+    }
+    for o in 0..streams {
+        writeln!(spec, "stream out[{o}] \"o{o}\": Int64 = static single").unwrap();
+    }
+    spec.push_str("\nlivetime (inputs");
+    for o in 0..streams {
+        write!(spec, ", out[{o}]").unwrap();
+    }
+    spec.push_str(")\n\nstmt\nseq {\n");
+    for i in 0..INPUTS {
+        writeln!(
+            spec,
+            "if @in[{i}] then\nseq {{\nshift in[{i}];\ninput in[{i}];\n}}\nfi;"
+        )
+        .unwrap();
+    }
+    let mut seed = 42;
+    let mut previous = (0, String::new(), String::new());
+    for o in 0..streams {
+        let (input, condition, expr) = if o % 10 == 9 {
+            previous.clone()
+        } else {
+            (
+                (seed >> 40) as usize % INPUTS,
+                generate_expr(&mut seed, depth / 2, o),
+                generate_expr(&mut seed, depth, o),
+            )
+        };
+        writeln!(
+            spec,
+            "if (@in[{input}] && dynamic(({condition} > i64(0)))) then\nseq {{\nshift out[{o}];\neval(0) out[{o}] = {expr};\n}}\nfi;"
+        )
+        .unwrap();
+        previous = (input, condition, expr);
+    }
+    spec.push_str("}\n");
+    spec
+}
+
+/// Collects all subexpressions of the guards and evaluations of the statement in pre-order.
+fn collect_exprs<'a>(stmt: &'a Stmt, exprs: &mut Vec<&'a Expr>) {
+    fn expr<'a>(e: &'a Expr, exprs: &mut Vec<&'a Expr>) {
+        exprs.push(e);
+        if let ExprKind::BinaryOperation(_, lhs, rhs) = &e.kind {
+            expr(lhs, exprs);
+            expr(rhs, exprs);
+        }
+    }
+    fn guard<'a>(g: &'a Guard, exprs: &mut Vec<&'a Expr>) {
+        match g {
+            Guard::Dynamic(e) => expr(e, exprs),
+            Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => {
+                guard(lhs, exprs);
+                guard(rhs, exprs);
+            }
+            _ => {}
+        }
+    }
+    match stmt {
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|stmt| collect_exprs(stmt, exprs))
+        }
+        Stmt::If(stmt) => {
+            guard(stmt.guard(), exprs);
+            collect_exprs(stmt.cons(), exprs);
+            if let Some(alt) = stmt.alt() {
+                collect_exprs(alt, exprs);
+            }
+        }
+        Stmt::Eval { with, .. } => expr(with, exprs),
+        _ => {}
+    }
+}
+
+/// An expression hashed as in version 0.1.0, where only the type and the kind of the outermost node were hashed.
+struct BaselineHash<'a>(&'a Expr);
+
+impl PartialEq for BaselineHash<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for BaselineHash<'_> {}
+
+impl Hash for BaselineHash<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.ty.hash(state);
+        discriminant(&self.0.kind).hash(state);
+    }
+}
+
+fn measure<T>(name: &str, iterations: u32, mut f: impl FnMut() -> T) {
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        black_box(f());
+        total += start.elapsed();
+    }
+    println!("{name:<30} {:>12.3?}", total / iterations);
+}
+
+fn main() {
+    for (streams, depth) in [(100, 4), (500, 6), (1_000, 6)] {
+        let spec = generate_spec(streams, depth);
+        let ir = text::parse(&spec).expect("generated specification is valid");
+        let mut exprs = Vec::new();
+        collect_exprs(&ir.stmt, &mut exprs);
+        println!(
+            "--- {streams} streams, expression depth {depth}, {} subexpressions ---",
+            exprs.len()
+        );
+        measure("dedup, 0.1.0 hash (baseline)", 1, || {
+            let mut counts: HashMap<BaselineHash, usize> = HashMap::new();
+            exprs
+                .iter()
+                .for_each(|e| *counts.entry(BaselineHash(e)).or_default() += 1);
+            counts.len()
+        });
+        measure("dedup, structural hash", 3, || {
+            let mut counts: HashMap<&Expr, usize> = HashMap::new();
+            exprs
+                .iter()
+                .for_each(|e| *counts.entry(*e).or_default() += 1);
+            counts.len()
+        });
+        measure("dedup, ExprInterner", 3, || {
+            let mut interner = ExprInterner::new();
+            let mut counts = HashMap::new();
+            exprs
+                .iter()
+                .for_each(|e| *counts.entry(interner.intern(e)).or_insert(0usize) += 1);
+            counts.len()
+        });
+        measure("optimize_all", 3, || optimize_all(ir.clone()).unwrap());
+    }
+}
@@ -1,48 +1,275 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use anyhow::Context;
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use rtlola_frontend::{Handler, ParserConfig};
-use rtlola_streamir::{ir::DebugFormatter, parse, translate, ParseError};
+use rtlola_streamir::{
+    diagnostics::Diagnostic,
+    ir::{
+        graph::{GraphFormat, GraphFormatter, GraphView},
+        lint::{self, Lint},
+        ranges::{self, Ranges},
+        text, DebugFormatter, StreamIr,
+    },
+    parse, translate, ParseError,
+};
 
 #[derive(Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    spec: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[clap(required = true)]
+    spec: Option<PathBuf>,
     #[clap(short, long)]
     optimize_all: bool,
+    /// The representation of the StreamIR to print
+    #[clap(long, value_enum, default_value_t = Emit::Debug)]
+    emit: Emit,
+    /// The part of the StreamIR shown by the graph representations
+    #[clap(long, value_enum, default_value_t = Graph::Dependencies)]
+    graph: Graph,
+    /// The range of the values of an input stream as `name=lo..hi`, used to warn about triggers that never or always fire
+    #[clap(long)]
+    input_range: Vec<String>,
+    /// Report the given lints as errors and fail if they are found
+    #[clap(long, value_parser = Lint::from_str, value_delimiter = ',')]
+    deny: Vec<Lint>,
+    /// The representation of errors and warnings
+    #[clap(long, value_enum, default_value_t = MessageFormat::Human)]
+    message_format: MessageFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Compares the StreamIRs of two specifications, exits with status 1 if they differ and 2 on errors
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        #[clap(short, long)]
+        optimize_all: bool,
+        /// The representation of the differences
+        #[clap(long, value_enum, default_value_t = DiffFormat::Text)]
+        format: DiffFormat,
+        /// The representation of errors and warnings
+        #[clap(long, value_enum, default_value_t = MessageFormat::Human)]
+        message_format: MessageFormat,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum MessageFormat {
+    /// Messages rendered for humans, with the source of the specification where available
+    Human,
+    /// One JSON object per message and line on stderr
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum DiffFormat {
+    /// A human-readable representation
+    Text,
+    /// A JSON document
+    #[cfg(feature = "serde")]
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Emit {
+    /// A human-readable representation
+    Debug,
+    /// The textual representation that can be parsed back
+    Text,
+    /// The versioned JSON representation
+    #[cfg(feature = "serde")]
+    Json,
+    /// A graph in the Graphviz DOT language
+    Dot,
+    /// A graph as a Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Graph {
+    /// The streams and the accesses between them
+    Dependencies,
+    /// The control-flow tree of the statements
+    ControlFlow,
 }
 
 #[derive(ValueEnum, Clone)]
 enum Optimization {}
 
-fn print(config: &ParserConfig, optimize_all: bool) -> anyhow::Result<String> {
-    let streamir = parse(config).context("parsing specification to StreamIR")?;
-    let streamir = if optimize_all {
-        rtlola_streamir::optimize_all(streamir).context("optimizing StreamIR")?
-    } else {
-        streamir
+/// Reports the diagnostics about a specification in the selected format
+struct Reporter<'a> {
+    handler: Handler<'a>,
+    format: MessageFormat,
+    /// The exit status on errors
+    status: i32,
+    /// The source of the specification, used to locate the diagnostics
+    source: &'a str,
+}
+
+impl Reporter<'_> {
+    fn report(&self, diagnostic: &Diagnostic) {
+        let diagnostic = diagnostic.clone().locate(self.source);
+        match self.format {
+            MessageFormat::Human => diagnostic.emit(&self.handler),
+            #[cfg(feature = "serde")]
+            MessageFormat::Json => eprintln!("{}", diagnostic.to_json()),
+        }
+    }
+
+    /// Reports the error and exits with the error status
+    fn fail(&self, error: &ParseError) -> ! {
+        match (self.format, error) {
+            (MessageFormat::Human, ParseError::FrontendError(e)) => self.handler.emit_error(e),
+            _ => self.report(&Diagnostic::from(error)),
+        }
+        std::process::exit(self.status)
+    }
+}
+
+/// Reads the specification file
+fn config(spec: &Path) -> anyhow::Result<ParserConfig> {
+    ParserConfig::from_path(spec.to_path_buf())
+        .with_context(|| format!("loading specification file: {}", spec.display()))
+}
+
+/// Parses the specification into the StreamIR, returning the reporter for diagnostics about the specification
+fn load(config: &ParserConfig, format: MessageFormat, status: i32) -> (Reporter<'_>, StreamIr) {
+    let reporter = Reporter {
+        handler: Handler::from(config),
+        format,
+        status,
+        source: config.spec(),
     };
-    let formatter = DebugFormatter::new(&streamir);
-    Ok(translate(streamir, formatter))
+    match parse(config) {
+        Ok(streamir) => (reporter, streamir),
+        Err(e) => reporter.fail(&e),
+    }
 }
 
-fn main() -> anyhow::Result<()> {
-    let Args { spec, optimize_all } = Args::parse();
+fn optimize(streamir: StreamIr, optimize_all: bool, reporter: &Reporter) -> StreamIr {
+    if !optimize_all {
+        return streamir;
+    }
+    rtlola_streamir::optimize_all(streamir).unwrap_or_else(|e| {
+        reporter.report(&Diagnostic::from(&e));
+        std::process::exit(reporter.status)
+    })
+}
 
-    let config = ParserConfig::from_path(spec.clone())
-        .with_context(|| format!("loading specification file: {}", spec.display()))?;
+/// The exit status of the `diff` subcommand on errors, as the status 1 reports differences
+const DIFF_ERROR: i32 = 2;
+
+/// Compares the StreamIRs of the two specifications and returns whether they differ
+fn diff(
+    old: &Path,
+    new: &Path,
+    optimize_all: bool,
+    format: DiffFormat,
+    message_format: MessageFormat,
+) -> anyhow::Result<bool> {
+    let lower = |spec| -> anyhow::Result<StreamIr> {
+        let config = config(spec)?;
+        let (reporter, streamir) = load(&config, message_format, DIFF_ERROR);
+        Ok(optimize(streamir, optimize_all, &reporter))
+    };
+    let diff = rtlola_streamir::ir::diff::diff(&lower(old)?, &lower(new)?);
+    match format {
+        DiffFormat::Text => print!("{diff}"),
+        #[cfg(feature = "serde")]
+        DiffFormat::Json => println!("{}", diff.to_json()),
+    }
+    Ok(!diff.is_empty())
+}
 
-    match print(&config, optimize_all) {
-        Ok(res) => {
-            println!("{res}")
+fn print(streamir: StreamIr, emit: Emit, graph: Graph) -> anyhow::Result<String> {
+    match emit {
+        Emit::Debug => {
+            let formatter = DebugFormatter::new(&streamir);
+            Ok(translate(streamir, formatter))
         }
-        Err(e) => {
-            if let Some(ParseError::FrontendError(e)) = e.downcast_ref() {
-                let handler = Handler::from(&config);
-                handler.emit_error(e);
-                std::process::exit(1);
+        Emit::Text => Ok(text::print(&streamir)),
+        #[cfg(feature = "serde")]
+        Emit::Json => {
+            rtlola_streamir::ir::json::to_json(&streamir).context("serializing StreamIR as JSON")
+        }
+        Emit::Dot | Emit::Mermaid => {
+            let format = match emit {
+                Emit::Dot => GraphFormat::Dot,
+                _ => GraphFormat::Mermaid,
+            };
+            let view = match graph {
+                Graph::Dependencies => GraphView::Dependencies,
+                Graph::ControlFlow => GraphView::ControlFlow,
+            };
+            Ok(translate(streamir, GraphFormatter::new(format, view)))
+        }
+    }
+}
+
+fn main() -> anyhow::Result<()> {
+    let Args {
+        command,
+        spec,
+        optimize_all,
+        emit,
+        graph,
+        input_range,
+        deny,
+        message_format,
+    } = Args::parse();
+
+    if let Some(Command::Diff {
+        old,
+        new,
+        optimize_all,
+        format,
+        message_format,
+    }) = command
+    {
+        match diff(&old, &new, optimize_all, format, message_format) {
+            Ok(false) => return Ok(()),
+            Ok(true) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {e:?}");
+                std::process::exit(DIFF_ERROR)
             }
         }
     }
+
+    let spec = spec.expect("required without a subcommand");
+    let config = config(&spec)?;
+    let (reporter, streamir) = load(&config, message_format, 1);
+    let inputs = input_range
+        .iter()
+        .map(|annotation| ranges::parse_annotation(&streamir, annotation))
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(anyhow::Error::msg)
+        .context("parsing input ranges")?;
+    let ranges = Ranges::new(&streamir, &inputs);
+    let diagnostics = ranges
+        .trigger_warnings(&streamir)
+        .iter()
+        .map(Diagnostic::from)
+        .chain(
+            lint::lint(&streamir, &ranges)
+                .iter()
+                .map(|warning| warning.diagnostic(&streamir, &deny)),
+        )
+        .collect::<Vec<_>>();
+    diagnostics.iter().for_each(|d| reporter.report(d));
+    if diagnostics.iter().any(Diagnostic::is_error) {
+        std::process::exit(1);
+    }
+    let streamir = optimize(streamir, optimize_all, &reporter);
+    println!("{}", print(streamir, emit, graph)?);
     Ok(())
 }
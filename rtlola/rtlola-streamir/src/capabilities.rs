@@ -0,0 +1,651 @@
+//! The check whether a StreamIR only uses features supported by a backend.
+//!
+//! The check walks the whole StreamIR before the formatting, such that unsupported features are reported as an error
+//! listing all of them instead of aborting the compilation at the first one.
+//! What a backend supports is described by its implementation of [Capabilities].
+//! Each finding names the stream it originates from and carries the span of the declaration of the stream,
+//! which is translated into a line and column in the specification by [UnsupportedFeatures::locate].
+
+use std::{collections::BTreeSet, fmt::Display};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use crate::{
+    diagnostics::Location,
+    ir::{
+        expressions::{Expr, ExprKind, Function, Operator},
+        memory::{StreamBuffer, StreamMemory},
+        Guard, LocalFreqRef, OutputReference, Stmt, StreamIr, StreamReference, Type,
+        WindowReference,
+    },
+    Span,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A feature of the StreamIR a backend might not be able to compile
+pub enum Feature {
+    /// Streams with parameters and accesses to their instances
+    Parameterization,
+    /// Periodic streams with a global frequency
+    GlobalFrequency,
+    /// Periodic streams with a local frequency
+    LocalFrequency,
+    /// Sliding windows
+    SlidingWindow,
+    /// Discrete windows
+    DiscreteWindow,
+    /// Aggregations over the instances of a stream
+    InstanceAggregation,
+    /// Streams whose memory is unbounded
+    UnboundedMemory,
+    /// Streams evaluated later than the event they refer to
+    DelayedEvaluation,
+    /// Values of the given type
+    Type(Type),
+    /// Input streams of the given type
+    InputType(Type),
+    /// The function with the given return type
+    Function(Function, Type),
+    /// The binary operator on values of the given type
+    Operator(Operator, Type),
+    /// Accesses to the stream with the given name, which has no memory
+    MemorylessAccess(String),
+}
+
+impl Display for Feature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Feature::Parameterization => write!(f, "parameterized streams"),
+            Feature::GlobalFrequency => write!(f, "periodic streams"),
+            Feature::LocalFrequency => write!(f, "periodic streams with a local frequency"),
+            Feature::SlidingWindow => write!(f, "sliding windows"),
+            Feature::DiscreteWindow => write!(f, "discrete windows"),
+            Feature::InstanceAggregation => write!(f, "instance aggregations"),
+            Feature::UnboundedMemory => write!(f, "unbounded memory"),
+            Feature::DelayedEvaluation => write!(f, "delayed evaluation"),
+            Feature::Type(ty) => write!(f, "values of type {ty}"),
+            Feature::InputType(ty) => write!(f, "input values of type {ty}"),
+            Feature::Function(function, ty) => write!(
+                f,
+                "the function {} on {ty}",
+                format!("{function:?}").to_lowercase()
+            ),
+            Feature::Operator(op, ty) => write!(f, "the operator {op:?} on {ty}"),
+            Feature::MemorylessAccess(stream) => {
+                write!(f, "accesses to stream `{stream}`, which has no memory")
+            }
+        }
+    }
+}
+
+/// The features of the StreamIR supported by a backend.
+///
+/// Parameterized streams and local frequencies are supported by no backend and always reported.
+pub trait Capabilities {
+    /// The name of the backend in the reported errors
+    fn backend(&self) -> &'static str;
+
+    /// Whether values of the type are supported.
+    ///
+    /// The walk only asks for the inner types of tuples and optional types if the outer type is supported.
+    fn supports_type(&self, ty: &Type) -> bool;
+
+    /// Whether the function is supported with the given return type
+    fn supports_function(&self, function: Function, ty: &Type) -> bool;
+
+    /// Whether the binary operator is supported on operands of the given types
+    fn supports_operator(&self, op: Operator, lhs: &Type, rhs: &Type) -> bool;
+
+    /// Whether input streams of the type are supported
+    fn supports_input(&self, _ty: &Type) -> bool {
+        true
+    }
+
+    /// Whether the window is supported
+    fn supports_window(&self, _window: WindowReference) -> bool {
+        false
+    }
+
+    /// Whether streams with unbounded memory are supported
+    fn supports_unbounded_memory(&self) -> bool {
+        false
+    }
+
+    /// Whether streams evaluated later than the event they refer to are supported
+    fn supports_delayed_evaluation(&self) -> bool {
+        false
+    }
+
+    /// Whether periodic streams with a global frequency are supported
+    fn supports_global_frequency(&self) -> bool {
+        true
+    }
+
+    /// Whether accesses to streams without memory are supported
+    fn supports_memoryless_access(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The place an unsupported feature is used at
+pub struct FeatureLocation {
+    /// The name of the stream using the feature
+    pub stream: String,
+    /// The span of the declaration of the stream in the specification
+    pub declaration: Span,
+    /// The line and column of the declaration of the stream, if known
+    pub span: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The use of an unsupported feature by a stream
+pub struct UnsupportedFeature {
+    /// The place the feature is used at
+    pub location: FeatureLocation,
+    /// The unsupported feature
+    pub feature: Feature,
+    /// The name of the backend that does not support the feature
+    pub backend: &'static str,
+}
+
+impl Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(span) = &self.location.span {
+            write!(f, "{span}: ")?;
+        }
+        write!(
+            f,
+            "stream `{}` uses {}, which is not supported by the {} backend",
+            self.location.stream, self.feature, self.backend
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{}", self.0.iter().join("\n"))]
+/// All uses of unsupported features in a StreamIR
+pub struct UnsupportedFeatures(pub Vec<UnsupportedFeature>);
+
+impl UnsupportedFeatures {
+    /// Returns the unsupported features without their location
+    pub fn features(&self) -> impl Iterator<Item = &Feature> {
+        self.0.iter().map(|f| &f.feature)
+    }
+
+    /// Locates the features at the declarations of their streams in the source of the specification
+    pub fn locate(self, source: &str) -> Self {
+        let features = self
+            .0
+            .into_iter()
+            .map(|mut feature| {
+                let location = &mut feature.location;
+                location.span = Location::of_span(source, location.declaration);
+                feature
+            })
+            .collect();
+        Self(features)
+    }
+}
+
+/// Checks that the backend with the given capabilities can compile the StreamIR.
+pub fn check(ir: &StreamIr, capabilities: &impl Capabilities) -> Result<(), UnsupportedFeatures> {
+    let mut checker = Checker {
+        ir,
+        capabilities,
+        found: BTreeSet::new(),
+    };
+    checker.check();
+    if checker.found.is_empty() {
+        Ok(())
+    } else {
+        let found = checker
+            .found
+            .into_iter()
+            .map(|(stream, declaration, feature)| UnsupportedFeature {
+                location: FeatureLocation {
+                    stream,
+                    declaration,
+                    span: None,
+                },
+                feature,
+                backend: capabilities.backend(),
+            })
+            .collect();
+        Err(UnsupportedFeatures(found))
+    }
+}
+
+struct Checker<'a, C> {
+    ir: &'a StreamIr,
+    capabilities: &'a C,
+    /// The names and declarations of the streams using unsupported features
+    found: BTreeSet<(String, Span, Feature)>,
+}
+
+impl<C: Capabilities> Checker<'_, C> {
+    fn found(&mut self, sr: StreamReference, feature: Feature) {
+        let memory = self.ir.stream_memory(sr);
+        self.found
+            .insert((memory.name.clone(), memory.span, feature));
+    }
+
+    fn check(&mut self) {
+        let ir = self.ir;
+        let capabilities = self.capabilities;
+        for (sr, memory) in &ir.sr2memory {
+            if memory.buffer.parameters().is_some() {
+                self.found(*sr, Feature::Parameterization);
+            }
+            if !capabilities.supports_unbounded_memory()
+                && matches!(memory.buffer.buffer(), Some(StreamBuffer::UnBounded))
+            {
+                self.found(*sr, Feature::UnboundedMemory);
+            }
+            if !capabilities.supports_delayed_evaluation() && memory.delay > 0 {
+                self.found(*sr, Feature::DelayedEvaluation);
+            }
+            self.ty(*sr, &memory.ty);
+            if matches!(sr, StreamReference::In(_)) && !capabilities.supports_input(&memory.ty) {
+                self.found(*sr, Feature::InputType(memory.ty.clone()));
+            }
+        }
+        for window in ir.wref2window.values() {
+            self.window(window.caller, window.wref);
+        }
+        for freq in ir.lref2lfreq.values() {
+            self.found(freq.sr.sr(), Feature::LocalFrequency);
+        }
+        self.stmt(&ir.stmt, None);
+    }
+
+    fn ty(&mut self, sr: StreamReference, ty: &Type) {
+        if !self.capabilities.supports_type(ty) {
+            self.found(sr, Feature::Type(ty.clone()));
+            return;
+        }
+        match ty {
+            Type::Tuple(inner) => inner.iter().for_each(|ty| self.ty(sr, ty)),
+            Type::Option(inner) => self.ty(sr, inner),
+            Type::Int(_)
+            | Type::UInt(_)
+            | Type::Bool
+            | Type::String
+            | Type::Float32
+            | Type::Float64
+            | Type::Fixed(_)
+            | Type::UFixed(_)
+            | Type::Bytes => {}
+        }
+    }
+
+    fn window(&mut self, sr: StreamReference, window: WindowReference) {
+        if !self.capabilities.supports_window(window) {
+            let feature = match window {
+                WindowReference::Sliding(_) => Feature::SlidingWindow,
+                WindowReference::Discrete(_) => Feature::DiscreteWindow,
+                WindowReference::Instance(_) => Feature::InstanceAggregation,
+            };
+            self.found(sr, feature);
+        }
+    }
+
+    /// Checks the statement, where the expressions of temporaries are attributed to the given stream
+    fn stmt(&mut self, stmt: &Stmt, context: Option<StreamReference>) {
+        let mut owners = BTreeSet::new();
+        owners_of(stmt, &mut owners);
+        let context = owners.first().copied().or(context);
+        match stmt {
+            Stmt::Skip | Stmt::Shift(_) | Stmt::Input(_) => {}
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                stmts.iter().for_each(|s| self.stmt(s, context))
+            }
+            Stmt::Spawn {
+                sr,
+                with,
+                local_frequencies,
+                windows,
+            } => {
+                if with.is_some() {
+                    self.found(sr.sr(), Feature::Parameterization);
+                }
+                self.clocks(sr.sr(), local_frequencies, windows);
+            }
+            Stmt::Close {
+                sr,
+                local_frequencies,
+                windows,
+            } => self.clocks(sr.sr(), local_frequencies, windows),
+            Stmt::Eval { sr, with, .. } => self.expr(sr.sr(), with),
+            Stmt::If(stmt) => {
+                for owner in owners {
+                    self.guard(owner, stmt.guard());
+                }
+                self.stmt(stmt.cons(), context);
+                if let Some(alt) = stmt.alt() {
+                    self.stmt(alt, context);
+                }
+            }
+            Stmt::Iterate { sr, stmt } | Stmt::Assign { sr, stmt, .. } => {
+                for sr in sr {
+                    self.found(sr.sr(), Feature::Parameterization);
+                }
+                self.stmt(stmt, context);
+            }
+            Stmt::Let { expr, .. } => {
+                if let Some(sr) = context {
+                    self.expr(sr, expr);
+                }
+            }
+        }
+    }
+
+    /// Checks the local frequencies and windows started or stopped with an instance of the stream
+    fn clocks(
+        &mut self,
+        sr: StreamReference,
+        local_frequencies: &[LocalFreqRef],
+        windows: &[WindowReference],
+    ) {
+        if !local_frequencies.is_empty() {
+            self.found(sr, Feature::LocalFrequency);
+        }
+        for window in windows {
+            self.window(sr, *window);
+        }
+    }
+
+    fn guard(&mut self, sr: StreamReference, guard: &Guard) {
+        match guard {
+            Guard::GlobalFreq(_) => {
+                if !self.capabilities.supports_global_frequency() {
+                    self.found(sr, Feature::GlobalFrequency)
+                }
+            }
+            Guard::LocalFreq(_) => self.found(sr, Feature::LocalFrequency),
+            Guard::Dynamic(expr) => self.expr(sr, expr),
+            Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => {
+                self.guard(sr, lhs);
+                self.guard(sr, rhs);
+            }
+            Guard::Stream(_)
+            | Guard::Alive(_)
+            | Guard::Constant(_)
+            | Guard::FastAnd(_)
+            | Guard::FastOr(_) => {}
+        }
+    }
+
+    fn expr(&mut self, sr: StreamReference, expr: &Expr) {
+        self.ty(sr, &expr.ty);
+        match &expr.kind {
+            ExprKind::Constant(_) | ExprKind::Temporary(_) => {}
+            ExprKind::ParameterAccess(_, _) => self.found(sr, Feature::Parameterization),
+            ExprKind::LambdaParameterAccess(_, _) => self.found(sr, Feature::InstanceAggregation),
+            ExprKind::BinaryOperation(op, lhs, rhs) => {
+                if !self.capabilities.supports_operator(*op, &lhs.ty, &rhs.ty) {
+                    self.found(sr, Feature::Operator(*op, lhs.ty.clone()));
+                }
+                self.expr(sr, lhs);
+                self.expr(sr, rhs);
+            }
+            ExprKind::Cast(_, inner)
+            | ExprKind::UnaryOperation(_, inner)
+            | ExprKind::TupleAccess(inner, _)
+            | ExprKind::Some(inner) => self.expr(sr, inner),
+            ExprKind::UnwrapOr(inner, default) => {
+                self.expr(sr, inner);
+                self.expr(sr, default);
+            }
+            ExprKind::Ite(cond, cons, alt) => {
+                self.expr(sr, cond);
+                self.expr(sr, cons);
+                self.expr(sr, alt);
+            }
+            ExprKind::FunctionCall(function, args) => {
+                if !self.capabilities.supports_function(*function, &expr.ty) {
+                    self.found(sr, Feature::Function(*function, expr.ty.clone()));
+                }
+                args.iter().for_each(|arg| self.expr(sr, arg));
+            }
+            ExprKind::Tuple(inner) => inner.iter().for_each(|e| self.expr(sr, e)),
+            ExprKind::SyncStreamAccess { target, parameters }
+            | ExprKind::IsFresh { target, parameters } => {
+                self.target(sr, *target);
+                self.parameters(sr, parameters)
+            }
+            ExprKind::OffsetStreamAccess {
+                target,
+                default,
+                parameters,
+                ..
+            }
+            | ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            }
+            | ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => {
+                self.target(sr, *target);
+                self.parameters(sr, parameters);
+                self.expr(sr, default);
+            }
+            ExprKind::WindowAccess {
+                window,
+                parameters,
+                default,
+                ..
+            } => {
+                self.window(sr, *window);
+                self.parameters(sr, parameters);
+                if let Some(default) = default {
+                    self.expr(sr, default);
+                }
+            }
+        }
+    }
+
+    /// Checks that the target of a stream access has memory to read from, if the backend requires it
+    fn target(&mut self, sr: StreamReference, target: StreamReference) {
+        if !self.capabilities.supports_memoryless_access()
+            && matches!(self.ir.stream_memory(target).buffer, StreamMemory::NoMemory)
+        {
+            let name = self.ir.name(target).into();
+            self.found(sr, Feature::MemorylessAccess(name));
+        }
+    }
+
+    fn parameters(&mut self, sr: StreamReference, parameters: &[Expr]) {
+        if !parameters.is_empty() {
+            self.found(sr, Feature::Parameterization);
+        }
+        parameters.iter().for_each(|p| self.expr(sr, p));
+    }
+}
+
+/// Collects the streams that are evaluated, spawned or closed in the statement
+fn owners_of(stmt: &Stmt, owners: &mut BTreeSet<StreamReference>) {
+    let mut insert = |sr: &OutputReference| {
+        owners.insert(sr.sr());
+    };
+    match stmt {
+        Stmt::Eval { sr, .. } | Stmt::Spawn { sr, .. } | Stmt::Close { sr, .. } => insert(sr),
+        Stmt::Input(i) => {
+            owners.insert(StreamReference::In(*i));
+        }
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|stmt| owners_of(stmt, owners))
+        }
+        Stmt::If(stmt) => {
+            owners_of(stmt.cons(), owners);
+            stmt.alt()
+                .into_iter()
+                .for_each(|alt| owners_of(alt, owners));
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => owners_of(stmt, owners),
+        Stmt::Skip | Stmt::Shift(_) | Stmt::Let { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check, Capabilities, Feature};
+    use crate::{
+        ir::{
+            expressions::{Function, Operator},
+            text::parse,
+            Type, WindowReference,
+        },
+        ParserConfig,
+    };
+
+    /// A backend supporting all types, functions and operators, but no optional feature
+    struct Core;
+
+    impl Capabilities for Core {
+        fn backend(&self) -> &'static str {
+            "test"
+        }
+
+        fn supports_type(&self, ty: &Type) -> bool {
+            !matches!(ty, Type::Bytes)
+        }
+
+        fn supports_function(&self, function: Function, _ty: &Type) -> bool {
+            function != Function::Tan
+        }
+
+        fn supports_operator(&self, op: Operator, _lhs: &Type, _rhs: &Type) -> bool {
+            op != Operator::Pow
+        }
+
+        fn supports_window(&self, window: WindowReference) -> bool {
+            matches!(window, WindowReference::Discrete(_))
+        }
+
+        fn supports_global_frequency(&self) -> bool {
+            false
+        }
+
+        fn supports_memoryless_access(&self) -> bool {
+            false
+        }
+    }
+
+    /// Returns the unsupported features of a StreamIR with the input `a` and the given declarations and statement,
+    /// together with the name of the stream using them
+    fn features(declarations: &str, stmt: &str) -> Vec<(String, Feature)> {
+        let ir = parse(&format!(
+            "streamir 1\nstream in[0] \"a\": Int64 = static bounded(2)\n{declarations}\nstmt\n{stmt}"
+        ))
+        .unwrap_or_else(|e| panic!("{e}"));
+        match check(&ir, &Core) {
+            Ok(()) => Vec::new(),
+            Err(found) => found
+                .0
+                .into_iter()
+                .map(|f| (f.location.stream, f.feature))
+                .collect(),
+        }
+    }
+
+    const OUTPUT: &str = "stream out[0] \"b\": Int64 = static single";
+
+    #[test]
+    fn supported() {
+        let declarations = format!("{OUTPUT}\nwindow discrete[0]: Int64 = sum(in[0]) over discrete(5, nowait) caller out[0] eval_with(0) pacing @in[0]");
+        let stmt = "if @in[0] then seq { shift in[0]; input in[0]; eval(0) out[0] = (offset(in[0], 1, i64(0)) + (window(in[0], discrete[0]) :: Int64)); } fi";
+        assert_eq!(features(&declarations, stmt), []);
+    }
+
+    #[test]
+    fn always_unsupported() {
+        let declarations = format!("{OUTPUT}\nstream pout[0] \"p\": Int64 = instances(\"x\": Int64) single\nlocal_freq 0 = 500ms for out[0]");
+        let stmt = "seq { if @in[0] then spawn pout[0] with (in[0]) fi; if local(0) then eval(0) out[0] = i64(1) fi; }";
+        assert_eq!(
+            features(&declarations, stmt),
+            [
+                ("b".into(), Feature::LocalFrequency),
+                ("p".into(), Feature::Parameterization)
+            ]
+        );
+    }
+
+    #[test]
+    fn policy() {
+        let declarations = format!("{OUTPUT}\nstream out[1] \"c\": Bytes = static single\nstream out[2] \"d\": Int64 = static unbounded\nwindow sliding[0]: Int64 = sum(in[0]) over sliding(1s, 10, 100ms, wait) caller out[0] eval_with(0) pacing @in[0]");
+        let stmt = "if global(1s) then eval(0) out[0] = ((in[0] ** i64(2)) + (window(in[0], sliding[0]) :: Int64)) fi";
+        assert_eq!(
+            features(&declarations, stmt),
+            [
+                ("b".into(), Feature::GlobalFrequency),
+                ("b".into(), Feature::SlidingWindow),
+                ("b".into(), Feature::Operator(Operator::Pow, Type::Int(64))),
+                ("c".into(), Feature::Type(Type::Bytes)),
+                ("d".into(), Feature::UnboundedMemory),
+            ]
+        );
+    }
+
+    #[test]
+    fn nested_types() {
+        let declarations = "stream out[0] \"b\": (Int64, Option<Bytes>) = static single";
+        assert_eq!(
+            features(declarations, "skip"),
+            [("b".into(), Feature::Type(Type::Bytes))]
+        );
+    }
+
+    #[test]
+    fn temporaries() {
+        // the expression of a temporary is attributed to the stream evaluated with it
+        let declarations = "stream out[0] \"b\": Float64 = static single";
+        let stmt = "if @in[0] then seq { let tmp[0] = tan(f64(1)); eval(0) out[0] = (tmp[0] :: Float64); } fi";
+        assert_eq!(
+            features(declarations, stmt),
+            [("b".into(), Feature::Function(Function::Tan, Type::Float64))]
+        );
+    }
+
+    #[test]
+    fn spawn_clocks() {
+        // local frequencies and windows started by a spawn are rejected even if they are not declared
+        let declarations = "stream out[0] \"b\": Int64 = dynamic(spawn: true, close: false) single";
+        let stmt = "if @in[0] then spawn out[0] clocks (0) fi";
+        assert_eq!(
+            features(declarations, stmt),
+            [("b".into(), Feature::LocalFrequency)]
+        );
+    }
+
+    #[test]
+    fn memoryless_access() {
+        let declarations =
+            "stream out[0] \"b\": Int64 = none\nstream out[1] \"c\": Int64 = static single";
+        let stmt = "if @in[0] then eval(0) out[1] = (out[0] + i64(1)) fi";
+        assert_eq!(
+            features(declarations, stmt),
+            [("c".into(), Feature::MemorylessAccess("b".into()))]
+        );
+    }
+
+    #[test]
+    fn located() {
+        let source = "input a: Int64\noutput b := a ** 2";
+        let ir = crate::parse(&ParserConfig::for_string(source.into())).unwrap();
+        let error = check(&ir, &Core).unwrap_err();
+        assert_eq!(error.0[0].location.span, None);
+        let error = error.locate(source);
+        let span = error.0[0].location.span.clone().unwrap();
+        assert_eq!((span.line, span.column), (2, 1));
+        assert_eq!(
+            error.to_string(),
+            "2:1: stream `b` uses the operator Pow on Int(64), which is not supported by the test backend"
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! A common representation of the errors and warnings reported about a specification.
+//!
+//! Each [Diagnostic] has a stable code identifying its kind, such that tools can react to specific diagnostics.
+//! Diagnostics are either rendered through the [Handler] of the frontend or serialized as JSON (with the `serde` feature).
+//! Diagnostics carry the span in the specification they refer to, which is the span of the offending expression for lowering errors
+//! and the declaration of the stream (see [Memory::span](crate::ir::memory::Memory::span)) for diagnostics about a stream.
+
+use std::{fmt::Display, ops::Range};
+
+use crate::{
+    ir::{
+        lint::{Lint, LintWarning},
+        ranges::{TriggerReachability, TriggerWarning},
+        LoweringError, StreamIr,
+    },
+    rewrite_rules::{PipelineError, RewriteError},
+    Handler, ParseError, Span,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
+/// The severity of a diagnostic
+pub enum Severity {
+    /// The specification cannot be processed or violates a denied lint
+    Error,
+    /// The specification is processed, but contains a suspicious construct
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// An error or warning about a specification
+pub struct Diagnostic {
+    /// The severity of the diagnostic
+    pub severity: Severity,
+    /// The stable code of the kind of the diagnostic
+    pub code: &'static str,
+    /// The description of the diagnostic
+    pub message: String,
+    /// The name of the stream the diagnostic refers to, if known
+    pub stream: Option<String>,
+    /// The span in the specification the diagnostic refers to
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub span: Span,
+    /// The line and column of the span in the specification, if known (see [Diagnostic::locate])
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub location: Option<Location>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A location in the source of a specification
+pub struct Location {
+    /// The line of the location, starting at 1
+    pub line: usize,
+    /// The column of the location in characters, starting at 1
+    pub column: usize,
+    /// The byte range in the source
+    pub span: Range<usize>,
+}
+
+impl Location {
+    /// Returns the location of the span in the source of the specification, if the span is known
+    pub fn of_span(source: &str, span: Span) -> Option<Self> {
+        match span {
+            Span::Direct { start, end } | Span::Indirect { start, end } if end <= source.len() => {
+                Some(Self::new(source, start..end))
+            }
+            _ => None,
+        }
+    }
+
+    fn new(source: &str, span: Range<usize>) -> Self {
+        let before = &source[..span.start];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            span,
+        }
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+impl Diagnostic {
+    /// Creates an error with the given code and message
+    pub fn error(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            stream: None,
+            span: Span::Unknown,
+            location: None,
+        }
+    }
+
+    /// Creates a warning with the given code and message
+    pub fn warning(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            severity: Severity::Warning,
+            code,
+            message: message.into(),
+            stream: None,
+            span: Span::Unknown,
+            location: None,
+        }
+    }
+
+    /// Sets the stream the diagnostic refers to
+    pub fn with_stream(self, stream: impl Into<String>) -> Self {
+        Self {
+            stream: Some(stream.into()),
+            ..self
+        }
+    }
+
+    /// Sets the span in the specification the diagnostic refers to
+    pub fn with_span(self, span: Span) -> Self {
+        Self { span, ..self }
+    }
+
+    /// Computes the line and column of the span of the diagnostic in the source of the specification
+    pub fn locate(self, source: &str) -> Self {
+        let location = self
+            .location
+            .or_else(|| Location::of_span(source, self.span));
+        Self { location, ..self }
+    }
+
+    /// Returns whether the diagnostic is an error
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Reports the diagnostic through the handler of the frontend, which renders the source at its span
+    pub fn emit(&self, handler: &Handler) {
+        let message = format!("{} [{}]", self.message, self.code);
+        let label = self
+            .stream
+            .as_ref()
+            .map(|stream| format!("declaration of `{stream}`"));
+        match self.severity {
+            Severity::Error => handler.error_with_span(&message, self.span, label.as_deref()),
+            Severity::Warning => handler.warn_with_span(&message, self.span, label.as_deref()),
+        }
+    }
+
+    /// Serializes the diagnostic into a single line of JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("diagnostics are always serializable")
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(location) = &self.location {
+            write!(f, "{location}: ")?;
+        }
+        write!(f, "{} [{}]", self.message, self.code)
+    }
+}
+
+impl LoweringError {
+    /// Returns the stable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            LoweringError::FutureAccess { .. } => "future-access",
+            LoweringError::UnsupportedFunction { .. } => "unsupported-function",
+            LoweringError::LocalFreq { .. } => "invalid-local-frequency",
+            LoweringError::ComputeSchedule { .. } => "schedule",
+        }
+    }
+}
+
+impl From<&LoweringError> for Diagnostic {
+    fn from(error: &LoweringError) -> Self {
+        Diagnostic::error(error.code(), error.to_string()).with_span(error.span())
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(error: &ParseError) -> Self {
+        match error {
+            ParseError::FrontendError(e) => Diagnostic::error("frontend", e.to_string()),
+            ParseError::LoweringError(e) => e.into(),
+        }
+    }
+}
+
+impl RewriteError {
+    /// Returns the stable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            RewriteError::Other(_) => "rewrite",
+            RewriteError::InvalidPipeline(PipelineError::UnsatisfiedRequirement { .. }) => {
+                "unsatisfied-requirement"
+            }
+            RewriteError::InvalidPipeline(PipelineError::NonTerminatingRepair { .. }) => {
+                "non-terminating-repair"
+            }
+        }
+    }
+}
+
+impl From<&RewriteError> for Diagnostic {
+    fn from(error: &RewriteError) -> Self {
+        Diagnostic::error(error.code(), error.to_string())
+    }
+}
+
+impl From<&TriggerWarning> for Diagnostic {
+    fn from(warning: &TriggerWarning) -> Self {
+        let code = match warning.reachability {
+            TriggerReachability::Never => "trigger-never-fires",
+            TriggerReachability::Always => "trigger-always-fires",
+        };
+        Diagnostic::warning(code, warning.to_string())
+            .with_stream(&warning.name)
+            .with_span(warning.span)
+    }
+}
+
+impl LintWarning {
+    /// Returns the diagnostic of the warning, which is an error if its lint is denied
+    pub fn diagnostic(&self, ir: &StreamIr, deny: &[Lint]) -> Diagnostic {
+        let diagnostic = if deny.contains(&self.lint) {
+            Diagnostic::error(self.lint.id(), &self.message)
+        } else {
+            Diagnostic::warning(self.lint.id(), &self.message)
+        };
+        diagnostic
+            .with_stream(ir.name(self.stream))
+            .with_span(ir.stream_memory(self.stream).span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{
+            lint::{Lint, LintWarning},
+            parse::parse_ir,
+            LoweringError, StreamReference,
+        },
+        parse,
+        rewrite_rules::RewriteError,
+        ParserConfig, Span,
+    };
+
+    use super::{Diagnostic, Location, Severity};
+
+    const SOURCE: &str = "input altitude: Float64\ninput a : Int64\n\noutput  alt_ok := altitude > 0.0\ntrigger !alt_ok";
+
+    fn unused_input() -> Diagnostic {
+        let ir = parse(&ParserConfig::for_string(SOURCE.into())).unwrap();
+        let warning = LintWarning {
+            lint: Lint::UnusedInput,
+            stream: ir.stream_by_name("a").unwrap(),
+            message: "input `a` is never accessed".into(),
+        };
+        warning.diagnostic(&ir, &[])
+    }
+
+    #[test]
+    fn codes() {
+        let lowering = Diagnostic::from(&LoweringError::FutureAccess {
+            span: Span::Direct { start: 3, end: 5 },
+        });
+        assert_eq!(lowering.code, "future-access");
+        assert!(lowering.is_error());
+        assert_eq!(lowering.span, Span::Direct { start: 3, end: 5 });
+        assert_eq!(
+            lowering.to_string(),
+            "specification contains a future access [future-access]"
+        );
+        let rewrite = Diagnostic::from(&RewriteError::Other("no fixpoint".into()));
+        assert_eq!(rewrite.code, "rewrite");
+
+        let ir = parse_ir("input 0");
+        let warning = LintWarning {
+            lint: Lint::UnusedInput,
+            stream: StreamReference::In(0),
+            message: "input `i0` is never accessed".into(),
+        };
+        let diagnostic = warning.diagnostic(&ir, &[]);
+        assert_eq!(diagnostic.severity, Severity::Warning);
+        assert_eq!(diagnostic.stream.as_deref(), Some("i0"));
+        assert_eq!(diagnostic.span, Span::Unknown);
+        assert!(warning.diagnostic(&ir, &[Lint::UnusedInput]).is_error());
+    }
+
+    #[test]
+    fn locations() {
+        let location = Location::of_span(SOURCE, Span::Direct { start: 49, end: 55 }).unwrap();
+        assert_eq!((location.line, location.column), (4, 9));
+        assert_eq!(&SOURCE[location.span], "alt_ok");
+        assert_eq!(Location::of_span(SOURCE, Span::Unknown), None);
+        assert_eq!(
+            Location::of_span(
+                SOURCE,
+                Span::Direct {
+                    start: 0,
+                    end: 1000
+                }
+            ),
+            None
+        );
+
+        // the lowering carries the span of the declaration of the stream into the StreamIR
+        let diagnostic = unused_input().locate(SOURCE);
+        let location = diagnostic.location.clone().unwrap();
+        assert_eq!(&SOURCE[location.span.clone()], "a : Int64");
+        assert_eq!(
+            diagnostic.to_string(),
+            "2:7: input `a` is never accessed [unused-input]"
+        );
+        let unlocated = Diagnostic::from(&RewriteError::Other("no fixpoint".into())).locate(SOURCE);
+        assert_eq!(unlocated.location, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json() {
+        let diagnostic =
+            Diagnostic::warning("unused-input", "input `a` is never accessed").with_stream("a");
+        assert_eq!(
+            diagnostic.to_json(),
+            r#"{"severity":"warning","code":"unused-input","message":"input `a` is never accessed","stream":"a"}"#
+        );
+        assert_eq!(
+            unused_input().locate(SOURCE).to_json(),
+            r#"{"severity":"warning","code":"unused-input","message":"input `a` is never accessed","stream":"a","location":{"line":2,"column":7,"span":{"start":30,"end":39}}}"#
+        );
+    }
+}
@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use crate::ir::{
     expressions::{Constant, Expr, ExprKind, Function, Operator},
-    StreamReference, Type, WindowReference,
+    StreamReference, TempRef, Type, WindowReference,
 };
 
 /// A trait definining how the parts of the stream expression are represented in the target language.
@@ -80,9 +80,22 @@ pub trait ExprFormatter {
     /// Returns the code, that is used to call a function.
     fn function_call(&self, function: Function, args: Vec<Expr>, ty: &Type) -> Self::Return;
 
+    /// Returns the code, that is used to access a temporary.
+    fn temporary(&self, temp: TempRef, ty: &Type) -> Self::Return;
+
+    /// Returns the code for the absent value of the given optional type.
+    fn none(&self, ty: &Type) -> Self::Return;
+
+    /// Returns the code, that constructs an optional value of the given type out of the inner value.
+    fn some(&self, inner: Expr, ty: &Type) -> Self::Return;
+
+    /// Returns the code, that is used to unwrap an optional value or otherwise return the default.
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> Self::Return;
+
     /// Formats any expression in the target language.
     fn expr(&self, expr: Expr) -> Self::Return {
         match expr.kind {
+            ExprKind::Constant(Constant::None) => self.none(&expr.ty),
             ExprKind::Constant(c) => self.constant(c),
             ExprKind::BinaryOperation(op, lhs, rhs) => self.binary(op, *lhs, *rhs),
             ExprKind::UnaryOperation(op, operand) => self.unary(op, *operand),
@@ -127,6 +140,9 @@ pub trait ExprFormatter {
             ExprKind::LambdaParameterAccess(window_reference, idx) => {
                 self.lambda_parameter_access(window_reference, idx)
             }
+            ExprKind::Temporary(temp) => self.temporary(temp, &expr.ty),
+            ExprKind::Some(inner) => self.some(*inner, &expr.ty),
+            ExprKind::UnwrapOr(expr, default) => self.unwrap_or(*expr, *default),
         }
     }
 }
@@ -219,6 +235,24 @@ where
         let args = args.into_iter().map(|e| self.expr(e)).join(", ");
         format!("{}({})", self.function(function, &arg_tys, ty), args)
     }
+
+    /// Returns the code, that is used to access a temporary.
+    fn temporary(&self, temp: TempRef, ty: &Type) -> String;
+
+    /// Returns the code for the absent value of the given optional type.
+    fn none(&self, _ty: &Type) -> String {
+        <Self as ConstantFormatter>::constant(self, Constant::None)
+    }
+
+    /// Returns the code, that constructs an optional value of the given type out of the inner value.
+    fn some(&self, inner: Expr, _ty: &Type) -> String {
+        format!("some({})", self.expr(inner))
+    }
+
+    /// Returns the code, that is used to unwrap an optional value or otherwise return the default.
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> String {
+        format!("unwrap_or({}, {})", self.expr(expr), self.expr(default))
+    }
 }
 
 impl<X> ExprFormatter for X
@@ -314,6 +348,22 @@ where
     fn function_call(&self, function: Function, args: Vec<Expr>, ty: &Type) -> Self::Return {
         <Self as DefaultExprFormatter>::function_call(self, function, args, ty)
     }
+
+    fn temporary(&self, temp: TempRef, ty: &Type) -> Self::Return {
+        <Self as DefaultExprFormatter>::temporary(self, temp, ty)
+    }
+
+    fn none(&self, ty: &Type) -> Self::Return {
+        <Self as DefaultExprFormatter>::none(self, ty)
+    }
+
+    fn some(&self, inner: Expr, ty: &Type) -> Self::Return {
+        <Self as DefaultExprFormatter>::some(self, inner, ty)
+    }
+
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> Self::Return {
+        <Self as DefaultExprFormatter>::unwrap_or(self, expr, default)
+    }
 }
 
 /// A formatter defining how operators are represented in the target language.
@@ -803,6 +853,9 @@ pub trait ConstantFormatter {
     /// Returns the Self::Return that the given language uses to format the tuple
     fn constant_tuple(&self, f: Vec<Constant>) -> Self::Return;
 
+    /// Returns the Self::Return that the given language uses to format the absent optional value
+    fn constant_none(&self) -> Self::Return;
+
     /// Formats a constant in the target language.
     fn constant(&self, c: Constant) -> Self::Return {
         match c {
@@ -813,6 +866,7 @@ pub trait ConstantFormatter {
             Constant::Float32(f) => self.constant_float32(f),
             Constant::Float64(f) => self.constant_float64(f),
             Constant::Tuple(constants) => self.constant_tuple(constants),
+            Constant::None => self.constant_none(),
         }
     }
 }
@@ -861,6 +915,11 @@ where
         let fields = f.into_iter().map(|v| self.constant(v)).join(", ");
         format!("({})", fields)
     }
+
+    /// Returns the string that the given language uses to format the absent optional value
+    fn constant_none(&self) -> String {
+        "none".into()
+    }
 }
 
 impl<X> ConstantFormatter for X
@@ -896,4 +955,8 @@ where
     fn constant_tuple(&self, f: Vec<Constant>) -> Self::Return {
         <Self as DefaultConstantFormatter>::constant_tuple(self, f)
     }
+
+    fn constant_none(&self) -> Self::Return {
+        <Self as DefaultConstantFormatter>::constant_none(self)
+    }
 }
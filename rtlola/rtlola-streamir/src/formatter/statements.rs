@@ -4,7 +4,7 @@ use itertools::Itertools;
 
 use crate::ir::{
     expressions::Expr, Guard, InputReference, LocalFreqRef, OutputReference, Stmt, StreamReference,
-    WindowReference,
+    TempRef, WindowReference,
 };
 
 /// A trait for formatting StreamIR statements in the target language (see [DefaultStmtFormatter] for a
@@ -62,6 +62,9 @@ pub trait StmtFormatter {
         inner: Stmt,
     ) -> Self::Return;
 
+    /// The representation of [Stmt::Let] in the target language
+    fn r#let(&self, temp: TempRef, expr: Expr) -> Self::Return;
+
     /// The representation of a statement in the target language
     fn stmt(&self, stmt: Stmt) -> Self::Return {
         match stmt {
@@ -92,6 +95,7 @@ pub trait StmtFormatter {
                 sr,
                 stmt,
             } => self.assign(sr, parameter_expr, *stmt),
+            Stmt::Let { temp, expr } => self.r#let(temp, expr),
         }
     }
 }
@@ -141,6 +145,9 @@ where
     /// The representation of [Stmt::Assign] in the target language
     fn assign(&self, sr: Vec<OutputReference>, parameter_expr: Vec<Expr>, inner: Stmt) -> String;
 
+    /// The representation of [Stmt::Let] in the target language
+    fn r#let(&self, temp: TempRef, expr: Expr) -> String;
+
     /// The representation of [Stmt::Seq] in the target language
     fn seq(&self, inner: Vec<Stmt>) -> String {
         inner.into_iter().map(|stmt| self.stmt(stmt)).join("\n")
@@ -214,4 +221,8 @@ impl<F: DefaultStmtFormatter> StmtFormatter for F {
     ) -> Self::Return {
         <Self as DefaultStmtFormatter>::assign(self, sr, parameter_expr, inner)
     }
+
+    fn r#let(&self, temp: TempRef, expr: Expr) -> Self::Return {
+        <Self as DefaultStmtFormatter>::r#let(self, temp, expr)
+    }
 }
@@ -11,39 +11,55 @@ pub use lowering::livetime_equivalences::LivetimeEquivalences;
 use memory::{Memory, StreamMemory};
 use rtlola_frontend::mir::{self};
 use windows::Window;
+pub mod cost;
+pub mod diff;
 mod display;
 pub mod expressions;
+pub mod graph;
+pub mod interner;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod lint;
 mod lowering;
 pub mod memory;
 #[cfg(test)]
 pub(crate) mod parse;
 mod print;
+pub mod ranges;
 mod schedule;
+pub mod text;
 pub mod windows;
 pub use lowering::LoweringError;
 pub use print::DebugFormatter;
 pub use schedule::{Deadline, StaticSchedule, Task};
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The internal representation of the StreamIR.
 pub struct StreamIr {
     /// The outermost statement of the StreamIR program.
     pub stmt: Stmt,
     /// The memory representation of each stream.
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub sr2memory: HashMap<StreamReference, Memory>,
     /// Information on windows in the specification.
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub wref2window: HashMap<WindowReference, Window>,
     /// A mapping from references to information of local frequencies.
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub lref2lfreq: HashMap<LocalFreqRef, LocalFreq>,
     /// Equivalence classes for the livetime information of streams.
     pub livetime_equivalences: LivetimeEquivalences,
     /// The precomputed schedule for global periodic output streams
     pub static_schedule: Option<StaticSchedule>,
     /// The mapping of all output references that represent triggers to the corresponding trigger reference
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub triggers: HashMap<OutputReference, usize>,
     /// The collection of streams this stream accesses non-transitively.  Includes this stream's spawn, evaluation condition, and close expressions.
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub accesses: HashMap<StreamReference, Accesses>,
     /// The collection of streams that access the current stream non-transitively
+    #[cfg_attr(feature = "serde", serde(with = "json::pairs"))]
     pub accessed_by: HashMap<StreamReference, Accesses>,
 }
 
@@ -134,7 +150,8 @@ impl StreamIr {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A statement of the StreamIR.
 pub enum Stmt {
     /// A no-op operation
@@ -195,9 +212,17 @@ pub enum Stmt {
         /// The inner statement that is executed once with the calculated instance
         stmt: Box<Stmt>,
     },
+    /// A statement computing the value of a temporary, that can be accessed by the following statements
+    Let {
+        /// The reference of the temporary
+        temp: TempRef,
+        /// The expression computing the value of the temporary
+        expr: Expr,
+    },
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// An conditional statement in the StreamIR.
 pub struct IfStmt {
     /// The condition
@@ -232,6 +257,7 @@ impl IfStmt {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The condition of an conditional statement
 pub enum Guard {
     /// Is true when the given stream received a new value in the current evaluation cycle
@@ -321,8 +347,9 @@ impl Hash for Guard {
                 state.write_u8(2);
                 sr.hash(state);
             }
-            Guard::Dynamic(_) => {
+            Guard::Dynamic(expr) => {
                 state.write_u8(3);
+                expr.hash(state);
             }
             Guard::GlobalFreq(duration) => {
                 state.write_u8(4);
@@ -361,7 +388,11 @@ impl Hash for Guard {
 /// A reference to a local frequency
 pub type LocalFreqRef = usize;
 
+/// A reference to a temporary introduced by a [Stmt::Let] statement
+pub type TempRef = usize;
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The information of a local frequency
 pub struct LocalFreq {
     /// The frequency
@@ -385,6 +416,7 @@ pub type InputReference = usize;
 
 /// Allows for referencing an output stream within the specification.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OutputReference {
     /// Un unparameterized stream
     Unparameterized(usize),
@@ -416,6 +448,7 @@ impl OutputReference {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A reference of an input or output stream
 pub enum StreamReference {
     /// The reference is for an input stream
@@ -443,6 +476,7 @@ impl StreamReference {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The origin of a stream expression (the clauses of an output stream)
 pub enum Origin {
     /// The expression was in the spawn clause
@@ -467,6 +501,7 @@ impl From<mir::Origin> for Origin {
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A reference of a window in the specification
 pub enum WindowReference {
     /// The window is a sliding window
@@ -490,6 +525,7 @@ impl WindowReference {
 
 /// Represents the type of a stream
 #[derive(Clone, Debug, Hash, Eq, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Type {
     /// An signed integer with a specific number of bits
     Int(u16),
@@ -546,7 +582,8 @@ impl Stmt {
             | Stmt::Input(_)
             | Stmt::Spawn { .. }
             | Stmt::Eval { .. }
-            | Stmt::Close { .. } => HashSet::new(),
+            | Stmt::Close { .. }
+            | Stmt::Let { .. } => HashSet::new(),
             Stmt::Parallel(stmts) | Stmt::Seq(stmts) => {
                 stmts.iter().flat_map(|s| s.all_global_freqs()).collect()
             }
@@ -586,6 +623,7 @@ pub type Accesses = Vec<(StreamReference, Vec<(Origin, StreamAccessKind)>)>;
 
 /// Representation of the different stream accesses
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum StreamAccessKind {
     /// Represents the synchronous access
     Sync,
@@ -615,6 +653,7 @@ pub enum StreamAccessKind {
 
 /// Offset used in the lookup expression
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Offset {
     /// A strictly positive discrete offset, e.g., `4`, or `42`
     Future(u32),
@@ -651,7 +690,8 @@ impl Stmt {
             | Stmt::Input(_)
             | Stmt::Spawn { .. }
             | Stmt::Close { .. }
-            | Stmt::Eval { .. } => false,
+            | Stmt::Eval { .. }
+            | Stmt::Let { .. } => false,
             Stmt::Parallel(stmts) | Stmt::Seq(stmts) => {
                 stmts.iter().any(|s| s.contains_interate(sr))
             }
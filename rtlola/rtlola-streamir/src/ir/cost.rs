@@ -0,0 +1,550 @@
+//! The analysis of the memory footprint and the work per evaluation cycle of a StreamIR.
+//!
+//! The memory of a stream is the size of its values for the given [TargetSizes] times the length of its buffer
+//! and the number of instances. The memory of a window is the storage of its buckets.
+//! The bookkeeping of a backend, e.g., the indices into the buffers, is not included.
+//!
+//! The work is an upper bound on the number of evaluated expression nodes and checked guards.
+//! It is computed for an arbitrary evaluation cycle and for each deadline of the static schedule,
+//! where no input arrives and the due global frequencies are known.
+
+use std::{fmt::Display, time::Duration};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use super::{
+    memory::StreamMemory,
+    windows::{WindowKind, WindowOperation},
+    Guard, IfStmt, Stmt, StreamIr, Type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The sizes of the types on the target platform
+pub struct TargetSizes {
+    /// The size of a boolean in bytes
+    pub bool: usize,
+    /// The size of a pointer in bytes, used for strings and bytestrings
+    pub pointer: usize,
+    /// The maximal number of instances of a parameterized stream
+    pub instances: usize,
+    /// The number of values a backend stores for streams with unbounded memory, if it caps them
+    pub unbounded: Option<usize>,
+}
+
+impl Default for TargetSizes {
+    fn default() -> Self {
+        Self {
+            bool: 1,
+            pointer: 8,
+            instances: 16,
+            unbounded: None,
+        }
+    }
+}
+
+impl TargetSizes {
+    /// Returns the size of a value of the given type in bytes (without padding)
+    pub fn size_of(&self, ty: &Type) -> usize {
+        match ty {
+            Type::Int(bits) | Type::UInt(bits) | Type::Fixed(bits) | Type::UFixed(bits) => {
+                bits.div_ceil(8) as usize
+            }
+            Type::Bool => self.bool,
+            Type::String | Type::Bytes => self.pointer,
+            Type::Float32 => 4,
+            Type::Float64 => 8,
+            Type::Option(inner) => self.size_of(inner) + self.bool,
+            Type::Tuple(inner) => inner.iter().map(|ty| self.size_of(ty)).sum(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The memory footprint and the work per evaluation cycle of a StreamIR
+pub struct CostReport {
+    /// The memory of each stream
+    pub streams: Vec<StreamCost>,
+    /// The memory of each window
+    pub windows: Vec<WindowCost>,
+    /// The work of an arbitrary evaluation cycle
+    pub cycle: Work,
+    /// The work at each deadline of the static schedule
+    pub deadlines: Vec<DeadlineCost>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The memory of a single stream
+pub struct StreamCost {
+    /// The name of the stream
+    pub name: String,
+    /// The size of a single value in bytes
+    pub value_bytes: usize,
+    /// The number of values stored per instance (or None if unbounded)
+    pub values: Option<usize>,
+    /// The number of instances
+    pub instances: usize,
+    /// The memory of the stream in bytes (or None if unbounded)
+    pub bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The memory of a single window
+pub struct WindowCost {
+    /// The name of the window
+    pub window: String,
+    /// The name of the stream the window occurs in
+    pub caller: String,
+    /// The name of the aggregated stream
+    pub target: String,
+    /// The size of a single bucket in bytes (or None if the bucket is unbounded)
+    pub bucket_bytes: Option<usize>,
+    /// The number of buckets per instance of the caller
+    pub buckets: usize,
+    /// The number of instances of the caller
+    pub instances: usize,
+    /// The memory of the window in bytes (or None if unbounded)
+    pub bytes: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// An upper bound on the work of an evaluation cycle
+pub struct Work {
+    /// The number of evaluated expression nodes
+    pub expressions: usize,
+    /// The number of checked guards
+    pub guards: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The work at a deadline of the static schedule
+pub struct DeadlineCost {
+    /// The time of the deadline relative to the start of the hyperperiod
+    pub time: Duration,
+    /// The work of the evaluation cycle at the deadline
+    pub work: Work,
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+/// A budget that is exceeded by a StreamIR
+pub enum BudgetError {
+    #[error("the monitor requires {} of memory, but the budget is {budget} bytes", required.map_or("unbounded".into(), |r| format!("{r} bytes")))]
+    /// The memory exceeds the budget
+    Ram {
+        /// The required memory in bytes (or None if unbounded)
+        required: Option<usize>,
+        /// The budget in bytes
+        budget: usize,
+    },
+    #[error("an evaluation cycle of the monitor requires up to {required} operations, but the budget is {budget}")]
+    /// The work of an evaluation cycle exceeds the budget
+    Ops {
+        /// The number of operations required
+        required: usize,
+        /// The budget of operations
+        budget: usize,
+    },
+}
+
+impl Work {
+    /// Returns the number of operations, i.e., evaluated expression nodes and checked guards
+    pub fn ops(&self) -> usize {
+        self.expressions + self.guards
+    }
+
+    fn add(self, other: Work) -> Work {
+        Work {
+            expressions: self.expressions + other.expressions,
+            guards: self.guards + other.guards,
+        }
+    }
+
+    fn max(self, other: Work) -> Work {
+        Work {
+            expressions: self.expressions.max(other.expressions),
+            guards: self.guards.max(other.guards),
+        }
+    }
+
+    fn times(self, n: usize) -> Work {
+        Work {
+            expressions: self.expressions * n,
+            guards: self.guards * n,
+        }
+    }
+}
+
+impl CostReport {
+    /// Returns the total memory in bytes (or None if unbounded)
+    pub fn ram(&self) -> Option<usize> {
+        self.streams
+            .iter()
+            .map(|s| s.bytes)
+            .chain(self.windows.iter().map(|w| w.bytes))
+            .sum()
+    }
+
+    /// Checks the report against the given budgets for the memory in bytes and the operations per evaluation cycle
+    pub fn check_budget(
+        &self,
+        max_ram: Option<usize>,
+        max_ops: Option<usize>,
+    ) -> Result<(), BudgetError> {
+        if let Some(budget) = max_ram {
+            let required = self.ram();
+            if required.is_none_or(|r| r > budget) {
+                return Err(BudgetError::Ram { required, budget });
+            }
+        }
+        if let Some(budget) = max_ops {
+            let required = self.cycle.ops();
+            if required > budget {
+                return Err(BudgetError::Ops { required, budget });
+            }
+        }
+        Ok(())
+    }
+
+    /// Serializes the report into a JSON document.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("reports are always serializable")
+    }
+}
+
+/// Computes the memory footprint and the work per evaluation cycle of the StreamIR for the given target.
+pub fn analyze(ir: &StreamIr, sizes: &TargetSizes) -> CostReport {
+    let instances = |memory: &StreamMemory| match memory {
+        StreamMemory::Instances { .. } => sizes.instances,
+        _ => 1,
+    };
+
+    let streams = ir
+        .streams()
+        .sorted()
+        .map(|sr| {
+            let memory = ir.stream_memory(sr);
+            let value_bytes = sizes.size_of(&memory.ty);
+            let values = memory
+                .buffer
+                .buffer()
+                .map_or(Some(0), |b| b.bound().or(sizes.unbounded));
+            let instances = instances(&memory.buffer);
+            StreamCost {
+                name: memory.name.clone(),
+                value_bytes,
+                values,
+                instances,
+                bytes: values.map(|v| v * value_bytes * instances),
+            }
+        })
+        .collect();
+
+    let windows = ir
+        .wref2window
+        .values()
+        .sorted_by_key(|w| w.wref)
+        .map(|window| {
+            let factor = match window.op {
+                WindowOperation::Sum
+                | WindowOperation::Conjunction
+                | WindowOperation::Disjunction
+                | WindowOperation::Min
+                | WindowOperation::Max
+                | WindowOperation::Count
+                | WindowOperation::Product
+                | WindowOperation::Last => Some(1),
+                WindowOperation::Average | WindowOperation::Integral => Some(2),
+                WindowOperation::Variance | WindowOperation::StandardDeviation => Some(3),
+                WindowOperation::Covariance => Some(4),
+                WindowOperation::NthPercentile(_) => None,
+            };
+            let value_bytes = sizes.size_of(window.ty.inner_ty());
+            let (buckets, bucket_bytes) = match &window.kind {
+                WindowKind::Sliding { bucket_count, .. } => {
+                    (*bucket_count, factor.map(|f| f * value_bytes))
+                }
+                WindowKind::Discrete { num_values, .. } => {
+                    (*num_values, Some(factor.unwrap_or(1) * value_bytes))
+                }
+                WindowKind::Instances { .. } => (0, Some(0)),
+            };
+            let instances = instances(&ir.stream_memory(window.caller).buffer);
+            WindowCost {
+                window: window.wref.to_string(),
+                caller: ir.name(window.caller).to_string(),
+                target: ir.name(window.target).to_string(),
+                bucket_bytes,
+                buckets,
+                instances,
+                bytes: bucket_bytes.map(|b| b * buckets * instances),
+            }
+        })
+        .collect();
+
+    let cycle = work(&ir.stmt, &|_| None, sizes);
+    let deadlines = ir
+        .static_schedule
+        .as_ref()
+        .map(|schedule| {
+            schedule
+                .deadlines
+                .iter()
+                .scan(Duration::ZERO, |time, deadline| {
+                    *time += deadline.pause;
+                    Some(*time)
+                })
+                .map(|time| {
+                    let due = |guard: &Guard| match guard {
+                        Guard::Stream(_) | Guard::FastAnd(_) | Guard::FastOr(_) => Some(false),
+                        Guard::GlobalFreq(freq) => {
+                            Some(time.as_nanos().is_multiple_of(freq.as_nanos()))
+                        }
+                        _ => None,
+                    };
+                    DeadlineCost {
+                        time,
+                        work: work(&ir.stmt, &due, sizes),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    CostReport {
+        streams,
+        windows,
+        cycle,
+        deadlines,
+    }
+}
+
+/// Returns the work of the statement, where `known` returns the value of an atomic guard if it is known in the evaluation cycle.
+fn work(stmt: &Stmt, known: &dyn Fn(&Guard) -> Option<bool>, sizes: &TargetSizes) -> Work {
+    let exprs = |n: usize| Work {
+        expressions: n,
+        guards: 0,
+    };
+    match stmt {
+        Stmt::Skip | Stmt::Shift(_) | Stmt::Input(_) | Stmt::Close { .. } => Work::default(),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .map(|stmt| work(stmt, known, sizes))
+            .fold(Work::default(), Work::add),
+        Stmt::Spawn { with, .. } => exprs(with.iter().flatten().map(|e| e.size()).sum()),
+        Stmt::Eval { with, .. } => exprs(with.size()),
+        Stmt::Let { expr, .. } => exprs(expr.size()),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            let (checks, value) = guard_work(guard, known);
+            let branches = match value {
+                Some(true) => work(cons, known, sizes),
+                Some(false) => work(alt, known, sizes),
+                None => work(cons, known, sizes).max(work(alt, known, sizes)),
+            };
+            checks.add(branches)
+        }
+        Stmt::Iterate { stmt, .. } => work(stmt, known, sizes).times(sizes.instances),
+        Stmt::Assign {
+            parameter_expr,
+            stmt,
+            ..
+        } => exprs(parameter_expr.iter().map(|e| e.size()).sum()).add(work(stmt, known, sizes)),
+    }
+}
+
+/// Returns the work of checking the guard and its value if it is known in the evaluation cycle.
+fn guard_work(guard: &Guard, known: &dyn Fn(&Guard) -> Option<bool>) -> (Work, Option<bool>) {
+    let check = Work {
+        expressions: 0,
+        guards: 1,
+    };
+    match guard {
+        Guard::Constant(b) => (Work::default(), Some(*b)),
+        Guard::Dynamic(expr) => (
+            check.add(Work {
+                expressions: expr.size(),
+                guards: 0,
+            }),
+            known(guard),
+        ),
+        Guard::Stream(_)
+        | Guard::Alive(_)
+        | Guard::GlobalFreq(_)
+        | Guard::LocalFreq(_)
+        | Guard::FastAnd(_)
+        | Guard::FastOr(_) => (check, known(guard)),
+        Guard::And { lhs, rhs } => {
+            let (lw, lv) = guard_work(lhs, known);
+            let (rw, rv) = guard_work(rhs, known);
+            let value = match (lv, rv) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            };
+            (lw.add(rw), value)
+        }
+        Guard::Or { lhs, rhs } => {
+            let (lw, lv) = guard_work(lhs, known);
+            let (rw, rv) = guard_work(rhs, known);
+            let value = match (lv, rv) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            };
+            (lw.add(rw), value)
+        }
+    }
+}
+
+impl Display for CostReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let bytes = |b: Option<usize>| b.map_or("unbounded".into(), |b| b.to_string());
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>10} {:>10} {:>12}",
+            "stream", "value bytes", "values", "instances", "bytes"
+        )?;
+        for s in &self.streams {
+            writeln!(
+                f,
+                "{:<24} {:>12} {:>10} {:>10} {:>12}",
+                s.name,
+                s.value_bytes,
+                bytes(s.values),
+                s.instances,
+                bytes(s.bytes)
+            )?;
+        }
+        if !self.windows.is_empty() {
+            writeln!(f)?;
+            writeln!(
+                f,
+                "{:<24} {:>12} {:>10} {:>10} {:>12}",
+                "window", "bucket bytes", "buckets", "instances", "bytes"
+            )?;
+            for w in &self.windows {
+                writeln!(
+                    f,
+                    "{:<24} {:>12} {:>10} {:>10} {:>12}",
+                    format!("{} ({} in {})", w.window, w.target, w.caller),
+                    bytes(w.bucket_bytes),
+                    w.buckets,
+                    w.instances,
+                    bytes(w.bytes)
+                )?;
+            }
+        }
+        writeln!(f)?;
+        writeln!(f, "total memory: {} bytes", bytes(self.ram()))?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{:<24} {:>12} {:>10} {:>10}",
+            "cycle", "expressions", "guards", "ops"
+        )?;
+        let row = |f: &mut std::fmt::Formatter<'_>, name: String, w: &Work| {
+            writeln!(
+                f,
+                "{:<24} {:>12} {:>10} {:>10}",
+                name,
+                w.expressions,
+                w.guards,
+                w.ops()
+            )
+        };
+        row(f, "any".into(), &self.cycle)?;
+        for d in &self.deadlines {
+            row(f, format!("deadline at {:?}", d.time), &d.work)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{parse::parse_ir, text, Type};
+
+    use super::{analyze, BudgetError, TargetSizes, Work};
+
+    #[test]
+    fn sizes() {
+        let sizes = TargetSizes::default();
+        assert_eq!(sizes.size_of(&Type::UInt(12)), 2);
+        assert_eq!(sizes.size_of(&Type::Option(Box::new(Type::Float64))), 9);
+        assert_eq!(
+            sizes.size_of(&Type::Tuple(vec![
+                Type::UInt(8),
+                Type::String,
+                Type::Float64
+            ])),
+            17
+        );
+    }
+
+    #[test]
+    fn memory() {
+        let ir = text::parse(include_str!("text/example.sir")).unwrap();
+        let report = analyze(&ir, &TargetSizes::default());
+        let stream = |name: &str| report.streams.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(stream("a").bytes, Some(16));
+        assert_eq!(stream("t").bytes, Some(0));
+        assert_eq!(stream("e").bytes, None);
+        assert_eq!(stream("h").instances, 16);
+        assert_eq!(stream("h").bytes, Some(16 * 9));
+        let window = |name: &str| report.windows.iter().find(|w| w.window == name).unwrap();
+        assert_eq!(window("sliding[0]").bytes, Some(10 * 8));
+        assert_eq!(window("discrete[0]").bytes, Some(5 * 8));
+        assert_eq!(window("instance[0]").bytes, Some(0));
+        assert_eq!(report.ram(), None);
+        assert_eq!(report.deadlines.len(), 2);
+        let capped = analyze(
+            &ir,
+            &TargetSizes {
+                unbounded: Some(4),
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            capped.streams.iter().find(|s| s.name == "e").unwrap().bytes,
+            Some(4)
+        );
+        assert!(matches!(
+            report.check_budget(Some(1 << 20), None),
+            Err(BudgetError::Ram { required: None, .. })
+        ));
+    }
+
+    #[test]
+    fn work() {
+        let ir = parse_ir(
+            "seq { input 0; if @0 then seq { eval 0 (s0 || t1); eval 1 true } else if (@1 && @2) then eval 2 t0 fi fi; if Global(1) then iterate 3 eval 3 s1 fi }",
+        );
+        let report = analyze(
+            &ir,
+            &TargetSizes {
+                instances: 4,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            report.cycle,
+            Work {
+                expressions: 4 + 4,
+                guards: 4
+            }
+        );
+        assert_eq!(report.check_budget(None, Some(12)), Ok(()));
+        assert_eq!(
+            report.check_budget(None, Some(11)),
+            Err(BudgetError::Ops {
+                required: 12,
+                budget: 11
+            })
+        );
+    }
+}
@@ -0,0 +1,399 @@
+//! The semantic comparison of two StreamIRs, e.g., of two versions of a specification.
+//!
+//! Streams are matched by their name and compared in their kind, type, memory bound, pacing and expressions
+//! (the message of a trigger is the expression of its output stream).
+//! Additionally, the regions of the statements that differ are reported as a line-based diff of the [DebugFormatter] output.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use itertools::Itertools;
+use similar::{ChangeTag, TextDiff};
+
+use crate::formatter::StreamIrFormatter;
+
+use super::{graph, DebugFormatter, Stmt, StreamIr, StreamReference};
+
+/// The number of unchanged lines shown around a changed region of statements
+const CONTEXT_LINES: usize = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The differences between two StreamIRs
+pub struct SpecDiff {
+    /// The streams that were added, removed or changed, sorted by their name
+    pub streams: Vec<StreamDiff>,
+    /// The regions of the statements that changed
+    pub regions: Vec<StmtRegion>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The difference of a single stream
+pub struct StreamDiff {
+    /// The name of the stream
+    pub name: String,
+    /// How the stream changed
+    pub change: StreamChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// How a stream changed between the two StreamIRs
+pub enum StreamChange {
+    /// The stream only exists in the new StreamIR
+    Added,
+    /// The stream only exists in the old StreamIR
+    Removed,
+    /// The stream exists in both StreamIRs but the given properties differ
+    Changed(Vec<PropertyChange>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A property of a stream that differs between the two StreamIRs
+pub struct PropertyChange {
+    /// The property that changed
+    pub property: Property,
+    /// The value in the old StreamIR
+    pub old: String,
+    /// The value in the new StreamIR
+    pub new: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// The properties of a stream that are compared
+pub enum Property {
+    /// Whether the stream is an input, an output or a trigger
+    Kind,
+    /// The value type of the stream
+    Type,
+    /// The memory bound of the stream
+    Memory,
+    /// The guards under which the stream is read or evaluated
+    Pacing,
+    /// The expressions of the eval clauses of an output stream
+    Expression,
+    /// The message of a trigger
+    TriggerMessage,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+/// A region of the statements that differs between the two StreamIRs
+pub struct StmtRegion {
+    /// The first line of the region in the old statements (starting at 1)
+    pub old_start: usize,
+    /// The number of lines of the region in the old statements
+    pub old_len: usize,
+    /// The first line of the region in the new statements (starting at 1)
+    pub new_start: usize,
+    /// The number of lines of the region in the new statements
+    pub new_len: usize,
+    /// The lines of the region, prefixed by `-` if removed, `+` if added, and ` ` if unchanged
+    pub lines: Vec<String>,
+}
+
+impl SpecDiff {
+    /// Returns whether the two StreamIRs are equivalent
+    pub fn is_empty(&self) -> bool {
+        self.streams.is_empty() && self.regions.is_empty()
+    }
+
+    /// Serializes the differences into a JSON document.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("differences are always serializable")
+    }
+}
+
+/// Compares two StreamIRs and returns their differences.
+pub fn diff(old: &StreamIr, new: &StreamIr) -> SpecDiff {
+    let old_streams = summaries(old);
+    let mut new_streams = summaries(new);
+    let mut streams: Vec<StreamDiff> = old_streams
+        .into_iter()
+        .filter_map(|(name, old)| {
+            let change = match new_streams.remove(&name) {
+                None => StreamChange::Removed,
+                Some(new) => {
+                    let changes = old
+                        .into_iter()
+                        .merge_join_by(new, |(p1, _), (p2, _)| p1.cmp(p2))
+                        .filter_map(|entry| {
+                            let (property, old, new) = match entry {
+                                itertools::EitherOrBoth::Both((p, old), (_, new)) => (p, old, new),
+                                itertools::EitherOrBoth::Left((p, old)) => (p, old, String::new()),
+                                itertools::EitherOrBoth::Right((p, new)) => (p, String::new(), new),
+                            };
+                            (old != new).then_some(PropertyChange { property, old, new })
+                        })
+                        .collect::<Vec<_>>();
+                    if changes.is_empty() {
+                        return None;
+                    }
+                    StreamChange::Changed(changes)
+                }
+            };
+            Some(StreamDiff { name, change })
+        })
+        .collect();
+    streams.extend(new_streams.into_keys().map(|name| StreamDiff {
+        name,
+        change: StreamChange::Added,
+    }));
+    streams.sort_by(|a, b| a.name.cmp(&b.name));
+
+    SpecDiff {
+        streams,
+        regions: regions(old, new),
+    }
+}
+
+/// Returns the compared properties of all streams by their name.
+fn summaries(ir: &StreamIr) -> BTreeMap<String, Vec<(Property, String)>> {
+    let printer = DebugFormatter::new(ir);
+    let mut pacing = graph::pacing(ir, &printer);
+    let mut expressions: BTreeMap<StreamReference, Vec<(usize, String)>> = BTreeMap::new();
+    collect_expressions(&ir.stmt, &printer, &mut expressions);
+
+    ir.streams()
+        .map(|sr| {
+            let memory = ir.stream_memory(sr);
+            let is_trigger = match sr {
+                StreamReference::In(_) => false,
+                StreamReference::Out(o) => ir.triggers.contains_key(&o),
+            };
+            let kind = match sr {
+                StreamReference::In(_) => "input",
+                StreamReference::Out(_) if is_trigger => "trigger",
+                StreamReference::Out(_) => "output",
+            };
+            let mut properties = vec![
+                (Property::Kind, kind.to_string()),
+                (Property::Type, memory.ty.to_string()),
+                (Property::Memory, memory.buffer.to_string()),
+                (
+                    Property::Pacing,
+                    pacing.remove(&sr).unwrap_or_default().join(" | "),
+                ),
+            ];
+            if let StreamReference::Out(_) = sr {
+                let expressions = expressions
+                    .remove(&sr)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .sorted()
+                    .dedup()
+                    .map(|(idx, expr)| format!("eval_{idx}: {expr}"))
+                    .join("; ");
+                let property = if is_trigger {
+                    Property::TriggerMessage
+                } else {
+                    Property::Expression
+                };
+                properties.push((property, expressions));
+            }
+            properties.sort_by_key(|(p, _)| *p);
+            (memory.name.clone(), properties)
+        })
+        .collect()
+}
+
+fn collect_expressions(
+    stmt: &Stmt,
+    printer: &DebugFormatter,
+    expressions: &mut BTreeMap<StreamReference, Vec<(usize, String)>>,
+) {
+    match stmt {
+        Stmt::Eval { sr, with, idx } => expressions
+            .entry(sr.sr())
+            .or_default()
+            .push((*idx, printer.format_expr(with.clone()))),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .for_each(|stmt| collect_expressions(stmt, printer, expressions)),
+        Stmt::If(stmt) => {
+            collect_expressions(&stmt.cons, printer, expressions);
+            collect_expressions(&stmt.alt, printer, expressions);
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => {
+            collect_expressions(stmt, printer, expressions)
+        }
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+    }
+}
+
+fn regions(old: &StreamIr, new: &StreamIr) -> Vec<StmtRegion> {
+    let old = DebugFormatter::new(old).format(old.clone());
+    let new = DebugFormatter::new(new).format(new.clone());
+    let diff = TextDiff::from_lines(&old, &new);
+    diff.grouped_ops(CONTEXT_LINES)
+        .into_iter()
+        .map(|ops| {
+            let (first, last) = (&ops[0], &ops[ops.len() - 1]);
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = ops
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| {
+                    let prefix = match change.tag() {
+                        ChangeTag::Equal => ' ',
+                        ChangeTag::Delete => '-',
+                        ChangeTag::Insert => '+',
+                    };
+                    format!("{prefix}{}", change.value().trim_end_matches('\n'))
+                })
+                .collect();
+            StmtRegion {
+                old_start: old_range.start + 1,
+                old_len: old_range.len(),
+                new_start: new_range.start + 1,
+                new_len: new_range.len(),
+                lines,
+            }
+        })
+        .collect()
+}
+
+impl Display for SpecDiff {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no differences");
+        }
+        let value = |v: &str| {
+            if v.is_empty() {
+                "(none)".to_string()
+            } else {
+                v.to_string()
+            }
+        };
+        for StreamDiff { name, change } in &self.streams {
+            match change {
+                StreamChange::Added => writeln!(f, "+ {name}")?,
+                StreamChange::Removed => writeln!(f, "- {name}")?,
+                StreamChange::Changed(changes) => {
+                    writeln!(f, "~ {name}")?;
+                    for PropertyChange { property, old, new } in changes {
+                        writeln!(f, "    {property}: {} -> {}", value(old), value(new))?;
+                    }
+                }
+            }
+        }
+        for region in &self.regions {
+            writeln!(
+                f,
+                "@@ -{},{} +{},{} @@",
+                region.old_start, region.old_len, region.new_start, region.new_len
+            )?;
+            for line in &region.lines {
+                writeln!(f, "{line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Display for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Property::Kind => "kind",
+            Property::Type => "type",
+            Property::Memory => "memory",
+            Property::Pacing => "pacing",
+            Property::Expression => "expression",
+            Property::TriggerMessage => "trigger message",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{
+        memory::{StreamBuffer, StreamMemory},
+        parse::parse_ir,
+        text, OutputReference, StreamReference,
+    };
+
+    use super::{diff, Property, PropertyChange, StreamChange};
+
+    #[test]
+    fn identical() {
+        let ir = text::parse(include_str!("text/example.sir")).unwrap();
+        let diff = diff(&ir, &ir);
+        assert!(diff.is_empty(), "{diff}");
+    }
+
+    #[test]
+    fn streams() {
+        let old = parse_ir("seq { input 0; if @0 then seq { eval 0 true; eval 1 false } fi }");
+        let mut new = parse_ir("seq { input 0; if @0 then eval 0 false fi; eval 2 true }");
+        new.sr2memory
+            .get_mut(&StreamReference::In(0))
+            .unwrap()
+            .buffer = StreamMemory::Static(StreamBuffer::Bounded(3));
+        new.sr2memory
+            .remove(&StreamReference::Out(OutputReference::Unparameterized(9)))
+            .unwrap();
+        new.triggers.insert(OutputReference::Unparameterized(1), 0);
+        let diff = diff(&old, &new);
+
+        let change = |name: &str| {
+            diff.streams
+                .iter()
+                .find(|s| s.name == name)
+                .map(|s| s.change.clone())
+        };
+        let changed = |changes: &[(Property, &str, &str)]| {
+            Some(StreamChange::Changed(
+                changes
+                    .iter()
+                    .map(|(property, old, new)| PropertyChange {
+                        property: *property,
+                        old: old.to_string(),
+                        new: new.to_string(),
+                    })
+                    .collect(),
+            ))
+        };
+        assert_eq!(
+            change("i0"),
+            changed(&[(Property::Memory, "1 per instance", "3")])
+        );
+        assert_eq!(
+            change("o0"),
+            changed(&[(Property::Expression, "eval_0: true", "eval_0: false")])
+        );
+        assert_eq!(
+            change("o1"),
+            changed(&[
+                (Property::Kind, "output", "trigger"),
+                (Property::Pacing, "@i0", ""),
+                (Property::Expression, "eval_0: false", ""),
+            ])
+        );
+        assert_eq!(
+            change("o2"),
+            changed(&[
+                (Property::Pacing, "", "true"),
+                (Property::Expression, "", "eval_0: true"),
+            ])
+        );
+        assert_eq!(change("o3"), None);
+        assert_eq!(change("o9"), Some(StreamChange::Removed));
+        assert_eq!(diff.regions.len(), 1);
+        assert!(diff.regions[0]
+            .lines
+            .contains(&"+eval_0 o2 with true".to_string()));
+        assert!(diff
+            .to_string()
+            .contains("~ o0\n    expression: eval_0: true -> eval_0: false\n"));
+    }
+}
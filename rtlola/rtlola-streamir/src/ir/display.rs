@@ -1,6 +1,9 @@
 use itertools::Itertools;
 
-use super::Type;
+use super::{
+    memory::{StreamBuffer, StreamMemory},
+    Type, WindowReference,
+};
 
 impl std::fmt::Display for Type {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -19,3 +22,34 @@ impl std::fmt::Display for Type {
         }
     }
 }
+
+impl std::fmt::Display for StreamBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamBuffer::SingleValue => write!(f, "1"),
+            StreamBuffer::Bounded(b) => write!(f, "{b}"),
+            StreamBuffer::UnBounded => write!(f, "unbounded"),
+        }
+    }
+}
+
+impl std::fmt::Display for StreamMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamMemory::NoMemory => write!(f, "none"),
+            StreamMemory::Static(buffer) => write!(f, "{buffer}"),
+            StreamMemory::Dynamic { buffer, .. } => write!(f, "{buffer} (dynamic)"),
+            StreamMemory::Instances { buffer, .. } => write!(f, "{buffer} per instance"),
+        }
+    }
+}
+
+impl std::fmt::Display for WindowReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowReference::Sliding(w) => write!(f, "sliding[{w}]"),
+            WindowReference::Discrete(w) => write!(f, "discrete[{w}]"),
+            WindowReference::Instance(w) => write!(f, "instance[{w}]"),
+        }
+    }
+}
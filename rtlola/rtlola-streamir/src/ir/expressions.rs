@@ -1,8 +1,9 @@
 //! Contains the internal representation of stream expressions inside the StreamIR
 
-use super::{StreamReference, Type, WindowReference};
+use super::{StreamReference, TempRef, Type, WindowReference};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents a stream expression
 pub struct Expr {
     /// The type of the expression
@@ -13,6 +14,7 @@ pub struct Expr {
 
 /// Represents the different kinds a expression can have
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ExprKind {
     /// A Constant
     Constant(Constant),
@@ -30,6 +32,9 @@ pub enum ExprKind {
         parameters: Vec<Expr>,
     },
     /// An synchronous access to a stream with an offset
+    ///
+    /// For this and the other accesses with a default, the access can have the [Type::Option] of the type of the target stream.
+    /// Then, the value of the target stream is wrapped as with [ExprKind::Some], and the default is typically [Constant::None].
     OffsetStreamAccess {
         /// with that stream reference
         target: StreamReference,
@@ -88,15 +93,105 @@ pub enum ExprKind {
     TupleAccess(Box<Expr>, usize),
     /// The access to a parameter of a lambda expression
     LambdaParameterAccess(WindowReference, usize),
+    /// The access to a temporary computed by a [Stmt::Let](super::Stmt::Let) statement
+    Temporary(TempRef),
+    /// The construction of an optional value containing the value of the inner expression
+    Some(Box<Expr>),
+    /// The value of the optional first expression if present, and the value of the second expression otherwise
+    UnwrapOr(Box<Expr>, Box<Expr>),
 }
 
 impl std::hash::Hash for ExprKind {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         core::mem::discriminant(self).hash(state);
+        match self {
+            ExprKind::Constant(c) => c.hash(state),
+            ExprKind::BinaryOperation(op, lhs, rhs) => {
+                op.hash(state);
+                lhs.hash(state);
+                rhs.hash(state);
+            }
+            ExprKind::UnaryOperation(op, inner) => {
+                op.hash(state);
+                inner.hash(state);
+            }
+            ExprKind::Ite(cond, cons, alt) => {
+                cond.hash(state);
+                cons.hash(state);
+                alt.hash(state);
+            }
+            ExprKind::SyncStreamAccess { target, parameters }
+            | ExprKind::IsFresh { target, parameters } => {
+                target.hash(state);
+                parameters.hash(state);
+            }
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => {
+                target.hash(state);
+                offset.hash(state);
+                default.hash(state);
+                parameters.hash(state);
+            }
+            ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            }
+            | ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => {
+                target.hash(state);
+                default.hash(state);
+                parameters.hash(state);
+            }
+            ExprKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            } => {
+                target.hash(state);
+                window.hash(state);
+                parameters.hash(state);
+                default.hash(state);
+            }
+            ExprKind::Cast(ty, inner) => {
+                ty.hash(state);
+                inner.hash(state);
+            }
+            // the stream reference is ignored by the equality as well
+            ExprKind::ParameterAccess(_, p) => p.hash(state),
+            ExprKind::FunctionCall(f, args) => {
+                f.hash(state);
+                args.hash(state);
+            }
+            ExprKind::Tuple(inner) => inner.hash(state),
+            ExprKind::TupleAccess(inner, i) => {
+                inner.hash(state);
+                i.hash(state);
+            }
+            ExprKind::LambdaParameterAccess(wref, idx) => {
+                wref.hash(state);
+                idx.hash(state);
+            }
+            ExprKind::Temporary(t) => t.hash(state),
+            ExprKind::Some(inner) => inner.hash(state),
+            ExprKind::UnwrapOr(expr, default) => {
+                expr.hash(state);
+                default.hash(state);
+            }
+        }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represent the different functions usable in expressions
 pub enum Function {
     /// The square root function
@@ -123,6 +218,7 @@ pub enum Function {
 
 /// Represents a constant of a stream expression
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constant {
     /// A string
     Str(String),
@@ -138,10 +234,37 @@ pub enum Constant {
     Float64(f64),
     /// A constant tuple
     Tuple(Vec<Constant>),
+    /// The absent value of an optional type
+    None,
+}
+
+impl std::hash::Hash for Constant {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            Constant::Str(s) => s.hash(state),
+            Constant::Bool(b) => b.hash(state),
+            Constant::UInt(v, bits) => {
+                v.hash(state);
+                bits.hash(state);
+            }
+            Constant::Int(v, bits) => {
+                v.hash(state);
+                bits.hash(state);
+            }
+            // 0.0 and -0.0 are equal and thus need to have the same hash
+            Constant::Float32(f) | Constant::Float64(f) => {
+                if *f == 0.0 { 0 } else { f.to_bits() }.hash(state)
+            }
+            Constant::Tuple(inner) => inner.hash(state),
+            Constant::None => {}
+        }
+    }
 }
 
 /// Represents a binary or unary operation in a stream expression
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Operator {
     /// Binary negation
     Not,
@@ -288,6 +411,9 @@ impl PartialEq for ExprKind {
             (Self::LambdaParameterAccess(l0, l1), Self::LambdaParameterAccess(r0, r1)) => {
                 l0 == r0 && l1 == r1
             }
+            (Self::Temporary(l0), Self::Temporary(r0)) => l0 == r0,
+            (Self::Some(l0), Self::Some(r0)) => l0 == r0,
+            (Self::UnwrapOr(l0, l1), Self::UnwrapOr(r0, r1)) => l0 == r0 && l1 == r1,
             _ => false,
         }
     }
@@ -353,8 +479,123 @@ impl Expr {
             ExprKind::FunctionCall(_, exprs) | ExprKind::Tuple(exprs) => {
                 exprs.iter().find_map(Self::contains_parameter_access)
             }
-            ExprKind::TupleAccess(expr, _) => expr.contains_parameter_access(),
-            ExprKind::LambdaParameterAccess(_, _) => None,
+            ExprKind::TupleAccess(expr, _) | ExprKind::Some(expr) => {
+                expr.contains_parameter_access()
+            }
+            ExprKind::UnwrapOr(expr, default) => expr
+                .contains_parameter_access()
+                .or_else(|| default.contains_parameter_access()),
+            ExprKind::LambdaParameterAccess(_, _) | ExprKind::Temporary(_) => None,
+        }
+    }
+
+    /// Returns the temporaries accessed in the expression together with their types, ordered by their reference
+    pub fn temporaries(&self) -> Vec<(TempRef, Type)> {
+        fn collect(expr: &Expr, temps: &mut Vec<(TempRef, Type)>) {
+            match &expr.kind {
+                ExprKind::Temporary(t) => temps.push((*t, expr.ty.clone())),
+                ExprKind::Constant(_)
+                | ExprKind::ParameterAccess(_, _)
+                | ExprKind::LambdaParameterAccess(_, _) => {}
+                ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+                    collect(lhs, temps);
+                    collect(rhs, temps);
+                }
+                ExprKind::Cast(_, expr)
+                | ExprKind::UnaryOperation(_, expr)
+                | ExprKind::TupleAccess(expr, _)
+                | ExprKind::Some(expr) => collect(expr, temps),
+                ExprKind::Ite(cond, cons, alt) => {
+                    collect(cond, temps);
+                    collect(cons, temps);
+                    collect(alt, temps);
+                }
+                ExprKind::IsFresh { parameters, .. }
+                | ExprKind::SyncStreamAccess { parameters, .. }
+                | ExprKind::WindowAccess {
+                    parameters,
+                    default: None,
+                    ..
+                } => parameters.iter().for_each(|p| collect(p, temps)),
+                ExprKind::HoldStreamAccess {
+                    default,
+                    parameters,
+                    ..
+                }
+                | ExprKind::GetAccess {
+                    default,
+                    parameters,
+                    ..
+                }
+                | ExprKind::WindowAccess {
+                    parameters,
+                    default: Some(default),
+                    ..
+                }
+                | ExprKind::OffsetStreamAccess {
+                    default,
+                    parameters,
+                    ..
+                } => {
+                    parameters.iter().for_each(|p| collect(p, temps));
+                    collect(default, temps);
+                }
+                ExprKind::FunctionCall(_, exprs) | ExprKind::Tuple(exprs) => {
+                    exprs.iter().for_each(|e| collect(e, temps))
+                }
+            }
+        }
+        let mut temps = Vec::new();
+        collect(self, &mut temps);
+        temps.sort_by_key(|(t, _)| *t);
+        temps.dedup_by_key(|(t, _)| *t);
+        temps
+    }
+
+    /// Returns the number of nodes of the expression, i.e., an upper bound on the operations needed to evaluate it
+    pub fn size(&self) -> usize {
+        1 + match &self.kind {
+            ExprKind::Constant(_)
+            | ExprKind::ParameterAccess(_, _)
+            | ExprKind::LambdaParameterAccess(_, _)
+            | ExprKind::Temporary(_) => 0,
+            ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+                lhs.size() + rhs.size()
+            }
+            ExprKind::Cast(_, expr)
+            | ExprKind::UnaryOperation(_, expr)
+            | ExprKind::TupleAccess(expr, _)
+            | ExprKind::Some(expr) => expr.size(),
+            ExprKind::Ite(cond, cons, alt) => cond.size() + cons.size() + alt.size(),
+            ExprKind::IsFresh { parameters, .. }
+            | ExprKind::SyncStreamAccess { parameters, .. }
+            | ExprKind::WindowAccess {
+                parameters,
+                default: None,
+                ..
+            }
+            | ExprKind::FunctionCall(_, parameters)
+            | ExprKind::Tuple(parameters) => parameters.iter().map(Self::size).sum(),
+            ExprKind::HoldStreamAccess {
+                default,
+                parameters,
+                ..
+            }
+            | ExprKind::GetAccess {
+                default,
+                parameters,
+                ..
+            }
+            | ExprKind::WindowAccess {
+                parameters,
+                default: Some(default),
+                ..
+            }
+            | ExprKind::OffsetStreamAccess {
+                default,
+                parameters,
+                ..
+            } => parameters.iter().map(Self::size).sum::<usize>() + default.size(),
         }
     }
 }
@@ -0,0 +1,406 @@
+//! The export of the StreamIR as a graph in the Graphviz DOT or the Mermaid format.
+//!
+//! Two views are supported:
+//! - [GraphView::Dependencies] shows the streams as nodes, labelled with their type, memory bound and pacing,
+//!   and the accesses between them as edges, labelled with the [StreamAccessKind]s.
+//! - [GraphView::ControlFlow] shows the tree of statements of the (optimized) evaluation plan.
+
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use crate::formatter::StreamIrFormatter;
+
+use super::{
+    DebugFormatter, IfStmt, Offset, OutputReference, Stmt, StreamAccessKind, StreamIr,
+    StreamReference,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The output format of the [GraphFormatter]
+pub enum GraphFormat {
+    /// The Graphviz DOT language
+    Dot,
+    /// A Mermaid flowchart
+    Mermaid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The part of the StreamIR that is shown by the [GraphFormatter]
+pub enum GraphView {
+    /// The dependency graph of the streams given by [StreamIr::accesses]
+    Dependencies,
+    /// The control-flow tree of the statements given by [StreamIr::stmt]
+    ControlFlow,
+}
+
+/// A [StreamIrFormatter] trait implementation to export the StreamIR as a graph
+#[derive(Debug, Clone, Copy)]
+pub struct GraphFormatter {
+    format: GraphFormat,
+    view: GraphView,
+}
+
+impl GraphFormatter {
+    /// Creates a new graph formatter for the given format and view
+    pub fn new(format: GraphFormat, view: GraphView) -> Self {
+        Self { format, view }
+    }
+}
+
+impl StreamIrFormatter for GraphFormatter {
+    type Return = String;
+
+    fn id(&self) -> String {
+        "GraphFormatter".into()
+    }
+
+    fn format(self, ir: StreamIr) -> Self::Return {
+        let graph = match self.view {
+            GraphView::Dependencies => Graph::dependencies(&ir),
+            GraphView::ControlFlow => Graph::control_flow(&ir),
+        };
+        match self.format {
+            GraphFormat::Dot => graph.dot(),
+            GraphFormat::Mermaid => graph.mermaid(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Box,
+    Diamond,
+    Round,
+}
+
+#[derive(Debug, Default)]
+struct Graph {
+    /// The nodes with their id, the lines of their label and their shape
+    nodes: Vec<(String, Vec<String>, Shape)>,
+    /// The edges with the ids of source and target and an optional label
+    edges: Vec<(String, String, Option<String>)>,
+}
+
+impl Graph {
+    fn dependencies(ir: &StreamIr) -> Self {
+        let mut pacing = pacing(ir, &DebugFormatter::new(ir));
+
+        let nodes = ir
+            .sr2memory
+            .iter()
+            .sorted_by_key(|(sr, _)| **sr)
+            .map(|(sr, memory)| {
+                let mut label = vec![
+                    format!("{}: {}", memory.name, memory.ty),
+                    format!("memory: {}", memory.buffer),
+                ];
+                let pacing: Vec<String> = pacing.remove(sr).unwrap_or_default();
+                if !pacing.is_empty() {
+                    label.push(format!("pacing: {}", pacing.join(" | ")));
+                }
+                (Self::stream_id(*sr), label, Shape::Box)
+            })
+            .collect();
+
+        let edges = ir
+            .accesses
+            .iter()
+            .sorted_by_key(|(sr, _)| **sr)
+            .flat_map(|(source, accesses)| {
+                accesses.iter().map(move |(target, kinds)| {
+                    let label = kinds
+                        .iter()
+                        .map(|(_, kind)| Self::access_kind(kind))
+                        .unique()
+                        .join(", ");
+                    (
+                        Self::stream_id(*source),
+                        Self::stream_id(*target),
+                        Some(label),
+                    )
+                })
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    fn control_flow(ir: &StreamIr) -> Self {
+        let printer = DebugFormatter::new(ir);
+        let mut graph = Graph::default();
+        graph.stmt(&ir.stmt, ir, &printer);
+        graph
+    }
+
+    /// Adds the node of the statement (and its children) to the graph and returns its id.
+    fn stmt(&mut self, stmt: &Stmt, ir: &StreamIr, printer: &DebugFormatter) -> String {
+        let id = format!("n{}", self.nodes.len());
+        let name = |sr: StreamReference| ir.name(sr).to_string();
+        let (label, shape, children): (_, _, Vec<(&Stmt, Option<String>)>) = match stmt {
+            Stmt::Skip => ("skip".into(), Shape::Box, vec![]),
+            Stmt::Seq(stmts) => (
+                "seq".into(),
+                Shape::Round,
+                stmts
+                    .iter()
+                    .enumerate()
+                    .map(|(i, stmt)| (stmt, Some((i + 1).to_string())))
+                    .collect(),
+            ),
+            Stmt::Parallel(stmts) => (
+                "par".into(),
+                Shape::Round,
+                stmts.iter().map(|stmt| (stmt, None)).collect(),
+            ),
+            Stmt::Shift(sr) => (format!("shift {}", name(*sr)), Shape::Box, vec![]),
+            Stmt::Input(i) => (
+                format!("input {}", name(StreamReference::In(*i))),
+                Shape::Box,
+                vec![],
+            ),
+            Stmt::Spawn { sr, with, .. } => {
+                let with = with
+                    .as_ref()
+                    .map(|with| {
+                        format!(
+                            " with ({})",
+                            with.iter()
+                                .map(|e| printer.format_expr(e.clone()))
+                                .join(", ")
+                        )
+                    })
+                    .unwrap_or_default();
+                (format!("spawn {}{with}", name(sr.sr())), Shape::Box, vec![])
+            }
+            Stmt::Eval { sr, with, idx } => (
+                format!(
+                    "eval_{idx} {} = {}",
+                    name(sr.sr()),
+                    printer.format_expr(with.clone())
+                ),
+                Shape::Box,
+                vec![],
+            ),
+            Stmt::Close { sr, .. } => (format!("close {}", name(sr.sr())), Shape::Box, vec![]),
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                let mut children = vec![(cons.as_ref(), Some("then".to_string()))];
+                if !matches!(alt.as_ref(), Stmt::Skip) {
+                    children.push((alt.as_ref(), Some("else".to_string())));
+                }
+                (
+                    printer.format_guard(guard.clone()),
+                    Shape::Diamond,
+                    children,
+                )
+            }
+            Stmt::Iterate { sr, stmt } => (
+                format!("iterate {}", sr.iter().map(|sr| name(sr.sr())).join(", ")),
+                Shape::Round,
+                vec![(stmt.as_ref(), None)],
+            ),
+            Stmt::Assign {
+                parameter_expr,
+                sr,
+                stmt,
+            } => (
+                format!(
+                    "assign {} = ({})",
+                    sr.iter().map(|sr| name(sr.sr())).join(", "),
+                    parameter_expr
+                        .iter()
+                        .map(|e| printer.format_expr(e.clone()))
+                        .join(", ")
+                ),
+                Shape::Round,
+                vec![(stmt.as_ref(), None)],
+            ),
+            Stmt::Let { temp, expr } => (
+                format!("let tmp_{temp} = {}", printer.format_expr(expr.clone())),
+                Shape::Box,
+                vec![],
+            ),
+        };
+        self.nodes.push((id.clone(), vec![label], shape));
+        for (child, label) in children {
+            let child = self.stmt(child, ir, printer);
+            self.edges.push((id.clone(), child, label));
+        }
+        id
+    }
+
+    fn stream_id(sr: StreamReference) -> String {
+        match sr {
+            StreamReference::In(i) => format!("in_{i}"),
+            StreamReference::Out(OutputReference::Unparameterized(o)) => format!("out_{o}"),
+            StreamReference::Out(OutputReference::Parameterized(o)) => format!("pout_{o}"),
+        }
+    }
+
+    fn access_kind(kind: &StreamAccessKind) -> String {
+        match kind {
+            StreamAccessKind::Sync => "sync".into(),
+            StreamAccessKind::Offset(Offset::Past(o)) => format!("offset(-{o})"),
+            StreamAccessKind::Offset(Offset::Future(o)) => format!("offset(+{o})"),
+            StreamAccessKind::Hold => "hold".into(),
+            StreamAccessKind::SlidingWindow(w)
+            | StreamAccessKind::DiscreteWindow(w)
+            | StreamAccessKind::InstanceAggregation(w) => format!("window({w})"),
+            StreamAccessKind::Get => "get".into(),
+            StreamAccessKind::Fresh => "fresh".into(),
+        }
+    }
+
+    fn dot(&self) -> String {
+        let escape = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let nodes = self.nodes.iter().map(|(id, label, shape)| {
+            let shape = match shape {
+                Shape::Box => "box",
+                Shape::Diamond => "diamond",
+                Shape::Round => "ellipse",
+            };
+            let label = label.iter().map(|l| escape(l)).join("\\n");
+            format!("    {id} [shape={shape}, label=\"{label}\"];")
+        });
+        let edges = self.edges.iter().map(|(from, to, label)| match label {
+            Some(label) => format!("    {from} -> {to} [label=\"{}\"];", escape(label)),
+            None => format!("    {from} -> {to};"),
+        });
+        format!(
+            "digraph streamir {{\n{}\n}}\n",
+            nodes.chain(edges).join("\n")
+        )
+    }
+
+    fn mermaid(&self) -> String {
+        let escape = |s: &str| {
+            s.replace('#', "#35;")
+                .replace('"', "#quot;")
+                .replace('<', "#lt;")
+                .replace('>', "#gt;")
+        };
+        let nodes = self.nodes.iter().map(|(id, label, shape)| {
+            let label = label.iter().map(|l| escape(l)).join("<br/>");
+            match shape {
+                Shape::Box => format!("    {id}[\"{label}\"]"),
+                Shape::Diamond => format!("    {id}{{\"{label}\"}}"),
+                Shape::Round => format!("    {id}(\"{label}\")"),
+            }
+        });
+        let edges = self.edges.iter().map(|(from, to, label)| match label {
+            Some(label) => format!("    {from} -->|\"{}\"| {to}", escape(label)),
+            None => format!("    {from} --> {to}"),
+        });
+        format!("flowchart TD\n{}\n", nodes.chain(edges).join("\n"))
+    }
+}
+
+/// Returns the conjunctions of the guards under which the inputs are read and the output streams are evaluated.
+pub(crate) fn pacing(
+    ir: &StreamIr,
+    printer: &DebugFormatter,
+) -> HashMap<StreamReference, Vec<String>> {
+    let mut pacing = HashMap::new();
+    collect_pacing(&ir.stmt, &mut Vec::new(), printer, &mut pacing);
+    pacing
+}
+
+/// Collects the conjunction of enclosing guards for each input and evaluation of an output stream.
+fn collect_pacing(
+    stmt: &Stmt,
+    guards: &mut Vec<String>,
+    printer: &DebugFormatter,
+    pacing: &mut HashMap<StreamReference, Vec<String>>,
+) {
+    let mut add = |sr: StreamReference| {
+        let guard = if guards.is_empty() {
+            "true".into()
+        } else {
+            guards.join(" && ")
+        };
+        let paces = pacing.entry(sr).or_default();
+        if !paces.contains(&guard) {
+            paces.push(guard);
+        }
+    };
+    match stmt {
+        Stmt::Input(i) => add(StreamReference::In(*i)),
+        Stmt::Eval { sr, .. } => add(StreamReference::Out(*sr)),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .for_each(|stmt| collect_pacing(stmt, guards, printer, pacing)),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            let guard = printer.format_guard(guard.clone());
+            guards.push(guard.clone());
+            collect_pacing(cons, guards, printer, pacing);
+            guards.pop();
+            guards.push(format!("!{guard}"));
+            collect_pacing(alt, guards, printer, pacing);
+            guards.pop();
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => {
+            collect_pacing(stmt, guards, printer, pacing)
+        }
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{parse::parse_ir, text},
+        translate,
+    };
+
+    use super::{GraphFormat, GraphFormatter, GraphView};
+
+    #[test]
+    fn dependencies() {
+        let ir = text::parse(include_str!("text/example.sir")).unwrap();
+        let dot = translate(
+            ir.clone(),
+            GraphFormatter::new(GraphFormat::Dot, GraphView::Dependencies),
+        );
+        assert!(dot.starts_with("digraph streamir {"));
+        assert!(dot.contains("in_0 [shape=box, label=\"a: UInt(64)\\nmemory: 2\\npacing: @a\"];"));
+        assert!(dot.contains("out_0 -> in_0 [label=\"window(sliding[0]), offset(-1), hold\"];"));
+        assert!(dot.contains("out_3 -> in_1 [label=\"window(discrete[0]), get, fresh, sync\"];"));
+        assert!(
+            dot.contains("pout_0 [shape=box, label=\"h: (UInt(64),Bool)\\nmemory: 1 per instance")
+        );
+
+        let mermaid = translate(
+            ir,
+            GraphFormatter::new(GraphFormat::Mermaid, GraphView::Dependencies),
+        );
+        assert!(mermaid.starts_with("flowchart TD\n"));
+        assert!(mermaid.contains("in_2[\"c #quot;quoted#quot;\t\\: String<br/>memory: 1"));
+        assert!(mermaid.contains("out_0 -->|\"window(sliding[0]), offset(-1), hold\"| in_0"));
+    }
+
+    #[test]
+    fn control_flow() {
+        let ir = parse_ir("seq { input 0; if @0 then eval 0 true else iterate 1 eval 1 false fi }");
+        let dot = translate(
+            ir.clone(),
+            GraphFormatter::new(GraphFormat::Dot, GraphView::ControlFlow),
+        );
+        assert!(dot.contains("n0 [shape=ellipse, label=\"seq\"];"));
+        assert!(dot.contains("n2 [shape=diamond, label=\"@i0\"];"));
+        assert!(dot.contains("n0 -> n2 [label=\"2\"];"));
+        assert!(dot.contains("n2 -> n3 [label=\"then\"];"));
+        assert!(dot.contains("n2 -> n4 [label=\"else\"];"));
+        assert!(dot.contains("n4 -> n5;"));
+
+        let mermaid = translate(
+            ir,
+            GraphFormatter::new(GraphFormat::Mermaid, GraphView::ControlFlow),
+        );
+        assert!(mermaid.contains("    n2{\"@i0\"}"));
+        assert!(mermaid.contains("    n2 -->|\"then\"| n3"));
+    }
+}
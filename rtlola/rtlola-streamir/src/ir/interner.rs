@@ -0,0 +1,584 @@
+//! Contains a hash-consed storage for stream expressions
+//!
+//! Interning an [Expr] stores every structurally distinct subexpression exactly once and assigns it an [ExprId].
+//! Structurally equal expressions (in the sense of [PartialEq] for [Expr]) receive the same id, such that
+//! comparing and hashing interned expressions is constant time.
+//!
+//! The interner is a side table: the StreamIR itself keeps storing expressions as [Expr] trees, and the
+//! code that deduplicates expressions, such as common subexpression elimination, interns the expressions it inspects.
+
+use std::collections::HashMap;
+
+use super::{
+    expressions::{Constant, Expr, ExprKind, Function, Operator},
+    StreamReference, TempRef, Type, WindowReference,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A cheap reference to an expression stored in an [ExprInterner]
+pub struct ExprId(u32);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// An expression node stored in the [ExprInterner], referring to its subexpressions by their ids
+pub struct ExprNode {
+    /// The type of the expression
+    pub ty: Type,
+    /// The kind of the expression
+    pub kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+/// The kind of an [ExprNode], mirroring [ExprKind] with subexpressions replaced by their ids
+pub enum NodeKind {
+    /// A Constant
+    Constant(Constant),
+    /// An binary operation
+    BinaryOperation(Operator, ExprId, ExprId),
+    /// An unary operation
+    UnaryOperation(Operator, ExprId),
+    /// An if-then-else operation
+    Ite(ExprId, ExprId, ExprId),
+    /// An synchronous access to a stream
+    SyncStreamAccess {
+        /// with that stream reference
+        target: StreamReference,
+        /// with these parameters
+        parameters: Vec<ExprId>,
+    },
+    /// An synchronous access to a stream with an offset
+    OffsetStreamAccess {
+        /// with that stream reference
+        target: StreamReference,
+        /// the offset
+        offset: u32,
+        /// the default value of the access
+        default: ExprId,
+        /// and these parameters
+        parameters: Vec<ExprId>,
+    },
+    /// An asynchronous access to a stream
+    HoldStreamAccess {
+        /// with that stream reference
+        target: StreamReference,
+        /// the default value of the access
+        default: ExprId,
+        /// and these parameters
+        parameters: Vec<ExprId>,
+    },
+    /// An is fresh acess to a stream
+    IsFresh {
+        /// The target of the access
+        target: StreamReference,
+        /// and these parameters
+        parameters: Vec<ExprId>,
+    },
+    /// An get access to a stream
+    GetAccess {
+        /// the target of the access
+        target: StreamReference,
+        /// the default value of the access
+        default: ExprId,
+        /// and these parameters
+        parameters: Vec<ExprId>,
+    },
+    /// The access to a window
+    WindowAccess {
+        /// the window aggregates of this stream
+        target: StreamReference,
+        /// the reference of the accessed sliding window
+        window: WindowReference,
+        /// the parameters
+        parameters: Vec<ExprId>,
+        /// the default value (for e.g. average, min max window operations)
+        default: Option<ExprId>,
+    },
+    /// The cast to another type
+    Cast(Type, ExprId),
+    /// The access of a parameter of a stream
+    ParameterAccess(StreamReference, usize),
+    /// A function call (with the given arguments)
+    FunctionCall(Function, Vec<ExprId>),
+    /// The construction of a tuple expression
+    Tuple(Vec<ExprId>),
+    /// The access of an element in a tuple
+    TupleAccess(ExprId, usize),
+    /// The access to a parameter of a lambda expression
+    LambdaParameterAccess(WindowReference, usize),
+    /// The access to a temporary
+    Temporary(TempRef),
+    /// The construction of an optional value
+    Some(ExprId),
+    /// The value of an optional expression or the default
+    UnwrapOr(ExprId, ExprId),
+}
+
+impl PartialEq for NodeKind {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // ignore the stream reference, as for [ExprKind]
+            (Self::ParameterAccess(_, l), Self::ParameterAccess(_, r)) => l == r,
+            (Self::Constant(l), Self::Constant(r)) => l == r,
+            (Self::BinaryOperation(l0, l1, l2), Self::BinaryOperation(r0, r1, r2)) => {
+                l0 == r0 && l1 == r1 && l2 == r2
+            }
+            (Self::UnaryOperation(l0, l1), Self::UnaryOperation(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Ite(l0, l1, l2), Self::Ite(r0, r1, r2)) => l0 == r0 && l1 == r1 && l2 == r2,
+            (
+                Self::SyncStreamAccess {
+                    target: l_target,
+                    parameters: l_parameters,
+                },
+                Self::SyncStreamAccess {
+                    target: r_target,
+                    parameters: r_parameters,
+                },
+            )
+            | (
+                Self::IsFresh {
+                    target: l_target,
+                    parameters: l_parameters,
+                },
+                Self::IsFresh {
+                    target: r_target,
+                    parameters: r_parameters,
+                },
+            ) => l_target == r_target && l_parameters == r_parameters,
+            (
+                Self::OffsetStreamAccess {
+                    target: l_target,
+                    offset: l_offset,
+                    default: l_default,
+                    parameters: l_parameters,
+                },
+                Self::OffsetStreamAccess {
+                    target: r_target,
+                    offset: r_offset,
+                    default: r_default,
+                    parameters: r_parameters,
+                },
+            ) => {
+                l_target == r_target
+                    && l_offset == r_offset
+                    && l_default == r_default
+                    && l_parameters == r_parameters
+            }
+            (
+                Self::HoldStreamAccess {
+                    target: l_target,
+                    default: l_default,
+                    parameters: l_parameters,
+                },
+                Self::HoldStreamAccess {
+                    target: r_target,
+                    default: r_default,
+                    parameters: r_parameters,
+                },
+            )
+            | (
+                Self::GetAccess {
+                    target: l_target,
+                    default: l_default,
+                    parameters: l_parameters,
+                },
+                Self::GetAccess {
+                    target: r_target,
+                    default: r_default,
+                    parameters: r_parameters,
+                },
+            ) => l_target == r_target && l_default == r_default && l_parameters == r_parameters,
+            (
+                Self::WindowAccess {
+                    target: l_target,
+                    window: l_window,
+                    parameters: l_parameters,
+                    default: l_default,
+                },
+                Self::WindowAccess {
+                    target: r_target,
+                    window: r_window,
+                    parameters: r_parameters,
+                    default: r_default,
+                },
+            ) => {
+                l_target == r_target
+                    && l_window == r_window
+                    && l_parameters == r_parameters
+                    && l_default == r_default
+            }
+            (Self::Cast(l0, l1), Self::Cast(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::FunctionCall(l0, l1), Self::FunctionCall(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::Tuple(l0), Self::Tuple(r0)) => l0 == r0,
+            (Self::TupleAccess(l0, l1), Self::TupleAccess(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::LambdaParameterAccess(l0, l1), Self::LambdaParameterAccess(r0, r1)) => {
+                l0 == r0 && l1 == r1
+            }
+            (Self::Temporary(l0), Self::Temporary(r0)) => l0 == r0,
+            (Self::Some(l0), Self::Some(r0)) => l0 == r0,
+            (Self::UnwrapOr(l0, l1), Self::UnwrapOr(r0, r1)) => l0 == r0 && l1 == r1,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for NodeKind {}
+
+impl std::hash::Hash for NodeKind {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::mem::discriminant(self).hash(state);
+        match self {
+            NodeKind::Constant(c) => c.hash(state),
+            NodeKind::BinaryOperation(op, lhs, rhs) => (op, lhs, rhs).hash(state),
+            NodeKind::UnaryOperation(op, inner) => (op, inner).hash(state),
+            NodeKind::Ite(cond, cons, alt) => (cond, cons, alt).hash(state),
+            NodeKind::SyncStreamAccess { target, parameters }
+            | NodeKind::IsFresh { target, parameters } => (target, parameters).hash(state),
+            NodeKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => (target, offset, default, parameters).hash(state),
+            NodeKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            }
+            | NodeKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => (target, default, parameters).hash(state),
+            NodeKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            } => (target, window, parameters, default).hash(state),
+            NodeKind::Cast(ty, inner) => (ty, inner).hash(state),
+            NodeKind::ParameterAccess(_, p) => p.hash(state),
+            NodeKind::FunctionCall(f, args) => (f, args).hash(state),
+            NodeKind::Tuple(inner) => inner.hash(state),
+            NodeKind::TupleAccess(inner, i) => (inner, i).hash(state),
+            NodeKind::LambdaParameterAccess(wref, idx) => (wref, idx).hash(state),
+            NodeKind::Temporary(t) => t.hash(state),
+            NodeKind::Some(inner) => inner.hash(state),
+            NodeKind::UnwrapOr(expr, default) => (expr, default).hash(state),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A hash-consed storage of expressions
+///
+/// As the nodes only refer to their subexpressions by id, hashing a node is independent of the size of the expression.
+pub struct ExprInterner {
+    nodes: Vec<ExprNode>,
+    ids: HashMap<ExprNode, ExprId>,
+}
+
+impl ExprInterner {
+    /// Creates a new empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of structurally distinct expressions stored in the interner
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns whether the interner does not contain any expression
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Stores the given expression node and returns its id, reusing the id of a structurally equal node
+    pub fn insert(&mut self, node: ExprNode) -> ExprId {
+        if let Some(id) = self.ids.get(&node) {
+            return *id;
+        }
+        let id = ExprId(u32::try_from(self.nodes.len()).expect("too many expressions"));
+        self.nodes.push(node.clone());
+        self.ids.insert(node, id);
+        id
+    }
+
+    /// Stores the given expression (and all its subexpressions) and returns its id
+    pub fn intern(&mut self, expr: &Expr) -> ExprId {
+        let kind = match &expr.kind {
+            ExprKind::Constant(c) => NodeKind::Constant(c.clone()),
+            ExprKind::BinaryOperation(op, lhs, rhs) => {
+                NodeKind::BinaryOperation(*op, self.intern(lhs), self.intern(rhs))
+            }
+            ExprKind::UnaryOperation(op, inner) => {
+                NodeKind::UnaryOperation(*op, self.intern(inner))
+            }
+            ExprKind::Ite(cond, cons, alt) => {
+                NodeKind::Ite(self.intern(cond), self.intern(cons), self.intern(alt))
+            }
+            ExprKind::SyncStreamAccess { target, parameters } => NodeKind::SyncStreamAccess {
+                target: *target,
+                parameters: self.intern_all(parameters),
+            },
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => NodeKind::OffsetStreamAccess {
+                target: *target,
+                offset: *offset,
+                default: self.intern(default),
+                parameters: self.intern_all(parameters),
+            },
+            ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            } => NodeKind::HoldStreamAccess {
+                target: *target,
+                default: self.intern(default),
+                parameters: self.intern_all(parameters),
+            },
+            ExprKind::IsFresh { target, parameters } => NodeKind::IsFresh {
+                target: *target,
+                parameters: self.intern_all(parameters),
+            },
+            ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => NodeKind::GetAccess {
+                target: *target,
+                default: self.intern(default),
+                parameters: self.intern_all(parameters),
+            },
+            ExprKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            } => NodeKind::WindowAccess {
+                target: *target,
+                window: *window,
+                parameters: self.intern_all(parameters),
+                default: default.as_ref().map(|d| self.intern(d)),
+            },
+            ExprKind::Cast(ty, inner) => NodeKind::Cast(ty.clone(), self.intern(inner)),
+            ExprKind::ParameterAccess(sr, p) => NodeKind::ParameterAccess(*sr, *p),
+            ExprKind::FunctionCall(f, args) => NodeKind::FunctionCall(*f, self.intern_all(args)),
+            ExprKind::Tuple(inner) => NodeKind::Tuple(self.intern_all(inner)),
+            ExprKind::TupleAccess(inner, i) => NodeKind::TupleAccess(self.intern(inner), *i),
+            ExprKind::LambdaParameterAccess(wref, idx) => {
+                NodeKind::LambdaParameterAccess(*wref, *idx)
+            }
+            ExprKind::Temporary(t) => NodeKind::Temporary(*t),
+            ExprKind::Some(inner) => NodeKind::Some(self.intern(inner)),
+            ExprKind::UnwrapOr(expr, default) => {
+                NodeKind::UnwrapOr(self.intern(expr), self.intern(default))
+            }
+        };
+        self.insert(ExprNode {
+            ty: expr.ty.clone(),
+            kind,
+        })
+    }
+
+    fn intern_all(&mut self, exprs: &[Expr]) -> Vec<ExprId> {
+        exprs.iter().map(|e| self.intern(e)).collect()
+    }
+
+    /// Returns the node of the expression with the given id
+    pub fn node(&self, id: ExprId) -> &ExprNode {
+        &self.nodes[id.0 as usize]
+    }
+
+    /// Returns the type of the expression with the given id
+    pub fn ty(&self, id: ExprId) -> &Type {
+        &self.node(id).ty
+    }
+
+    /// Reconstructs the expression with the given id
+    pub fn expr(&self, id: ExprId) -> Expr {
+        let ExprNode { ty, kind } = self.node(id);
+        let boxed = |id: &ExprId| Box::new(self.expr(*id));
+        let all = |ids: &[ExprId]| ids.iter().map(|id| self.expr(*id)).collect();
+        let kind = match kind {
+            NodeKind::Constant(c) => ExprKind::Constant(c.clone()),
+            NodeKind::BinaryOperation(op, lhs, rhs) => {
+                ExprKind::BinaryOperation(*op, boxed(lhs), boxed(rhs))
+            }
+            NodeKind::UnaryOperation(op, inner) => ExprKind::UnaryOperation(*op, boxed(inner)),
+            NodeKind::Ite(cond, cons, alt) => ExprKind::Ite(boxed(cond), boxed(cons), boxed(alt)),
+            NodeKind::SyncStreamAccess { target, parameters } => ExprKind::SyncStreamAccess {
+                target: *target,
+                parameters: all(parameters),
+            },
+            NodeKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => ExprKind::OffsetStreamAccess {
+                target: *target,
+                offset: *offset,
+                default: boxed(default),
+                parameters: all(parameters),
+            },
+            NodeKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            } => ExprKind::HoldStreamAccess {
+                target: *target,
+                default: boxed(default),
+                parameters: all(parameters),
+            },
+            NodeKind::IsFresh { target, parameters } => ExprKind::IsFresh {
+                target: *target,
+                parameters: all(parameters),
+            },
+            NodeKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => ExprKind::GetAccess {
+                target: *target,
+                default: boxed(default),
+                parameters: all(parameters),
+            },
+            NodeKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            } => ExprKind::WindowAccess {
+                target: *target,
+                window: *window,
+                parameters: all(parameters),
+                default: default.as_ref().map(boxed),
+            },
+            NodeKind::Cast(ty, inner) => ExprKind::Cast(ty.clone(), boxed(inner)),
+            NodeKind::ParameterAccess(sr, p) => ExprKind::ParameterAccess(*sr, *p),
+            NodeKind::FunctionCall(f, args) => ExprKind::FunctionCall(*f, all(args)),
+            NodeKind::Tuple(inner) => ExprKind::Tuple(all(inner)),
+            NodeKind::TupleAccess(inner, i) => ExprKind::TupleAccess(boxed(inner), *i),
+            NodeKind::LambdaParameterAccess(wref, idx) => {
+                ExprKind::LambdaParameterAccess(*wref, *idx)
+            }
+            NodeKind::Temporary(t) => ExprKind::Temporary(*t),
+            NodeKind::Some(inner) => ExprKind::Some(boxed(inner)),
+            NodeKind::UnwrapOr(expr, default) => ExprKind::UnwrapOr(boxed(expr), boxed(default)),
+        };
+        Expr {
+            ty: ty.clone(),
+            kind,
+        }
+    }
+
+    /// Returns the ids of the direct subexpressions of the expression with the given id
+    pub fn children(&self, id: ExprId) -> Vec<ExprId> {
+        match &self.node(id).kind {
+            NodeKind::Constant(_)
+            | NodeKind::ParameterAccess(_, _)
+            | NodeKind::LambdaParameterAccess(_, _)
+            | NodeKind::Temporary(_) => Vec::new(),
+            NodeKind::BinaryOperation(_, lhs, rhs) | NodeKind::UnwrapOr(lhs, rhs) => {
+                vec![*lhs, *rhs]
+            }
+            NodeKind::UnaryOperation(_, inner)
+            | NodeKind::Cast(_, inner)
+            | NodeKind::TupleAccess(inner, _)
+            | NodeKind::Some(inner) => vec![*inner],
+            NodeKind::Ite(cond, cons, alt) => vec![*cond, *cons, *alt],
+            NodeKind::SyncStreamAccess { parameters, .. }
+            | NodeKind::IsFresh { parameters, .. }
+            | NodeKind::FunctionCall(_, parameters)
+            | NodeKind::Tuple(parameters) => parameters.clone(),
+            NodeKind::OffsetStreamAccess {
+                default,
+                parameters,
+                ..
+            }
+            | NodeKind::HoldStreamAccess {
+                default,
+                parameters,
+                ..
+            }
+            | NodeKind::GetAccess {
+                default,
+                parameters,
+                ..
+            } => parameters.iter().copied().chain([*default]).collect(),
+            NodeKind::WindowAccess {
+                parameters,
+                default,
+                ..
+            } => parameters.iter().copied().chain(*default).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ir::{
+        expressions::{Constant, Expr, ExprKind, Operator},
+        StreamReference, Type,
+    };
+
+    use super::ExprInterner;
+
+    fn access(i: usize) -> Expr {
+        Expr {
+            ty: Type::Bool,
+            kind: ExprKind::SyncStreamAccess {
+                target: StreamReference::In(i),
+                parameters: vec![],
+            },
+        }
+    }
+
+    fn and(lhs: Expr, rhs: Expr) -> Expr {
+        Expr {
+            ty: Type::Bool,
+            kind: ExprKind::BinaryOperation(Operator::And, Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    #[test]
+    fn structural_sharing() {
+        let mut interner = ExprInterner::new();
+        let a = interner.intern(&and(access(0), access(1)));
+        let b = interner.intern(&and(access(0), access(1)));
+        let c = interner.intern(&and(access(1), access(0)));
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        // s0, s1, s0 && s1, s1 && s0
+        assert_eq!(interner.len(), 4);
+        let d = interner.intern(&and(and(access(0), access(1)), access(0)));
+        assert_eq!(interner.children(d)[0], a);
+        assert_eq!(interner.len(), 5);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let expr = and(
+            access(0),
+            Expr {
+                ty: Type::Bool,
+                kind: ExprKind::Ite(
+                    Box::new(access(2)),
+                    Box::new(Expr {
+                        ty: Type::Bool,
+                        kind: ExprKind::Constant(Constant::Bool(true)),
+                    }),
+                    Box::new(access(0)),
+                ),
+            },
+        );
+        let mut interner = ExprInterner::new();
+        let id = interner.intern(&expr);
+        assert_eq!(interner.expr(id), expr);
+        assert_eq!(interner.ty(id), &Type::Bool);
+    }
+}
@@ -0,0 +1,509 @@
+//! The (de-)serialization of the StreamIR as JSON.
+//!
+//! A document consists of the version of the schema and the StreamIR:
+//! ```json
+//! { "version": 3, "ir": { "stmt": ..., "sr2memory": [[{ "In": 0 }, { ... }], ...], ... } }
+//! ```
+//! Structs are represented as objects with their field names and enums are externally tagged.
+//! Maps are represented as lists of key-value pairs sorted by the key, so the output is deterministic.
+//! The [SCHEMA_VERSION] is increased with every change to this representation.
+
+use std::{collections::HashMap, hash::Hash};
+
+use disjoint::DisjointSet;
+use itertools::Itertools;
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+use super::{
+    expressions::{Expr, ExprKind},
+    memory::Memory,
+    Guard, IfStmt, LivetimeEquivalences, LocalFreqRef, OutputReference, Stmt, StreamIr,
+    StreamReference, Task, TempRef, Type, WindowReference,
+};
+
+/// The version of the JSON schema
+pub const SCHEMA_VERSION: u32 = 3;
+
+#[derive(Error, Debug)]
+/// An error when reading or writing the JSON representation of a StreamIR
+pub enum JsonError {
+    #[error("invalid JSON: {0}")]
+    /// The document is not a valid JSON representation of a StreamIR
+    Json(#[from] serde_json::Error),
+    #[error("unsupported schema version {found}, expected version {SCHEMA_VERSION}")]
+    /// The document was written with a different version of the schema
+    UnsupportedVersion {
+        /// The version of the document
+        found: u32,
+    },
+    #[error("invalid StreamIR: {0}")]
+    /// The document represents a StreamIR that is not well-formed, e.g., it accesses a stream that is not declared
+    Invalid(String),
+}
+
+#[derive(Serialize)]
+struct Document<'a> {
+    version: u32,
+    ir: &'a StreamIr,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct OwnedDocument {
+    ir: StreamIr,
+}
+
+/// Serializes the StreamIR into a JSON document.
+pub fn to_json(ir: &StreamIr) -> Result<String, JsonError> {
+    let document = Document {
+        version: SCHEMA_VERSION,
+        ir,
+    };
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+/// Deserializes a StreamIR from a JSON document.
+///
+/// As the backends rely on the StreamIR being well-formed, the document is rejected if it references
+/// streams, windows, local frequencies, parameters or temporaries that are not declared,
+/// or if it accesses a stream with an offset exceeding its buffer.
+pub fn from_json(json: &str) -> Result<StreamIr, JsonError> {
+    let Header { version } = serde_json::from_str(json)?;
+    if version != SCHEMA_VERSION {
+        return Err(JsonError::UnsupportedVersion { found: version });
+    }
+    let OwnedDocument { ir } = serde_json::from_str(json)?;
+    Validator { ir: &ir }
+        .validate()
+        .map_err(JsonError::Invalid)?;
+    Ok(ir)
+}
+
+/// Checks the well-formedness of a deserialized StreamIR
+struct Validator<'a> {
+    ir: &'a StreamIr,
+}
+
+impl Validator<'_> {
+    fn validate(&self) -> Result<(), String> {
+        for window in self.ir.wref2window.values() {
+            self.stream(window.target)?;
+            self.stream(window.caller)?;
+            self.guard(&window.origin_pacing, &HashMap::new())?;
+        }
+        for lfreq in self.ir.lref2lfreq.values() {
+            self.stream(lfreq.sr.sr())?;
+        }
+        for sr in self.ir.triggers.keys() {
+            self.stream(sr.sr())?;
+        }
+        for (sr, accesses) in self.ir.accesses.iter().chain(&self.ir.accessed_by) {
+            self.stream(*sr)?;
+            for (target, _) in accesses {
+                self.stream(*target)?;
+            }
+        }
+        for deadline in self.ir.static_schedule.iter().flat_map(|s| &s.deadlines) {
+            for task in &deadline.due {
+                let (Task::Spawn(sr) | Task::Eval(sr) | Task::Close(sr)) = task;
+                self.stream(sr.sr())?;
+            }
+        }
+        self.stmt(&self.ir.stmt, &mut HashMap::new())
+    }
+
+    fn stream(&self, sr: StreamReference) -> Result<&Memory, String> {
+        self.ir
+            .sr2memory
+            .get(&sr)
+            .ok_or_else(|| format!("stream {sr:?} is not declared"))
+    }
+
+    fn window(&self, wref: WindowReference) -> Result<(), String> {
+        if self.ir.wref2window.contains_key(&wref) {
+            Ok(())
+        } else {
+            Err(format!("window {wref:?} is not declared"))
+        }
+    }
+
+    fn local_freq(&self, lref: LocalFreqRef) -> Result<(), String> {
+        if self.ir.lref2lfreq.contains_key(&lref) {
+            Ok(())
+        } else {
+            Err(format!("local frequency {lref} is not declared"))
+        }
+    }
+
+    /// Checks the statement, where `temps` contains the types of the temporaries defined before it in its block
+    fn stmt(&self, stmt: &Stmt, temps: &mut HashMap<TempRef, Type>) -> Result<(), String> {
+        match stmt {
+            Stmt::Skip => Ok(()),
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                stmts.iter().try_for_each(|stmt| self.stmt(stmt, temps))
+            }
+            Stmt::Shift(sr) => self.stream(*sr).map(drop),
+            Stmt::Input(i) => self.stream(StreamReference::In(*i)).map(drop),
+            Stmt::Spawn {
+                sr,
+                with,
+                local_frequencies,
+                windows,
+            } => {
+                self.stream(sr.sr())?;
+                with.iter()
+                    .flatten()
+                    .try_for_each(|expr| self.expr(expr, temps))?;
+                local_frequencies
+                    .iter()
+                    .try_for_each(|lref| self.local_freq(*lref))?;
+                windows.iter().try_for_each(|wref| self.window(*wref))
+            }
+            Stmt::Eval { sr, with, idx: _ } => {
+                self.stream(sr.sr())?;
+                self.expr(with, temps)
+            }
+            Stmt::Close {
+                sr,
+                local_frequencies,
+                windows,
+            } => {
+                self.stream(sr.sr())?;
+                local_frequencies
+                    .iter()
+                    .try_for_each(|lref| self.local_freq(*lref))?;
+                windows.iter().try_for_each(|wref| self.window(*wref))
+            }
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                self.guard(guard, temps)?;
+                self.stmt(cons, &mut temps.clone())?;
+                self.stmt(alt, &mut temps.clone())
+            }
+            Stmt::Iterate { sr, stmt } => {
+                sr.iter()
+                    .try_for_each(|sr| self.stream(sr.sr()).map(drop))?;
+                self.stmt(stmt, &mut temps.clone())
+            }
+            Stmt::Assign {
+                parameter_expr,
+                sr,
+                stmt,
+            } => {
+                sr.iter()
+                    .try_for_each(|sr| self.stream(sr.sr()).map(drop))?;
+                parameter_expr
+                    .iter()
+                    .try_for_each(|expr| self.expr(expr, temps))?;
+                self.stmt(stmt, &mut temps.clone())
+            }
+            Stmt::Let { temp, expr } => {
+                self.expr(expr, temps)?;
+                if temps.insert(*temp, expr.ty.clone()).is_some() {
+                    return Err(format!("temporary {temp} is defined twice"));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn guard(&self, guard: &Guard, temps: &HashMap<TempRef, Type>) -> Result<(), String> {
+        match guard {
+            Guard::Stream(sr) | Guard::Alive(sr) => self.stream(*sr).map(drop),
+            Guard::FastAnd(srs) | Guard::FastOr(srs) => {
+                srs.iter().try_for_each(|sr| self.stream(*sr).map(drop))
+            }
+            Guard::Dynamic(expr) => self.expr(expr, temps),
+            Guard::LocalFreq(lref) => self.local_freq(*lref),
+            Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => {
+                self.guard(lhs, temps)?;
+                self.guard(rhs, temps)
+            }
+            Guard::GlobalFreq(_) | Guard::Constant(_) => Ok(()),
+        }
+    }
+
+    fn expr(&self, expr: &Expr, temps: &HashMap<TempRef, Type>) -> Result<(), String> {
+        let exprs = |exprs: &[Expr]| exprs.iter().try_for_each(|expr| self.expr(expr, temps));
+        match &expr.kind {
+            ExprKind::Constant(_) | ExprKind::LambdaParameterAccess(_, _) => Ok(()),
+            ExprKind::Temporary(temp) => match temps.get(temp) {
+                None => Err(format!(
+                    "temporary {temp} is accessed before its definition"
+                )),
+                Some(ty) if *ty != expr.ty => Err(format!(
+                    "temporary {temp} of type {ty} is accessed with type {}",
+                    expr.ty
+                )),
+                Some(_) => Ok(()),
+            },
+            ExprKind::ParameterAccess(sr, idx) => {
+                let num_parameters = self.stream(*sr)?.num_parameters();
+                if *idx >= num_parameters {
+                    return Err(format!(
+                        "parameter {idx} of stream {sr:?} is accessed, but it has {num_parameters} parameters"
+                    ));
+                }
+                Ok(())
+            }
+            ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+                self.expr(lhs, temps)?;
+                self.expr(rhs, temps)
+            }
+            ExprKind::Cast(_, inner)
+            | ExprKind::UnaryOperation(_, inner)
+            | ExprKind::TupleAccess(inner, _)
+            | ExprKind::Some(inner) => self.expr(inner, temps),
+            ExprKind::Ite(cond, cons, alt) => {
+                self.expr(cond, temps)?;
+                self.expr(cons, temps)?;
+                self.expr(alt, temps)
+            }
+            ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => exprs(args),
+            ExprKind::SyncStreamAccess { target, parameters }
+            | ExprKind::IsFresh { target, parameters } => {
+                self.stream(*target)?;
+                exprs(parameters)
+            }
+            ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            }
+            | ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => {
+                self.stream(*target)?;
+                self.expr(default, temps)?;
+                exprs(parameters)
+            }
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => {
+                let memory = self.stream(*target)?;
+                match memory.buffer.buffer() {
+                    None => {
+                        return Err(format!(
+                            "stream {target:?} is accessed with offset {offset}, but has no memory"
+                        ))
+                    }
+                    Some(buffer) => {
+                        if let Some(bound) = buffer.bound().filter(|b| *offset as usize >= *b) {
+                            return Err(format!(
+                                "stream {target:?} is accessed with offset {offset}, but only stores {bound} values"
+                            ));
+                        }
+                    }
+                }
+                self.expr(default, temps)?;
+                exprs(parameters)
+            }
+            ExprKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            } => {
+                self.stream(*target)?;
+                self.window(*window)?;
+                default
+                    .iter()
+                    .try_for_each(|default| self.expr(default, temps))?;
+                exprs(parameters)
+            }
+        }
+    }
+}
+
+/// (De-)serializes maps as lists of key-value pairs, as JSON only allows strings as keys.
+pub(crate) mod pairs {
+    use super::*;
+
+    pub(crate) fn serialize<K, V, S>(map: &HashMap<K, V>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        K: Serialize + Ord,
+        V: Serialize,
+        S: Serializer,
+    {
+        serializer.collect_seq(map.iter().sorted_by_key(|(k, _)| *k))
+    }
+
+    pub(crate) fn deserialize<'de, K, V, D>(deserializer: D) -> Result<HashMap<K, V>, D::Error>
+    where
+        K: Deserialize<'de> + Eq + Hash,
+        V: Deserialize<'de>,
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<(K, V)>::deserialize(deserializer)?
+            .into_iter()
+            .collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+/// The representation of the livetime equivalences by the classes with more than one member
+struct LivetimeClasses {
+    outputs: Vec<(OutputReference, usize)>,
+    input_idx: usize,
+    classes: Vec<Vec<usize>>,
+}
+
+impl Serialize for LivetimeEquivalences {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        LivetimeClasses {
+            outputs: self.idx.iter().map(|(o, i)| (*o, *i)).sorted().collect(),
+            input_idx: self.input_idx,
+            classes: self
+                .sets
+                .sets()
+                .into_iter()
+                .filter(|set| set.len() > 1)
+                .map(|set| set.into_iter().sorted().collect())
+                .sorted()
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LivetimeEquivalences {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let LivetimeClasses {
+            outputs,
+            input_idx,
+            classes,
+        } = LivetimeClasses::deserialize(deserializer)?;
+        let len = outputs
+            .iter()
+            .map(|(_, i)| *i)
+            .chain([input_idx])
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let mut sets = DisjointSet::with_len(len);
+        for class in classes {
+            if let Some(invalid) = class.iter().find(|i| **i >= len) {
+                return Err(D::Error::custom(format!(
+                    "livetime class member {invalid} is not an index of a stream"
+                )));
+            }
+            if let Some((first, rest)) = class.split_first() {
+                rest.iter().for_each(|i| {
+                    sets.join(*first, *i);
+                });
+            }
+        }
+        Ok(LivetimeEquivalences {
+            idx: outputs.into_iter().collect(),
+            sets,
+            input_idx,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::ir::{
+        expressions::ExprKind, parse::parse_ir, text, Stmt, StreamIr, StreamReference,
+    };
+    use crate::{optimize_all, ParserConfig};
+
+    use super::{from_json, to_json, JsonError, SCHEMA_VERSION};
+
+    fn assert_invalid(ir: &StreamIr, message: &str) {
+        let json = to_json(ir).unwrap();
+        match from_json(&json) {
+            Err(JsonError::Invalid(m)) => assert_eq!(m, message),
+            other => panic!("expected an invalid StreamIR, got {other:?}"),
+        }
+    }
+
+    fn assert_round_trip(ir: &StreamIr) {
+        let json = to_json(ir).unwrap();
+        let parsed = from_json(&json).unwrap_or_else(|e| panic!("{e}\n{json}"));
+        assert_eq!(text::print(&parsed), text::print(ir));
+        assert_eq!(to_json(&parsed).unwrap(), json);
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_round_trip(&text::parse(include_str!("text/example.sir")).unwrap());
+        assert_round_trip(&parse_ir(
+            "seq { input 0; if (@0 && ?1) || Local(2) then iterate 1 assign 2 (s0, true) seq { let 0 s1; let 1 (s1 || t0) } fi }",
+        ));
+    }
+
+    #[test]
+    fn repo_specs() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "lola") {
+                let config = ParserConfig::from_path(path).unwrap();
+                let ir = crate::parse(&config).unwrap();
+                assert_round_trip(&ir);
+                assert_round_trip(&optimize_all(ir).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn version() {
+        let json = to_json(&parse_ir("input 0")).unwrap();
+        let json = json.replacen(
+            &format!("\"version\": {SCHEMA_VERSION}"),
+            "\"version\": 0",
+            1,
+        );
+        assert!(matches!(
+            from_json(&json),
+            Err(JsonError::UnsupportedVersion { found: 0 })
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        assert_invalid(&parse_ir("input 12"), "stream In(12) is not declared");
+        assert_invalid(
+            &parse_ir("if @0 then eval 0 p3 fi"),
+            "parameter 3 of stream In(0) is accessed, but it has 3 parameters",
+        );
+        assert_invalid(
+            &parse_ir("if @0 then eval 0 t3 fi"),
+            "temporary 3 is accessed before its definition",
+        );
+        assert_invalid(
+            &parse_ir("seq { if @0 then let 0 s0 fi; eval 0 t0 }"),
+            "temporary 0 is accessed before its definition",
+        );
+        assert_invalid(
+            &parse_ir("seq { let 0 s0; let 0 s1 }"),
+            "temporary 0 is defined twice",
+        );
+        let mut ir = parse_ir("eval 0 true");
+        let Stmt::Eval { with, .. } = &mut ir.stmt else {
+            unreachable!()
+        };
+        with.kind = ExprKind::OffsetStreamAccess {
+            target: StreamReference::In(0),
+            offset: 1,
+            default: Box::new(with.clone()),
+            parameters: Vec::new(),
+        };
+        assert_invalid(
+            &ir,
+            "stream In(0) is accessed with offset 1, but only stores 1 values",
+        );
+    }
+}
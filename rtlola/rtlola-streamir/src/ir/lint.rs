@@ -0,0 +1,586 @@
+//! A lint pass finding suspicious constructs in a specification.
+//!
+//! Each [Lint] has a stable id that is used to deny it, e.g., with `--deny unused-input` in the binary.
+//! The resulting [LintWarning]s are reported through the [Handler] of the frontend by [emit].
+
+use std::{collections::BTreeSet, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+
+use crate::Handler;
+
+use super::{
+    expressions::{Expr, ExprKind, Operator},
+    ranges::{Range, Ranges},
+    Guard, IfStmt, Offset, OutputReference, Stmt, StreamAccessKind, StreamIr, StreamReference,
+    Type,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A check of the lint pass
+pub enum Lint {
+    /// An input stream that is never accessed
+    UnusedInput,
+    /// An output stream that is neither a trigger nor accessed by another stream
+    UnusedOutput,
+    /// Two output streams with identical expressions and pacing
+    DuplicateStream,
+    /// Two triggers with identical conditions
+    DuplicateTrigger,
+    /// An offset access to a stream whose value never changes
+    ExcessiveOffset,
+    /// An equality comparison of floating-point values
+    FloatEquality,
+    /// A stream whose memory bound is larger than any access requires
+    OversizedMemory,
+}
+
+impl Lint {
+    /// All lints in the order they are checked
+    pub const ALL: [Lint; 7] = [
+        Lint::UnusedInput,
+        Lint::UnusedOutput,
+        Lint::DuplicateStream,
+        Lint::DuplicateTrigger,
+        Lint::ExcessiveOffset,
+        Lint::FloatEquality,
+        Lint::OversizedMemory,
+    ];
+
+    /// Returns the stable id of the lint
+    pub fn id(self) -> &'static str {
+        match self {
+            Lint::UnusedInput => "unused-input",
+            Lint::UnusedOutput => "unused-output",
+            Lint::DuplicateStream => "duplicate-stream",
+            Lint::DuplicateTrigger => "duplicate-trigger",
+            Lint::ExcessiveOffset => "excessive-offset",
+            Lint::FloatEquality => "float-equality",
+            Lint::OversizedMemory => "oversized-memory",
+        }
+    }
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl FromStr for Lint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.id() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown lint `{s}`, expected one of: {}",
+                    Lint::ALL.iter().join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A finding of the lint pass
+pub struct LintWarning {
+    /// The lint that found the construct
+    pub lint: Lint,
+    /// The stream the finding is about
+    pub stream: StreamReference,
+    /// A description of the finding
+    pub message: String,
+}
+
+impl Display for LintWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.message, self.lint)
+    }
+}
+
+/// Runs all lints on the StreamIR, the ranges of the stream values are used to find constant streams.
+///
+/// The warnings are ordered by lint and stream.
+pub fn lint(ir: &StreamIr, ranges: &Ranges) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut warn = |lint, stream, message| {
+        warnings.push(LintWarning {
+            lint,
+            stream,
+            message,
+        })
+    };
+    let accessed = |sr: &StreamReference| ir.accessed_by.get(sr).is_some_and(|a| !a.is_empty());
+
+    for sr in ir
+        .inputs()
+        .map(StreamReference::In)
+        .filter(|sr| !accessed(sr))
+    {
+        warn(
+            Lint::UnusedInput,
+            sr,
+            format!("input `{}` is never accessed", ir.name(sr)),
+        );
+    }
+
+    for sr in ir
+        .outputs()
+        .filter(|o| !ir.triggers.contains_key(o))
+        .map(StreamReference::Out)
+        .filter(|sr| !accessed(sr))
+    {
+        warn(
+            Lint::UnusedOutput,
+            sr,
+            format!(
+                "output `{}` is neither a trigger nor accessed by another stream",
+                ir.name(sr)
+            ),
+        );
+    }
+
+    let mut evals = Vec::new();
+    collect_evals(&ir.stmt, &mut Vec::new(), &mut evals);
+    let definition = |o: OutputReference| {
+        evals
+            .iter()
+            .filter(|(sr, _, _)| *sr == o)
+            .map(|(_, path, expr)| (path, expr))
+            .collect::<Vec<_>>()
+    };
+    let condition = |o: OutputReference| {
+        evals
+            .iter()
+            .filter(|(sr, _, _)| *sr == o)
+            .map(|(_, path, _)| path)
+            .collect::<Vec<_>>()
+    };
+    let unparameterized = |o: &OutputReference| matches!(o, OutputReference::Unparameterized(_));
+    let streams: Vec<_> = ir
+        .outputs()
+        .sorted()
+        .filter(|o| unparameterized(o) && !ir.triggers.contains_key(o))
+        .collect();
+    for (i, &o) in streams.iter().enumerate() {
+        let sr = o.sr();
+        if let Some(&original) = streams[..i].iter().find(|&&other| {
+            ir.stream_memory(other.sr()).ty == ir.stream_memory(sr).ty
+                && definition(other) == definition(o)
+        }) {
+            warn(
+                Lint::DuplicateStream,
+                sr,
+                format!(
+                    "output `{}` has the same expression and pacing as `{}`",
+                    ir.name(sr),
+                    ir.name(original.sr())
+                ),
+            );
+        }
+    }
+    let triggers: Vec<_> = ir.triggers().sorted().filter(unparameterized).collect();
+    for (i, &o) in triggers.iter().enumerate() {
+        if let Some(&original) = triggers[..i]
+            .iter()
+            .find(|&&other| condition(other) == condition(o))
+        {
+            warn(
+                Lint::DuplicateTrigger,
+                o.sr(),
+                format!(
+                    "trigger `{}` has the same condition as `{}`",
+                    ir.name(o.sr()),
+                    ir.name(original.sr())
+                ),
+            );
+        }
+    }
+
+    for target in ir.streams().sorted() {
+        let value = match ranges.stream(target) {
+            Some(range @ Range::Bool(Some(_))) => range.to_string(),
+            Some(Range::Num(interval)) if interval.lo == interval.hi => interval.lo.to_string(),
+            _ => continue,
+        };
+        for (accessor, kinds) in ir.accessed_by.get(&target).into_iter().flatten() {
+            let Some(offset) = kinds
+                .iter()
+                .filter_map(|(_, kind)| match kind {
+                    StreamAccessKind::Offset(Offset::Past(n)) if *n > 0 => Some(*n),
+                    _ => None,
+                })
+                .max()
+            else {
+                continue;
+            };
+            warn(
+                Lint::ExcessiveOffset,
+                *accessor,
+                format!(
+                    "`{}` accesses `{}` with offset -{offset}, but `{}` always has the value {value}",
+                    ir.name(*accessor),
+                    ir.name(target),
+                    ir.name(target)
+                ),
+            );
+        }
+    }
+
+    let mut comparisons = BTreeSet::new();
+    float_comparisons(&ir.stmt, &mut comparisons);
+    for o in comparisons {
+        warn(
+            Lint::FloatEquality,
+            o.sr(),
+            format!(
+                "`{}` compares floating-point values for equality",
+                ir.name(o.sr())
+            ),
+        );
+    }
+
+    for sr in ir.streams().sorted() {
+        let Some(buffer) = ir.stream_memory(sr).buffer.buffer() else {
+            continue;
+        };
+        let required = ir
+            .accessed_by
+            .get(&sr)
+            .into_iter()
+            .flatten()
+            .flat_map(|(_, kinds)| kinds)
+            .filter_map(|(_, kind)| match kind {
+                StreamAccessKind::Offset(Offset::Past(n)) => Some(*n as usize + 1),
+                StreamAccessKind::Offset(Offset::Future(_)) => None,
+                _ => Some(1),
+            })
+            .max()
+            .unwrap_or(1);
+        if buffer.bound().is_none_or(|bound| bound > required) {
+            warn(
+                Lint::OversizedMemory,
+                sr,
+                format!(
+                    "the memory of `{}` holds {buffer} values, but its accesses require at most {required}",
+                    ir.name(sr)
+                ),
+            );
+        }
+    }
+
+    warnings.sort_by_key(|w| (w.lint, w.stream));
+    warnings
+}
+
+/// Reports the warnings through the handler, where the denied lints are reported as errors.
+///
+/// Returns the number of reported errors.
+pub fn emit(warnings: &[LintWarning], ir: &StreamIr, handler: &Handler, deny: &[Lint]) -> usize {
+    warnings
+        .iter()
+        .map(|warning| warning.diagnostic(ir, deny))
+        .filter(|diagnostic| {
+            diagnostic.emit(handler);
+            diagnostic.is_error()
+        })
+        .count()
+}
+
+type Path<'a> = Vec<(&'a Guard, bool)>;
+
+/// Collects the evaluations of output streams together with the guards of the if-statements around them
+fn collect_evals<'a>(
+    stmt: &'a Stmt,
+    path: &mut Path<'a>,
+    evals: &mut Vec<(OutputReference, Path<'a>, &'a Expr)>,
+) {
+    match stmt {
+        Stmt::Eval { sr, with, .. } => evals.push((*sr, path.clone(), with)),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .for_each(|stmt| collect_evals(stmt, path, evals)),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            path.push((guard, true));
+            collect_evals(cons, path, evals);
+            path.pop();
+            path.push((guard, false));
+            collect_evals(alt, path, evals);
+            path.pop();
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => collect_evals(stmt, path, evals),
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+    }
+}
+
+/// Collects the output streams whose expressions (or the conditions of their evaluation) compare floats for equality
+fn float_comparisons(stmt: &Stmt, streams: &mut BTreeSet<OutputReference>) {
+    match stmt {
+        Stmt::Eval { sr, with, .. } => {
+            if compares_floats(with) {
+                streams.insert(*sr);
+            }
+        }
+        Stmt::Spawn {
+            sr,
+            with: Some(with),
+            ..
+        } => {
+            if with.iter().any(compares_floats) {
+                streams.insert(*sr);
+            }
+        }
+        Stmt::Assign {
+            parameter_expr,
+            sr,
+            stmt,
+        } => {
+            if parameter_expr.iter().any(compares_floats) {
+                streams.extend(sr);
+            }
+            float_comparisons(stmt, streams);
+        }
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .for_each(|stmt| float_comparisons(stmt, streams)),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            if guard_compares_floats(guard) {
+                owners(cons, streams);
+                owners(alt, streams);
+            }
+            float_comparisons(cons, streams);
+            float_comparisons(alt, streams);
+        }
+        Stmt::Iterate { stmt, .. } => float_comparisons(stmt, streams),
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { with: None, .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+    }
+}
+
+/// Collects the output streams that are spawned, evaluated or closed in the statement
+fn owners(stmt: &Stmt, streams: &mut BTreeSet<OutputReference>) {
+    match stmt {
+        Stmt::Eval { sr, .. } | Stmt::Spawn { sr, .. } | Stmt::Close { sr, .. } => {
+            streams.insert(*sr);
+        }
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|stmt| owners(stmt, streams))
+        }
+        Stmt::If(IfStmt { cons, alt, .. }) => {
+            owners(cons, streams);
+            owners(alt, streams);
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => owners(stmt, streams),
+        Stmt::Skip | Stmt::Shift(_) | Stmt::Input(_) | Stmt::Let { .. } => {}
+    }
+}
+
+fn guard_compares_floats(guard: &Guard) -> bool {
+    match guard {
+        Guard::Dynamic(expr) => compares_floats(expr),
+        Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => {
+            guard_compares_floats(lhs) || guard_compares_floats(rhs)
+        }
+        Guard::Stream(_)
+        | Guard::Alive(_)
+        | Guard::GlobalFreq(_)
+        | Guard::LocalFreq(_)
+        | Guard::Constant(_)
+        | Guard::FastAnd(_)
+        | Guard::FastOr(_) => false,
+    }
+}
+
+/// Returns whether the expression contains an (in-)equality comparison of floating-point values
+fn compares_floats(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::BinaryOperation(Operator::Eq | Operator::Ne, lhs, _)
+            if matches!(lhs.ty.inner_ty(), Type::Float32 | Type::Float64) =>
+        {
+            true
+        }
+        ExprKind::Constant(_)
+        | ExprKind::ParameterAccess(_, _)
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => false,
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            compares_floats(lhs) || compares_floats(rhs)
+        }
+        ExprKind::Cast(_, expr)
+        | ExprKind::UnaryOperation(_, expr)
+        | ExprKind::TupleAccess(expr, _)
+        | ExprKind::Some(expr) => compares_floats(expr),
+        ExprKind::Ite(cond, cons, alt) => {
+            compares_floats(cond) || compares_floats(cons) || compares_floats(alt)
+        }
+        ExprKind::IsFresh { parameters, .. }
+        | ExprKind::SyncStreamAccess { parameters, .. }
+        | ExprKind::WindowAccess {
+            parameters,
+            default: None,
+            ..
+        }
+        | ExprKind::FunctionCall(_, parameters)
+        | ExprKind::Tuple(parameters) => parameters.iter().any(compares_floats),
+        ExprKind::HoldStreamAccess {
+            default,
+            parameters,
+            ..
+        }
+        | ExprKind::GetAccess {
+            default,
+            parameters,
+            ..
+        }
+        | ExprKind::WindowAccess {
+            parameters,
+            default: Some(default),
+            ..
+        }
+        | ExprKind::OffsetStreamAccess {
+            default,
+            parameters,
+            ..
+        } => parameters.iter().any(compares_floats) || compares_floats(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::ir::{
+        expressions::{Constant, Expr, ExprKind, Operator},
+        memory::{StreamBuffer, StreamMemory},
+        parse::parse_ir,
+        ranges::Ranges,
+        Offset, Origin, OutputReference, Stmt, StreamAccessKind, StreamIr, StreamReference, Type,
+    };
+
+    use super::{lint, Lint};
+
+    fn out(o: usize) -> StreamReference {
+        StreamReference::Out(OutputReference::Unparameterized(o))
+    }
+
+    /// Keeps the streams i0, o0, o1 and o2 of the parsed program with the given types as single instances
+    fn ir(stmt: &str, types: [Type; 4]) -> StreamIr {
+        let mut ir = parse_ir(stmt);
+        let streams = [StreamReference::In(0), out(0), out(1), out(2)];
+        ir.sr2memory.retain(|sr, _| streams.contains(sr));
+        for (sr, ty) in streams.into_iter().zip(types) {
+            let memory = ir.sr2memory.get_mut(&sr).unwrap();
+            memory.buffer = StreamMemory::Static(StreamBuffer::SingleValue);
+            memory.ty = ty;
+        }
+        ir
+    }
+
+    fn lints(ir: &StreamIr) -> Vec<(Lint, String)> {
+        lint(ir, &Ranges::new(ir, &HashMap::new()))
+            .into_iter()
+            .map(|w| (w.lint, ir.name(w.stream).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn unused_and_duplicates() {
+        let mut ir = ir(
+            "seq { input 0; if @0 then par { eval 0 s0; eval 1 s0; eval 2 o1 } fi }",
+            [Type::Bool, Type::Bool, Type::Bool, Type::Bool],
+        );
+        ir.accessed_by = HashMap::from([
+            (
+                StreamReference::In(0),
+                vec![(out(0), vec![(Origin::EvalWith(0), StreamAccessKind::Sync)])],
+            ),
+            (
+                out(1),
+                vec![(out(2), vec![(Origin::EvalWith(0), StreamAccessKind::Sync)])],
+            ),
+        ]);
+        ir.triggers = HashMap::from([(OutputReference::Unparameterized(2), 0)]);
+        assert_eq!(
+            lints(&ir),
+            vec![
+                (Lint::UnusedOutput, "o0".into()),
+                (Lint::DuplicateStream, "o1".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn offsets_memory_and_floats() {
+        let mut ir = ir(
+            "seq { input 0; eval 1 true }",
+            [Type::Float64, Type::Bool, Type::Bool, Type::Bool],
+        );
+        let float = |kind| Expr {
+            ty: Type::Float64,
+            kind,
+        };
+        let Stmt::Seq(stmts) = &mut ir.stmt else {
+            unreachable!()
+        };
+        stmts.push(Stmt::Eval {
+            sr: OutputReference::Unparameterized(0),
+            with: Expr {
+                ty: Type::Bool,
+                kind: ExprKind::BinaryOperation(
+                    Operator::Eq,
+                    Box::new(float(ExprKind::SyncStreamAccess {
+                        target: StreamReference::In(0),
+                        parameters: Vec::new(),
+                    })),
+                    Box::new(float(ExprKind::Constant(Constant::Float64(1.0)))),
+                ),
+            },
+            idx: 0,
+        });
+        ir.sr2memory.get_mut(&out(0)).unwrap().buffer =
+            StreamMemory::Static(StreamBuffer::Bounded(4));
+        let access = |accessor, kind| vec![(accessor, vec![(Origin::EvalWith(0), kind)])];
+        ir.accessed_by = HashMap::from([
+            (
+                StreamReference::In(0),
+                access(out(0), StreamAccessKind::Sync),
+            ),
+            (
+                out(0),
+                access(out(2), StreamAccessKind::Offset(Offset::Past(2))),
+            ),
+            (
+                out(1),
+                access(out(2), StreamAccessKind::Offset(Offset::Past(1))),
+            ),
+        ]);
+        ir.triggers = HashMap::from([(OutputReference::Unparameterized(2), 0)]);
+        assert_eq!(
+            lints(&ir),
+            vec![
+                (Lint::ExcessiveOffset, "o2".into()),
+                (Lint::FloatEquality, "o0".into()),
+                (Lint::OversizedMemory, "o0".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn ids() {
+        for lint in Lint::ALL {
+            assert_eq!(lint.id().parse(), Ok(lint));
+        }
+        assert!("unused".parse::<Lint>().is_err());
+    }
+}
@@ -12,7 +12,10 @@ use uom::si::{
 
 use expressions::convert_stream_expression;
 use itertools::Itertools;
-use rtlola_frontend::mir::{self, Expression, ExpressionKind, PacingType, Stream};
+use rtlola_frontend::{
+    mir::{self, Expression, ExpressionKind, PacingType, Stream},
+    Span,
+};
 
 use super::schedule::StaticSchedule;
 use super::windows::{InstanceSelection, Window, WindowKind, WindowOperation};
@@ -28,9 +31,16 @@ impl TryFrom<rtlola_frontend::RtLolaMir> for StreamIr {
     type Error = LoweringError;
 
     fn try_from(value: rtlola_frontend::RtLolaMir) -> Result<Self, Self::Error> {
-        let schedule = value
-            .compute_schedule()
-            .map_err(LoweringError::ComputeSchedule)?;
+        let schedule =
+            value
+                .compute_schedule()
+                .map_err(|reason| LoweringError::ComputeSchedule {
+                    reason,
+                    span: value
+                        .time_driven
+                        .first()
+                        .map_or(Span::Unknown, |s| value.output(s.reference).span),
+                })?;
         let rtlola_frontend::RtLolaMir {
             inputs,
             outputs,
@@ -41,6 +51,7 @@ impl TryFrom<rtlola_frontend::RtLolaMir> for StreamIr {
             instance_aggregations,
             triggers,
             global_tags: _,
+            global_tags_span: _,
         } = value;
 
         let mut cur_unparameterized = 0;
@@ -220,7 +231,12 @@ impl From<mir::Layer> for MyLayer {
 
 impl From<mir::Parameter> for Parameter {
     fn from(value: mir::Parameter) -> Self {
-        let mir::Parameter { name, ty, idx: _ } = value;
+        let mir::Parameter {
+            name,
+            ty,
+            idx: _,
+            span: _,
+        } = value;
         Parameter {
             name,
             ty: ty.into(),
@@ -238,6 +254,8 @@ impl StreamIr {
             buffer: StreamMemory::Static(input.memory_bound.into()),
             ty: input.ty.into(),
             name: input.name,
+            delay: 0,
+            span: input.span,
         };
         let stmt = Stmt::seq([Stmt::Shift(sr), Stmt::Input(sr.in_idx())]).filter(Guard::Stream(sr));
         (memory, stmt)
@@ -268,6 +286,8 @@ impl StreamIr {
             reference,
             params,
             tags: _,
+            tags_span: _,
+            span,
         } = output;
         let sr = sr2sr[&reference];
         let parameter = params.into_iter().map(|p| p.into()).collect::<Vec<_>>();
@@ -329,6 +349,8 @@ impl StreamIr {
             buffer,
             ty: ty.into(),
             name,
+            delay: 0,
+            span,
         };
         Ok((
             mem,
@@ -353,21 +375,26 @@ impl StreamIr {
             expression,
             pacing,
             condition,
+            span,
         } = spawn;
         let (guard, lref) = Self::lower_guard(pacing, condition, sr, sr2sr, lref2lfreq)?;
         if lref.is_some() {
-            Err(LoweringError::LocalFreq)
+            Err(LoweringError::LocalFreq { span })
         } else {
             Ok(Stmt::Spawn {
                 sr: sr.out_idx(),
                 with: expression
-                    .map(|Expression { ty, kind }| match kind {
+                    .map(|Expression { ty, kind, span }| match kind {
                         ExpressionKind::Tuple(inner) => inner
                             .into_iter()
                             .map(|expr| convert_stream_expression(expr, None, sr2sr))
                             .collect(),
                         other => Ok(vec![convert_stream_expression(
-                            Expression { ty, kind: other },
+                            Expression {
+                                ty,
+                                kind: other,
+                                span,
+                            },
                             None,
                             sr2sr,
                         )?]),
@@ -411,6 +438,7 @@ impl StreamIr {
                             condition,
                             expression,
                             pacing,
+                            span: _,
                         },
                     )| {
                         let (guard, lref) =
@@ -429,6 +457,7 @@ impl StreamIr {
                         condition,
                         expression,
                         pacing,
+                        span: _,
                     },
                 )| {
                     let (guard, lref) =
@@ -868,19 +897,44 @@ impl InstanceSelection {
 #[derive(Debug, Clone, Error)]
 /// An error that can happen during the lowering of the RtLolaMIR into the StreamIR
 pub enum LoweringError {
-    #[error("specification contains optional expression that is not immediately followed by a default value")]
-    /// The specification contains an optional expression that is not immediately followed by a default value
-    DefaultRequired,
     #[error("specification contains a future access")]
     /// The specification contains a future access
-    FutureAccess,
-    #[error("specification contains the unsupported function {0}")]
+    FutureAccess {
+        /// The span of the access
+        span: Span,
+    },
+    #[error("specification contains the unsupported function {name}")]
     /// The specification contains an unsupported function
-    UnsupportedFunction(String),
+    UnsupportedFunction {
+        /// The name of the function
+        name: String,
+        /// The span of the function call
+        span: Span,
+    },
     #[error("Local frequency in an invalid position")]
     /// The specification contains a local frequency in an invalid position
-    LocalFreq,
-    #[error("Error computing static schedule: {0}")]
+    LocalFreq {
+        /// The span of the spawn clause containing the local frequency
+        span: Span,
+    },
+    #[error("Error computing static schedule: {reason}")]
     /// An error happened when computing the static schedule from the RtLolaMir
-    ComputeSchedule(String),
+    ComputeSchedule {
+        /// The reason reported by the frontend
+        reason: String,
+        /// The span of the first periodic stream
+        span: Span,
+    },
+}
+
+impl LoweringError {
+    /// Returns the span in the specification the error refers to
+    pub fn span(&self) -> Span {
+        match self {
+            LoweringError::FutureAccess { span }
+            | LoweringError::UnsupportedFunction { span, .. }
+            | LoweringError::LocalFreq { span }
+            | LoweringError::ComputeSchedule { span, .. } => *span,
+        }
+    }
 }
@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use rtlola_frontend::mir::{self, StreamAccessKind};
+use rtlola_frontend::{
+    mir::{self, StreamAccessKind},
+    Span,
+};
 
 use crate::ir::{
     expressions::{Constant, Expr, ExprKind, Function, Operator},
@@ -14,13 +17,19 @@ pub(super) fn convert_stream_expression(
     default: Option<Expr>,
     sr2sr: &HashMap<mir::StreamReference, StreamReference>,
 ) -> Result<Expr, LoweringError> {
-    let mir::Expression { ty, kind } = expr;
+    let mir::Expression { ty, kind, span } = expr;
 
-    let lir_ty = match ty {
-        mir::Type::Option(inner) => *inner,
+    // Accesses without a default (except for windows) keep their optional type
+    let optional = default.is_none() && !is_window_access(&kind);
+    let lir_ty: Type = match ty {
+        mir::Type::Option(inner) if !optional => *inner,
         other => other,
     }
     .into();
+    let none = || Expr {
+        ty: lir_ty.clone(),
+        kind: ExprKind::Constant(Constant::None),
+    };
 
     let lir_kind = match kind {
         mir::ExpressionKind::LoadConstant(c) => {
@@ -49,23 +58,23 @@ pub(super) fn convert_stream_expression(
             target,
             parameters,
             offset,
-            default.ok_or(LoweringError::DefaultRequired)?,
+            default.unwrap_or_else(none),
+            span,
             sr2sr,
         ),
         mir::ExpressionKind::StreamAccess {
             target,
             parameters,
             access_kind: StreamAccessKind::Hold,
-        } => translate_hold_access(
-            target,
-            parameters,
-            default.ok_or(LoweringError::DefaultRequired)?,
-            sr2sr,
-        ),
+        } => translate_hold_access(target, parameters, default.unwrap_or_else(none), sr2sr),
 
         mir::ExpressionKind::Default { expr, default } => {
             let default_expr = convert_stream_expression(*default, None, sr2sr)?;
-            return convert_stream_expression(*expr, Some(default_expr), sr2sr);
+            if takes_default(&expr.kind) {
+                return convert_stream_expression(*expr, Some(default_expr), sr2sr);
+            }
+            let expr = convert_stream_expression(*expr, None, sr2sr)?;
+            Ok(ExprKind::UnwrapOr(Box::new(expr), Box::new(default_expr)))
         }
         mir::ExpressionKind::StreamAccess {
             target,
@@ -86,12 +95,7 @@ pub(super) fn convert_stream_expression(
             target,
             parameters,
             access_kind: StreamAccessKind::Get,
-        } => translate_get(
-            target,
-            parameters,
-            default.ok_or(LoweringError::DefaultRequired)?,
-            sr2sr,
-        ),
+        } => translate_get(target, parameters, default.unwrap_or_else(none), sr2sr),
 
         mir::ExpressionKind::Convert { expr: inner_exp } => {
             translate_convert(*inner_exp, &lir_ty, sr2sr)
@@ -118,7 +122,12 @@ pub(super) fn convert_stream_expression(
                 "abs" => Function::Abs,
                 "min" => Function::Min,
                 "max" => Function::Max,
-                name => return Err(LoweringError::UnsupportedFunction(name.into())),
+                name => {
+                    return Err(LoweringError::UnsupportedFunction {
+                        name: name.into(),
+                        span,
+                    })
+                }
             };
             let args = inner_exps
                 .into_iter()
@@ -246,10 +255,11 @@ fn translate_offset_access(
     parameters: Vec<mir::Expression>,
     offset: mir::Offset,
     default: Expr,
+    span: Span,
     sr2sr: &HashMap<mir::StreamReference, StreamReference>,
 ) -> Result<ExprKind, LoweringError> {
     let offset = match offset {
-        mir::Offset::Future(_) => return Err(LoweringError::FutureAccess),
+        mir::Offset::Future(_) => return Err(LoweringError::FutureAccess { span }),
         mir::Offset::Past(i) => i,
     };
 
@@ -280,6 +290,31 @@ fn translate_hold_access(
     })
 }
 
+/// Returns whether the expression is an access that directly takes the default of a surrounding `defaults(to:)`
+fn takes_default(kind: &mir::ExpressionKind) -> bool {
+    matches!(
+        kind,
+        mir::ExpressionKind::StreamAccess {
+            access_kind: StreamAccessKind::Offset(_)
+                | StreamAccessKind::Hold
+                | StreamAccessKind::Get,
+            ..
+        }
+    ) || is_window_access(kind)
+}
+
+fn is_window_access(kind: &mir::ExpressionKind) -> bool {
+    matches!(
+        kind,
+        mir::ExpressionKind::StreamAccess {
+            access_kind: StreamAccessKind::SlidingWindow(_)
+                | StreamAccessKind::DiscreteWindow(_)
+                | StreamAccessKind::InstanceAggregation(_),
+            ..
+        }
+    )
+}
+
 fn translate_window(
     target: mir::StreamReference,
     parameters: Vec<mir::Expression>,
@@ -305,10 +340,12 @@ fn translate_convert(
     convert_to: &Type,
     sr2sr: &HashMap<mir::StreamReference, StreamReference>,
 ) -> Result<ExprKind, LoweringError> {
-    Ok(ExprKind::Cast(
-        convert_to.clone(),
-        Box::new(convert_stream_expression(expr, None, sr2sr)?),
-    ))
+    let expr = Box::new(convert_stream_expression(expr, None, sr2sr)?);
+    match convert_to {
+        // a value is converted into a present optional value
+        Type::Option(inner) if **inner == expr.ty => Ok(ExprKind::Some(expr)),
+        _ => Ok(ExprKind::Cast(convert_to.clone(), expr)),
+    }
 }
 
 fn translate_parameter_access(
@@ -348,3 +385,92 @@ fn translate_get(
             .collect::<Result<Vec<_>, LoweringError>>()?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rtlola_frontend::mir;
+
+    use super::convert_stream_expression;
+    use crate::{
+        ir::{
+            expressions::{Constant, Expr, ExprKind},
+            Stmt, StreamIr, Type,
+        },
+        parse, ParserConfig, Span,
+    };
+
+    /// Returns the expression evaluating the stream with the given name
+    fn eval_expr<'a>(ir: &'a StreamIr, name: &str) -> &'a Expr {
+        fn find<'a>(stmt: &'a Stmt, ir: &StreamIr, name: &str) -> Option<&'a Expr> {
+            match stmt {
+                Stmt::Eval { sr, with, .. } if ir.name(sr.sr()) == name => Some(with),
+                Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                    stmts.iter().find_map(|s| find(s, ir, name))
+                }
+                Stmt::If(stmt) => find(stmt.cons(), ir, name)
+                    .or_else(|| stmt.alt().and_then(|alt| find(alt, ir, name))),
+                Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => find(stmt, ir, name),
+                _ => None,
+            }
+        }
+        find(&ir.stmt, ir, name).unwrap_or_else(|| panic!("stream {name} is not evaluated"))
+    }
+
+    #[test]
+    fn some() {
+        let int = || mir::Type::Int(mir::IntTy::Int64);
+        let expr = mir::Expression {
+            kind: mir::ExpressionKind::Convert {
+                expr: Box::new(mir::Expression {
+                    kind: mir::ExpressionKind::LoadConstant(mir::Constant::Int(1)),
+                    ty: int(),
+                    span: Span::Unknown,
+                }),
+            },
+            ty: mir::Type::Option(Box::new(int())),
+            span: Span::Unknown,
+        };
+        let expr = convert_stream_expression(expr, None, &HashMap::new()).unwrap();
+        assert_eq!(expr.ty, Type::Option(Box::new(Type::Int(64))));
+        let ExprKind::Some(inner) = expr.kind else {
+            panic!("expected a present optional value, found {:?}", expr.kind)
+        };
+        assert_eq!(inner.kind, ExprKind::Constant(Constant::Int(1, 64)));
+    }
+
+    #[test]
+    fn optional_conditional() {
+        let ir = parse(&ParserConfig::for_string(
+            "input a : Int64
+            input c : Bool
+            output value := (if c then a.offset(by: -1) else a.offset(by: -2)).defaults(to: 0)"
+                .into(),
+        ))
+        .unwrap();
+        let optional = Type::Option(Box::new(Type::Int(64)));
+
+        // the default applies to the whole conditional, which keeps its optional type
+        let value = eval_expr(&ir, "value");
+        assert_eq!(value.ty, Type::Int(64));
+        let ExprKind::UnwrapOr(inner, default) = &value.kind else {
+            panic!("expected an unwrap with a default, found {:?}", value.kind)
+        };
+        assert_eq!(inner.ty, optional);
+        assert_eq!(default.kind, ExprKind::Constant(Constant::Int(0, 64)));
+
+        // the offset accesses in the branches default to an absent value
+        let ExprKind::Ite(_, cons, alt) = &inner.kind else {
+            panic!("expected a conditional, found {:?}", inner.kind)
+        };
+        for branch in [cons, alt] {
+            assert_eq!(branch.ty, optional);
+            let ExprKind::OffsetStreamAccess { default, .. } = &branch.kind else {
+                panic!("expected an offset access, found {:?}", branch.kind)
+            };
+            assert_eq!(default.kind, ExprKind::Constant(Constant::None));
+            assert_eq!(default.ty, optional);
+        }
+    }
+}
@@ -35,22 +35,26 @@ impl LivetimeEquivalences {
                 expression: i_spawn_expr,
                 pacing: i_spawn_pacing,
                 condition: i_spawn_condition,
+                span: _,
             } = &output.spawn;
             let Close {
                 condition: i_close_condition,
                 pacing: i_close_pacing,
                 has_self_reference: _,
+                span: _,
             } = &output.close;
             for (j, other_output) in outputs.iter().enumerate().take(i) {
                 let Spawn {
                     expression: j_spawn_expr,
                     pacing: j_spawn_pacing,
                     condition: j_spawn_condition,
+                    span: _,
                 } = &other_output.spawn;
                 let Close {
                     condition: j_close_condition,
                     pacing: j_close_pacing,
                     has_self_reference: _,
+                    span: _,
                 } = &other_output.close;
                 if compare_expr_option(j_spawn_expr.as_ref(), i_spawn_expr.as_ref())
                     && compare_expr_option(j_spawn_condition.as_ref(), i_spawn_condition.as_ref())
@@ -2,9 +2,12 @@
 
 use std::ops::Add;
 
+use crate::Span;
+
 use super::Type;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// All memory information of a stream
 pub struct Memory {
     /// The buffer of the stream
@@ -13,9 +16,20 @@ pub struct Memory {
     pub ty: Type,
     /// The name of the stream
     pub name: String,
+    /// The number of activations the evaluation of the stream lags behind
+    ///
+    /// The n-th evaluation of a delayed stream computes its value for its (n - delay)-th activation.
+    /// Backends skip the first `delay` evaluations and attribute each value to the time of the activation it refers to.
+    /// The lowering never delays a stream, as the frontend rejects the future offsets that would require it.
+    pub delay: u32,
+    /// The span of the declaration of the stream in the specification
+    ///
+    /// The span is unknown if the StreamIR is not lowered from a specification, e.g., if it is parsed from its textual representation.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The kind of a stream's memory
 pub enum StreamMemory {
     /// No memory is needed for this stream
@@ -41,6 +55,7 @@ pub enum StreamMemory {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The kind of buffer for a stream's instance
 pub enum StreamBuffer {
     /// Only the newest value needs to be stored
@@ -63,6 +78,7 @@ impl StreamBuffer {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// A single parameter of a parameterized stream
 pub struct Parameter {
     /// The name of the parameter
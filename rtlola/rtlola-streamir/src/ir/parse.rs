@@ -9,6 +9,8 @@ use winnow::{
     Parser,
 };
 
+use crate::Span;
+
 use super::{
     expressions::{Constant, Expr, ExprKind, Operator},
     memory::{Memory, Parameter, StreamBuffer, StreamMemory},
@@ -53,6 +55,10 @@ fn parse_stmt_prime(input: &mut &str) -> winnow::ModalResult<Stmt> {
             with: expr,
             idx: 0
         }),
+        preceded("let", cut_err(seq!(_: multispace1, dec_uint, _: multispace1, parse_expr))).context(StrContext::Label("let")).map(|(temp, expr): (u64,_)| Stmt::Let {
+            temp: temp as usize,
+            expr
+        }),
         preceded("iterate", cut_err(seq!(_: multispace1, dec_int, _: multispace1, parse_stmt_prime))).context(StrContext::Label("iterate")).map(|(i,stmt): (i32,_)| Stmt::Iterate {
             sr: vec![OutputReference::Unparameterized(i as usize)],
             stmt: Box::new(stmt)
@@ -156,13 +162,24 @@ fn parse_expr_term(input: &mut &str) -> winnow::ModalResult<Expr> {
             ty: Type::Bool,
             kind: ExprKind::ParameterAccess(StreamReference::In(0), p as usize),
         }),
-        seq!(_: alt(("s", "o")), dec_uint).map(|(p,): (u64,)| Expr {
+        seq!(_: "s", dec_uint).map(|(p,): (u64,)| Expr {
             ty: Type::Bool,
             kind: ExprKind::SyncStreamAccess {
                 target: StreamReference::In(p as usize),
                 parameters: vec![],
             },
         }),
+        seq!(_: "o", dec_uint).map(|(p,): (u64,)| Expr {
+            ty: Type::Bool,
+            kind: ExprKind::SyncStreamAccess {
+                target: StreamReference::Out(OutputReference::Unparameterized(p as usize)),
+                parameters: vec![],
+            },
+        }),
+        seq!(_: "t", dec_uint).map(|(t,): (u64,)| Expr {
+            ty: Type::Bool,
+            kind: ExprKind::Temporary(t as usize),
+        }),
         delimited("(", parse_expr, ")"),
     )))
     .parse_next(input)
@@ -219,6 +236,8 @@ pub(crate) fn parse_ir(s: &str) -> StreamIr {
                         },
                         ty: Type::Bool,
                         name,
+                        delay: 0,
+                        span: Span::Unknown,
                     },
                 )
             })
@@ -249,3 +268,65 @@ pub(crate) fn parse_ir(s: &str) -> StreamIr {
         accesses: HashMap::new(),
     }
 }
+
+/// Generates random statements in the syntax of [parse_ir] with a small linear congruential generator,
+/// such that the generated IRs are reproducible
+pub(crate) struct Random(pub(crate) u64);
+
+impl Random {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((self.0 >> 33) as usize) % bound
+    }
+
+    fn expr(&mut self) -> String {
+        let (a, b) = (self.next(4), self.next(4));
+        match self.next(5) {
+            0 => format!("s{a}"),
+            1 => format!("o{a}"),
+            2 => format!("s{a} && o{b}"),
+            3 => format!("(p{} == s{b})", a % 3),
+            _ => "true".into(),
+        }
+    }
+
+    fn guard(&mut self, depth: usize) -> String {
+        let a = self.next(4);
+        match self.next(if depth == 0 { 6 } else { 8 }) {
+            0 | 1 => format!("@{a}"),
+            2 => format!("?{a}"),
+            3 => format!("Global({})", a + 1),
+            4 => format!("Local({a})"),
+            5 => format!("Expr({})", self.expr()),
+            6 => format!("({} && {})", self.guard(depth - 1), self.guard(depth - 1)),
+            _ => format!("({} || {})", self.guard(depth - 1), self.guard(depth - 1)),
+        }
+    }
+
+    pub(crate) fn stmt(&mut self, depth: usize) -> String {
+        let a = self.next(4);
+        match self.next(if depth == 0 { 2 } else { 8 }) {
+            0 => format!("input {a}"),
+            1 => format!("eval {a} {}", self.expr()),
+            2 | 3 => {
+                let inner = (0..self.next(3) + 1)
+                    .map(|_| self.stmt(depth - 1))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                let kind = if self.next(2) == 0 { "seq" } else { "par" };
+                format!("{kind} {{ {inner} }}")
+            }
+            4 | 5 => format!("if {} then {} fi", self.guard(2), self.stmt(depth - 1)),
+            6 => format!(
+                "if {} then {} else {} fi",
+                self.guard(2),
+                self.stmt(depth - 1),
+                self.stmt(depth - 1)
+            ),
+            _ => format!("iterate {a} {}", self.stmt(depth - 1)),
+        }
+    }
+}
@@ -15,8 +15,8 @@ use crate::formatter::{
 };
 
 use super::{
-    Expr, Guard, LocalFreq, LocalFreqRef, OutputReference, Stmt, StreamIr, StreamReference, Type,
-    WindowReference,
+    Expr, Guard, LocalFreq, LocalFreqRef, OutputReference, Stmt, StreamIr, StreamReference,
+    TempRef, Type, WindowReference,
 };
 
 /// A [StreamIrFormatter] trait implementation to display the StreamIR in a nicely formatted string
@@ -61,6 +61,25 @@ impl DebugFormatter {
             lref2lfreq,
         }
     }
+
+    fn printer(&self) -> StreamIrPrinter<'_> {
+        StreamIrPrinter::new(
+            &self.sr2name,
+            &self.sr2parameter,
+            &self.window_targets,
+            &self.lref2lfreq,
+        )
+    }
+
+    /// Formats a single guard
+    pub(crate) fn format_guard(&self, guard: Guard) -> String {
+        self.printer().guard(guard)
+    }
+
+    /// Formats a single expression
+    pub(crate) fn format_expr(&self, expr: Expr) -> String {
+        self.printer().expr(expr)
+    }
 }
 
 impl StreamIrFormatter for DebugFormatter {
@@ -71,13 +90,7 @@ impl StreamIrFormatter for DebugFormatter {
     }
 
     fn format(self, ir: StreamIr) -> Self::Return {
-        StreamIrPrinter::new(
-            &self.sr2name,
-            &self.sr2parameter,
-            &self.window_targets,
-            &self.lref2lfreq,
-        )
-        .stmt(ir.stmt)
+        self.printer().stmt(ir.stmt)
     }
 }
 
@@ -220,6 +233,10 @@ impl DefaultStmtFormatter for StreamIrPrinter<'_> {
         ))
     }
 
+    fn r#let(&self, temp: TempRef, expr: Expr) -> String {
+        self.whitespace(&format!("let tmp{temp} = {}", self.expr(expr)))
+    }
+
     fn seq(&self, inner: Vec<Stmt>) -> String {
         let inner_strings = inner
             .into_iter()
@@ -429,6 +446,10 @@ impl DefaultExprFormatter for StreamIrPrinter<'_> {
             self.expr(expr)
         )
     }
+
+    fn temporary(&self, temp: TempRef, _ty: &Type) -> String {
+        format!("tmp{temp}")
+    }
 }
 
 impl DefaultFunctionFormatter for StreamIrPrinter<'_> {}
@@ -0,0 +1,961 @@
+//! The interval analysis of the values of streams.
+//!
+//! The analysis propagates the ranges of the inputs (given by their type or an annotation) through all operators,
+//! functions, stream accesses and windows by abstract interpretation of the [Stmt] tree.
+//! Recursive streams are handled by a fixed-point iteration that widens the ranges that keep growing.
+//! The resulting [Ranges] can be used to evaluate expressions and guards, e.g., by the [RangeGuards](crate::rewrite_rules::RangeGuards)
+//! rewriting rule, and to find triggers that can never or always fire.
+
+use std::{collections::HashMap, f64::consts::FRAC_PI_2, fmt::Display, str::FromStr};
+
+use itertools::Itertools;
+
+use crate::Span;
+
+use super::{
+    expressions::{Constant, Expr, ExprKind, Function, Operator},
+    windows::{Window, WindowOperation},
+    Guard, IfStmt, InputReference, OutputReference, Stmt, StreamIr, StreamReference, TempRef, Type,
+    WindowReference,
+};
+
+/// The number of iterations after which growing ranges are widened
+const WIDENING_DELAY: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// A closed interval of numbers, the bounds can be infinite
+pub struct Interval {
+    /// The lower bound
+    pub lo: f64,
+    /// The upper bound
+    pub hi: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The range of values of an expression
+pub enum Range {
+    /// Nothing is known about the value
+    Unknown,
+    /// A boolean that is known to be the given value, or unknown if None
+    Bool(Option<bool>),
+    /// A number in the given interval
+    Num(Interval),
+    /// A tuple of values with the given ranges
+    Tuple(Vec<Range>),
+}
+
+impl Interval {
+    /// The interval containing all numbers
+    pub const ALL: Interval = Interval {
+        lo: f64::NEG_INFINITY,
+        hi: f64::INFINITY,
+    };
+
+    /// Creates the interval between the two bounds, or [Interval::ALL] if the bounds are invalid
+    pub fn new(lo: f64, hi: f64) -> Self {
+        if lo.is_nan() || hi.is_nan() || lo > hi {
+            Self::ALL
+        } else {
+            Self { lo, hi }
+        }
+    }
+
+    /// Creates the interval only containing the given number
+    pub fn point(v: f64) -> Self {
+        Self::new(v, v)
+    }
+
+    /// Returns the smallest interval containing both intervals
+    pub fn join(self, other: Self) -> Self {
+        Self::new(self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    /// Returns whether the interval contains the other interval
+    pub fn contains(self, other: Self) -> bool {
+        self.lo <= other.lo && other.hi <= self.hi
+    }
+
+    fn from_corners(corners: [f64; 4]) -> Self {
+        if corners.iter().any(|c| c.is_nan()) {
+            return Self::ALL;
+        }
+        Self::new(
+            corners.into_iter().fold(f64::INFINITY, f64::min),
+            corners.into_iter().fold(f64::NEG_INFINITY, f64::max),
+        )
+    }
+
+    fn add(self, other: Self) -> Self {
+        Self::new(self.lo + other.lo, self.hi + other.hi)
+    }
+
+    fn neg(self) -> Self {
+        Self::new(-self.hi, -self.lo)
+    }
+
+    fn mul(self, other: Self) -> Self {
+        // 0 * inf is 0, as an infinite bound is never reached
+        let mul = |a: f64, b: f64| if a == 0.0 || b == 0.0 { 0.0 } else { a * b };
+        Self::from_corners([
+            mul(self.lo, other.lo),
+            mul(self.lo, other.hi),
+            mul(self.hi, other.lo),
+            mul(self.hi, other.hi),
+        ])
+    }
+
+    fn div(self, other: Self) -> Self {
+        if other.lo <= 0.0 && other.hi >= 0.0 {
+            return Self::ALL;
+        }
+        Self::from_corners([
+            self.lo / other.lo,
+            self.lo / other.hi,
+            self.hi / other.lo,
+            self.hi / other.hi,
+        ])
+    }
+
+    fn rem(self, other: Self) -> Self {
+        let bound = other.lo.abs().max(other.hi.abs());
+        if self.lo >= 0.0 {
+            Self::new(0.0, self.hi.min(bound))
+        } else if self.hi <= 0.0 {
+            Self::new(self.lo.max(-bound), 0.0)
+        } else {
+            Self::new(self.lo.max(-bound), self.hi.min(bound))
+        }
+    }
+
+    fn pow(self, other: Self) -> Self {
+        if self.lo < 0.0 {
+            return Self::ALL;
+        }
+        Self::from_corners([
+            self.lo.powf(other.lo),
+            self.lo.powf(other.hi),
+            self.hi.powf(other.lo),
+            self.hi.powf(other.hi),
+        ])
+    }
+
+    fn abs(self) -> Self {
+        let (lo, hi) = (self.lo.abs(), self.hi.abs());
+        if self.lo <= 0.0 && self.hi >= 0.0 {
+            Self::new(0.0, lo.max(hi))
+        } else {
+            Self::new(lo.min(hi), lo.max(hi))
+        }
+    }
+
+    fn monotone(self, f: impl Fn(f64) -> f64) -> Self {
+        Self::new(f(self.lo), f(self.hi))
+    }
+
+    fn trunc(self) -> Self {
+        Self::new(self.lo.ceil(), self.hi.floor())
+    }
+
+    /// Widens the bounds that grew compared to the previous interval to infinity
+    fn widen(self, previous: Self) -> Self {
+        Self::new(
+            if self.lo < previous.lo {
+                f64::NEG_INFINITY
+            } else {
+                self.lo
+            },
+            if self.hi > previous.hi {
+                f64::INFINITY
+            } else {
+                self.hi
+            },
+        )
+    }
+
+    /// Compares the intervals and returns whether all numbers of self are less (or equal) than all numbers of other
+    fn less(self, other: Self, or_equal: bool) -> Option<bool> {
+        let (always, never) = if or_equal {
+            (self.hi <= other.lo, self.lo > other.hi)
+        } else {
+            (self.hi < other.lo, self.lo >= other.hi)
+        };
+        match (always, never) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    /// Parses an interval of the form `lo..hi`, where a missing bound is infinite
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lo, hi) = s
+            .split_once("..")
+            .ok_or_else(|| format!("expected an interval `lo..hi`, found `{s}`"))?;
+        let bound = |b: &str, default: f64| {
+            let b = b.trim();
+            if b.is_empty() {
+                Ok(default)
+            } else {
+                b.parse::<f64>()
+                    .map_err(|e| format!("invalid bound `{b}`: {e}"))
+            }
+        };
+        let (lo, hi) = (bound(lo, f64::NEG_INFINITY)?, bound(hi, f64::INFINITY)?);
+        if lo > hi {
+            return Err(format!(
+                "the lower bound {lo} is greater than the upper bound {hi}"
+            ));
+        }
+        Ok(Self::new(lo, hi))
+    }
+}
+
+/// Parses an annotation `name=lo..hi` of the range of the input stream `name` of the StreamIR
+pub fn parse_annotation(
+    ir: &StreamIr,
+    annotation: &str,
+) -> Result<(InputReference, Interval), String> {
+    let (name, interval) = annotation
+        .split_once('=')
+        .ok_or_else(|| format!("expected an annotation `name=lo..hi`, found `{annotation}`"))?;
+    let name = name.trim();
+    match ir.stream_by_name(name) {
+        Some(StreamReference::In(i)) => Ok((i, interval.parse()?)),
+        Some(StreamReference::Out(_)) => Err(format!("stream `{name}` is not an input stream")),
+        None => Err(format!(
+            "stream `{name}` does not exist in the specification"
+        )),
+    }
+}
+
+impl Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[{}, {}]", self.lo, self.hi)
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Range::Unknown | Range::Bool(None) => write!(f, "unknown"),
+            Range::Bool(Some(b)) => write!(f, "{b}"),
+            Range::Num(i) => write!(f, "{i}"),
+            Range::Tuple(inner) => write!(f, "({})", inner.iter().join(", ")),
+        }
+    }
+}
+
+impl Range {
+    /// Returns the range of all values of the given type
+    pub fn of_type(ty: &Type) -> Self {
+        match ty {
+            Type::UInt(bits) => Range::Num(Interval::new(0.0, 2f64.powi(*bits as i32) - 1.0)),
+            Type::Int(bits) => {
+                let bound = 2f64.powi(*bits as i32 - 1);
+                Range::Num(Interval::new(-bound, bound - 1.0))
+            }
+            Type::Float32 | Type::Float64 | Type::Fixed(_) | Type::UFixed(_) => {
+                Range::Num(Interval::ALL)
+            }
+            Type::Bool => Range::Bool(None),
+            Type::Tuple(inner) => Range::Tuple(inner.iter().map(Self::of_type).collect()),
+            Type::String | Type::Bytes | Type::Option(_) => Range::Unknown,
+        }
+    }
+
+    /// Returns the range containing the values of both ranges
+    pub fn join(&self, other: &Self) -> Self {
+        match (self, other) {
+            (Range::Bool(a), Range::Bool(b)) => Range::Bool((a == b).then_some(*a).flatten()),
+            (Range::Num(a), Range::Num(b)) => Range::Num(a.join(*b)),
+            (Range::Tuple(a), Range::Tuple(b)) if a.len() == b.len() => {
+                Range::Tuple(a.iter().zip(b).map(|(a, b)| a.join(b)).collect())
+            }
+            _ => Range::Unknown,
+        }
+    }
+
+    /// Returns the boolean value if it is known
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Range::Bool(b) => *b,
+            _ => None,
+        }
+    }
+
+    /// Returns the interval of a number
+    pub fn as_interval(&self) -> Option<Interval> {
+        match self {
+            Range::Num(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// Restricts the range to the values of the given type, i.e., integers that can overflow fall back to the whole type
+    fn fit(self, ty: &Type) -> Self {
+        match (self, Range::of_type(ty)) {
+            (Range::Num(i), Range::Num(bounds)) if matches!(ty, Type::Int(_) | Type::UInt(_)) => {
+                let i = i.trunc();
+                Range::Num(if bounds.contains(i) { i } else { bounds })
+            }
+            (Range::Num(i), Range::Num(_)) => Range::Num(i),
+            (Range::Bool(b), Range::Bool(_)) => Range::Bool(b),
+            (Range::Tuple(inner), Range::Tuple(_)) => match ty {
+                Type::Tuple(tys) if tys.len() == inner.len() => Range::Tuple(
+                    inner
+                        .into_iter()
+                        .zip(tys)
+                        .map(|(r, ty)| r.fit(ty))
+                        .collect(),
+                ),
+                _ => Range::of_type(ty),
+            },
+            (_, of_type) => of_type,
+        }
+    }
+
+    fn widen(self, previous: &Self, ty: &Type) -> Self {
+        match (self, previous) {
+            (Range::Num(i), Range::Num(p)) => Range::Num(i.widen(*p)).fit(ty),
+            (Range::Tuple(inner), Range::Tuple(p)) => match ty {
+                Type::Tuple(tys) if tys.len() == inner.len() && p.len() == inner.len() => {
+                    Range::Tuple(
+                        inner
+                            .into_iter()
+                            .zip(p)
+                            .zip(tys)
+                            .map(|((r, p), ty)| r.widen(p, ty))
+                            .collect(),
+                    )
+                }
+                _ => Range::of_type(ty),
+            },
+            (range, _) => range,
+        }
+    }
+}
+
+impl From<&Constant> for Range {
+    fn from(c: &Constant) -> Self {
+        match c {
+            Constant::Str(_) | Constant::None => Range::Unknown,
+            Constant::Bool(b) => Range::Bool(Some(*b)),
+            Constant::UInt(v, _) => Range::Num(Interval::point(*v as f64)),
+            Constant::Int(v, _) => Range::Num(Interval::point(*v as f64)),
+            Constant::Float32(v) | Constant::Float64(v) => Range::Num(Interval::point(*v)),
+            Constant::Tuple(inner) => Range::Tuple(inner.iter().map(Range::from).collect()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The ranges of the values of all streams and temporaries of a StreamIR
+pub struct Ranges {
+    streams: HashMap<StreamReference, Range>,
+    temps: HashMap<TempRef, Range>,
+    windows: HashMap<WindowReference, Window>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Whether a trigger can fire
+pub enum TriggerReachability {
+    /// The condition of the trigger is never satisfied
+    Never,
+    /// The condition of the trigger is always satisfied, i.e., the trigger fires whenever it is evaluated
+    Always,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A trigger that can never or always fire
+pub struct TriggerWarning {
+    /// The output stream of the trigger
+    pub trigger: OutputReference,
+    /// The name of the trigger
+    pub name: String,
+    /// Whether the trigger never or always fires
+    pub reachability: TriggerReachability,
+    /// The span of the declaration of the trigger
+    pub span: Span,
+}
+
+impl Display for TriggerWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reachability {
+            TriggerReachability::Never => write!(f, "trigger `{}` can never fire", self.name),
+            TriggerReachability::Always => {
+                write!(f, "trigger `{}` fires whenever it is evaluated", self.name)
+            }
+        }
+    }
+}
+
+impl Ranges {
+    /// Computes the ranges of all streams of the StreamIR, given the ranges of (some of) the inputs.
+    pub fn new(ir: &StreamIr, inputs: &HashMap<InputReference, Interval>) -> Self {
+        let streams = ir
+            .inputs()
+            .map(|i| {
+                let sr = StreamReference::In(i);
+                let ty = &ir.stream_memory(sr).ty;
+                let range = match inputs.get(&i) {
+                    Some(interval) => Range::Num(*interval).fit(ty),
+                    None => Range::of_type(ty),
+                };
+                (sr, range)
+            })
+            .collect();
+        let mut ranges = Ranges {
+            streams,
+            temps: HashMap::new(),
+            windows: ir.wref2window.clone(),
+        };
+
+        for iteration in 0.. {
+            let mut evals = HashMap::new();
+            ranges.temps.clear();
+            ranges.walk(&ir.stmt, &mut evals);
+            let mut changed = false;
+            for (sr, new) in evals {
+                let ty = &ir.stream_memory(sr).ty;
+                let range = match ranges.streams.get(&sr) {
+                    None => new,
+                    Some(old) if iteration >= WIDENING_DELAY => old.join(&new).widen(old, ty),
+                    Some(old) => old.join(&new),
+                };
+                if ranges.streams.get(&sr) != Some(&range) {
+                    changed = true;
+                    ranges.streams.insert(sr, range);
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        ranges
+    }
+
+    /// Returns the range of the values of the given stream
+    pub fn stream(&self, sr: StreamReference) -> Option<&Range> {
+        self.streams.get(&sr)
+    }
+
+    /// Returns the range of the values of the expression
+    pub fn expr(&self, expr: &Expr) -> Range {
+        let stream = |sr: &StreamReference| {
+            self.streams
+                .get(sr)
+                .cloned()
+                .unwrap_or_else(|| Range::of_type(&expr.ty))
+        };
+        let with_default = |sr: &StreamReference, default: &Expr| match self.streams.get(sr) {
+            Some(range) => range.join(&self.expr(default)),
+            None => self.expr(default),
+        };
+        let range = match &expr.kind {
+            ExprKind::Constant(c) => c.into(),
+            ExprKind::BinaryOperation(op, lhs, rhs) => {
+                binary(*op, self.expr(lhs), self.expr(rhs), &expr.ty)
+            }
+            ExprKind::UnaryOperation(op, inner) => match (op, self.expr(inner)) {
+                (Operator::Not, Range::Bool(b)) => Range::Bool(b.map(|b| !b)),
+                (Operator::Neg, Range::Num(i)) => Range::Num(i.neg()),
+                _ => Range::Unknown,
+            },
+            ExprKind::Ite(cond, cons, alt) => match self.expr(cond).as_bool() {
+                Some(true) => self.expr(cons),
+                Some(false) => self.expr(alt),
+                None => self.expr(cons).join(&self.expr(alt)),
+            },
+            ExprKind::SyncStreamAccess { target, .. } => stream(target),
+            ExprKind::OffsetStreamAccess {
+                target, default, ..
+            }
+            | ExprKind::HoldStreamAccess {
+                target, default, ..
+            }
+            | ExprKind::GetAccess {
+                target, default, ..
+            } => with_default(target, default),
+            ExprKind::IsFresh { .. } => Range::Bool(None),
+            ExprKind::WindowAccess {
+                window, default, ..
+            } => {
+                let range = self.window(*window);
+                match default {
+                    Some(default) => range.join(&self.expr(default)),
+                    None => range,
+                }
+            }
+            ExprKind::Cast(_, inner) => self.expr(inner),
+            ExprKind::ParameterAccess(_, _) | ExprKind::LambdaParameterAccess(_, _) => {
+                Range::Unknown
+            }
+            ExprKind::FunctionCall(f, args) => {
+                let args: Vec<_> = args.iter().map(|a| self.expr(a)).collect();
+                function(*f, &args)
+            }
+            ExprKind::Tuple(inner) => Range::Tuple(inner.iter().map(|e| self.expr(e)).collect()),
+            ExprKind::TupleAccess(inner, idx) => match self.expr(inner) {
+                Range::Tuple(mut inner) if *idx < inner.len() => inner.swap_remove(*idx),
+                _ => Range::Unknown,
+            },
+            ExprKind::Temporary(t) => self.temps.get(t).cloned().unwrap_or(Range::Unknown),
+            // the ranges of optional values are not tracked
+            ExprKind::Some(_) => Range::Unknown,
+            ExprKind::UnwrapOr(expr, default) => self.expr(expr).join(&self.expr(default)),
+        };
+        range.fit(&expr.ty)
+    }
+
+    /// Returns the value of the guard if it is known from the ranges
+    pub fn guard(&self, guard: &Guard) -> Option<bool> {
+        self.guard_with(guard, None)
+    }
+
+    /// Returns the value of the guard, where atomic guards that are not expressions have the given value
+    fn guard_with(&self, guard: &Guard, atoms: Option<bool>) -> Option<bool> {
+        match guard {
+            Guard::Constant(b) => Some(*b),
+            Guard::Dynamic(expr) => self.expr(expr).as_bool(),
+            Guard::And { lhs, rhs } => {
+                match (self.guard_with(lhs, atoms), self.guard_with(rhs, atoms)) {
+                    (Some(false), _) | (_, Some(false)) => Some(false),
+                    (Some(true), Some(true)) => Some(true),
+                    _ => None,
+                }
+            }
+            Guard::Or { lhs, rhs } => {
+                match (self.guard_with(lhs, atoms), self.guard_with(rhs, atoms)) {
+                    (Some(true), _) | (_, Some(true)) => Some(true),
+                    (Some(false), Some(false)) => Some(false),
+                    _ => None,
+                }
+            }
+            Guard::Stream(_)
+            | Guard::Alive(_)
+            | Guard::GlobalFreq(_)
+            | Guard::LocalFreq(_)
+            | Guard::FastAnd(_)
+            | Guard::FastOr(_) => atoms,
+        }
+    }
+
+    /// Returns the triggers of the StreamIR whose condition can never or is always satisfied.
+    pub fn trigger_warnings(&self, ir: &StreamIr) -> Vec<TriggerWarning> {
+        let mut evals: HashMap<OutputReference, Vec<Vec<(&Guard, bool)>>> = HashMap::new();
+        collect_paths(&ir.stmt, &mut Vec::new(), &mut evals);
+        ir.triggers()
+            .sorted()
+            .filter_map(|trigger| {
+                let paths = evals.get(&trigger)?;
+                // the reachable evaluations with the conditions (guards containing an expression) of their path
+                let reachable: Vec<Vec<_>> = paths
+                    .iter()
+                    .filter(|path| {
+                        path.iter()
+                            .all(|(guard, positive)| self.guard(guard) != Some(!positive))
+                    })
+                    .map(|path| {
+                        path.iter()
+                            .filter(|(guard, _)| guard.contains_expr())
+                            .collect()
+                    })
+                    .collect();
+                let reachability = if reachable.is_empty() {
+                    TriggerReachability::Never
+                } else if reachable.iter().all(|conditions| {
+                    !conditions.is_empty()
+                        && conditions.iter().all(|(guard, positive)| {
+                            *positive && self.guard_with(guard, Some(true)) == Some(true)
+                        })
+                }) {
+                    TriggerReachability::Always
+                } else {
+                    return None;
+                };
+                Some(TriggerWarning {
+                    trigger,
+                    name: ir.name(trigger.sr()).to_string(),
+                    reachability,
+                    span: ir.stream_memory(trigger.sr()).span,
+                })
+            })
+            .collect()
+    }
+
+    fn window(&self, wref: WindowReference) -> Range {
+        let Some(window) = self.windows.get(&wref) else {
+            return Range::Unknown;
+        };
+        let target = self
+            .streams
+            .get(&window.target)
+            .and_then(Range::as_interval)
+            .unwrap_or(Interval::ALL);
+        let non_negative = Interval::new(0.0, f64::INFINITY);
+        match window.op {
+            WindowOperation::Count => Range::Num(non_negative),
+            WindowOperation::Sum if target.lo >= 0.0 => Range::Num(non_negative),
+            WindowOperation::Sum if target.hi <= 0.0 => {
+                Range::Num(Interval::new(f64::NEG_INFINITY, 0.0))
+            }
+            WindowOperation::Min
+            | WindowOperation::Max
+            | WindowOperation::Last
+            | WindowOperation::Average
+            | WindowOperation::NthPercentile(_) => Range::Num(target),
+            WindowOperation::Variance | WindowOperation::StandardDeviation => {
+                Range::Num(non_negative)
+            }
+            WindowOperation::Conjunction | WindowOperation::Disjunction => {
+                match self.streams.get(&window.target) {
+                    Some(Range::Bool(b)) => Range::Bool(*b),
+                    _ => Range::Bool(None),
+                }
+            }
+            WindowOperation::Sum
+            | WindowOperation::Product
+            | WindowOperation::Integral
+            | WindowOperation::Covariance => Range::Num(Interval::ALL),
+        }
+        .fit(window.ty.inner_ty())
+    }
+
+    /// Abstractly executes the statement and collects the ranges of the evaluated streams.
+    fn walk(&mut self, stmt: &Stmt, evals: &mut HashMap<StreamReference, Range>) {
+        match stmt {
+            Stmt::Skip
+            | Stmt::Shift(_)
+            | Stmt::Input(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Close { .. } => {}
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                stmts.iter().for_each(|stmt| self.walk(stmt, evals))
+            }
+            Stmt::Eval { sr, with, .. } => {
+                let range = self.expr(with);
+                let range = match evals.remove(&sr.sr()) {
+                    Some(old) => old.join(&range),
+                    None => range,
+                };
+                evals.insert(sr.sr(), range);
+            }
+            Stmt::Let { temp, expr } => {
+                let range = self.expr(expr);
+                let range = match self.temps.get(temp) {
+                    Some(old) => old.join(&range),
+                    None => range,
+                };
+                self.temps.insert(*temp, range);
+            }
+            Stmt::If(IfStmt { guard, cons, alt }) => match self.guard(guard) {
+                Some(true) => self.walk(cons, evals),
+                Some(false) => self.walk(alt, evals),
+                None => {
+                    self.walk(cons, evals);
+                    self.walk(alt, evals);
+                }
+            },
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => self.walk(stmt, evals),
+        }
+    }
+}
+
+impl Guard {
+    fn contains_expr(&self) -> bool {
+        match self {
+            Guard::Dynamic(_) | Guard::Constant(_) => true,
+            Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => {
+                lhs.contains_expr() || rhs.contains_expr()
+            }
+            Guard::Stream(_)
+            | Guard::Alive(_)
+            | Guard::GlobalFreq(_)
+            | Guard::LocalFreq(_)
+            | Guard::FastAnd(_)
+            | Guard::FastOr(_) => false,
+        }
+    }
+}
+
+/// Collects the guards (and whether they hold) on the paths to the evaluations of each output stream.
+fn collect_paths<'a>(
+    stmt: &'a Stmt,
+    path: &mut Vec<(&'a Guard, bool)>,
+    evals: &mut HashMap<OutputReference, Vec<Vec<(&'a Guard, bool)>>>,
+) {
+    match stmt {
+        Stmt::Eval { sr, .. } => evals.entry(*sr).or_default().push(path.clone()),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter()
+            .for_each(|stmt| collect_paths(stmt, path, evals)),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            path.push((guard, true));
+            collect_paths(cons, path, evals);
+            path.pop();
+            path.push((guard, false));
+            collect_paths(alt, path, evals);
+            path.pop();
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => collect_paths(stmt, path, evals),
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+    }
+}
+
+fn binary(op: Operator, lhs: Range, rhs: Range, ty: &Type) -> Range {
+    let is_int = matches!(ty, Type::Int(_) | Type::UInt(_));
+    match (op, lhs, rhs) {
+        (Operator::And, Range::Bool(a), Range::Bool(b)) => Range::Bool(match (a, b) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        }),
+        (Operator::Or, Range::Bool(a), Range::Bool(b)) => Range::Bool(match (a, b) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        }),
+        (Operator::Eq | Operator::Ne, Range::Bool(Some(a)), Range::Bool(Some(b))) => {
+            Range::Bool(Some((a == b) == (op == Operator::Eq)))
+        }
+        (Operator::Add, Range::Num(a), Range::Num(b)) => Range::Num(a.add(b)),
+        (Operator::Sub, Range::Num(a), Range::Num(b)) => Range::Num(a.add(b.neg())),
+        (Operator::Mul, Range::Num(a), Range::Num(b)) => Range::Num(a.mul(b)),
+        (Operator::Div, Range::Num(a), Range::Num(b)) if is_int => Range::Num(
+            // the truncated quotient lies between the exact quotient and zero
+            a.div(b).join(Interval::point(0.0)).trunc(),
+        ),
+        (Operator::Div, Range::Num(a), Range::Num(b)) => Range::Num(a.div(b)),
+        (Operator::Rem, Range::Num(a), Range::Num(b)) => Range::Num(a.rem(b)),
+        (Operator::Pow, Range::Num(a), Range::Num(b)) => Range::Num(a.pow(b)),
+        (Operator::Lt, Range::Num(a), Range::Num(b)) => Range::Bool(a.less(b, false)),
+        (Operator::Le, Range::Num(a), Range::Num(b)) => Range::Bool(a.less(b, true)),
+        (Operator::Gt, Range::Num(a), Range::Num(b)) => Range::Bool(b.less(a, false)),
+        (Operator::Ge, Range::Num(a), Range::Num(b)) => Range::Bool(b.less(a, true)),
+        (Operator::Eq | Operator::Ne, Range::Num(a), Range::Num(b)) => {
+            let eq = if a.lo == a.hi && a == b {
+                Some(true)
+            } else if a.hi < b.lo || b.hi < a.lo {
+                Some(false)
+            } else {
+                None
+            };
+            Range::Bool(eq.map(|eq| eq == (op == Operator::Eq)))
+        }
+        (Operator::Eq | Operator::Ne, _, _) => Range::Bool(None),
+        _ => Range::Unknown,
+    }
+}
+
+fn function(f: Function, args: &[Range]) -> Range {
+    let intervals: Option<Vec<Interval>> = args.iter().map(Range::as_interval).collect();
+    let Some(intervals) = intervals else {
+        return match f {
+            Function::Sin | Function::Cos => Range::Num(Interval::new(-1.0, 1.0)),
+            _ => Range::Unknown,
+        };
+    };
+    let range = match (f, intervals.as_slice()) {
+        (Function::Sqrt, [a]) => Interval::new(a.lo.max(0.0).sqrt(), a.hi.sqrt()),
+        (Function::Abs, [a]) => a.abs(),
+        (Function::Sin | Function::Cos, [_]) => Interval::new(-1.0, 1.0),
+        (Function::Arcsin, [_]) => Interval::new(-FRAC_PI_2, FRAC_PI_2),
+        (Function::Arccos, [_]) => Interval::new(0.0, std::f64::consts::PI),
+        (Function::Arctan, [a]) => a.monotone(f64::atan),
+        (Function::Tan, [_]) => Interval::ALL,
+        (Function::Min, [a, b]) => Interval::new(a.lo.min(b.lo), a.hi.min(b.hi)),
+        (Function::Max, [a, b]) => Interval::new(a.lo.max(b.lo), a.hi.max(b.hi)),
+        _ => Interval::ALL,
+    };
+    Range::Num(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        ir::{
+            expressions::{Constant, Expr, ExprKind, Function, Operator},
+            memory::{Memory, StreamBuffer, StreamMemory},
+            parse::parse_ir,
+            Guard, IfStmt, OutputReference, Stmt, StreamIr, StreamReference, Type,
+        },
+        Span,
+    };
+
+    use super::{parse_annotation, Interval, Range, Ranges, TriggerReachability};
+
+    fn float(v: f64) -> Expr {
+        Expr {
+            ty: Type::Float64,
+            kind: ExprKind::Constant(Constant::Float64(v)),
+        }
+    }
+
+    fn access(sr: StreamReference) -> Expr {
+        Expr {
+            ty: Type::Float64,
+            kind: ExprKind::SyncStreamAccess {
+                target: sr,
+                parameters: Vec::new(),
+            },
+        }
+    }
+
+    fn binary(op: Operator, lhs: Expr, rhs: Expr) -> Expr {
+        let ty = match op {
+            Operator::Lt | Operator::Le | Operator::Gt | Operator::Ge | Operator::Eq => Type::Bool,
+            _ => lhs.ty.clone(),
+        };
+        Expr {
+            ty,
+            kind: ExprKind::BinaryOperation(op, Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    fn out(o: usize) -> StreamReference {
+        StreamReference::Out(OutputReference::Unparameterized(o))
+    }
+
+    /// A StreamIR with a float input, where out[0] = abs(in[0]) and two triggers on out[0]
+    fn pitch_ir() -> StreamIr {
+        let mut ir = parse_ir("input 0");
+        for (sr, ty) in [
+            (StreamReference::In(0), Type::Float64),
+            (out(0), Type::Float64),
+        ] {
+            ir.sr2memory.insert(
+                sr,
+                Memory {
+                    buffer: StreamMemory::Static(StreamBuffer::SingleValue),
+                    ty,
+                    name: ir.name(sr).into(),
+                    delay: 0,
+                    span: Span::Unknown,
+                },
+            );
+        }
+        let abs = Expr {
+            ty: Type::Float64,
+            kind: ExprKind::FunctionCall(Function::Abs, vec![access(StreamReference::In(0))]),
+        };
+        let trigger = |o: usize, cond: Expr| {
+            Stmt::If(IfStmt {
+                guard: Guard::And {
+                    lhs: Box::new(Guard::Stream(StreamReference::In(0))),
+                    rhs: Box::new(Guard::Dynamic(cond)),
+                },
+                cons: Box::new(Stmt::Eval {
+                    sr: OutputReference::Unparameterized(o),
+                    with: Expr {
+                        ty: Type::String,
+                        kind: ExprKind::Constant(Constant::Str("msg".into())),
+                    },
+                    idx: 0,
+                }),
+                alt: Box::new(Stmt::Skip),
+            })
+        };
+        ir.stmt = Stmt::Seq(vec![
+            Stmt::Input(0),
+            Stmt::Eval {
+                sr: OutputReference::Unparameterized(0),
+                with: abs,
+                idx: 0,
+            },
+            trigger(1, binary(Operator::Lt, access(out(0)), float(0.0))),
+            trigger(2, binary(Operator::Ge, access(out(0)), float(0.0))),
+            trigger(3, binary(Operator::Gt, access(out(0)), float(0.3))),
+        ]);
+        for o in 1..4 {
+            ir.triggers.insert(OutputReference::Unparameterized(o), o);
+        }
+        ir
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!("-1..2.5".parse(), Ok(Interval::new(-1.0, 2.5)));
+        assert_eq!("0..".parse(), Ok(Interval::new(0.0, f64::INFINITY)));
+        assert!("2..1".parse::<Interval>().is_err());
+        let a = Interval::new(-2.0, 3.0);
+        assert_eq!(a.mul(Interval::new(0.0, f64::INFINITY)), Interval::ALL);
+        assert_eq!(a.abs(), Interval::new(0.0, 3.0));
+        assert_eq!(a.div(Interval::new(-1.0, 1.0)), Interval::ALL);
+        assert_eq!(
+            Range::Num(Interval::new(0.0, 300.0)).fit(&Type::UInt(8)),
+            Range::Num(Interval::new(0.0, 255.0))
+        );
+    }
+
+    #[test]
+    fn triggers() {
+        let ir = pitch_ir();
+        assert_eq!(
+            parse_annotation(&ir, "i0=-1..1"),
+            Ok((0, Interval::new(-1.0, 1.0)))
+        );
+        assert!(parse_annotation(&ir, "o0=0..1").is_err());
+        let ranges = Ranges::new(&ir, &HashMap::new());
+        assert_eq!(
+            ranges.stream(out(0)),
+            Some(&Range::Num(Interval::new(0.0, f64::INFINITY)))
+        );
+        let warnings = ranges.trigger_warnings(&ir);
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|w| (w.name.as_str(), w.reachability))
+                .collect::<Vec<_>>(),
+            vec![
+                ("o1", TriggerReachability::Never),
+                ("o2", TriggerReachability::Always)
+            ]
+        );
+
+        let ranges = Ranges::new(&ir, &HashMap::from([(0, Interval::new(-0.2, 0.1))]));
+        let warnings = ranges.trigger_warnings(&ir);
+        assert_eq!(warnings.len(), 3);
+        assert_eq!(warnings[2].to_string(), "trigger `o3` can never fire");
+    }
+
+    #[test]
+    fn recursion() {
+        // out[0] = out[0].offset(-1).defaults(0) + 1 is widened to all non-negative integers
+        let mut ir = parse_ir("input 0");
+        let uint = |v| Expr {
+            ty: Type::UInt(64),
+            kind: ExprKind::Constant(Constant::UInt(v, 64)),
+        };
+        let offset = Expr {
+            ty: Type::UInt(64),
+            kind: ExprKind::OffsetStreamAccess {
+                target: out(0),
+                offset: 1,
+                default: Box::new(uint(0)),
+                parameters: Vec::new(),
+            },
+        };
+        ir.sr2memory.get_mut(&out(0)).unwrap().ty = Type::UInt(64);
+        ir.stmt = Stmt::Eval {
+            sr: OutputReference::Unparameterized(0),
+            with: binary(Operator::Add, offset, uint(1)),
+            idx: 0,
+        };
+        let ranges = Ranges::new(&ir, &HashMap::new());
+        assert_eq!(
+            ranges.stream(out(0)),
+            Some(&Range::of_type(&Type::UInt(64)))
+        );
+    }
+}
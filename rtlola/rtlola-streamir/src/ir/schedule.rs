@@ -4,7 +4,8 @@ use rtlola_frontend::mir::{self};
 
 use super::{OutputReference, StreamReference};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents the precomuted schedule for global periodic output streams
 pub struct StaticSchedule {
     /// The hyperperiod of the schedule
@@ -13,7 +14,8 @@ pub struct StaticSchedule {
     pub deadlines: Vec<Deadline>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents a deadline of the static schedule. A set of output streams that need to be evaluated at the same time.
 pub struct Deadline {
     /// The time duration after the last deadline
@@ -22,7 +24,8 @@ pub struct Deadline {
     pub due: Vec<Task>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Represents a task that is due at a deadline of the static schedule
 pub enum Task {
     /// A (global-periodic) output stream is spawned
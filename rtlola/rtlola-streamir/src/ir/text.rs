@@ -0,0 +1,336 @@
+//! A complete textual representation of the StreamIR that can be printed and parsed back.
+//!
+//! The format starts with a version header followed by sections describing the memory of the streams, triggers,
+//! local frequencies, windows, livetime equivalences, the static schedule, stream accesses and finally the statement.
+//! Line comments start with `//`.
+//!
+//! ```text
+//! streamir 1
+//!
+//! stream in[0] "a": UInt64 = static single
+//! stream out[0] "b": UInt64 = static bounded(2)
+//!
+//! livetime (inputs, out[0])
+//!
+//! accesses out[0] (in[0]: eval_with(0) sync)
+//! accessed_by in[0] (out[0]: eval_with(0) sync)
+//!
+//! stmt
+//! if @in[0] then
+//!     seq {
+//!         input in[0];
+//!         shift out[0];
+//!         eval(0) out[0] = (in[0] + u64(1));
+//!     }
+//! fi
+//! ```
+//!
+//! Streams are referenced by `in[i]`, `out[i]` (unparameterized) and `pout[i]` (parameterized), windows by
+//! `sliding[i]`, `discrete[i]` and `instance[i]`, and temporaries by `tmp[i]`.
+//! Binary operations in expressions and guards are always parenthesized.
+//! The type of an expression is only written (as `(expr :: Type)`) if it can not be inferred from the expression itself.
+//! The printer produces exactly the representation the parser reads, i.e., printing a parsed StreamIR yields the same text.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use super::{
+    expressions::{Constant, ExprKind, Function, Operator},
+    memory::Memory,
+    windows::WindowOperation,
+    StreamIr, StreamReference, Type,
+};
+
+mod parser;
+mod printer;
+
+/// The version of the textual format
+pub const VERSION: u32 = 1;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{line}:{column}: {message}")]
+/// An error when parsing the textual representation of a StreamIR
+pub struct TextError {
+    /// The line the error occurred in (starting at 1)
+    pub line: usize,
+    /// The column the error occurred in (starting at 1)
+    pub column: usize,
+    /// The description of the error
+    pub message: String,
+}
+
+/// Prints the StreamIR in its textual representation.
+pub fn print(ir: &StreamIr) -> String {
+    printer::print(ir)
+}
+
+/// Parses a StreamIR from its textual representation.
+pub fn parse(input: &str) -> Result<StreamIr, TextError> {
+    parser::parse(input)
+}
+
+impl std::str::FromStr for StreamIr {
+    type Err = TextError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s)
+    }
+}
+
+impl StreamIr {
+    /// Returns the textual representation of the StreamIR, see [print].
+    pub fn to_text(&self) -> String {
+        print(self)
+    }
+}
+
+const OPERATORS: [(Operator, &str); 22] = [
+    (Operator::Not, "!"),
+    (Operator::Neg, "-"),
+    (Operator::BitNot, "~"),
+    (Operator::Pow, "**"),
+    (Operator::Shl, "<<"),
+    (Operator::Shr, ">>"),
+    (Operator::Le, "<="),
+    (Operator::Ge, ">="),
+    (Operator::Eq, "=="),
+    (Operator::Ne, "!="),
+    (Operator::And, "&&"),
+    (Operator::Or, "||"),
+    (Operator::Add, "+"),
+    (Operator::Sub, "-"),
+    (Operator::Mul, "*"),
+    (Operator::Div, "/"),
+    (Operator::Rem, "%"),
+    (Operator::BitXor, "^"),
+    (Operator::BitAnd, "&"),
+    (Operator::BitOr, "|"),
+    (Operator::Lt, "<"),
+    (Operator::Gt, ">"),
+];
+
+fn is_unary(op: Operator) -> bool {
+    matches!(op, Operator::Not | Operator::Neg | Operator::BitNot)
+}
+
+fn operator_symbol(op: Operator) -> &'static str {
+    OPERATORS.iter().find(|(o, _)| *o == op).unwrap().1
+}
+
+const FUNCTIONS: [(Function, &str); 10] = [
+    (Function::Sqrt, "sqrt"),
+    (Function::Abs, "abs"),
+    (Function::Sin, "sin"),
+    (Function::Arcsin, "arcsin"),
+    (Function::Cos, "cos"),
+    (Function::Arccos, "arccos"),
+    (Function::Tan, "tan"),
+    (Function::Arctan, "arctan"),
+    (Function::Min, "min"),
+    (Function::Max, "max"),
+];
+
+fn function_name(f: Function) -> &'static str {
+    FUNCTIONS.iter().find(|(o, _)| *o == f).unwrap().1
+}
+
+const WINDOW_OPERATIONS: [(WindowOperation, &str); 13] = [
+    (WindowOperation::Sum, "sum"),
+    (WindowOperation::Average, "average"),
+    (WindowOperation::Conjunction, "conjunction"),
+    (WindowOperation::Disjunction, "disjunction"),
+    (WindowOperation::Min, "min"),
+    (WindowOperation::Max, "max"),
+    (WindowOperation::Integral, "integral"),
+    (WindowOperation::Count, "count"),
+    (WindowOperation::Product, "product"),
+    (WindowOperation::Last, "last"),
+    (WindowOperation::Variance, "variance"),
+    (WindowOperation::Covariance, "covariance"),
+    (WindowOperation::StandardDeviation, "standard_deviation"),
+];
+
+/// Returns the type of an expression if it follows from the kind of the expression.
+///
+/// The type of an expression is only printed if it differs from this type.
+fn inferred_type(kind: &ExprKind, memory: &HashMap<StreamReference, Memory>) -> Option<Type> {
+    match kind {
+        ExprKind::Constant(c) => constant_type(c),
+        ExprKind::BinaryOperation(
+            Operator::And
+            | Operator::Or
+            | Operator::Eq
+            | Operator::Ne
+            | Operator::Lt
+            | Operator::Le
+            | Operator::Gt
+            | Operator::Ge,
+            _,
+            _,
+        )
+        | ExprKind::IsFresh { .. } => Some(Type::Bool),
+        ExprKind::BinaryOperation(_, inner, _)
+        | ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Ite(_, inner, _)
+        | ExprKind::OffsetStreamAccess { default: inner, .. }
+        | ExprKind::HoldStreamAccess { default: inner, .. }
+        | ExprKind::GetAccess { default: inner, .. }
+        | ExprKind::WindowAccess {
+            default: Some(inner),
+            ..
+        } => Some(inner.ty.clone()),
+        ExprKind::SyncStreamAccess { target, .. } => memory.get(target).map(|m| m.ty.clone()),
+        ExprKind::Cast(ty, _) => Some(ty.clone()),
+        ExprKind::ParameterAccess(sr, p) => {
+            memory.get(sr)?.parameters()?.get(*p).map(|p| p.ty.clone())
+        }
+        ExprKind::FunctionCall(_, args) => args.first().map(|arg| arg.ty.clone()),
+        ExprKind::Tuple(inner) => Some(Type::Tuple(inner.iter().map(|e| e.ty.clone()).collect())),
+        ExprKind::TupleAccess(inner, i) => match &inner.ty {
+            Type::Tuple(types) => types.get(*i).cloned(),
+            _ => None,
+        },
+        ExprKind::Some(inner) => Some(Type::Option(Box::new(inner.ty.clone()))),
+        ExprKind::UnwrapOr(_, default) => Some(default.ty.clone()),
+        ExprKind::WindowAccess { default: None, .. }
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => None,
+    }
+}
+
+/// Returns the type of a constant, which is unknown for the absent optional value
+fn constant_type(c: &Constant) -> Option<Type> {
+    Some(match c {
+        Constant::Str(_) => Type::String,
+        Constant::Bool(_) => Type::Bool,
+        Constant::UInt(_, bits) => Type::UInt(*bits),
+        Constant::Int(_, bits) => Type::Int(*bits),
+        Constant::Float32(_) => Type::Float32,
+        Constant::Float64(_) => Type::Float64,
+        Constant::Tuple(inner) => {
+            Type::Tuple(inner.iter().map(constant_type).collect::<Option<_>>()?)
+        }
+        Constant::None => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{
+        ir::{memory::Memory, parse::parse_ir, StreamIr},
+        optimize_all, ParserConfig, Span,
+    };
+
+    use super::{parse, print};
+
+    const EXAMPLE: &str = include_str!("text/example.sir");
+
+    fn assert_round_trip(ir: &StreamIr) {
+        let text = print(ir);
+        let parsed = parse(&text).unwrap_or_else(|e| panic!("{e}\n{text}"));
+        assert_eq!(print(&parsed), text);
+        assert_eq!(parsed.stmt, ir.stmt);
+        // the textual representation does not contain the spans of the declarations
+        for (sr, memory) in &ir.sr2memory {
+            let parsed = &parsed.sr2memory[sr];
+            assert_eq!(parsed.span, Span::Unknown);
+            assert_eq!(
+                *parsed,
+                Memory {
+                    span: Span::Unknown,
+                    ..memory.clone()
+                }
+            );
+        }
+        assert_eq!(parsed.sr2memory.len(), ir.sr2memory.len());
+        assert_eq!(parsed.wref2window, ir.wref2window);
+        assert_eq!(parsed.lref2lfreq, ir.lref2lfreq);
+        assert_eq!(parsed.static_schedule, ir.static_schedule);
+        assert_eq!(parsed.triggers, ir.triggers);
+        assert_eq!(parsed.accesses, ir.accesses);
+        assert_eq!(parsed.accessed_by, ir.accessed_by);
+    }
+
+    #[test]
+    fn example() {
+        let ir = parse(EXAMPLE).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(print(&ir), EXAMPLE);
+        assert_round_trip(&ir);
+    }
+
+    #[test]
+    fn toy_irs() {
+        for stmt in [
+            "input 0",
+            "seq { input 0; par { eval 0 (s0 && o1); eval 1 (p0 == p1) } }",
+            "if (@0 && ?1) || Local(2) then iterate 1 assign 2 (s0, true) let 0 (s1 || t1) else input 2 fi",
+            "if FastAnd(0, 1) then if Expr(true) then seq {} fi fi",
+        ] {
+            assert_round_trip(&parse_ir(stmt));
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let text = EXAMPLE.replace("\nstmt\n", "\n// the statement\nstmt // follows\n");
+        assert_eq!(print(&parse(&text).unwrap()), EXAMPLE);
+    }
+
+    #[test]
+    fn optionals() {
+        let text = EXAMPLE.replacen(
+            "let tmp[0] = -i8(-3);",
+            "let tmp[0] = unwrap_or(if(true, some(-i8(-3)), (none :: Option<Int8>)), i8(0));",
+            1,
+        );
+        let ir = parse(&text).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(print(&ir), text);
+        assert_round_trip(&ir);
+    }
+
+    #[test]
+    fn delays() {
+        let text = EXAMPLE.replacen(
+            "\"d\": UInt64 = static single",
+            "\"d\": UInt64 = static single delay(2)",
+            1,
+        );
+        let ir = parse(&text).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(ir.sr2memory.values().map(|m| m.delay).max(), Some(2));
+        assert_eq!(print(&ir), text);
+        assert_round_trip(&ir);
+    }
+
+    #[test]
+    fn errors() {
+        let unknown = EXAMPLE.replacen("input in[0]", "input in[9]", 1);
+        let err = parse(&unknown).unwrap_err();
+        assert!(err.message.contains("in[9]"), "{err}");
+        let line = unknown.lines().position(|l| l.contains("in[9]")).unwrap() + 1;
+        assert_eq!(err.line, line);
+
+        let version = EXAMPLE.replacen("streamir 1", "streamir 2", 1);
+        assert_eq!(parse(&version).unwrap_err().line, 1);
+
+        let syntax = EXAMPLE.replacen("fi;", ";", 1);
+        let err = parse(&syntax).unwrap_err();
+        assert!(err.message.contains("`fi`"), "{err}");
+    }
+
+    #[test]
+    fn repo_specs() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "lola") {
+                let config = ParserConfig::from_path(path).unwrap();
+                let ir = crate::parse(&config).unwrap();
+                assert_round_trip(&ir);
+                assert_round_trip(&optimize_all(ir).unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,1182 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    collections::HashMap,
+    time::Duration,
+};
+
+use disjoint::DisjointSet;
+use winnow::{
+    ascii::{dec_int, dec_uint, Uint},
+    error::{ContextError, ErrMode, StrContext, StrContextValue},
+    token::take_while,
+    ModalResult, Parser,
+};
+
+use crate::{
+    ir::{
+        expressions::{Constant, Expr, ExprKind},
+        memory::{Memory, Parameter, StreamBuffer, StreamMemory},
+        windows::{InstanceSelection, Window, WindowKind, WindowOperation},
+        Accesses, Deadline, Guard, IfStmt, LivetimeEquivalences, LocalFreq, Offset, Origin,
+        OutputReference, StaticSchedule, Stmt, StreamAccessKind, StreamIr, StreamReference, Task,
+        Type, WindowReference,
+    },
+    Span,
+};
+
+use super::{inferred_type, is_unary, TextError, FUNCTIONS, OPERATORS, VERSION, WINDOW_OPERATIONS};
+
+pub(super) fn parse(source: &str) -> Result<StreamIr, TextError> {
+    let ctx = Ctx {
+        source,
+        memory: OnceCell::new(),
+        error: RefCell::new(None),
+    };
+    let mut input = source;
+    streamir(&ctx, &mut input).map_err(|e| {
+        let (offset, message) = ctx.error.take().unwrap_or_else(|| {
+            let message = match e {
+                ErrMode::Backtrack(e) | ErrMode::Cut(e) => e.to_string(),
+                ErrMode::Incomplete(_) => String::new(),
+            };
+            let message = if message.is_empty() {
+                "invalid syntax".into()
+            } else {
+                message.replace('\n', ", ")
+            };
+            (source.len() - input.len(), message)
+        });
+        let before = &source[..offset];
+        TextError {
+            line: before.matches('\n').count() + 1,
+            column: before
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1,
+            message,
+        }
+    })
+}
+
+struct Ctx<'s> {
+    source: &'s str,
+    /// The memory of the streams, available after the stream declarations are parsed
+    memory: OnceCell<HashMap<StreamReference, Memory>>,
+    /// The first semantic error, reported instead of the syntax error
+    error: RefCell<Option<(usize, String)>>,
+}
+
+impl Ctx<'_> {
+    /// Records an error at the start of the given input and aborts parsing.
+    fn fail<T>(&self, at: &str, message: impl Into<String>) -> ModalResult<T> {
+        self.error
+            .borrow_mut()
+            .get_or_insert((self.source.len() - at.len(), message.into()));
+        Err(ErrMode::Cut(ContextError::new()))
+    }
+
+    fn memory(&self) -> &HashMap<StreamReference, Memory> {
+        self.memory.get().expect("streams are declared first")
+    }
+
+    fn typed(&self, at: &str, kind: ExprKind, ty: Option<Type>) -> ModalResult<Expr> {
+        match ty.or_else(|| inferred_type(&kind, self.memory())) {
+            Some(ty) => Ok(Expr { ty, kind }),
+            None => self.fail(
+                at,
+                "the type of the expression can not be inferred and needs to be annotated with `(expr :: Type)`",
+            ),
+        }
+    }
+}
+
+fn streamir(c: &Ctx, i: &mut &str) -> ModalResult<StreamIr> {
+    token(i, "streamir")?;
+    ws(i)?;
+    let start = *i;
+    let version: u32 = number(i)?;
+    if version != VERSION {
+        return c.fail(
+            start,
+            format!("unsupported version {version}, expected version {VERSION}"),
+        );
+    }
+
+    let sr2memory = streams(c, i)?;
+    c.memory.set(sr2memory).unwrap();
+
+    let mut triggers = HashMap::new();
+    while keyword(i, "trigger")? {
+        let sr = output(c, i)?;
+        token(i, "=")?;
+        triggers.insert(sr, number(i)?);
+    }
+
+    let mut lref2lfreq = HashMap::new();
+    while keyword(i, "local_freq")? {
+        let reference = number(i)?;
+        token(i, "=")?;
+        let dur = duration(c, i)?;
+        token(i, "for")?;
+        let sr = output(c, i)?;
+        lref2lfreq.insert(reference, LocalFreq { dur, sr, reference });
+    }
+
+    let mut wref2window = HashMap::new();
+    while keyword(i, "window")? {
+        let window = window(c, i)?;
+        wref2window.insert(window.wref, window);
+    }
+
+    let mut classes = Vec::new();
+    while keyword(i, "livetime")? {
+        classes.push(list(i, "(", ",", ")", |i| {
+            if keyword(i, "inputs")? {
+                Ok(None)
+            } else {
+                output(c, i).map(Some)
+            }
+        })?);
+    }
+    let livetime_equivalences = livetime_equivalences(c.memory(), classes);
+
+    let static_schedule = keyword(i, "schedule")?
+        .then(|| schedule(c, i))
+        .transpose()?;
+
+    let mut accesses = HashMap::new();
+    while keyword(i, "accesses")? {
+        let sr = stream(c, i)?;
+        accesses.insert(sr, stream_accesses(c, i)?);
+    }
+    let mut accessed_by = HashMap::new();
+    while keyword(i, "accessed_by")? {
+        let sr = stream(c, i)?;
+        accessed_by.insert(sr, stream_accesses(c, i)?);
+    }
+
+    token(i, "stmt")?;
+    let stmt = stmt(c, i)?;
+    ws(i)?;
+    if !i.is_empty() {
+        return c.fail(i, "expected the end of the input after the statement");
+    }
+
+    Ok(StreamIr {
+        stmt,
+        sr2memory: c.memory().clone(),
+        wref2window,
+        lref2lfreq,
+        livetime_equivalences,
+        static_schedule,
+        triggers,
+        accesses,
+        accessed_by,
+    })
+}
+
+fn streams(c: &Ctx, i: &mut &str) -> ModalResult<HashMap<StreamReference, Memory>> {
+    let mut memory = HashMap::new();
+    while keyword(i, "stream")? {
+        ws(i)?;
+        let start = *i;
+        let sr = stream(c, i)?;
+        let name = string(c, i)?;
+        token(i, ":")?;
+        let ty = ty(c, i)?;
+        token(i, "=")?;
+        let buffer = stream_memory(c, i)?;
+        let delay = if keyword(i, "delay")? {
+            token(i, "(")?;
+            let delay = number(i)?;
+            token(i, ")")?;
+            delay
+        } else {
+            0
+        };
+        let memory_entry = Memory {
+            buffer,
+            ty,
+            name,
+            delay,
+            span: Span::Unknown,
+        };
+        if memory.insert(sr, memory_entry).is_some() {
+            return c.fail(start, "the stream is declared twice");
+        }
+    }
+    Ok(memory)
+}
+
+fn stream_memory(c: &Ctx, i: &mut &str) -> ModalResult<StreamMemory> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "none" => Ok(StreamMemory::NoMemory),
+        "static" => Ok(StreamMemory::Static(stream_buffer(c, i)?)),
+        "dynamic" => {
+            token(i, "(")?;
+            token(i, "spawn")?;
+            token(i, ":")?;
+            let has_spawn = boolean(c, i)?;
+            token(i, ",")?;
+            token(i, "close")?;
+            token(i, ":")?;
+            let has_close = boolean(c, i)?;
+            token(i, ")")?;
+            Ok(StreamMemory::Dynamic {
+                buffer: stream_buffer(c, i)?,
+                has_spawn,
+                has_close,
+            })
+        }
+        "instances" => {
+            let parameter = parameters(c, i)?;
+            Ok(StreamMemory::Instances {
+                buffer: stream_buffer(c, i)?,
+                parameter,
+            })
+        }
+        other => c.fail(start, format!("expected a stream memory, found `{other}`")),
+    }
+}
+
+fn stream_buffer(c: &Ctx, i: &mut &str) -> ModalResult<StreamBuffer> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "single" => Ok(StreamBuffer::SingleValue),
+        "bounded" => {
+            token(i, "(")?;
+            let bound = number(i)?;
+            token(i, ")")?;
+            Ok(StreamBuffer::Bounded(bound))
+        }
+        "unbounded" => Ok(StreamBuffer::UnBounded),
+        other => c.fail(start, format!("expected a stream buffer, found `{other}`")),
+    }
+}
+
+fn parameters(c: &Ctx, i: &mut &str) -> ModalResult<Vec<Parameter>> {
+    list(i, "(", ",", ")", |i| {
+        let name = string(c, i)?;
+        token(i, ":")?;
+        Ok(Parameter {
+            name,
+            ty: ty(c, i)?,
+        })
+    })
+}
+
+fn window(c: &Ctx, i: &mut &str) -> ModalResult<Window> {
+    let wref = window_reference(c, i)?;
+    token(i, ":")?;
+    let ty = ty(c, i)?;
+    token(i, "=")?;
+    ws(i)?;
+    let start = *i;
+    let op = match ident(i)? {
+        "nth_percentile" => {
+            token(i, "[")?;
+            let n = number(i)?;
+            token(i, "]")?;
+            WindowOperation::NthPercentile(n)
+        }
+        name => match WINDOW_OPERATIONS.iter().find(|(_, n)| *n == name) {
+            Some((op, _)) => *op,
+            None => return c.fail(start, format!("unknown window operation `{name}`")),
+        },
+    };
+    token(i, "(")?;
+    let target = stream(c, i)?;
+    token(i, ")")?;
+    token(i, "over")?;
+    let kind = window_kind(c, i)?;
+    token(i, "caller")?;
+    let caller = stream(c, i)?;
+    let origin = origin(c, i)?;
+    token(i, "pacing")?;
+    let origin_pacing = guard(c, i)?;
+    Ok(Window {
+        wref,
+        op,
+        target,
+        caller,
+        origin,
+        origin_pacing,
+        kind,
+        ty,
+    })
+}
+
+fn window_kind(c: &Ctx, i: &mut &str) -> ModalResult<WindowKind> {
+    ws(i)?;
+    let start = *i;
+    let kind = ident(i)?;
+    token(i, "(")?;
+    let kind = match kind {
+        "sliding" => {
+            let length = duration(c, i)?;
+            token(i, ",")?;
+            let bucket_count = number(i)?;
+            token(i, ",")?;
+            let bucket_duration = duration(c, i)?;
+            token(i, ",")?;
+            WindowKind::Sliding {
+                duration: length,
+                bucket_count,
+                bucket_duration,
+                wait: wait(c, i)?,
+            }
+        }
+        "discrete" => {
+            let num_values = number(i)?;
+            token(i, ",")?;
+            WindowKind::Discrete {
+                num_values,
+                wait: wait(c, i)?,
+            }
+        }
+        "instances" => {
+            ws(i)?;
+            let start = *i;
+            let fresh = match ident(i)? {
+                "all" => false,
+                "fresh" => true,
+                other => {
+                    return c.fail(
+                        start,
+                        format!("expected an instance selection, found `{other}`"),
+                    )
+                }
+            };
+            let filter = if try_token(i, ",")? {
+                let parameters = parameters(c, i)?;
+                token(i, ",")?;
+                Some((parameters, expr(c, i)?))
+            } else {
+                None
+            };
+            let selection = match (fresh, filter) {
+                (false, None) => InstanceSelection::All,
+                (true, None) => InstanceSelection::Fresh,
+                (false, Some((parameters, cond))) => {
+                    InstanceSelection::FilteredAll { parameters, cond }
+                }
+                (true, Some((parameters, cond))) => {
+                    InstanceSelection::FilteredFresh { parameters, cond }
+                }
+            };
+            WindowKind::Instances { selection }
+        }
+        other => return c.fail(start, format!("expected a window kind, found `{other}`")),
+    };
+    token(i, ")")?;
+    Ok(kind)
+}
+
+fn wait(c: &Ctx, i: &mut &str) -> ModalResult<bool> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "wait" => Ok(true),
+        "nowait" => Ok(false),
+        other => c.fail(
+            start,
+            format!("expected `wait` or `nowait`, found `{other}`"),
+        ),
+    }
+}
+
+fn livetime_equivalences(
+    memory: &HashMap<StreamReference, Memory>,
+    classes: Vec<Vec<Option<OutputReference>>>,
+) -> LivetimeEquivalences {
+    let mut outputs = memory
+        .keys()
+        .filter_map(|sr| match sr {
+            StreamReference::In(_) => None,
+            StreamReference::Out(o) => Some(*o),
+        })
+        .collect::<Vec<_>>();
+    outputs.sort();
+    let idx = outputs
+        .into_iter()
+        .enumerate()
+        .map(|(i, o)| (o, i))
+        .collect::<HashMap<_, _>>();
+    let input_idx = idx.len();
+    let mut sets = DisjointSet::with_len(idx.len() + 1);
+    for class in classes {
+        let mut members = class.into_iter().map(|o| o.map_or(input_idx, |o| idx[&o]));
+        if let Some(first) = members.next() {
+            members.for_each(|member| {
+                sets.join(first, member);
+            });
+        }
+    }
+    LivetimeEquivalences {
+        idx,
+        sets,
+        input_idx,
+    }
+}
+
+fn schedule(c: &Ctx, i: &mut &str) -> ModalResult<StaticSchedule> {
+    let hyper_period = duration(c, i)?;
+    let mut deadlines = Vec::new();
+    while keyword(i, "after")? {
+        let pause = duration(c, i)?;
+        let due = list(i, "(", ",", ")", |i| {
+            ws(i)?;
+            let start = *i;
+            let task = ident(i)?;
+            let sr = output(c, i)?;
+            match task {
+                "spawn" => Ok(Task::Spawn(sr)),
+                "eval" => Ok(Task::Eval(sr)),
+                "close" => Ok(Task::Close(sr)),
+                other => c.fail(start, format!("expected a task, found `{other}`")),
+            }
+        })?;
+        deadlines.push(Deadline { pause, due });
+    }
+    Ok(StaticSchedule {
+        hyper_period,
+        deadlines,
+    })
+}
+
+fn stream_accesses(c: &Ctx, i: &mut &str) -> ModalResult<Accesses> {
+    list(i, "(", ";", ")", |i| {
+        let sr = stream(c, i)?;
+        token(i, ":")?;
+        let mut kinds = Vec::new();
+        ws(i)?;
+        if !i.starts_with([';', ')']) {
+            loop {
+                kinds.push((origin(c, i)?, access_kind(c, i)?));
+                if !try_token(i, ",")? {
+                    break;
+                }
+            }
+        }
+        Ok((sr, kinds))
+    })
+}
+
+fn access_kind(c: &Ctx, i: &mut &str) -> ModalResult<StreamAccessKind> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "sync" => Ok(StreamAccessKind::Sync),
+        "hold" => Ok(StreamAccessKind::Hold),
+        "get" => Ok(StreamAccessKind::Get),
+        "fresh" => Ok(StreamAccessKind::Fresh),
+        "offset" => {
+            token(i, "(")?;
+            ws(i)?;
+            let start = *i;
+            let offset = match ident(i)? {
+                "past" => Offset::Past(number(i)?),
+                "future" => Offset::Future(number(i)?),
+                other => {
+                    return c.fail(
+                        start,
+                        format!("expected `past` or `future`, found `{other}`"),
+                    )
+                }
+            };
+            token(i, ")")?;
+            Ok(StreamAccessKind::Offset(offset))
+        }
+        "sliding" | "discrete" | "instance" => {
+            *i = start;
+            Ok(match window_reference(c, i)? {
+                wref @ WindowReference::Sliding(_) => StreamAccessKind::SlidingWindow(wref),
+                wref @ WindowReference::Discrete(_) => StreamAccessKind::DiscreteWindow(wref),
+                wref @ WindowReference::Instance(_) => StreamAccessKind::InstanceAggregation(wref),
+            })
+        }
+        other => c.fail(start, format!("expected a stream access, found `{other}`")),
+    }
+}
+
+fn origin(c: &Ctx, i: &mut &str) -> ModalResult<Origin> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "spawn" => Ok(Origin::Spawn),
+        "close" => Ok(Origin::Close),
+        clause @ ("eval_when" | "eval_with") => {
+            token(i, "(")?;
+            let idx = number(i)?;
+            token(i, ")")?;
+            Ok(if clause == "eval_when" {
+                Origin::EvalWhen(idx)
+            } else {
+                Origin::EvalWith(idx)
+            })
+        }
+        other => c.fail(start, format!("expected an origin, found `{other}`")),
+    }
+}
+
+fn stmt(c: &Ctx, i: &mut &str) -> ModalResult<Stmt> {
+    ws(i)?;
+    let start = *i;
+    let stmt = match ident(i)? {
+        "skip" => Stmt::Skip,
+        kind @ ("seq" | "par") => {
+            token(i, "{")?;
+            let mut stmts = Vec::new();
+            while !try_token(i, "}")? {
+                stmts.push(stmt(c, i)?);
+                try_token(i, ";")?;
+            }
+            if kind == "seq" {
+                Stmt::Seq(stmts)
+            } else {
+                Stmt::Parallel(stmts)
+            }
+        }
+        "shift" => Stmt::Shift(stream(c, i)?),
+        "input" => {
+            ws(i)?;
+            let start = *i;
+            match stream(c, i)? {
+                StreamReference::In(sr) => Stmt::Input(sr),
+                StreamReference::Out(_) => return c.fail(start, "expected an input stream"),
+            }
+        }
+        "spawn" => {
+            let sr = output(c, i)?;
+            let with = keyword(i, "with")?
+                .then(|| list(i, "(", ",", ")", |i| expr(c, i)))
+                .transpose()?;
+            let (local_frequencies, windows) = clocks(c, i)?;
+            Stmt::Spawn {
+                sr,
+                with,
+                local_frequencies,
+                windows,
+            }
+        }
+        "eval" => {
+            token(i, "(")?;
+            let idx = number(i)?;
+            token(i, ")")?;
+            let sr = output(c, i)?;
+            token(i, "=")?;
+            Stmt::Eval {
+                sr,
+                with: expr(c, i)?,
+                idx,
+            }
+        }
+        "close" => {
+            let sr = output(c, i)?;
+            let (local_frequencies, windows) = clocks(c, i)?;
+            Stmt::Close {
+                sr,
+                local_frequencies,
+                windows,
+            }
+        }
+        "if" => {
+            let guard = guard(c, i)?;
+            token(i, "then")?;
+            let cons = stmt(c, i)?;
+            let alt = if keyword(i, "else")? {
+                stmt(c, i)?
+            } else {
+                Stmt::Skip
+            };
+            token(i, "fi")?;
+            Stmt::If(IfStmt {
+                guard,
+                cons: Box::new(cons),
+                alt: Box::new(alt),
+            })
+        }
+        "iterate" => {
+            let sr = list(i, "(", ",", ")", |i| output(c, i))?;
+            Stmt::Iterate {
+                sr,
+                stmt: Box::new(stmt(c, i)?),
+            }
+        }
+        "assign" => {
+            let sr = list(i, "(", ",", ")", |i| output(c, i))?;
+            token(i, "=")?;
+            let parameter_expr = list(i, "(", ",", ")", |i| expr(c, i))?;
+            Stmt::Assign {
+                parameter_expr,
+                sr,
+                stmt: Box::new(stmt(c, i)?),
+            }
+        }
+        "let" => {
+            let temp = temporary(i)?;
+            token(i, "=")?;
+            Stmt::Let {
+                temp,
+                expr: expr(c, i)?,
+            }
+        }
+        other => return c.fail(start, format!("expected a statement, found `{other}`")),
+    };
+    Ok(stmt)
+}
+
+fn clocks(c: &Ctx, i: &mut &str) -> ModalResult<(Vec<usize>, Vec<WindowReference>)> {
+    let local_frequencies = if keyword(i, "clocks")? {
+        list(i, "(", ",", ")", number)?
+    } else {
+        Vec::new()
+    };
+    let windows = if keyword(i, "windows")? {
+        list(i, "(", ",", ")", |i| window_reference(c, i))?
+    } else {
+        Vec::new()
+    };
+    Ok((local_frequencies, windows))
+}
+
+fn guard(c: &Ctx, i: &mut &str) -> ModalResult<Guard> {
+    if try_token(i, "(")? {
+        let lhs = Box::new(guard(c, i)?);
+        let and = if try_token(i, "&&")? {
+            true
+        } else {
+            token(i, "||")?;
+            false
+        };
+        let rhs = Box::new(guard(c, i)?);
+        token(i, ")")?;
+        return Ok(if and {
+            Guard::And { lhs, rhs }
+        } else {
+            Guard::Or { lhs, rhs }
+        });
+    }
+    if try_token(i, "@")? {
+        return Ok(Guard::Stream(stream(c, i)?));
+    }
+    ws(i)?;
+    let start = *i;
+    let guard = match ident(i)? {
+        "true" => return Ok(Guard::Constant(true)),
+        "false" => return Ok(Guard::Constant(false)),
+        "fast_and" => return Ok(Guard::FastAnd(list(i, "(", ",", ")", |i| stream(c, i))?)),
+        "fast_or" => return Ok(Guard::FastOr(list(i, "(", ",", ")", |i| stream(c, i))?)),
+        kind @ ("alive" | "dynamic" | "global" | "local") => {
+            token(i, "(")?;
+            let guard = match kind {
+                "alive" => Guard::Alive(stream(c, i)?),
+                "dynamic" => Guard::Dynamic(expr(c, i)?),
+                "global" => Guard::GlobalFreq(duration(c, i)?),
+                _ => Guard::LocalFreq(number(i)?),
+            };
+            token(i, ")")?;
+            guard
+        }
+        other => return c.fail(start, format!("expected a guard, found `{other}`")),
+    };
+    Ok(guard)
+}
+
+fn expr(c: &Ctx, i: &mut &str) -> ModalResult<Expr> {
+    ws(i)?;
+    let start = *i;
+    let (kind, ty) = partial_expr(c, i)?;
+    c.typed(start, kind, ty)
+}
+
+/// Parses an expression whose type is only known if it was annotated.
+fn partial_expr(c: &Ctx, i: &mut &str) -> ModalResult<(ExprKind, Option<Type>)> {
+    ws(i)?;
+    let start = *i;
+    if let Some((op, symbol)) = OPERATORS
+        .iter()
+        .find(|(op, symbol)| is_unary(*op) && i.starts_with(symbol))
+    {
+        *i = &i[symbol.len()..];
+        let inner = expr(c, i)?;
+        return Ok((ExprKind::UnaryOperation(*op, Box::new(inner)), None));
+    }
+    let (mut kind, mut ty) = atom(c, i)?;
+    while try_token(i, ".")? {
+        let inner = c.typed(start, kind, ty)?;
+        kind = ExprKind::TupleAccess(Box::new(inner), number(i)?);
+        ty = None;
+    }
+    Ok((kind, ty))
+}
+
+fn atom(c: &Ctx, i: &mut &str) -> ModalResult<(ExprKind, Option<Type>)> {
+    ws(i)?;
+    let start = *i;
+    if try_token(i, "(")? {
+        ws(i)?;
+        let inner_start = *i;
+        let (kind, ty) = partial_expr(c, i)?;
+        if try_token(i, "::")? {
+            let ty = self::ty(c, i)?;
+            token(i, ")")?;
+            return Ok((kind, Some(ty)));
+        }
+        let lhs = c.typed(inner_start, kind, ty)?;
+        if try_token(i, ")")? {
+            return Ok((lhs.kind, Some(lhs.ty)));
+        }
+        ws(i)?;
+        let Some((op, symbol)) = OPERATORS
+            .iter()
+            .find(|(op, symbol)| !is_unary(*op) && i.starts_with(symbol))
+        else {
+            return c.fail(i, "expected a binary operator, `::` or `)`");
+        };
+        *i = &i[symbol.len()..];
+        let rhs = expr(c, i)?;
+        token(i, ")")?;
+        return Ok((
+            ExprKind::BinaryOperation(*op, Box::new(lhs), Box::new(rhs)),
+            None,
+        ));
+    }
+    if i.starts_with('"') {
+        return Ok((ExprKind::Constant(constant(c, i)?), None));
+    }
+    let kind = match ident(i)? {
+        "in" | "out" | "pout" => {
+            *i = start;
+            let (target, parameters) = target(c, i)?;
+            ExprKind::SyncStreamAccess { target, parameters }
+        }
+        "tmp" => {
+            *i = start;
+            ExprKind::Temporary(temporary(i)?)
+        }
+        "if" => {
+            token(i, "(")?;
+            let cond = expr(c, i)?;
+            token(i, ",")?;
+            let cons = expr(c, i)?;
+            token(i, ",")?;
+            let alt = expr(c, i)?;
+            token(i, ")")?;
+            ExprKind::Ite(Box::new(cond), Box::new(cons), Box::new(alt))
+        }
+        "offset" => {
+            token(i, "(")?;
+            let (target, parameters) = target(c, i)?;
+            token(i, ",")?;
+            let offset = number(i)?;
+            token(i, ",")?;
+            let default = Box::new(expr(c, i)?);
+            token(i, ")")?;
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            }
+        }
+        access @ ("hold" | "get") => {
+            token(i, "(")?;
+            let (target, parameters) = target(c, i)?;
+            token(i, ",")?;
+            let default = Box::new(expr(c, i)?);
+            token(i, ")")?;
+            if access == "hold" {
+                ExprKind::HoldStreamAccess {
+                    target,
+                    default,
+                    parameters,
+                }
+            } else {
+                ExprKind::GetAccess {
+                    target,
+                    default,
+                    parameters,
+                }
+            }
+        }
+        "fresh" => {
+            token(i, "(")?;
+            let (target, parameters) = target(c, i)?;
+            token(i, ")")?;
+            ExprKind::IsFresh { target, parameters }
+        }
+        "window" => {
+            token(i, "(")?;
+            let (target, parameters) = target(c, i)?;
+            token(i, ",")?;
+            let window = window_reference(c, i)?;
+            let default = try_token(i, ",")?
+                .then(|| expr(c, i).map(Box::new))
+                .transpose()?;
+            token(i, ")")?;
+            ExprKind::WindowAccess {
+                target,
+                window,
+                parameters,
+                default,
+            }
+        }
+        "cast" => {
+            token(i, "(")?;
+            let ty = ty(c, i)?;
+            token(i, ",")?;
+            let inner = expr(c, i)?;
+            token(i, ")")?;
+            ExprKind::Cast(ty, Box::new(inner))
+        }
+        "param" => {
+            token(i, "(")?;
+            let sr = stream(c, i)?;
+            token(i, ",")?;
+            let p = number(i)?;
+            token(i, ")")?;
+            ExprKind::ParameterAccess(sr, p)
+        }
+        "tuple" => ExprKind::Tuple(list(i, "(", ",", ")", |i| expr(c, i))?),
+        "some" => {
+            token(i, "(")?;
+            let inner = expr(c, i)?;
+            token(i, ")")?;
+            ExprKind::Some(Box::new(inner))
+        }
+        "unwrap_or" => {
+            token(i, "(")?;
+            let value = expr(c, i)?;
+            token(i, ",")?;
+            let default = expr(c, i)?;
+            token(i, ")")?;
+            ExprKind::UnwrapOr(Box::new(value), Box::new(default))
+        }
+        "lambda" => {
+            token(i, "(")?;
+            let wref = window_reference(c, i)?;
+            token(i, ",")?;
+            let p = number(i)?;
+            token(i, ")")?;
+            ExprKind::LambdaParameterAccess(wref, p)
+        }
+        name => {
+            if let Some((f, _)) = FUNCTIONS.iter().find(|(_, n)| *n == name) {
+                ExprKind::FunctionCall(*f, list(i, "(", ",", ")", |i| expr(c, i))?)
+            } else {
+                *i = start;
+                ExprKind::Constant(constant(c, i)?)
+            }
+        }
+    };
+    Ok((kind, None))
+}
+
+fn target(c: &Ctx, i: &mut &str) -> ModalResult<(StreamReference, Vec<Expr>)> {
+    let sr = stream(c, i)?;
+    let parameters = if i.starts_with('(') {
+        list(i, "(", ",", ")", |i| expr(c, i))?
+    } else {
+        Vec::new()
+    };
+    Ok((sr, parameters))
+}
+
+fn constant(c: &Ctx, i: &mut &str) -> ModalResult<Constant> {
+    ws(i)?;
+    if i.starts_with('"') {
+        return Ok(Constant::Str(string(c, i)?));
+    }
+    let start = *i;
+    let constant = match ident(i)? {
+        "true" => Constant::Bool(true),
+        "false" => Constant::Bool(false),
+        "const" => Constant::Tuple(list(i, "(", ",", ")", |i| constant(c, i))?),
+        "none" => Constant::None,
+        kind @ ("f32" | "f64") => {
+            token(i, "(")?;
+            ws(i)?;
+            let value_start = *i;
+            let (value, rest) = i.split_at(i.find(')').unwrap_or(i.len()));
+            *i = rest;
+            let Ok(value) = value.trim().parse() else {
+                return c.fail(value_start, format!("invalid float `{value}`"));
+            };
+            token(i, ")")?;
+            if kind == "f32" {
+                Constant::Float32(value)
+            } else {
+                Constant::Float64(value)
+            }
+        }
+        name => {
+            let bits = name.get(1..).and_then(|bits| bits.parse::<u16>().ok());
+            match (name.chars().next(), bits) {
+                (Some('u'), Some(bits)) => {
+                    token(i, "(")?;
+                    let value = number(i)?;
+                    token(i, ")")?;
+                    Constant::UInt(value, bits)
+                }
+                (Some('i'), Some(bits)) => {
+                    token(i, "(")?;
+                    let value = integer(i)?;
+                    token(i, ")")?;
+                    Constant::Int(value, bits)
+                }
+                _ => return c.fail(start, format!("expected an expression, found `{name}`")),
+            }
+        }
+    };
+    Ok(constant)
+}
+
+fn ty(c: &Ctx, i: &mut &str) -> ModalResult<Type> {
+    if try_token(i, "(")? {
+        let mut inner = Vec::new();
+        while !try_token(i, ")")? {
+            inner.push(ty(c, i)?);
+            if !try_token(i, ",")? {
+                token(i, ")")?;
+                break;
+            }
+        }
+        return Ok(Type::Tuple(inner));
+    }
+    ws(i)?;
+    let start = *i;
+    let ty = match ident(i)? {
+        "Bool" => Type::Bool,
+        "String" => Type::String,
+        "Float32" => Type::Float32,
+        "Float64" => Type::Float64,
+        "Bytes" => Type::Bytes,
+        "Option" => {
+            token(i, "<")?;
+            let inner = ty(c, i)?;
+            token(i, ">")?;
+            Type::Option(Box::new(inner))
+        }
+        name => match sized_type(name) {
+            Some(ty) => ty,
+            None => return c.fail(start, format!("unknown type `{name}`")),
+        },
+    };
+    Ok(ty)
+}
+
+/// Parses types with a number of bits, e.g., `UInt64`.
+fn sized_type(name: &str) -> Option<Type> {
+    let (kind, bits) = name.split_at(name.find(|c: char| c.is_ascii_digit())?);
+    let bits = bits.parse().ok()?;
+    match kind {
+        "Int" => Some(Type::Int(bits)),
+        "UInt" => Some(Type::UInt(bits)),
+        "Fixed" => Some(Type::Fixed(bits)),
+        "UFixed" => Some(Type::UFixed(bits)),
+        _ => None,
+    }
+}
+
+fn stream(c: &Ctx, i: &mut &str) -> ModalResult<StreamReference> {
+    ws(i)?;
+    let start = *i;
+    let kind = ident(i)?;
+    token(i, "[")?;
+    let idx = number(i)?;
+    token(i, "]")?;
+    let sr = match kind {
+        "in" => StreamReference::In(idx),
+        "out" => StreamReference::Out(OutputReference::Unparameterized(idx)),
+        "pout" => StreamReference::Out(OutputReference::Parameterized(idx)),
+        other => return c.fail(start, format!("expected a stream, found `{other}`")),
+    };
+    match c.memory.get() {
+        Some(memory) if !memory.contains_key(&sr) => {
+            let text = &start[..start.len() - i.len()];
+            c.fail(start, format!("unknown stream {text}"))
+        }
+        _ => Ok(sr),
+    }
+}
+
+fn output(c: &Ctx, i: &mut &str) -> ModalResult<OutputReference> {
+    ws(i)?;
+    let start = *i;
+    match stream(c, i)? {
+        StreamReference::Out(sr) => Ok(sr),
+        StreamReference::In(_) => c.fail(start, "expected an output stream"),
+    }
+}
+
+fn window_reference(c: &Ctx, i: &mut &str) -> ModalResult<WindowReference> {
+    ws(i)?;
+    let start = *i;
+    let kind = ident(i)?;
+    token(i, "[")?;
+    let idx = number(i)?;
+    token(i, "]")?;
+    match kind {
+        "sliding" => Ok(WindowReference::Sliding(idx)),
+        "discrete" => Ok(WindowReference::Discrete(idx)),
+        "instance" => Ok(WindowReference::Instance(idx)),
+        other => c.fail(start, format!("expected a window, found `{other}`")),
+    }
+}
+
+fn temporary(i: &mut &str) -> ModalResult<usize> {
+    token(i, "tmp")?;
+    token(i, "[")?;
+    let temp = number(i)?;
+    token(i, "]")?;
+    Ok(temp)
+}
+
+fn duration(c: &Ctx, i: &mut &str) -> ModalResult<Duration> {
+    ws(i)?;
+    let start = *i;
+    let value: u64 = number(i)?;
+    let end = i
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(i.len());
+    let (unit, rest) = i.split_at(end);
+    *i = rest;
+    let duration = match unit {
+        "s" => Duration::from_secs(value),
+        "ms" => Duration::from_millis(value),
+        "us" => Duration::from_micros(value),
+        "ns" => Duration::from_nanos(value),
+        other => return c.fail(start, format!("invalid duration unit `{other}`")),
+    };
+    Ok(duration)
+}
+
+fn boolean(c: &Ctx, i: &mut &str) -> ModalResult<bool> {
+    ws(i)?;
+    let start = *i;
+    match ident(i)? {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        other => c.fail(start, format!("expected a boolean, found `{other}`")),
+    }
+}
+
+fn string(c: &Ctx, i: &mut &str) -> ModalResult<String> {
+    token(i, "\"")?;
+    let rest = *i;
+    let mut s = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' => {
+                *i = &rest[idx + 1..];
+                return Ok(s);
+            }
+            '\\' => match chars.next() {
+                Some((_, '"')) => s.push('"'),
+                Some((_, '\\')) => s.push('\\'),
+                Some((_, 'n')) => s.push('\n'),
+                Some((_, 'r')) => s.push('\r'),
+                Some((_, 't')) => s.push('\t'),
+                _ => return c.fail(&rest[idx..], "invalid escape sequence"),
+            },
+            ch => s.push(ch),
+        }
+    }
+    c.fail(rest, "unterminated string")
+}
+
+/// Parses a list of items between `open` and `close` separated by `sep`.
+fn list<'i, T>(
+    i: &mut &'i str,
+    open: &'static str,
+    sep: &'static str,
+    close: &'static str,
+    mut item: impl FnMut(&mut &'i str) -> ModalResult<T>,
+) -> ModalResult<Vec<T>> {
+    token(i, open)?;
+    let mut items = Vec::new();
+    if try_token(i, close)? {
+        return Ok(items);
+    }
+    loop {
+        items.push(item(i)?);
+        if !try_token(i, sep)? {
+            token(i, close)?;
+            return Ok(items);
+        }
+    }
+}
+
+fn number<T: Uint>(i: &mut &str) -> ModalResult<T> {
+    ws(i)?;
+    dec_uint
+        .context(StrContext::Expected(StrContextValue::Description("number")))
+        .parse_next(i)
+}
+
+fn integer(i: &mut &str) -> ModalResult<i64> {
+    ws(i)?;
+    dec_int
+        .context(StrContext::Expected(StrContextValue::Description(
+            "integer",
+        )))
+        .parse_next(i)
+}
+
+fn ident<'i>(i: &mut &'i str) -> ModalResult<&'i str> {
+    ws(i)?;
+    take_while(1.., |c: char| c.is_ascii_alphanumeric() || c == '_')
+        .context(StrContext::Expected(StrContextValue::Description(
+            "identifier",
+        )))
+        .parse_next(i)
+}
+
+/// Consumes the keyword if the input starts with it.
+fn keyword(i: &mut &str, keyword: &str) -> ModalResult<bool> {
+    ws(i)?;
+    let found = i
+        .strip_prefix(keyword)
+        .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_'));
+    if found {
+        *i = &i[keyword.len()..];
+    }
+    Ok(found)
+}
+
+fn token(i: &mut &str, token: &'static str) -> ModalResult<()> {
+    ws(i)?;
+    token
+        .void()
+        .context(StrContext::Expected(StrContextValue::StringLiteral(token)))
+        .parse_next(i)
+}
+
+/// Consumes the token if the input starts with it.
+fn try_token(i: &mut &str, token: &'static str) -> ModalResult<bool> {
+    ws(i)?;
+    let found = i.starts_with(token);
+    if found {
+        *i = &i[token.len()..];
+    }
+    Ok(found)
+}
+
+/// Skips whitespace and line comments.
+fn ws(i: &mut &str) -> ModalResult<()> {
+    loop {
+        *i = i.trim_start();
+        match i.strip_prefix("//") {
+            Some(rest) => *i = rest.find('\n').map_or("", |n| &rest[n..]),
+            None => return Ok(()),
+        }
+    }
+}
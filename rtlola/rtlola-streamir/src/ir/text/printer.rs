@@ -0,0 +1,606 @@
+use std::{collections::HashMap, fmt::Write, time::Duration};
+
+use itertools::Itertools;
+
+use crate::ir::{
+    expressions::{Constant, Expr, ExprKind},
+    memory::{Memory, Parameter, StreamBuffer, StreamMemory},
+    windows::{InstanceSelection, Window, WindowKind, WindowOperation},
+    Accesses, Guard, IfStmt, Offset, Origin, OutputReference, Stmt, StreamAccessKind, StreamIr,
+    StreamReference, Task, Type, WindowReference,
+};
+
+use super::{function_name, inferred_type, operator_symbol, VERSION, WINDOW_OPERATIONS};
+
+const INDENT: &str = "    ";
+
+pub(super) fn print(ir: &StreamIr) -> String {
+    let printer = Printer {
+        memory: &ir.sr2memory,
+    };
+    let sections = [
+        format!("streamir {VERSION}"),
+        printer.streams(),
+        printer.triggers(&ir.triggers),
+        printer.local_frequencies(ir),
+        printer.windows(&ir.wref2window),
+        printer.livetime_equivalences(ir),
+        ir.static_schedule
+            .as_ref()
+            .map(|schedule| printer.schedule(schedule.hyper_period, &schedule.deadlines))
+            .unwrap_or_default(),
+        printer.accesses(ir),
+        format!("stmt\n{}", printer.stmt(&ir.stmt, 0)),
+    ];
+    let mut text = sections.into_iter().filter(|s| !s.is_empty()).join("\n\n");
+    text.push('\n');
+    text
+}
+
+struct Printer<'a> {
+    memory: &'a HashMap<StreamReference, Memory>,
+}
+
+impl Printer<'_> {
+    fn streams(&self) -> String {
+        self.memory
+            .iter()
+            .sorted_by_key(|(sr, _)| **sr)
+            .map(|(sr, memory)| {
+                let mut s = format!(
+                    "stream {} {}: {} = {}",
+                    stream(*sr),
+                    string(&memory.name),
+                    ty(&memory.ty),
+                    stream_memory(&memory.buffer)
+                );
+                if memory.delay > 0 {
+                    write!(s, " delay({})", memory.delay).unwrap();
+                }
+                s
+            })
+            .join("\n")
+    }
+
+    fn triggers(&self, triggers: &HashMap<OutputReference, usize>) -> String {
+        triggers
+            .iter()
+            .sorted()
+            .map(|(sr, idx)| format!("trigger {} = {idx}", stream(sr.sr())))
+            .join("\n")
+    }
+
+    fn local_frequencies(&self, ir: &StreamIr) -> String {
+        ir.lref2lfreq
+            .iter()
+            .sorted_by_key(|(lref, _)| **lref)
+            .map(|(lref, freq)| {
+                format!(
+                    "local_freq {lref} = {} for {}",
+                    duration(freq.dur),
+                    stream(freq.sr.sr())
+                )
+            })
+            .join("\n")
+    }
+
+    fn windows(&self, windows: &HashMap<WindowReference, Window>) -> String {
+        windows
+            .values()
+            .sorted_by_key(|w| w.wref)
+            .map(|w| {
+                format!(
+                    "window {}: {} = {}({}) over {} caller {} {} pacing {}",
+                    window(w.wref),
+                    ty(&w.ty),
+                    window_operation(w.op),
+                    stream(w.target),
+                    self.window_kind(&w.kind),
+                    stream(w.caller),
+                    origin(w.origin),
+                    self.guard(&w.origin_pacing)
+                )
+            })
+            .join("\n")
+    }
+
+    fn window_kind(&self, kind: &WindowKind) -> String {
+        match kind {
+            WindowKind::Sliding {
+                duration: dur,
+                bucket_count,
+                bucket_duration,
+                wait,
+            } => format!(
+                "sliding({}, {bucket_count}, {}, {})",
+                duration(*dur),
+                duration(*bucket_duration),
+                waiting(*wait)
+            ),
+            WindowKind::Discrete { num_values, wait } => {
+                format!("discrete({num_values}, {})", waiting(*wait))
+            }
+            WindowKind::Instances { selection } => match selection {
+                InstanceSelection::All => "instances(all)".into(),
+                InstanceSelection::Fresh => "instances(fresh)".into(),
+                InstanceSelection::FilteredAll { parameters, cond } => {
+                    format!(
+                        "instances(all, ({}), {})",
+                        self::parameters(parameters),
+                        self.expr(cond)
+                    )
+                }
+                InstanceSelection::FilteredFresh { parameters, cond } => {
+                    format!(
+                        "instances(fresh, ({}), {})",
+                        self::parameters(parameters),
+                        self.expr(cond)
+                    )
+                }
+            },
+        }
+    }
+
+    fn livetime_equivalences(&self, ir: &StreamIr) -> String {
+        let equivalences = &ir.livetime_equivalences;
+        let outputs = equivalences
+            .idx
+            .iter()
+            .map(|(sr, idx)| (*idx, *sr))
+            .collect::<HashMap<_, _>>();
+        equivalences
+            .sets
+            .sets()
+            .into_iter()
+            .filter_map(|set| {
+                let inputs = set.contains(&equivalences.input_idx);
+                let members = set
+                    .iter()
+                    .filter_map(|idx| outputs.get(idx))
+                    .sorted()
+                    .map(|sr| stream(sr.sr()));
+                let members = inputs
+                    .then(|| "inputs".to_string())
+                    .into_iter()
+                    .chain(members)
+                    .collect::<Vec<_>>();
+                // streams without equivalent streams are not printed
+                (members.len() > 1).then(|| format!("livetime ({})", members.join(", ")))
+            })
+            .sorted()
+            .join("\n")
+    }
+
+    fn schedule(&self, hyper_period: Duration, deadlines: &[crate::ir::Deadline]) -> String {
+        let deadlines = deadlines.iter().map(|deadline| {
+            let due = deadline.due.iter().map(|task| match task {
+                Task::Spawn(sr) => format!("spawn {}", stream(sr.sr())),
+                Task::Eval(sr) => format!("eval {}", stream(sr.sr())),
+                Task::Close(sr) => format!("close {}", stream(sr.sr())),
+            });
+            format!(
+                "\n{INDENT}after {} ({})",
+                duration(deadline.pause),
+                due.format(", ")
+            )
+        });
+        format!(
+            "schedule {}{}",
+            duration(hyper_period),
+            deadlines.format("")
+        )
+    }
+
+    fn accesses(&self, ir: &StreamIr) -> String {
+        let accesses = ir
+            .accesses
+            .iter()
+            .sorted_by_key(|(sr, _)| **sr)
+            .map(|(sr, accesses)| {
+                format!("accesses {} ({})", stream(*sr), self::accesses(accesses))
+            });
+        let accessed_by =
+            ir.accessed_by
+                .iter()
+                .sorted_by_key(|(sr, _)| **sr)
+                .map(|(sr, accesses)| {
+                    format!("accessed_by {} ({})", stream(*sr), self::accesses(accesses))
+                });
+        accesses.chain(accessed_by).join("\n")
+    }
+
+    fn stmt(&self, stmt: &Stmt, indent: usize) -> String {
+        let mut s = INDENT.repeat(indent);
+        match stmt {
+            Stmt::Skip => s.push_str("skip"),
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                s.push_str(if matches!(stmt, Stmt::Seq(_)) {
+                    "seq {"
+                } else {
+                    "par {"
+                });
+                if !stmts.is_empty() {
+                    for stmt in stmts {
+                        write!(s, "\n{};", self.stmt(stmt, indent + 1)).unwrap();
+                    }
+                    write!(s, "\n{}", INDENT.repeat(indent)).unwrap();
+                }
+                s.push('}');
+            }
+            Stmt::Shift(sr) => write!(s, "shift {}", stream(*sr)).unwrap(),
+            Stmt::Input(i) => write!(s, "input {}", stream(StreamReference::In(*i))).unwrap(),
+            Stmt::Spawn {
+                sr,
+                with,
+                local_frequencies,
+                windows,
+            } => {
+                write!(s, "spawn {}", stream(sr.sr())).unwrap();
+                if let Some(with) = with {
+                    write!(s, " with ({})", self.exprs(with)).unwrap();
+                }
+                s.push_str(&clocks(local_frequencies, windows));
+            }
+            Stmt::Eval { sr, with, idx } => {
+                write!(s, "eval({idx}) {} = {}", stream(sr.sr()), self.expr(with)).unwrap()
+            }
+            Stmt::Close {
+                sr,
+                local_frequencies,
+                windows,
+            } => {
+                write!(s, "close {}", stream(sr.sr())).unwrap();
+                s.push_str(&clocks(local_frequencies, windows));
+            }
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                write!(
+                    s,
+                    "if {} then\n{}",
+                    self.guard(guard),
+                    self.stmt(cons, indent + 1)
+                )
+                .unwrap();
+                if !matches!(**alt, Stmt::Skip) {
+                    write!(
+                        s,
+                        "\n{}else\n{}",
+                        INDENT.repeat(indent),
+                        self.stmt(alt, indent + 1)
+                    )
+                    .unwrap();
+                }
+                write!(s, "\n{}fi", INDENT.repeat(indent)).unwrap();
+            }
+            Stmt::Iterate { sr, stmt } => write!(
+                s,
+                "iterate ({})\n{}",
+                sr.iter().map(|sr| stream(sr.sr())).format(", "),
+                self.stmt(stmt, indent + 1)
+            )
+            .unwrap(),
+            Stmt::Assign {
+                parameter_expr,
+                sr,
+                stmt,
+            } => write!(
+                s,
+                "assign ({}) = ({})\n{}",
+                sr.iter().map(|sr| stream(sr.sr())).format(", "),
+                self.exprs(parameter_expr),
+                self.stmt(stmt, indent + 1)
+            )
+            .unwrap(),
+            Stmt::Let { temp, expr } => write!(s, "let tmp[{temp}] = {}", self.expr(expr)).unwrap(),
+        }
+        s
+    }
+
+    fn guard(&self, guard: &Guard) -> String {
+        match guard {
+            Guard::Stream(sr) => format!("@{}", stream(*sr)),
+            Guard::Alive(sr) => format!("alive({})", stream(*sr)),
+            Guard::Dynamic(expr) => format!("dynamic({})", self.expr(expr)),
+            Guard::GlobalFreq(dur) => format!("global({})", duration(*dur)),
+            Guard::LocalFreq(lref) => format!("local({lref})"),
+            Guard::And { lhs, rhs } => format!("({} && {})", self.guard(lhs), self.guard(rhs)),
+            Guard::Or { lhs, rhs } => format!("({} || {})", self.guard(lhs), self.guard(rhs)),
+            Guard::Constant(b) => b.to_string(),
+            Guard::FastAnd(srs) => format!(
+                "fast_and({})",
+                srs.iter().map(|sr| stream(*sr)).format(", ")
+            ),
+            Guard::FastOr(srs) => {
+                format!("fast_or({})", srs.iter().map(|sr| stream(*sr)).format(", "))
+            }
+        }
+    }
+
+    fn exprs(&self, exprs: &[Expr]) -> String {
+        exprs.iter().map(|e| self.expr(e)).join(", ")
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        let kind = self.expr_kind(&expr.kind);
+        if inferred_type(&expr.kind, self.memory).as_ref() == Some(&expr.ty) {
+            kind
+        } else {
+            format!("({kind} :: {})", ty(&expr.ty))
+        }
+    }
+
+    fn expr_kind(&self, kind: &ExprKind) -> String {
+        match kind {
+            ExprKind::Constant(c) => constant(c),
+            ExprKind::BinaryOperation(op, lhs, rhs) => format!(
+                "({} {} {})",
+                self.expr(lhs),
+                operator_symbol(*op),
+                self.expr(rhs)
+            ),
+            ExprKind::UnaryOperation(op, inner) => {
+                format!("{}{}", operator_symbol(*op), self.expr(inner))
+            }
+            ExprKind::Ite(cond, cons, alt) => format!(
+                "if({}, {}, {})",
+                self.expr(cond),
+                self.expr(cons),
+                self.expr(alt)
+            ),
+            ExprKind::SyncStreamAccess { target, parameters } => self.target(*target, parameters),
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => format!(
+                "offset({}, {offset}, {})",
+                self.target(*target, parameters),
+                self.expr(default)
+            ),
+            ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            } => format!(
+                "hold({}, {})",
+                self.target(*target, parameters),
+                self.expr(default)
+            ),
+            ExprKind::IsFresh { target, parameters } => {
+                format!("fresh({})", self.target(*target, parameters))
+            }
+            ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => format!(
+                "get({}, {})",
+                self.target(*target, parameters),
+                self.expr(default)
+            ),
+            ExprKind::WindowAccess {
+                target,
+                window: wref,
+                parameters,
+                default,
+            } => {
+                let default = default
+                    .as_ref()
+                    .map(|d| format!(", {}", self.expr(d)))
+                    .unwrap_or_default();
+                format!(
+                    "window({}, {}{default})",
+                    self.target(*target, parameters),
+                    window(*wref)
+                )
+            }
+            ExprKind::Cast(target, inner) => format!("cast({}, {})", ty(target), self.expr(inner)),
+            ExprKind::ParameterAccess(sr, p) => format!("param({}, {p})", stream(*sr)),
+            ExprKind::FunctionCall(f, args) => {
+                format!("{}({})", function_name(*f), self.exprs(args))
+            }
+            ExprKind::Tuple(inner) => format!("tuple({})", self.exprs(inner)),
+            ExprKind::TupleAccess(inner, i) => {
+                if matches!(inner.kind, ExprKind::UnaryOperation(..))
+                    && inferred_type(&inner.kind, self.memory).as_ref() == Some(&inner.ty)
+                {
+                    format!("({}).{i}", self.expr(inner))
+                } else {
+                    format!("{}.{i}", self.expr(inner))
+                }
+            }
+            ExprKind::LambdaParameterAccess(wref, i) => format!("lambda({}, {i})", window(*wref)),
+            ExprKind::Temporary(t) => format!("tmp[{t}]"),
+            ExprKind::Some(inner) => format!("some({})", self.expr(inner)),
+            ExprKind::UnwrapOr(expr, default) => {
+                format!("unwrap_or({}, {})", self.expr(expr), self.expr(default))
+            }
+        }
+    }
+
+    fn target(&self, target: StreamReference, parameters: &[Expr]) -> String {
+        if parameters.is_empty() {
+            stream(target)
+        } else {
+            format!("{}({})", stream(target), self.exprs(parameters))
+        }
+    }
+}
+
+fn stream(sr: StreamReference) -> String {
+    match sr {
+        StreamReference::In(i) => format!("in[{i}]"),
+        StreamReference::Out(OutputReference::Unparameterized(i)) => format!("out[{i}]"),
+        StreamReference::Out(OutputReference::Parameterized(i)) => format!("pout[{i}]"),
+    }
+}
+
+fn window(wref: WindowReference) -> String {
+    match wref {
+        WindowReference::Sliding(i) => format!("sliding[{i}]"),
+        WindowReference::Discrete(i) => format!("discrete[{i}]"),
+        WindowReference::Instance(i) => format!("instance[{i}]"),
+    }
+}
+
+fn window_operation(op: WindowOperation) -> String {
+    match op {
+        WindowOperation::NthPercentile(n) => format!("nth_percentile[{n}]"),
+        op => WINDOW_OPERATIONS
+            .iter()
+            .find(|(o, _)| *o == op)
+            .unwrap()
+            .1
+            .to_string(),
+    }
+}
+
+fn waiting(wait: bool) -> &'static str {
+    if wait {
+        "wait"
+    } else {
+        "nowait"
+    }
+}
+
+fn origin(origin: Origin) -> String {
+    match origin {
+        Origin::Spawn => "spawn".into(),
+        Origin::EvalWhen(i) => format!("eval_when({i})"),
+        Origin::EvalWith(i) => format!("eval_with({i})"),
+        Origin::Close => "close".into(),
+    }
+}
+
+fn clocks(local_frequencies: &[usize], windows: &[WindowReference]) -> String {
+    let mut s = String::new();
+    if !local_frequencies.is_empty() {
+        write!(s, " clocks ({})", local_frequencies.iter().format(", ")).unwrap();
+    }
+    if !windows.is_empty() {
+        write!(
+            s,
+            " windows ({})",
+            windows.iter().map(|w| window(*w)).format(", ")
+        )
+        .unwrap();
+    }
+    s
+}
+
+fn accesses(accesses: &Accesses) -> String {
+    accesses
+        .iter()
+        .map(|(sr, kinds)| {
+            let kinds = kinds.iter().map(|(o, kind)| {
+                let kind = match kind {
+                    StreamAccessKind::Sync => "sync".into(),
+                    StreamAccessKind::DiscreteWindow(w)
+                    | StreamAccessKind::SlidingWindow(w)
+                    | StreamAccessKind::InstanceAggregation(w) => window(*w),
+                    StreamAccessKind::Hold => "hold".into(),
+                    StreamAccessKind::Offset(Offset::Past(o)) => format!("offset(past {o})"),
+                    StreamAccessKind::Offset(Offset::Future(o)) => format!("offset(future {o})"),
+                    StreamAccessKind::Get => "get".into(),
+                    StreamAccessKind::Fresh => "fresh".into(),
+                };
+                format!("{} {kind}", origin(*o))
+            });
+            format!("{}: {}", stream(*sr), kinds.format(", "))
+        })
+        .join("; ")
+}
+
+fn stream_memory(memory: &StreamMemory) -> String {
+    match memory {
+        StreamMemory::NoMemory => "none".into(),
+        StreamMemory::Static(buffer) => format!("static {}", stream_buffer(*buffer)),
+        StreamMemory::Dynamic {
+            buffer,
+            has_spawn,
+            has_close,
+        } => format!(
+            "dynamic(spawn: {has_spawn}, close: {has_close}) {}",
+            stream_buffer(*buffer)
+        ),
+        StreamMemory::Instances { buffer, parameter } => {
+            format!(
+                "instances({}) {}",
+                parameters(parameter),
+                stream_buffer(*buffer)
+            )
+        }
+    }
+}
+
+fn stream_buffer(buffer: StreamBuffer) -> String {
+    match buffer {
+        StreamBuffer::SingleValue => "single".into(),
+        StreamBuffer::Bounded(n) => format!("bounded({n})"),
+        StreamBuffer::UnBounded => "unbounded".into(),
+    }
+}
+
+fn parameters(parameters: &[Parameter]) -> String {
+    parameters
+        .iter()
+        .map(|p| format!("{}: {}", string(&p.name), ty(&p.ty)))
+        .join(", ")
+}
+
+pub(super) fn ty(ty: &Type) -> String {
+    match ty {
+        Type::Int(bits) => format!("Int{bits}"),
+        Type::UInt(bits) => format!("UInt{bits}"),
+        Type::Bool => "Bool".into(),
+        Type::String => "String".into(),
+        Type::Float32 => "Float32".into(),
+        Type::Float64 => "Float64".into(),
+        Type::Fixed(bits) => format!("Fixed{bits}"),
+        Type::UFixed(bits) => format!("UFixed{bits}"),
+        Type::Option(inner) => format!("Option<{}>", self::ty(inner)),
+        Type::Tuple(inner) if inner.len() == 1 => format!("({},)", self::ty(&inner[0])),
+        Type::Tuple(inner) => format!("({})", inner.iter().map(self::ty).join(", ")),
+        Type::Bytes => "Bytes".into(),
+    }
+}
+
+fn constant(c: &Constant) -> String {
+    match c {
+        Constant::Str(s) => string(s),
+        Constant::Bool(b) => b.to_string(),
+        Constant::UInt(v, bits) => format!("u{bits}({v})"),
+        Constant::Int(v, bits) => format!("i{bits}({v})"),
+        Constant::Float32(v) => format!("f32({v:?})"),
+        Constant::Float64(v) => format!("f64({v:?})"),
+        Constant::Tuple(inner) => format!("const({})", inner.iter().map(constant).join(", ")),
+        Constant::None => "none".into(),
+    }
+}
+
+/// Prints a duration in the largest unit that represents it exactly.
+pub(super) fn duration(dur: Duration) -> String {
+    let nanos = dur.as_nanos();
+    [(1_000_000_000, "s"), (1_000_000, "ms"), (1_000, "us")]
+        .into_iter()
+        .find(|(factor, _)| nanos.is_multiple_of(*factor))
+        .map(|(factor, unit)| format!("{}{unit}", nanos / factor))
+        .unwrap_or_else(|| format!("{nanos}ns"))
+}
+
+fn string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
@@ -6,7 +6,8 @@ use super::{
     expressions::Expr, memory::Parameter, Guard, Origin, StreamReference, Type, WindowReference,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The representation of any window
 pub struct Window {
     /// The reference of the window
@@ -28,6 +29,7 @@ pub struct Window {
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The window operation
 pub enum WindowOperation {
     /// A sum aggregation
@@ -82,7 +84,8 @@ impl WindowOperation {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The kind of a window
 pub enum WindowKind {
     /// The window is a sliding window
@@ -110,7 +113,8 @@ pub enum WindowKind {
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The instance selection of a instance aggregation
 pub enum InstanceSelection {
     /// All values are aggregated
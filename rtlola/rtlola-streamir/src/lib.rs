@@ -18,15 +18,17 @@ use formatter::StreamIrFormatter;
 use ir::{LoweringError, StreamIr};
 use rewrite_rules::{
     CombineIf, CombineIterate, CombineNestedIf, CombineSeq, FastGuards, ImpliedGuards,
-    IterateAssign, MemoryOptimizations, MoveIfOutside, RemoveClose, RemoveIfs, RemoveShift,
-    RemoveSkip, RewriteError, RewriteRule, Rewriter, SimplifyGuard,
+    IterateAssign, MemoryOptimizations, MoveIfOutside, PassManager, RemoveClose, RemoveShift,
+    RemoveSkip, RewriteError, RewriteRule, SimplifyGuard,
 };
 use rewrite_rules::{MoveCommonGuardsOutside, RemoveSpawn};
 use rtlola_frontend::RtLolaError;
 pub use rtlola_frontend::{FrontendConfig, MemoryBoundMode, ParserConfigExt};
-pub use rtlola_frontend::{Handler, ParserConfig};
+pub use rtlola_frontend::{Handler, ParserConfig, Span};
 use thiserror::Error;
 
+pub mod capabilities;
+pub mod diagnostics;
 pub mod formatter;
 pub mod ir;
 pub mod rewrite_rules;
@@ -53,35 +55,45 @@ pub fn parse<'a>(config: impl Into<FrontendConfig<'a>>) -> Result<StreamIr, Pars
 }
 
 /// Applies all general optimizations to the given StreamIR.
+///
+/// The rules repairing the properties invalidated by a rule are inserted automatically (see [PassManager]).
+/// Compared to version 0.1.0, which applied a fixed list of rules, the resulting StreamIR differs in that
+/// - the skips left by RemoveShift are removed,
+/// - the guards introduced by FastGuards are simplified, and
+/// - conjunctions built by CombineNestedIf may be associated differently.
 pub fn optimize_all(ir: StreamIr) -> Result<StreamIr, RewriteError> {
-    let ir = optimize(
-        ir,
-        vec![
-            Box::new(CombineIf),
-            Box::new(SimplifyGuard),
-            Box::new(MoveCommonGuardsOutside),
-            Box::new(ImpliedGuards),
-            Box::new(SimplifyGuard),
-            Box::new(RemoveIfs),
-            Box::new(CombineSeq),
-            Box::new(MoveIfOutside),
-            Box::new(IterateAssign),
-            Box::new(CombineNestedIf),
-            Box::new(CombineIterate),
-            Box::new(RemoveIfs),
-            Box::new(RemoveShift),
-            Box::new(MemoryOptimizations),
-            Box::new(RemoveSpawn),
-            Box::new(RemoveClose),
-        ],
-    )?;
-    optimize(ir, vec![Box::new(FastGuards)])
+    PassManager::new()
+        .stage(general_optimizations())
+        .stage(vec![Box::new(FastGuards)])
+        .run(ir)
+}
+
+/// The rewriting rules applied by [optimize_all] before introducing fast guards.
+///
+/// The rules repairing the properties invalidated by each rule are inserted by the [PassManager].
+pub(crate) fn general_optimizations() -> Vec<Box<dyn RewriteRule>> {
+    vec![
+        Box::new(CombineIf),
+        Box::new(SimplifyGuard),
+        Box::new(MoveCommonGuardsOutside),
+        Box::new(ImpliedGuards),
+        Box::new(CombineSeq),
+        Box::new(MoveIfOutside),
+        Box::new(IterateAssign),
+        Box::new(CombineNestedIf),
+        Box::new(CombineIterate),
+        Box::new(RemoveShift),
+        Box::new(MemoryOptimizations),
+        Box::new(RemoveSpawn),
+        Box::new(RemoveClose),
+    ]
 }
 
 /// Applies a given list of rewriting rules to the StreamIR.
+///
+/// Fails without changing the StreamIR if the requirements of a rule are not met.
 pub fn optimize(ir: StreamIr, rules: Vec<Box<dyn RewriteRule>>) -> Result<StreamIr, RewriteError> {
-    let rewriter = Rewriter::new(rules);
-    rewriter.run(ir)
+    PassManager::new().stage(rules).run(ir)
 }
 
 /// Uses the given formatter for translating the StreamIR to the target language.
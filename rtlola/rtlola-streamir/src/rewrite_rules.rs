@@ -1,9 +1,14 @@
 //! A framework for optimizing the StreamIR through rewriting rules
 //! Applies the rewriting rules alternating until no rule does any changes anymore.
+//!
+//! Rules that declare the statement kinds they match (see [RewriteRule::matched_kinds]) are applied incrementally:
+//! subtrees that do not contain a matching statement, or that the rule already left unchanged before, are not revisited.
+//!
+//! Pipelines of several rewriting stages are validated and run by the [PassManager].
 
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    ops::{Add, AddAssign},
+    collections::{HashMap, HashSet},
+    ops::{Add, AddAssign, BitOr},
 };
 
 use thiserror::Error;
@@ -24,6 +29,8 @@ mod combine_if;
 pub use combine_if::CombineIf;
 mod combine_iterate;
 pub use combine_iterate::CombineIterate;
+mod common_subexpressions;
+pub use common_subexpressions::CommonSubexpressions;
 mod combine_seq;
 pub use combine_seq::CombineSeq;
 mod fast_guards;
@@ -32,12 +39,20 @@ mod if_outside;
 pub use if_outside::MoveIfOutside;
 mod implied_guards;
 pub use implied_guards::ImpliedGuards;
+mod incremental;
+use incremental::IncrementalState;
+mod inline_streams;
+pub use inline_streams::InlineStreams;
 mod memory_optimizations;
 pub use memory_optimizations::MemoryOptimizations;
 mod nested_ifs;
 pub use nested_ifs::CombineNestedIf;
 mod partial_evaluation;
 pub use partial_evaluation::EvaluateGuards;
+mod pass_manager;
+pub use pass_manager::{PassManager, PipelineError, Property};
+mod range_guards;
+pub use range_guards::RangeGuards;
 mod remove_ifs;
 pub use remove_ifs::RemoveIfs;
 mod remove_shift;
@@ -83,6 +98,11 @@ impl ChangeSet {
             global_instructions: HashSet::new(),
         }
     }
+
+    /// Returns whether nothing changed
+    fn is_empty(&self) -> bool {
+        !self.local_change && self.global_instructions.is_empty()
+    }
 }
 
 impl Add<ChangeSet> for ChangeSet {
@@ -119,11 +139,115 @@ pub enum RewriteError {
     #[error("other error: {0}")]
     /// An error that does not fit any of the other categories
     Other(String),
+    #[error("invalid pipeline: {0}")]
+    /// The pipeline of rewriting rules is invalid
+    InvalidPipeline(PipelineError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+/// A set of kinds of statements, used by rewriting rules to declare on which statements they match.
+pub struct StmtKinds(u16);
+
+impl StmtKinds {
+    /// No statement at all
+    pub const NONE: Self = Self(0);
+    /// [Stmt::Skip]
+    pub const SKIP: Self = Self(1 << 0);
+    /// [Stmt::Seq]
+    pub const SEQ: Self = Self(1 << 1);
+    /// [Stmt::Parallel]
+    pub const PARALLEL: Self = Self(1 << 2);
+    /// [Stmt::Shift]
+    pub const SHIFT: Self = Self(1 << 3);
+    /// [Stmt::Input]
+    pub const INPUT: Self = Self(1 << 4);
+    /// [Stmt::Spawn]
+    pub const SPAWN: Self = Self(1 << 5);
+    /// [Stmt::Eval]
+    pub const EVAL: Self = Self(1 << 6);
+    /// [Stmt::Close]
+    pub const CLOSE: Self = Self(1 << 7);
+    /// [Stmt::If], including the rewriting of its guard
+    pub const IF: Self = Self(1 << 8);
+    /// [Stmt::Iterate]
+    pub const ITERATE: Self = Self(1 << 9);
+    /// [Stmt::Assign]
+    pub const ASSIGN: Self = Self(1 << 10);
+    /// [Stmt::Let]
+    pub const LET: Self = Self(1 << 11);
+    /// All statements
+    pub const ALL: Self = Self((1 << 12) - 1);
+
+    /// Returns the kind of the given statement
+    pub fn of(stmt: &Stmt) -> Self {
+        match stmt {
+            Stmt::Skip => Self::SKIP,
+            Stmt::Seq(_) => Self::SEQ,
+            Stmt::Parallel(_) => Self::PARALLEL,
+            Stmt::Shift(_) => Self::SHIFT,
+            Stmt::Input(_) => Self::INPUT,
+            Stmt::Spawn { .. } => Self::SPAWN,
+            Stmt::Eval { .. } => Self::EVAL,
+            Stmt::Close { .. } => Self::CLOSE,
+            Stmt::If(_) => Self::IF,
+            Stmt::Iterate { .. } => Self::ITERATE,
+            Stmt::Assign { .. } => Self::ASSIGN,
+            Stmt::Let { .. } => Self::LET,
+        }
+    }
+
+    /// Returns whether the two sets have a common kind
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for StmtKinds {
+    type Output = StmtKinds;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
 }
 
 /// A trait representing a rewriting rule
 /// Desribes rewriting to different parts of the StreamIR.
 pub trait RewriteRule: std::fmt::Debug {
+    /// The name of the rewriting rule.
+    fn name(&self) -> &'static str {
+        let path = std::any::type_name::<Self>();
+        path.rsplit("::").next().unwrap_or(path)
+    }
+
+    /// The properties of the StreamIR that must hold whenever the rule is applied.
+    fn requires(&self) -> Vec<Property> {
+        Vec::new()
+    }
+
+    /// The properties of the StreamIR that hold once the rule reached a fixed point.
+    fn produces(&self) -> Vec<Property> {
+        Vec::new()
+    }
+
+    /// The properties of the StreamIR that may no longer hold after applying the rule.
+    ///
+    /// Properties that have an establishing rule (see [Property::establishing_rule]) are repaired
+    /// by applying that rule directly after this one.
+    fn invalidates(&self) -> Vec<Property> {
+        Vec::new()
+    }
+
+    /// The kinds of statements [RewriteRule::rewrite_stmt] (and [RewriteRule::rewrite_guard] for [StmtKinds::IF]) can change.
+    ///
+    /// If `Some`, the rewriter assumes that rewriting a statement only depends on the statement itself and the memory,
+    /// and only revisits subtrees that contain a matching statement and changed since the rule was last applied to them.
+    /// [RewriteRule::rewrite_stmt] must then report every change it makes, as an unreported change is not tracked.
+    /// Rules that override [RewriteRule::apply_stmt] must return `None`, in which case
+    /// [RewriteRule::apply_stmt] is called on the whole statement in every iteration and must report every change it makes.
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        None
+    }
+
     /// Rewrite a statement.
     /// Is called recursively for all children automatically.
     fn rewrite_stmt(
@@ -179,7 +303,8 @@ pub trait RewriteRule: std::fmt::Debug {
             | Stmt::Shift(_)
             | Stmt::Spawn { .. }
             | Stmt::Eval { .. }
-            | Stmt::Close { .. }) => old,
+            | Stmt::Close { .. }
+            | Stmt::Let { .. }) => old,
             Stmt::Seq(stmts) => {
                 let inner = stmts
                     .into_iter()
@@ -288,55 +413,63 @@ pub trait RewriteRule: std::fmt::Debug {
         let mut cs = ChangeSet::default();
         let new_memory = memory
             .into_iter()
-            .map(|(sr, Memory { buffer, ty, name })| {
-                let (new_buffer, cur_cs) = self.rewrite_memory(sr, buffer)?;
-                let new_buffer = match new_buffer {
-                    StreamMemory::NoMemory => StreamMemory::NoMemory,
-                    StreamMemory::Static(buffer) => {
-                        let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
-                        cs += cur_cs;
-                        StreamMemory::Static(new_buffer)
-                    }
-                    StreamMemory::Dynamic {
+            .map(
+                |(
+                    sr,
+                    Memory {
                         buffer,
-                        has_spawn,
-                        has_close,
-                    } => {
-                        let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
-                        cs += cur_cs;
+                        ty,
+                        name,
+                        delay,
+                        span,
+                    },
+                )| {
+                    let (new_buffer, cur_cs) = self.rewrite_memory(sr, buffer)?;
+                    let new_buffer = match new_buffer {
+                        StreamMemory::NoMemory => StreamMemory::NoMemory,
+                        StreamMemory::Static(buffer) => {
+                            let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
+                            cs += cur_cs;
+                            StreamMemory::Static(new_buffer)
+                        }
                         StreamMemory::Dynamic {
-                            buffer: new_buffer,
+                            buffer,
                             has_spawn,
                             has_close,
+                        } => {
+                            let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
+                            cs += cur_cs;
+                            StreamMemory::Dynamic {
+                                buffer: new_buffer,
+                                has_spawn,
+                                has_close,
+                            }
                         }
-                    }
-                    StreamMemory::Instances { buffer, parameter } => {
-                        let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
-                        cs += cur_cs;
-                        StreamMemory::Instances {
-                            buffer: new_buffer,
-                            parameter,
+                        StreamMemory::Instances { buffer, parameter } => {
+                            let (new_buffer, cur_cs) = self.rewrite_buffer(sr, buffer)?;
+                            cs += cur_cs;
+                            StreamMemory::Instances {
+                                buffer: new_buffer,
+                                parameter,
+                            }
                         }
-                    }
-                };
-                cs += cur_cs;
-                Ok((
-                    sr,
-                    Memory {
-                        buffer: new_buffer,
-                        ty,
-                        name,
-                    },
-                ))
-            })
+                    };
+                    cs += cur_cs;
+                    Ok((
+                        sr,
+                        Memory {
+                            buffer: new_buffer,
+                            ty,
+                            name,
+                            delay,
+                            span,
+                        },
+                    ))
+                },
+            )
             .collect::<Result<_, _>>()?;
         Ok((new_memory, cs))
     }
-
-    /// A set of rewrite rules that are applied directly after the given rewrite rules
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        Vec::new()
-    }
 }
 
 #[derive(Debug)]
@@ -346,39 +479,38 @@ pub struct Rewriter {
 }
 
 impl Rewriter {
-    /// Construct a new Rewriter with the given rules.
-    pub fn new(rules: Vec<Box<dyn RewriteRule>>) -> Self {
-        let original_length = rules.len();
-        let mut stack = rules
-            .into_iter()
-            .rev()
-            .collect::<VecDeque<Box<dyn RewriteRule>>>();
-        let mut rules = Vec::new();
-        while let Some(rule) = stack.pop_back() {
-            let cleanup = rule.cleanup_rules();
-            rules.push(rule);
-            stack.extend(cleanup);
-            if rules.len() > original_length * 10 {
-                panic!("possible infinite loop in rewrite rule expansion")
-            }
-        }
-        Self { rules }
+    /// Construct a new Rewriter with the given rules, inserting the rules that repair the properties
+    /// invalidated by each rule directly after it.
+    pub fn try_new(rules: Vec<Box<dyn RewriteRule>>) -> Result<Self, PipelineError> {
+        let rules = pass_manager::expand(rules)?;
+        Ok(Self { rules })
     }
 
     /// Run the rewriting on the given StreamIR until a fixedpoint is reached.
     pub fn run(&self, mut ir: StreamIr) -> Result<StreamIr, RewriteError> {
+        let mut state = IncrementalState::new(self.rules.len());
         let mut changed = true;
         while changed {
-            (ir, changed) = self.apply(ir)?;
+            (ir, changed) = self.apply_incremental(ir, &mut state)?;
         }
         Ok(ir)
     }
 
     /// Apply all rewriting rules once. Returns the resulting StreamIR and a boolean indicating
     /// whether something changed for any of the rules.
-    fn apply(&self, mut ir: StreamIr) -> Result<(StreamIr, bool), RewriteError> {
+    #[cfg(test)]
+    fn apply(&self, ir: StreamIr) -> Result<(StreamIr, bool), RewriteError> {
+        self.apply_incremental(ir, &mut IncrementalState::new(self.rules.len()))
+    }
+
+    /// Apply all rewriting rules once, skipping the subtrees that are known to be stable for a rule.
+    fn apply_incremental(
+        &self,
+        mut ir: StreamIr,
+        state: &mut IncrementalState,
+    ) -> Result<(StreamIr, bool), RewriteError> {
         let mut changed = false;
-        for rule in &self.rules {
+        for (i, rule) in self.rules.iter().enumerate() {
             let StreamIr {
                 stmt,
                 sr2memory,
@@ -392,7 +524,27 @@ impl Rewriter {
             } = ir;
 
             let (sr2memory, cs_memory) = rule.apply_memory(sr2memory, &livetime_equivalences)?;
-            let (stmt, cs_stmt) = rule.apply_stmt(stmt, &sr2memory, &livetime_equivalences)?;
+            if !cs_memory.is_empty() {
+                // the rewriting of statements may depend on the memory
+                state.invalidate_memory();
+            }
+            let (stmt, cs_stmt) = match rule.matched_kinds() {
+                Some(kinds) => state.apply_stmt(
+                    i,
+                    rule.as_ref(),
+                    kinds,
+                    stmt,
+                    &sr2memory,
+                    &livetime_equivalences,
+                )?,
+                None => {
+                    let (stmt, cs) = rule.apply_stmt(stmt, &sr2memory, &livetime_equivalences)?;
+                    if !cs.is_empty() {
+                        state.invalidate_stmt();
+                    }
+                    (stmt, cs)
+                }
+            };
 
             ir = StreamIr {
                 stmt,
@@ -410,6 +562,9 @@ impl Rewriter {
                 global_instructions,
             } = cs_memory + cs_stmt;
             changed |= local_change || !global_instructions.is_empty();
+            if !global_instructions.is_empty() {
+                state.invalidate_memory();
+            }
             for i in global_instructions {
                 i.apply(&mut ir);
             }
@@ -1,15 +1,12 @@
 use std::collections::HashMap;
 
-use crate::{
-    ir::{
-        expressions::{Expr, ExprKind, Operator},
-        memory::Memory,
-        Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference,
-    },
-    rewrite_rules::{CombineSeq, RemoveSkip},
+use crate::ir::{
+    expressions::{Expr, ExprKind, Operator},
+    memory::Memory,
+    Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that transforms an iterate statement into an assign if the parameter is uniquely defined by an
@@ -17,6 +14,10 @@ use super::{ChangeSet, RewriteError, RewriteRule};
 pub struct IterateAssign;
 
 impl RewriteRule for IterateAssign {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::ITERATE)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -62,8 +63,12 @@ impl RewriteRule for IterateAssign {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip), Box::new(CombineSeq)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![
+            Property::FlatSeqs,
+            Property::NoSkip,
+            Property::NoConstantIfs,
+        ]
     }
 }
 
@@ -181,7 +186,7 @@ mod tests {
                 fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(IterateAssign {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(IterateAssign {})]).unwrap();
         let (res, changed) = rewriter.apply(ir.clone()).unwrap();
         assert!(!changed);
         assert!(res.stmt.eq(&ir.stmt));
@@ -204,12 +209,13 @@ mod tests {
                 input 0
         ",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(IterateAssign {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
             Box::new(RemoveSkip {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -2,12 +2,9 @@ use std::collections::HashMap;
 
 use itertools::Itertools;
 
-use crate::{
-    ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference},
-    rewrite_rules::{CombineSeq, RemoveSkip},
-};
+use crate::ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule combining if conditions in sequences/parallel that have the same guard condition
@@ -35,9 +32,29 @@ impl CombineIf {
             (lhs, rhs) => Err((lhs, rhs)),
         }
     }
+
+    /// Returns whether two conditionals of the parallel statement have the same guard
+    fn combinable(stmts: &[Stmt], liveness_equivalences: &LivetimeEquivalences) -> bool {
+        let guards = stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                Stmt::If(IfStmt { guard, .. }) => Some(guard),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        guards.iter().enumerate().any(|(i, g)| {
+            guards[..i]
+                .iter()
+                .any(|h| h.eq_liveness(g, liveness_equivalences))
+        })
+    }
 }
 
 impl RewriteRule for CombineIf {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::SEQ | StmtKinds::PARALLEL)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -48,13 +65,19 @@ impl RewriteRule for CombineIf {
             Stmt::Seq(inner) => {
                 let old_length = inner.len();
                 let inner: Vec<Stmt> = inner.into_iter().coalesce(Self::apply).collect();
-                let cs = if old_length == inner.len() {
+                let cs = if old_length == inner.len() && old_length > 1 {
                     ChangeSet::default()
                 } else {
                     ChangeSet::local_change()
                 };
                 Ok((Stmt::seq(inner), cs))
             }
+            Stmt::Parallel(inner) if inner.len() < 2 => {
+                Ok((Stmt::parallel(inner), ChangeSet::local_change()))
+            }
+            Stmt::Parallel(inner) if !Self::combinable(&inner, liveness_equivalences) => {
+                Ok((Stmt::Parallel(inner), ChangeSet::default()))
+            }
             Stmt::Parallel(inner) => {
                 let (guarded, unguarded): (Vec<_>, Vec<_>) =
                     inner.into_iter().partition_map(|stmt| match stmt {
@@ -63,7 +86,6 @@ impl RewriteRule for CombineIf {
                         }
                         stmt => itertools::Either::Right(stmt),
                     });
-                let num_guarded = guarded.len();
                 let guarded = guarded.into_iter().fold(
                     Vec::new(),
                     |mut guarded: Vec<(Guard, Vec<Stmt>, Vec<Stmt>)>, (guard, cons, alt)| {
@@ -89,20 +111,15 @@ impl RewriteRule for CombineIf {
                         })
                     })
                     .collect();
-                let cs = if num_guarded == guarded.len() {
-                    ChangeSet::default()
-                } else {
-                    ChangeSet::local_change()
-                };
                 let stmts = guarded.into_iter().chain(unguarded);
-                Ok((Stmt::parallel(stmts), cs))
+                Ok((Stmt::parallel(stmts), ChangeSet::local_change()))
             }
             stmt => Ok((stmt, ChangeSet::default())),
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip), Box::new(CombineSeq)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::FlatSeqs, Property::NoSkip]
     }
 }
 
@@ -134,7 +151,7 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineIf {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineIf {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -161,7 +178,7 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineIf {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineIf {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
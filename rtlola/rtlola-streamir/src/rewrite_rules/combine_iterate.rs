@@ -2,15 +2,11 @@ use std::collections::HashMap;
 
 use itertools::Itertools;
 
-use crate::{
-    ir::{
-        expressions::Expr, memory::Memory, LivetimeEquivalences, OutputReference, Stmt,
-        StreamReference,
-    },
-    rewrite_rules::{CombineSeq, RemoveSkip},
+use crate::ir::{
+    expressions::Expr, memory::Memory, LivetimeEquivalences, OutputReference, Stmt, StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewring rule combining iterates in sequences/parallel that have the same spawn/close behavior.
@@ -51,9 +47,35 @@ impl CombineIterate {
             (lhs, rhs) => Err((lhs, rhs)),
         }
     }
+
+    /// Returns whether two iterates or two assigns of the parallel statement can be combined
+    fn combinable(stmts: &[Stmt], liveness_equivalences: &LivetimeEquivalences) -> bool {
+        stmts.iter().enumerate().any(|(i, lhs)| {
+            stmts[..i].iter().any(|rhs| match (lhs, rhs) {
+                (Stmt::Iterate { sr: sr1, .. }, Stmt::Iterate { sr: sr2, .. }) => {
+                    liveness_equivalences.is_equivalent_outputs(sr1[0], sr2[0])
+                }
+                (
+                    Stmt::Assign {
+                        parameter_expr: exp1,
+                        ..
+                    },
+                    Stmt::Assign {
+                        parameter_expr: exp2,
+                        ..
+                    },
+                ) => exp1 == exp2,
+                _ => false,
+            })
+        })
+    }
 }
 
 impl RewriteRule for CombineIterate {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::SEQ | StmtKinds::PARALLEL)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -67,13 +89,19 @@ impl RewriteRule for CombineIterate {
                     .into_iter()
                     .coalesce(|a, b| Self::apply(a, b, liveness_equivalences))
                     .collect();
-                let cs = if old_length == inner.len() {
+                let cs = if old_length == inner.len() && old_length > 1 {
                     ChangeSet::default()
                 } else {
                     ChangeSet::local_change()
                 };
                 Ok((Stmt::seq(inner), cs))
             }
+            Stmt::Parallel(inner) if inner.len() < 2 => {
+                Ok((Stmt::parallel(inner), ChangeSet::local_change()))
+            }
+            Stmt::Parallel(inner) if !Self::combinable(&inner, liveness_equivalences) => {
+                Ok((Stmt::Parallel(inner), ChangeSet::default()))
+            }
             Stmt::Parallel(inner) => {
                 let (iterated, assigned, other) = inner.into_iter().fold(
                     (Vec::new(), Vec::new(), Vec::new()),
@@ -94,8 +122,6 @@ impl RewriteRule for CombineIterate {
                         (iterated, assigned, other)
                     },
                 );
-                let num_iterated = iterated.len();
-                let num_assigned = assigned.len();
                 let iterated = iterated.into_iter().fold(
                     Vec::new(),
                     |mut iterated: Vec<(Vec<OutputReference>, Vec<Stmt>)>, (sr, stmt)| {
@@ -142,20 +168,15 @@ impl RewriteRule for CombineIterate {
                         stmt: Box::new(Stmt::parallel(stmt)),
                     })
                     .collect();
-                let cs = if num_iterated == iterated.len() && num_assigned == assigned.len() {
-                    ChangeSet::default()
-                } else {
-                    ChangeSet::local_change()
-                };
                 let stmts = iterated.into_iter().chain(assigned).chain(other);
-                Ok((Stmt::parallel(stmts), cs))
+                Ok((Stmt::parallel(stmts), ChangeSet::local_change()))
             }
             stmt => Ok((stmt, ChangeSet::default())),
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip), Box::new(CombineSeq)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::FlatSeqs, Property::NoSkip]
     }
 }
 
@@ -185,7 +206,7 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineIterate {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineIterate {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -209,7 +230,7 @@ mod tests {
                 }
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineIterate {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineIterate {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -1,17 +1,22 @@
 use std::collections::HashMap;
 
-use crate::{
-    ir::{memory::Memory, LivetimeEquivalences, Stmt, StreamReference},
-    rewrite_rules::RemoveSkip,
-};
+use crate::ir::{memory::Memory, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that simplifies sequences/parallel nested inside each other.
 pub struct CombineSeq;
 
 impl RewriteRule for CombineSeq {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::SEQ | StmtKinds::PARALLEL)
+    }
+
+    fn produces(&self) -> Vec<Property> {
+        vec![Property::FlatSeqs]
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -63,8 +68,8 @@ impl RewriteRule for CombineSeq {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::NoSkip]
     }
 }
 
@@ -96,7 +101,7 @@ mod tests {
             input 3
         }",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineSeq {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineSeq {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
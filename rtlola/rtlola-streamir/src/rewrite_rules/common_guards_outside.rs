@@ -7,13 +7,17 @@ use itertools::Itertools;
 
 use crate::ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// Tries to find guard conditions that are used in multiple conditionals in sequences/parallel and moves them outside
 pub struct MoveCommonGuardsOutside;
 
 impl RewriteRule for MoveCommonGuardsOutside {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::SEQ | StmtKinds::PARALLEL)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -193,13 +197,14 @@ mod tests {
 			input 4	
 		}",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(MoveCommonGuardsOutside),
             Box::new(ImpliedGuards),
             Box::new(SimplifyGuard),
             Box::new(RemoveIfs),
             Box::new(CombineSeq),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -248,13 +253,14 @@ mod tests {
 				fi
 			}",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(MoveCommonGuardsOutside),
             Box::new(ImpliedGuards),
             Box::new(SimplifyGuard),
             Box::new(RemoveIfs),
             Box::new(CombineSeq),
-        ]);
+        ])
+        .unwrap();
         let res = rewriter.run(ir).unwrap();
         let reference = parse_ir(
             "par {
@@ -293,13 +299,14 @@ mod tests {
 				fi ; 	
 			}",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(MoveCommonGuardsOutside),
             Box::new(ImpliedGuards),
             Box::new(SimplifyGuard),
             Box::new(RemoveIfs),
             Box::new(CombineSeq),
-        ]);
+        ])
+        .unwrap();
         let res = rewriter.run(ir).unwrap();
         println!("{}", res.display());
     }
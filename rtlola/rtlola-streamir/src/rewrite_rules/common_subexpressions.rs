@@ -0,0 +1,589 @@
+use std::{
+    cell::Cell,
+    collections::{BTreeMap, HashMap, HashSet},
+};
+
+use crate::ir::{
+    expressions::{Expr, ExprKind, Operator},
+    interner::{ExprId, ExprInterner},
+    memory::Memory,
+    IfStmt, LivetimeEquivalences, Stmt, StreamIr, StreamReference, TempRef,
+};
+
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
+
+#[derive(Debug)]
+/// A rewriting rule that hoists subexpressions occurring repeatedly in the eval statements
+/// under a common guard into temporaries (see [Stmt::Let]).
+///
+/// Only subexpressions that are evaluated unconditionally and only depend on the current values
+/// of streams (or parameters) are hoisted.
+/// A subexpression is hoisted before the first statement of the body of the if-statement using it,
+/// or before the first statement of the innermost nested sequence using it, if a stream it reads is written in between.
+///
+/// The rule is not cloneable, as the temporaries it introduces are numbered by the rule itself.
+pub struct CommonSubexpressions {
+    next_temp: Cell<TempRef>,
+}
+
+impl CommonSubexpressions {
+    /// Constructs the rewriting rule for the given StreamIR, such that new temporaries do not clash with existing ones.
+    pub fn new(ir: &StreamIr) -> Self {
+        Self {
+            next_temp: Cell::new(max_temp(&ir.stmt).map_or(0, |t| t + 1)),
+        }
+    }
+
+    fn fresh_temp(&self) -> TempRef {
+        let temp = self.next_temp.get();
+        self.next_temp.set(temp + 1);
+        temp
+    }
+
+    /// Hoists the common subexpressions of the given statement into temporaries.
+    fn eliminate(&self, stmt: Stmt) -> (Stmt, bool) {
+        let mut elements = match stmt {
+            Stmt::Seq(stmts) => stmts,
+            other => vec![other],
+        };
+        let written = elements
+            .iter()
+            .map(|stmt| {
+                let mut written = HashSet::new();
+                written_streams(stmt, &mut written);
+                written
+            })
+            .collect::<Vec<_>>();
+
+        let mut lets: Vec<(usize, TempRef, Expr)> = Vec::new();
+        loop {
+            let mut occurrences = Occurrences::default();
+            for (i, element) in elements.iter().enumerate() {
+                for_each_expr(element, &mut |expr| {
+                    collect_candidates(expr, i, &mut occurrences)
+                });
+            }
+            for (pos, _, expr) in &lets {
+                collect_candidates(expr, *pos, &mut occurrences);
+            }
+
+            let best = occurrences
+                .positions
+                .into_iter()
+                .filter(|(_, occ)| occ.len() >= 2)
+                .filter_map(|(id, occ)| {
+                    let expr = occurrences.interner.expr(id);
+                    let first = *occ.iter().min().unwrap();
+                    let last = *occ.iter().max().unwrap();
+                    let mut reads = HashSet::new();
+                    read_streams(&expr, &mut reads);
+                    written[first..=last]
+                        .iter()
+                        .all(|w| w.is_disjoint(&reads))
+                        .then_some((expr, first, last))
+                })
+                .fold(None, |best: Option<(Expr, usize, usize)>, cur| match best {
+                    Some(best) if expr_size(&best.0) >= expr_size(&cur.0) => Some(best),
+                    _ => Some(cur),
+                });
+            let Some((expr, first, last)) = best else {
+                break;
+            };
+
+            let temp = self.fresh_temp();
+            for element in &mut elements[first..=last] {
+                replace_in_stmt(element, &expr, temp);
+            }
+            for (pos, _, let_expr) in &mut lets {
+                if (first..=last).contains(pos) {
+                    *let_expr = replace(let_expr.clone(), &expr, temp);
+                }
+            }
+            lets.push((first, temp, expr));
+        }
+
+        let mut changed = !lets.is_empty();
+        let elements = elements
+            .into_iter()
+            .map(|element| {
+                let (element, nested_changed) = self.eliminate_nested(element);
+                changed |= nested_changed;
+                element
+            })
+            .collect::<Vec<_>>();
+        if lets.is_empty() {
+            return (Stmt::seq(elements), changed);
+        }
+        let stmts = elements
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, element)| {
+                lets.iter()
+                    .rev()
+                    .filter(|(pos, _, _)| *pos == i)
+                    .map(|(_, temp, expr)| Stmt::Let {
+                        temp: *temp,
+                        expr: expr.clone(),
+                    })
+                    .chain(std::iter::once(element))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        (Stmt::Seq(stmts), true)
+    }
+
+    /// Hoists the common subexpressions of the sequences nested in the given statement.
+    fn eliminate_nested(&self, stmt: Stmt) -> (Stmt, bool) {
+        match stmt {
+            Stmt::Seq(_) => self.eliminate(stmt),
+            Stmt::Parallel(stmts) => {
+                let mut changed = false;
+                let stmts = stmts
+                    .into_iter()
+                    .map(|stmt| {
+                        let (stmt, nested_changed) = self.eliminate_nested(stmt);
+                        changed |= nested_changed;
+                        stmt
+                    })
+                    .collect();
+                (Stmt::Parallel(stmts), changed)
+            }
+            other => (other, false),
+        }
+    }
+}
+
+impl RewriteRule for CommonSubexpressions {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
+    fn rewrite_stmt(
+        &self,
+        stmt: Stmt,
+        _memory: &HashMap<StreamReference, Memory>,
+        _liveness_equivalences: &LivetimeEquivalences,
+    ) -> Result<(Stmt, ChangeSet), RewriteError> {
+        match stmt {
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                let (cons, cons_changed) = self.eliminate(*cons);
+                let (alt, alt_changed) = self.eliminate(*alt);
+                let cs = if cons_changed || alt_changed {
+                    ChangeSet::local_change()
+                } else {
+                    ChangeSet::default()
+                };
+                Ok((
+                    Stmt::If(IfStmt {
+                        guard,
+                        cons: Box::new(cons),
+                        alt: Box::new(alt),
+                    }),
+                    cs,
+                ))
+            }
+            other => Ok((other, ChangeSet::default())),
+        }
+    }
+}
+
+fn max_temp(stmt: &Stmt) -> Option<TempRef> {
+    match stmt {
+        Stmt::Let { temp, .. } => Some(*temp),
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Eval { .. }
+        | Stmt::Close { .. } => None,
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts.iter().filter_map(max_temp).max(),
+        Stmt::If(IfStmt { cons, alt, .. }) => max_temp(cons).max(max_temp(alt)),
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => max_temp(stmt),
+    }
+}
+
+/// Calls `f` for the expressions of all eval and let statements that are executed
+/// unconditionally when the given statement is executed.
+fn for_each_expr<'a>(stmt: &'a Stmt, f: &mut impl FnMut(&'a Expr)) {
+    match stmt {
+        Stmt::Eval { with: expr, .. } | Stmt::Let { expr, .. } => f(expr),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|stmt| for_each_expr(stmt, f))
+        }
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::If(_)
+        | Stmt::Iterate { .. }
+        | Stmt::Assign { .. } => {}
+    }
+}
+
+fn replace_in_stmt(stmt: &mut Stmt, target: &Expr, temp: TempRef) {
+    match stmt {
+        Stmt::Eval { with: expr, .. } | Stmt::Let { expr, .. } => {
+            *expr = replace(expr.clone(), target, temp)
+        }
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+            .iter_mut()
+            .for_each(|stmt| replace_in_stmt(stmt, target, temp)),
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::If(_)
+        | Stmt::Iterate { .. }
+        | Stmt::Assign { .. } => {}
+    }
+}
+
+fn written_streams(stmt: &Stmt, written: &mut HashSet<StreamReference>) {
+    match stmt {
+        Stmt::Skip | Stmt::Let { .. } => {}
+        Stmt::Shift(sr) => {
+            written.insert(*sr);
+        }
+        Stmt::Input(i) => {
+            written.insert(StreamReference::In(*i));
+        }
+        Stmt::Spawn { sr, .. } | Stmt::Eval { sr, .. } | Stmt::Close { sr, .. } => {
+            written.insert(sr.sr());
+        }
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|stmt| written_streams(stmt, written))
+        }
+        Stmt::If(IfStmt { cons, alt, .. }) => {
+            written_streams(cons, written);
+            written_streams(alt, written);
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => written_streams(stmt, written),
+    }
+}
+
+/// Returns whether the expression can be hoisted into a temporary, i.e., it only consists of
+/// synchronous stream accesses, parameter accesses, constants and operations on them.
+fn is_pure(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Constant(_) | ExprKind::ParameterAccess(_, _) => true,
+        ExprKind::SyncStreamAccess { parameters, .. } => parameters.iter().all(is_pure),
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            is_pure(lhs) && is_pure(rhs)
+        }
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => is_pure(inner),
+        ExprKind::Ite(cond, cons, alt) => is_pure(cond) && is_pure(cons) && is_pure(alt),
+        ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => args.iter().all(is_pure),
+        ExprKind::OffsetStreamAccess { .. }
+        | ExprKind::HoldStreamAccess { .. }
+        | ExprKind::IsFresh { .. }
+        | ExprKind::GetAccess { .. }
+        | ExprKind::WindowAccess { .. }
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => false,
+    }
+}
+
+fn is_constant(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Constant(_) => true,
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            is_constant(lhs) && is_constant(rhs)
+        }
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => is_constant(inner),
+        ExprKind::Ite(cond, cons, alt) => {
+            is_constant(cond) && is_constant(cons) && is_constant(alt)
+        }
+        ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => args.iter().all(is_constant),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+/// The positions at which the candidate subexpressions occur, identified by their interned id
+struct Occurrences {
+    interner: ExprInterner,
+    positions: BTreeMap<ExprId, Vec<usize>>,
+}
+
+/// Records all hoistable subexpressions of `expr` that are evaluated unconditionally.
+fn collect_candidates(expr: &Expr, pos: usize, occurrences: &mut Occurrences) {
+    let children: Vec<&Expr> = match &expr.kind {
+        ExprKind::BinaryOperation(Operator::And | Operator::Or, lhs, _) => vec![lhs],
+        ExprKind::BinaryOperation(_, lhs, rhs) => vec![lhs, rhs],
+        ExprKind::Ite(cond, _, _) | ExprKind::UnwrapOr(cond, _) => vec![cond],
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => vec![inner],
+        ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => args.iter().collect(),
+        ExprKind::Constant(_)
+        | ExprKind::SyncStreamAccess { .. }
+        | ExprKind::OffsetStreamAccess { .. }
+        | ExprKind::HoldStreamAccess { .. }
+        | ExprKind::IsFresh { .. }
+        | ExprKind::GetAccess { .. }
+        | ExprKind::WindowAccess { .. }
+        | ExprKind::ParameterAccess(_, _)
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => return,
+    };
+    if is_pure(expr) && !is_constant(expr) {
+        let id = occurrences.interner.intern(expr);
+        occurrences.positions.entry(id).or_default().push(pos);
+    }
+    children
+        .into_iter()
+        .for_each(|child| collect_candidates(child, pos, occurrences));
+}
+
+fn read_streams(expr: &Expr, reads: &mut HashSet<StreamReference>) {
+    match &expr.kind {
+        ExprKind::SyncStreamAccess { target, parameters } => {
+            reads.insert(*target);
+            parameters.iter().for_each(|p| read_streams(p, reads));
+        }
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            read_streams(lhs, reads);
+            read_streams(rhs, reads);
+        }
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => read_streams(inner, reads),
+        ExprKind::Ite(cond, cons, alt) => {
+            read_streams(cond, reads);
+            read_streams(cons, reads);
+            read_streams(alt, reads);
+        }
+        ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => {
+            args.iter().for_each(|arg| read_streams(arg, reads))
+        }
+        ExprKind::Constant(_)
+        | ExprKind::OffsetStreamAccess { .. }
+        | ExprKind::HoldStreamAccess { .. }
+        | ExprKind::IsFresh { .. }
+        | ExprKind::GetAccess { .. }
+        | ExprKind::WindowAccess { .. }
+        | ExprKind::ParameterAccess(_, _)
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => {}
+    }
+}
+
+fn expr_size(expr: &Expr) -> usize {
+    1 + match &expr.kind {
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            expr_size(lhs) + expr_size(rhs)
+        }
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => expr_size(inner),
+        ExprKind::Ite(cond, cons, alt) => expr_size(cond) + expr_size(cons) + expr_size(alt),
+        ExprKind::FunctionCall(_, args)
+        | ExprKind::Tuple(args)
+        | ExprKind::SyncStreamAccess {
+            parameters: args, ..
+        } => args.iter().map(expr_size).sum(),
+        _ => 0,
+    }
+}
+
+/// Replaces all occurrences of `target` in `expr` by an access to the given temporary.
+fn replace(expr: Expr, target: &Expr, temp: TempRef) -> Expr {
+    if &expr == target {
+        return Expr {
+            ty: expr.ty,
+            kind: ExprKind::Temporary(temp),
+        };
+    }
+    let replace_box = |e: Box<Expr>| Box::new(replace(*e, target, temp));
+    let replace_vec = |e: Vec<Expr>| {
+        e.into_iter()
+            .map(|e| replace(e, target, temp))
+            .collect::<Vec<_>>()
+    };
+    let Expr { ty, kind } = expr;
+    let kind = match kind {
+        ExprKind::BinaryOperation(op, lhs, rhs) => {
+            ExprKind::BinaryOperation(op, replace_box(lhs), replace_box(rhs))
+        }
+        ExprKind::UnaryOperation(op, inner) => ExprKind::UnaryOperation(op, replace_box(inner)),
+        ExprKind::Ite(cond, cons, alt) => {
+            ExprKind::Ite(replace_box(cond), replace_box(cons), replace_box(alt))
+        }
+        ExprKind::Cast(ty, inner) => ExprKind::Cast(ty, replace_box(inner)),
+        ExprKind::TupleAccess(inner, i) => ExprKind::TupleAccess(replace_box(inner), i),
+        ExprKind::Some(inner) => ExprKind::Some(replace_box(inner)),
+        ExprKind::UnwrapOr(expr, default) => {
+            ExprKind::UnwrapOr(replace_box(expr), replace_box(default))
+        }
+        ExprKind::FunctionCall(f, args) => ExprKind::FunctionCall(f, replace_vec(args)),
+        ExprKind::Tuple(inner) => ExprKind::Tuple(replace_vec(inner)),
+        ExprKind::SyncStreamAccess { target, parameters } => ExprKind::SyncStreamAccess {
+            target,
+            parameters: replace_vec(parameters),
+        },
+        ExprKind::OffsetStreamAccess {
+            target,
+            offset,
+            default,
+            parameters,
+        } => ExprKind::OffsetStreamAccess {
+            target,
+            offset,
+            default: replace_box(default),
+            parameters: replace_vec(parameters),
+        },
+        ExprKind::HoldStreamAccess {
+            target,
+            default,
+            parameters,
+        } => ExprKind::HoldStreamAccess {
+            target,
+            default: replace_box(default),
+            parameters: replace_vec(parameters),
+        },
+        ExprKind::GetAccess {
+            target,
+            default,
+            parameters,
+        } => ExprKind::GetAccess {
+            target,
+            default: replace_box(default),
+            parameters: replace_vec(parameters),
+        },
+        ExprKind::IsFresh { target, parameters } => ExprKind::IsFresh {
+            target,
+            parameters: replace_vec(parameters),
+        },
+        ExprKind::WindowAccess {
+            target,
+            window,
+            parameters,
+            default,
+        } => ExprKind::WindowAccess {
+            target,
+            window,
+            parameters: replace_vec(parameters),
+            default: default.map(replace_box),
+        },
+        other @ (ExprKind::Constant(_)
+        | ExprKind::ParameterAccess(_, _)
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_)) => other,
+    };
+    Expr { ty, kind }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ir::parse::parse_ir, rewrite_rules::Rewriter};
+
+    use super::CommonSubexpressions;
+
+    #[test]
+    fn hoist_common_subexpression() {
+        let ir = parse_ir(
+            "if @0 then
+				par {
+					eval 0 (s0 && s1) == s2;
+					eval 1 (s0 && s1) == s3
+				}
+			fi",
+        );
+        let reference = parse_ir(
+            "if @0 then
+				seq {
+					let 0 s0 && s1;
+					par {
+						eval 0 t0 == s2;
+						eval 1 t0 == s3
+					}
+				}
+			fi",
+        );
+        let rewriter = Rewriter::try_new(vec![Box::new(CommonSubexpressions::new(&ir))]).unwrap();
+        let res = rewriter.run(ir).unwrap();
+        assert!(res.stmt.eq(&reference.stmt));
+    }
+
+    #[test]
+    fn written_in_between() {
+        let ir = parse_ir(
+            "if @0 then
+				seq {
+					eval 0 (s0 && s1) == s2;
+					input 1;
+					eval 1 (s0 && s1) == s3
+				}
+			fi",
+        );
+        let rewriter = Rewriter::try_new(vec![Box::new(CommonSubexpressions::new(&ir))]).unwrap();
+        let (res, changed) = rewriter.apply(ir.clone()).unwrap();
+        assert!(!changed);
+        assert!(res.stmt.eq(&ir.stmt));
+    }
+
+    #[test]
+    fn nested_sequence() {
+        // the write of input 1 prevents hoisting before the body, but not inside the nested sequence
+        let ir = parse_ir(
+            "if @0 then
+				seq {
+					eval 0 (s0 && s1) == s2;
+					input 1;
+					par {
+						seq {
+							eval 1 (s0 && s1) == s3;
+							eval 2 (s0 && s1) == s4
+						};
+						eval 3 s0
+					}
+				}
+			fi",
+        );
+        let reference = parse_ir(
+            "if @0 then
+				seq {
+					eval 0 (s0 && s1) == s2;
+					input 1;
+					par {
+						seq {
+							let 0 s0 && s1;
+							eval 1 t0 == s3;
+							eval 2 t0 == s4
+						};
+						eval 3 s0
+					}
+				}
+			fi",
+        );
+        let rewriter = Rewriter::try_new(vec![Box::new(CommonSubexpressions::new(&ir))]).unwrap();
+        let res = rewriter.run(ir).unwrap();
+        assert!(res.stmt.eq(&reference.stmt), "{:?}", res.stmt);
+    }
+
+    #[test]
+    fn conditional_subexpression() {
+        let ir = parse_ir(
+            "if @0 then
+				par {
+					eval 0 s2 && (s0 == s1);
+					eval 1 s3 && (s0 == s1)
+				}
+			fi",
+        );
+        let rewriter = Rewriter::try_new(vec![Box::new(CommonSubexpressions::new(&ir))]).unwrap();
+        let (_, changed) = rewriter.apply(ir).unwrap();
+        assert!(!changed);
+    }
+}
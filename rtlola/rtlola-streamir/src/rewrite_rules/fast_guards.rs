@@ -1,11 +1,8 @@
 use std::collections::HashMap;
 
-use crate::{
-    ir::{memory::Memory, Guard, LivetimeEquivalences, StreamReference},
-    rewrite_rules::SimplifyGuard,
-};
+use crate::ir::{memory::Memory, Guard, LivetimeEquivalences, StreamReference};
 
-use super::{ChangeSet, RewriteRule};
+use super::{ChangeSet, Property, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that allows for optimized implementations of guards by replacing
@@ -13,6 +10,10 @@ use super::{ChangeSet, RewriteRule};
 pub struct FastGuards;
 
 impl RewriteRule for FastGuards {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
     fn rewrite_guard(
         &self,
         guard: Guard,
@@ -67,8 +68,8 @@ impl RewriteRule for FastGuards {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(SimplifyGuard)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::SimplifiedGuards, Property::NoFastGuards]
     }
 }
 
@@ -1,17 +1,18 @@
 use std::collections::HashMap;
 
-use crate::{
-    ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference},
-    rewrite_rules::{CombineSeq, RemoveSkip},
-};
+use crate::ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that moves if statements outside of iterate statements if the guard does not make any statements of the parameter values.
 pub struct MoveIfOutside;
 
 impl RewriteRule for MoveIfOutside {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::ITERATE)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -68,8 +69,8 @@ impl RewriteRule for MoveIfOutside {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip), Box::new(CombineSeq)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::FlatSeqs, Property::NoSkip]
     }
 }
 
@@ -144,7 +145,8 @@ mod tests {
                 fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]);
+        let rewriter =
+            Rewriter::try_new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -159,7 +161,8 @@ mod tests {
                 fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]);
+        let rewriter =
+            Rewriter::try_new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]).unwrap();
         let (res, changed) = rewriter.apply(ir.clone()).unwrap();
         assert!(!changed);
         assert!(res.stmt.eq(&ir.stmt));
@@ -183,7 +186,8 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]);
+        let rewriter =
+            Rewriter::try_new(vec![Box::new(MoveIfOutside {}), Box::new(RemoveSkip {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -2,13 +2,21 @@ use std::iter;
 
 use crate::ir::{Guard, IfStmt, LivetimeEquivalences, Stmt};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule};
 
 #[derive(Debug, Clone, Copy)]
 /// Remove guard conditions that are already implied because the guard is nested inside an outer guard block.
 pub struct ImpliedGuards;
 
 impl RewriteRule for ImpliedGuards {
+    fn requires(&self) -> Vec<Property> {
+        vec![Property::NoFastGuards]
+    }
+
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::SimplifiedGuards, Property::NoConstantIfs]
+    }
+
     fn apply_stmt(
         &self,
         stmt: Stmt,
@@ -30,6 +38,7 @@ impl ImpliedGuards {
             | Stmt::Spawn { .. }
             | Stmt::Eval { .. }
             | Stmt::Close { .. }
+            | Stmt::Let { .. }
             | Stmt::Skip => Ok((stmt, ChangeSet::default())),
             Stmt::Seq(stmts) => {
                 let mut cs = ChangeSet::default();
@@ -188,11 +197,12 @@ mod tests {
 			input 0
 		fi",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -215,11 +225,12 @@ mod tests {
 			input 0
 		fi",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -244,11 +255,12 @@ mod tests {
 			fi
 		fi",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -271,11 +283,12 @@ mod tests {
 			input 0
 		fi",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -292,11 +305,12 @@ mod tests {
 			fi	
 		",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir.clone()).unwrap();
         assert!(!changed);
         assert!(res.stmt.eq(&res.stmt));
@@ -320,11 +334,12 @@ mod tests {
 			fi	
 		",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -366,11 +381,12 @@ mod tests {
 			fi	
 		",
         );
-        let rewriter = Rewriter::new(vec![
+        let rewriter = Rewriter::try_new(vec![
             Box::new(ImpliedGuards {}),
             Box::new(SimplifyGuard {}),
             Box::new(RemoveIfs {}),
-        ]);
+        ])
+        .unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -0,0 +1,523 @@
+//! Incremental application of rewriting rules that declare the statement kinds they match.
+//!
+//! The rewriter interns every subtree of the current statement, such that structurally equal subtrees share an id,
+//! and keeps these ids up to date alongside the rewriting. For each rule, the ids of the subtrees the rule left
+//! unchanged are remembered, such that these subtrees are skipped in the following iterations.
+
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{BuildHasherDefault, Hash, Hasher},
+};
+
+use crate::ir::{
+    expressions::Expr, memory::Memory, Guard, IfStmt, LivetimeEquivalences, OutputReference, Stmt,
+    StreamReference,
+};
+
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
+
+#[derive(Debug, Default)]
+/// A fast, non-cryptographic hasher for the interned statements (multiply-rotate as in FxHash).
+struct FingerprintHasher(u64);
+
+impl Hasher for FingerprintHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.write_u64(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        for &b in chunks.remainder() {
+            self.write_u64(b as u64);
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = (self.0.rotate_left(5) ^ i).wrapping_mul(0x51_7c_c1_b7_27_22_0a_95);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The id of a statement stored in a [StmtInterner]
+struct StmtId(u32);
+
+#[derive(Debug, Clone)]
+/// A statement stored in the [StmtInterner], referring to its direct children by their ids
+enum StmtNode {
+    Seq(Vec<StmtId>),
+    Parallel(Vec<StmtId>),
+    If(Guard, [StmtId; 2]),
+    Iterate(Vec<OutputReference>, [StmtId; 1]),
+    Assign(Vec<Expr>, Vec<OutputReference>, [StmtId; 1]),
+    /// A statement without any substatements
+    Leaf(Stmt),
+}
+
+impl StmtNode {
+    fn new(stmt: &Stmt, children: &[StmtId]) -> Self {
+        match stmt {
+            Stmt::Seq(_) => Self::Seq(children.to_vec()),
+            Stmt::Parallel(_) => Self::Parallel(children.to_vec()),
+            Stmt::If(IfStmt { guard, .. }) => Self::If(guard.clone(), children.try_into().unwrap()),
+            Stmt::Iterate { sr, .. } => Self::Iterate(sr.clone(), children.try_into().unwrap()),
+            Stmt::Assign {
+                parameter_expr, sr, ..
+            } => Self::Assign(
+                parameter_expr.clone(),
+                sr.clone(),
+                children.try_into().unwrap(),
+            ),
+            leaf => Self::Leaf(leaf.clone()),
+        }
+    }
+
+    /// Returns whether this node represents the statement with the given children.
+    fn matches(&self, stmt: &Stmt, children: &[StmtId]) -> bool {
+        match (self, stmt) {
+            (Self::Seq(ids), Stmt::Seq(_)) | (Self::Parallel(ids), Stmt::Parallel(_)) => {
+                ids == children
+            }
+            (Self::If(g, ids), Stmt::If(IfStmt { guard, .. })) => ids == children && g == guard,
+            (Self::Iterate(s, ids), Stmt::Iterate { sr, .. }) => ids == children && s == sr,
+            (
+                Self::Assign(p, s, ids),
+                Stmt::Assign {
+                    parameter_expr, sr, ..
+                },
+            ) => ids == children && s == sr && p == parameter_expr,
+            (Self::Leaf(leaf), stmt) => leaf == stmt,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// A hash-consed storage of statements, assigning structurally equal statements the same id.
+///
+/// As the nodes refer to their children by id, storing a statement whose children are already stored only requires
+/// hashing the statement itself. A hash hit is confirmed by comparing the statement with the stored node, such that
+/// two ids are equal exactly if the statements are structurally equal.
+struct StmtInterner {
+    nodes: Vec<StmtNode>,
+    buckets: HashMap<u64, Vec<StmtId>, BuildHasherDefault<FingerprintHasher>>,
+}
+
+impl StmtInterner {
+    /// Returns the id of the statement with the given children, storing the statement if it is new.
+    fn insert(&mut self, stmt: &Stmt, children: &[StmtId]) -> StmtId {
+        let mut hasher = FingerprintHasher::default();
+        core::mem::discriminant(stmt).hash(&mut hasher);
+        match stmt {
+            Stmt::Seq(_) | Stmt::Parallel(_) => {}
+            Stmt::If(IfStmt { guard, .. }) => guard.hash(&mut hasher),
+            Stmt::Iterate { sr, .. } => sr.hash(&mut hasher),
+            Stmt::Assign {
+                parameter_expr, sr, ..
+            } => {
+                parameter_expr.hash(&mut hasher);
+                sr.hash(&mut hasher);
+            }
+            leaf => leaf.hash(&mut hasher),
+        }
+        children.hash(&mut hasher);
+        let bucket = self.buckets.entry(hasher.finish()).or_default();
+        if let Some(id) = bucket
+            .iter()
+            .find(|id| self.nodes[id.0 as usize].matches(stmt, children))
+        {
+            return *id;
+        }
+        let id = StmtId(u32::try_from(self.nodes.len()).expect("too many statements"));
+        self.nodes.push(StmtNode::new(stmt, children));
+        bucket.push(id);
+        id
+    }
+
+    /// Returns whether the given fingerprint describes the statement, without storing anything.
+    fn represents(&self, stmt: &Stmt, fp: &Fingerprint) -> bool {
+        let children: Vec<&Stmt> = match stmt {
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts.iter().collect(),
+            Stmt::If(IfStmt { cons, alt, .. }) => vec![cons, alt],
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => vec![stmt],
+            Stmt::Skip
+            | Stmt::Shift(_)
+            | Stmt::Input(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Eval { .. }
+            | Stmt::Close { .. }
+            | Stmt::Let { .. } => Vec::new(),
+        };
+        let ids = fp.children.iter().map(|c| c.id).collect::<Vec<_>>();
+        children.len() == fp.children.len()
+            && self.nodes[fp.id.0 as usize].matches(stmt, &ids)
+            && children
+                .into_iter()
+                .zip(&fp.children)
+                .all(|(child, fp)| self.represents(child, fp))
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The interned id of a statement together with the kinds of statements it contains,
+/// mirroring the tree structure of the statement.
+struct Fingerprint {
+    id: StmtId,
+    kinds: StmtKinds,
+    children: Vec<Fingerprint>,
+}
+
+impl Fingerprint {
+    fn new(stmt: &Stmt, interner: &mut StmtInterner) -> Self {
+        let children = match stmt {
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                stmts.iter().map(|s| Self::new(s, interner)).collect()
+            }
+            Stmt::If(IfStmt { cons, alt, .. }) => {
+                vec![Self::new(cons, interner), Self::new(alt, interner)]
+            }
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => {
+                vec![Self::new(stmt, interner)]
+            }
+            Stmt::Skip
+            | Stmt::Shift(_)
+            | Stmt::Input(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Eval { .. }
+            | Stmt::Close { .. }
+            | Stmt::Let { .. } => Vec::new(),
+        };
+        Self::compose(stmt, children, interner)
+    }
+
+    /// Computes the fingerprint of the statement given the fingerprints of its direct children.
+    fn compose(stmt: &Stmt, children: Vec<Fingerprint>, interner: &mut StmtInterner) -> Self {
+        let ids = children.iter().map(|c| c.id).collect::<Vec<_>>();
+        let kinds = children
+            .iter()
+            .fold(StmtKinds::of(stmt), |kinds, c| kinds | c.kinds);
+        Self {
+            id: interner.insert(stmt, &ids),
+            kinds,
+            children,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// The state of the incremental rewriting that is kept between the application of the rules.
+pub(super) struct IncrementalState {
+    /// For each rule the ids of the subtrees the rule was applied to without any change.
+    /// Only valid as long as the memory of the StreamIR does not change.
+    stable: Vec<HashSet<StmtId>>,
+    /// The storage of all subtrees seen so far
+    interner: StmtInterner,
+    /// The fingerprint of the current statement, if known
+    fingerprint: Option<Fingerprint>,
+}
+
+impl IncrementalState {
+    pub(super) fn new(num_rules: usize) -> Self {
+        Self {
+            stable: vec![HashSet::new(); num_rules],
+            interner: StmtInterner::default(),
+            fingerprint: None,
+        }
+    }
+
+    /// Forgets about the stable subtrees, as the memory changed.
+    pub(super) fn invalidate_memory(&mut self) {
+        self.stable.iter_mut().for_each(HashSet::clear);
+    }
+
+    /// Forgets about the fingerprint of the current statement, as it was changed from outside.
+    pub(super) fn invalidate_stmt(&mut self) {
+        self.fingerprint = None;
+    }
+
+    /// Applies the `i`-th rule to the statement like [RewriteRule::apply_stmt], but skips subtrees that do not contain
+    /// a statement of the given kinds or that the rule already left unchanged before.
+    pub(super) fn apply_stmt(
+        &mut self,
+        i: usize,
+        rule: &dyn RewriteRule,
+        kinds: StmtKinds,
+        stmt: Stmt,
+        memory: &HashMap<StreamReference, Memory>,
+        livetime_equivalences: &LivetimeEquivalences,
+    ) -> Result<(Stmt, ChangeSet), RewriteError> {
+        let fingerprint = self
+            .fingerprint
+            .take()
+            .unwrap_or_else(|| Fingerprint::new(&stmt, &mut self.interner));
+        let (stmt, cs, fingerprint) = Incremental {
+            rule,
+            kinds,
+            memory,
+            livetime_equivalences,
+            stable: &mut self.stable[i],
+            interner: &mut self.interner,
+        }
+        .apply(stmt, fingerprint)?;
+        self.fingerprint = Some(fingerprint);
+        Ok((stmt, cs))
+    }
+}
+
+struct Incremental<'a> {
+    rule: &'a dyn RewriteRule,
+    kinds: StmtKinds,
+    memory: &'a HashMap<StreamReference, Memory>,
+    livetime_equivalences: &'a LivetimeEquivalences,
+    stable: &'a mut HashSet<StmtId>,
+    interner: &'a mut StmtInterner,
+}
+
+impl Incremental<'_> {
+    /// Applies the rule to the statement and additionally returns the fingerprint of the resulting statement.
+    fn apply(
+        &mut self,
+        stmt: Stmt,
+        fp: Fingerprint,
+    ) -> Result<(Stmt, ChangeSet, Fingerprint), RewriteError> {
+        if !fp.kinds.intersects(self.kinds) || self.stable.contains(&fp.id) {
+            return Ok((stmt, ChangeSet::default(), fp));
+        }
+        let Fingerprint {
+            id,
+            kinds,
+            children: fps,
+        } = fp;
+        let mut cs = ChangeSet::default();
+        let (stmt, fps) = match stmt {
+            old @ (Stmt::Skip
+            | Stmt::Input(_)
+            | Stmt::Shift(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Eval { .. }
+            | Stmt::Close { .. }
+            | Stmt::Let { .. }) => (old, fps),
+            Stmt::Seq(stmts) => {
+                let (stmts, fps) = self.apply_all(stmts, fps, &mut cs)?;
+                (Stmt::Seq(stmts), fps)
+            }
+            Stmt::Parallel(stmts) => {
+                let (stmts, fps) = self.apply_all(stmts, fps, &mut cs)?;
+                (Stmt::Parallel(stmts), fps)
+            }
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                let guard = if self.kinds.intersects(StmtKinds::IF) {
+                    let (guard, guard_cs) =
+                        self.rule
+                            .apply_guard(guard, self.memory, self.livetime_equivalences)?;
+                    cs += guard_cs;
+                    guard
+                } else {
+                    guard
+                };
+                let [cons_fp, alt_fp]: [Fingerprint; 2] = fps.try_into().unwrap();
+                let (cons, cons_cs, cons_fp) = self.apply(*cons, cons_fp)?;
+                let (alt, alt_cs, alt_fp) = self.apply(*alt, alt_fp)?;
+                cs += cons_cs + alt_cs;
+                let stmt = Stmt::If(IfStmt {
+                    guard,
+                    cons: Box::new(cons),
+                    alt: Box::new(alt),
+                });
+                (stmt, vec![cons_fp, alt_fp])
+            }
+            Stmt::Iterate { sr, stmt } => {
+                let (stmt, c, fp) = self.apply(*stmt, fps.into_iter().next().unwrap())?;
+                cs += c;
+                let stmt = Stmt::Iterate {
+                    sr,
+                    stmt: Box::new(stmt),
+                };
+                (stmt, vec![fp])
+            }
+            Stmt::Assign {
+                parameter_expr,
+                sr,
+                stmt,
+            } => {
+                let (stmt, c, fp) = self.apply(*stmt, fps.into_iter().next().unwrap())?;
+                cs += c;
+                let stmt = Stmt::Assign {
+                    parameter_expr,
+                    sr,
+                    stmt: Box::new(stmt),
+                };
+                (stmt, vec![fp])
+            }
+        };
+        let fp = if cs.is_empty() {
+            Fingerprint {
+                id,
+                kinds,
+                children: fps,
+            }
+        } else {
+            Fingerprint::compose(&stmt, fps, self.interner)
+        };
+        let (stmt, fp) = if self.kinds.intersects(StmtKinds::of(&stmt)) {
+            let (stmt, cur_cs) =
+                self.rule
+                    .rewrite_stmt(stmt, self.memory, self.livetime_equivalences)?;
+            let fp = if cur_cs.is_empty() {
+                // rules report every change, such that an unchanged statement keeps its id
+                debug_assert!(
+                    self.interner.represents(&stmt, &fp),
+                    "{} changed a statement without reporting it",
+                    self.rule.name()
+                );
+                fp
+            } else {
+                // the structure of the statement is unknown after rewriting it
+                Fingerprint::new(&stmt, self.interner)
+            };
+            cs += cur_cs;
+            (stmt, fp)
+        } else {
+            (stmt, fp)
+        };
+        if cs.is_empty() && fp.id == id {
+            self.stable.insert(id);
+        }
+        Ok((stmt, cs, fp))
+    }
+
+    fn apply_all(
+        &mut self,
+        stmts: Vec<Stmt>,
+        fps: Vec<Fingerprint>,
+        cs: &mut ChangeSet,
+    ) -> Result<(Vec<Stmt>, Vec<Fingerprint>), RewriteError> {
+        let mut new_fps = Vec::with_capacity(fps.len());
+        let stmts = stmts
+            .into_iter()
+            .zip(fps)
+            .map(|(stmt, fp)| {
+                let (stmt, c, fp) = self.apply(stmt, fp)?;
+                *cs += c;
+                new_fps.push(fp);
+                Ok(stmt)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((stmts, new_fps))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use itertools::Itertools;
+
+    use super::{Fingerprint, StmtInterner};
+    use crate::{
+        general_optimizations,
+        ir::{
+            parse::{parse_ir, Random},
+            Stmt, StreamIr,
+        },
+        rewrite_rules::{FastGuards, RemoveSkip, RewriteRule, Rewriter},
+        ParserConfig,
+    };
+
+    /// Applies the rules of the rewriter to the whole StreamIR until a fixed point is reached,
+    /// as done before the introduction of the incremental rewriting.
+    fn run_exhaustive(rewriter: &Rewriter, mut ir: StreamIr) -> StreamIr {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in &rewriter.rules {
+                let memory = std::mem::take(&mut ir.sr2memory);
+                let (memory, cs_memory) = rule
+                    .apply_memory(memory, &ir.livetime_equivalences)
+                    .unwrap();
+                ir.sr2memory = memory;
+                let stmt = std::mem::replace(&mut ir.stmt, Stmt::Skip);
+                let (stmt, cs_stmt) = rule
+                    .apply_stmt(stmt, &ir.sr2memory, &ir.livetime_equivalences)
+                    .unwrap();
+                ir.stmt = stmt;
+                changed |= !(cs_memory + cs_stmt).is_empty();
+            }
+        }
+        ir
+    }
+
+    fn pipelines() -> Vec<Vec<Box<dyn RewriteRule>>> {
+        vec![
+            general_optimizations(),
+            vec![Box::new(FastGuards)],
+            vec![Box::new(RemoveSkip)],
+        ]
+    }
+
+    fn assert_equivalent(ir: StreamIr) {
+        let mut incremental = ir.clone();
+        let mut exhaustive = ir;
+        for rules in pipelines() {
+            let rewriter = Rewriter::try_new(rules).unwrap();
+            incremental = rewriter.run(incremental).unwrap();
+            exhaustive = run_exhaustive(&rewriter, exhaustive);
+            assert_eq!(incremental.stmt, exhaustive.stmt);
+            assert_eq!(incremental.sr2memory, exhaustive.sr2memory);
+        }
+    }
+
+    #[test]
+    fn interned_ids() {
+        let mut random = Random(3);
+        let mut interner = StmtInterner::default();
+        let stmts = (0..300)
+            .map(|_| parse_ir(&random.stmt(2)).stmt)
+            .collect::<Vec<_>>();
+        let ids = stmts
+            .iter()
+            .map(|stmt| Fingerprint::new(stmt, &mut interner).id)
+            .collect::<Vec<_>>();
+        let mut shared = 0;
+        for (i, j) in (0..stmts.len()).tuple_combinations() {
+            assert_eq!(ids[i] == ids[j], stmts[i] == stmts[j]);
+            shared += usize::from(ids[i] == ids[j]);
+        }
+        assert!(shared > 0);
+    }
+
+    #[test]
+    fn randomized_irs() {
+        let mut random = Random(7);
+        for _ in 0..200 {
+            let stmt = random.stmt(5);
+            assert_equivalent(parse_ir(&stmt));
+        }
+    }
+
+    #[test]
+    fn repo_specs() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "lola") {
+                let config = ParserConfig::from_path(path).unwrap();
+                assert_equivalent(crate::parse(&config).unwrap());
+            }
+        }
+    }
+}
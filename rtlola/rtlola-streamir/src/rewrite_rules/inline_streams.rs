@@ -0,0 +1,358 @@
+use std::collections::HashMap;
+
+use crate::ir::{
+    expressions::{Expr, ExprKind},
+    memory::{Memory, StreamMemory},
+    Guard, IfStmt, LivetimeEquivalences, Origin, OutputReference, Stmt, StreamAccessKind, StreamIr,
+    StreamReference,
+};
+
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
+
+#[derive(Debug, Clone)]
+/// A rewriting rule that inlines the expression of an output stream into its single consumer.
+///
+/// A stream is inlined if it is synchronously accessed by exactly one other output stream,
+/// both streams are evaluated under the same guards, and the stream is neither a trigger nor a verdict stream.
+/// The evaluation of the inlined stream is removed and its memory is replaced by [StreamMemory::NoMemory].
+pub struct InlineStreams {
+    definitions: HashMap<OutputReference, Expr>,
+}
+
+impl InlineStreams {
+    /// Computes the streams that can be inlined in the given StreamIR.
+    ///
+    /// The `verdict_streams` are never inlined, as their value is still required after the evaluation cycle.
+    pub fn new(ir: &StreamIr, verdict_streams: &[StreamReference]) -> Self {
+        let mut evals = HashMap::new();
+        collect_evals(&ir.stmt, &mut Vec::new(), &mut evals);
+
+        let is_static = |sr: OutputReference| {
+            matches!(
+                ir.sr2memory.get(&sr.sr()).map(|m| &m.buffer),
+                Some(StreamMemory::Static(_) | StreamMemory::NoMemory)
+            )
+        };
+        let single_eval = |sr: OutputReference| match evals.get(&sr).map(Vec::as_slice) {
+            Some([(Some(guards), expr)]) => Some((guards, *expr)),
+            _ => None,
+        };
+
+        let definitions = ir
+            .outputs()
+            .filter(|sr| !ir.triggers.contains_key(sr) && !verdict_streams.contains(&sr.sr()))
+            .filter(|sr| is_static(*sr))
+            .filter_map(|sr| {
+                let (guards, expr) = single_eval(sr)?;
+                if !is_inlinable(expr) {
+                    return None;
+                }
+                let consumer = match ir.accessed_by.get(&sr.sr()).map(Vec::as_slice) {
+                    Some([(StreamReference::Out(consumer), kinds)])
+                        if *consumer != sr
+                            && kinds.iter().all(|(origin, kind)| {
+                                matches!(origin, Origin::EvalWith(_))
+                                    && *kind == StreamAccessKind::Sync
+                            }) =>
+                    {
+                        *consumer
+                    }
+                    _ => return None,
+                };
+                let same_pacing = is_static(consumer)
+                    && evals.get(&consumer).is_some_and(|consumer_evals| {
+                        consumer_evals
+                            .iter()
+                            .all(|(consumer_guards, _)| consumer_guards.as_ref() == Some(guards))
+                    });
+                same_pacing.then(|| (sr, expr.clone()))
+            })
+            .collect();
+        Self { definitions }
+    }
+
+    /// Replaces all synchronous accesses to inlined streams with their defining expression.
+    fn inline(&self, expr: Expr) -> (Expr, bool) {
+        let Expr { ty, kind } = expr;
+        let mut changed = false;
+        let mut inline = |e: Expr| {
+            let (e, c) = self.inline(e);
+            changed |= c;
+            e
+        };
+        let kind = match kind {
+            ExprKind::SyncStreamAccess {
+                target: StreamReference::Out(target),
+                parameters,
+            } if parameters.is_empty() && self.definitions.contains_key(&target) => {
+                return (self.definitions[&target].clone(), true);
+            }
+            ExprKind::BinaryOperation(op, lhs, rhs) => {
+                ExprKind::BinaryOperation(op, Box::new(inline(*lhs)), Box::new(inline(*rhs)))
+            }
+            ExprKind::UnaryOperation(op, inner) => {
+                ExprKind::UnaryOperation(op, Box::new(inline(*inner)))
+            }
+            ExprKind::Ite(cond, cons, alt) => ExprKind::Ite(
+                Box::new(inline(*cond)),
+                Box::new(inline(*cons)),
+                Box::new(inline(*alt)),
+            ),
+            ExprKind::Cast(ty, inner) => ExprKind::Cast(ty, Box::new(inline(*inner))),
+            ExprKind::FunctionCall(f, args) => {
+                ExprKind::FunctionCall(f, args.into_iter().map(&mut inline).collect())
+            }
+            ExprKind::Tuple(inner) => ExprKind::Tuple(inner.into_iter().map(&mut inline).collect()),
+            ExprKind::TupleAccess(inner, i) => ExprKind::TupleAccess(Box::new(inline(*inner)), i),
+            ExprKind::Some(inner) => ExprKind::Some(Box::new(inline(*inner))),
+            ExprKind::UnwrapOr(expr, default) => {
+                ExprKind::UnwrapOr(Box::new(inline(*expr)), Box::new(inline(*default)))
+            }
+            ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default,
+                parameters,
+            } => ExprKind::OffsetStreamAccess {
+                target,
+                offset,
+                default: Box::new(inline(*default)),
+                parameters,
+            },
+            ExprKind::HoldStreamAccess {
+                target,
+                default,
+                parameters,
+            } => ExprKind::HoldStreamAccess {
+                target,
+                default: Box::new(inline(*default)),
+                parameters,
+            },
+            ExprKind::GetAccess {
+                target,
+                default,
+                parameters,
+            } => ExprKind::GetAccess {
+                target,
+                default: Box::new(inline(*default)),
+                parameters,
+            },
+            other @ (ExprKind::Constant(_)
+            | ExprKind::SyncStreamAccess { .. }
+            | ExprKind::IsFresh { .. }
+            | ExprKind::WindowAccess { .. }
+            | ExprKind::ParameterAccess(_, _)
+            | ExprKind::LambdaParameterAccess(_, _)
+            | ExprKind::Temporary(_)) => other,
+        };
+        (Expr { ty, kind }, changed)
+    }
+}
+
+/// The expressions of the eval statements of each output stream with the guards they are nested in.
+type Evals<'a> = HashMap<OutputReference, Vec<(Option<Vec<Guard>>, &'a Expr)>>;
+
+/// Collects the expression of every eval statement together with the guards it is nested in.
+///
+/// The guards are `None` if the statement is nested inside the alternative of a conditional or inside
+/// an iterate/assign statement, in which case the stream is not considered for inlining.
+fn collect_evals<'a>(stmt: &'a Stmt, guards: &mut Vec<&'a Guard>, evals: &mut Evals<'a>) {
+    match stmt {
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => {}
+        Stmt::Eval { sr, with, idx: _ } => evals
+            .entry(*sr)
+            .or_default()
+            .push((Some(guards.iter().map(|g| (*g).clone()).collect()), with)),
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+            stmts.iter().for_each(|s| collect_evals(s, guards, evals))
+        }
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            guards.push(guard);
+            collect_evals(cons, guards, evals);
+            guards.pop();
+            collect_nested_evals(alt, evals);
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => collect_nested_evals(stmt, evals),
+    }
+}
+
+fn collect_nested_evals<'a>(stmt: &'a Stmt, evals: &mut Evals<'a>) {
+    let mut nested = HashMap::new();
+    collect_evals(stmt, &mut Vec::new(), &mut nested);
+    for (sr, nested) in nested {
+        evals
+            .entry(sr)
+            .or_default()
+            .extend(nested.into_iter().map(|(_, expr)| (None, expr)));
+    }
+}
+
+/// Returns whether the expression only depends on the current values of streams,
+/// such that evaluating it later in the same cycle yields the same value.
+fn is_inlinable(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Constant(_) => true,
+        ExprKind::SyncStreamAccess { parameters, .. } => parameters.is_empty(),
+        ExprKind::BinaryOperation(_, lhs, rhs) | ExprKind::UnwrapOr(lhs, rhs) => {
+            is_inlinable(lhs) && is_inlinable(rhs)
+        }
+        ExprKind::UnaryOperation(_, inner)
+        | ExprKind::Cast(_, inner)
+        | ExprKind::TupleAccess(inner, _)
+        | ExprKind::Some(inner) => is_inlinable(inner),
+        ExprKind::Ite(cond, cons, alt) => {
+            is_inlinable(cond) && is_inlinable(cons) && is_inlinable(alt)
+        }
+        ExprKind::FunctionCall(_, args) | ExprKind::Tuple(args) => args.iter().all(is_inlinable),
+        ExprKind::OffsetStreamAccess { .. }
+        | ExprKind::HoldStreamAccess { .. }
+        | ExprKind::IsFresh { .. }
+        | ExprKind::GetAccess { .. }
+        | ExprKind::WindowAccess { .. }
+        | ExprKind::ParameterAccess(_, _)
+        | ExprKind::LambdaParameterAccess(_, _)
+        | ExprKind::Temporary(_) => false,
+    }
+}
+
+impl RewriteRule for InlineStreams {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::EVAL | StmtKinds::SHIFT)
+    }
+
+    fn rewrite_stmt(
+        &self,
+        stmt: Stmt,
+        _memory: &HashMap<StreamReference, Memory>,
+        _liveness_equivalences: &LivetimeEquivalences,
+    ) -> Result<(Stmt, ChangeSet), RewriteError> {
+        match stmt {
+            Stmt::Eval { sr, .. } if self.definitions.contains_key(&sr) => {
+                Ok((Stmt::Skip, ChangeSet::local_change()))
+            }
+            Stmt::Shift(StreamReference::Out(sr)) if self.definitions.contains_key(&sr) => {
+                Ok((Stmt::Skip, ChangeSet::local_change()))
+            }
+            Stmt::Eval { sr, with, idx } => {
+                let (with, changed) = self.inline(with);
+                let cs = if changed {
+                    ChangeSet::local_change()
+                } else {
+                    ChangeSet::default()
+                };
+                Ok((Stmt::Eval { sr, with, idx }, cs))
+            }
+            other => Ok((other, ChangeSet::default())),
+        }
+    }
+
+    fn rewrite_memory(
+        &self,
+        sr: StreamReference,
+        memory: StreamMemory,
+    ) -> Result<(StreamMemory, ChangeSet), RewriteError> {
+        match (sr, memory) {
+            (StreamReference::Out(o), StreamMemory::Static(_))
+                if self.definitions.contains_key(&o) =>
+            {
+                Ok((StreamMemory::NoMemory, ChangeSet::local_change()))
+            }
+            (_, memory) => Ok((memory, ChangeSet::default())),
+        }
+    }
+
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::NoSkip]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ir::{
+            memory::{StreamBuffer, StreamMemory},
+            parse::parse_ir,
+            Origin, OutputReference, StreamAccessKind, StreamIr, StreamReference,
+        },
+        rewrite_rules::Rewriter,
+    };
+
+    use super::InlineStreams;
+
+    fn prepare(mut ir: StreamIr, accessed_by: &[(usize, usize)]) -> StreamIr {
+        for memory in ir.sr2memory.values_mut() {
+            memory.buffer = StreamMemory::Static(StreamBuffer::SingleValue);
+        }
+        for (target, consumer) in accessed_by {
+            ir.accessed_by
+                .entry(OutputReference::Unparameterized(*target).sr())
+                .or_default()
+                .push((
+                    OutputReference::Unparameterized(*consumer).sr(),
+                    vec![(Origin::EvalWith(0), StreamAccessKind::Sync)],
+                ));
+        }
+        ir
+    }
+
+    #[test]
+    fn single_consumer() {
+        let ir = prepare(
+            parse_ir(
+                "seq {
+					if @0 then eval 0 s0 && s1 fi;
+					if @0 then eval 1 o0 fi
+				}",
+            ),
+            &[(0, 1)],
+        );
+        let reference = parse_ir("seq { if @0 then eval 1 s0 && s1 fi }");
+        let rewriter = Rewriter::try_new(vec![Box::new(InlineStreams::new(&ir, &[]))]).unwrap();
+        let res = rewriter.run(ir).unwrap();
+        assert!(res.stmt.eq(&reference.stmt));
+        assert_eq!(
+            res.sr2memory[&StreamReference::Out(OutputReference::Unparameterized(0))].buffer,
+            StreamMemory::NoMemory
+        );
+    }
+
+    #[test]
+    fn different_pacing() {
+        let ir = prepare(
+            parse_ir(
+                "seq {
+					if @0 then eval 0 s0 fi;
+					if @1 then eval 1 o0 fi
+				}",
+            ),
+            &[(0, 1)],
+        );
+        let rewriter = Rewriter::try_new(vec![Box::new(InlineStreams::new(&ir, &[]))]).unwrap();
+        let (res, changed) = rewriter.apply(ir.clone()).unwrap();
+        assert!(!changed);
+        assert!(res.stmt.eq(&ir.stmt));
+    }
+
+    #[test]
+    fn verdict_stream() {
+        let ir = prepare(
+            parse_ir(
+                "seq {
+					if @0 then eval 0 s0 fi;
+					if @0 then eval 1 o0 fi
+				}",
+            ),
+            &[(0, 1)],
+        );
+        let verdict = [OutputReference::Unparameterized(0).sr()];
+        let rewriter =
+            Rewriter::try_new(vec![Box::new(InlineStreams::new(&ir, &verdict))]).unwrap();
+        let (_, changed) = rewriter.apply(ir).unwrap();
+        assert!(!changed);
+    }
+}
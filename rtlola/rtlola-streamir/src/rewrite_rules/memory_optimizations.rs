@@ -3,13 +3,17 @@ use crate::ir::{
     StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// Simplifies the memory by using special variants for stream with specific memory bounds.
 pub struct MemoryOptimizations;
 
 impl RewriteRule for MemoryOptimizations {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::NONE)
+    }
+
     fn rewrite_memory(
         &self,
         _sr: StreamReference,
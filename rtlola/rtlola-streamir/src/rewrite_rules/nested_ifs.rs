@@ -1,15 +1,16 @@
-use crate::{
-    ir::{Guard, IfStmt, LivetimeEquivalences, Stmt},
-    rewrite_rules::{CombineSeq, RemoveSkip},
-};
+use crate::ir::{Guard, IfStmt, LivetimeEquivalences, Stmt};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// Combines the guards of nested if statments to a single if statment with a conjunction of the guards.
 pub struct CombineNestedIf;
 
 impl RewriteRule for CombineNestedIf {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -50,8 +51,8 @@ impl RewriteRule for CombineNestedIf {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
-        vec![Box::new(RemoveSkip), Box::new(CombineSeq)]
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::FlatSeqs, Property::NoSkip]
     }
 }
 
@@ -83,7 +84,7 @@ mod tests {
 			input 1
 		fi",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineNestedIf {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineNestedIf {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -102,7 +103,7 @@ mod tests {
 		fi	
 		",
         );
-        let rewriter = Rewriter::new(vec![Box::new(CombineNestedIf {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineNestedIf {})]).unwrap();
         let (res, changed) = rewriter.apply(ir.clone()).unwrap();
         assert!(!changed);
         assert!(res.stmt.eq(&ir.stmt));
@@ -1,11 +1,8 @@
 use std::collections::HashMap;
 
-use crate::{
-    ir::{memory::Memory, Guard, LivetimeEquivalences, StreamReference},
-    rewrite_rules::{RemoveIfs, RemoveSkip, SimplifyGuard},
-};
+use crate::ir::{memory::Memory, Guard, LivetimeEquivalences, StreamReference};
 
-use super::{ChangeSet, RewriteRule};
+use super::{ChangeSet, Property, RewriteRule, StmtKinds};
 
 type GuardPredicate = Box<dyn Fn(&Guard) -> Option<bool>>;
 
@@ -19,6 +16,10 @@ impl std::fmt::Debug for EvaluateGuards {
 }
 
 impl RewriteRule for EvaluateGuards {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
     fn rewrite_guard(
         &self,
         guard: Guard,
@@ -34,11 +35,11 @@ impl RewriteRule for EvaluateGuards {
         }
     }
 
-    fn cleanup_rules(&self) -> Vec<Box<dyn RewriteRule>> {
+    fn invalidates(&self) -> Vec<Property> {
         vec![
-            Box::new(SimplifyGuard),
-            Box::new(RemoveIfs),
-            Box::new(RemoveSkip),
+            Property::NoSkip,
+            Property::NoConstantIfs,
+            Property::SimplifiedGuards,
         ]
     }
 }
@@ -93,7 +94,8 @@ mod tests {
 				input 0	
 			fi",
         );
-        let rewriter = Rewriter::new(vec![Box::new(EvaluateGuards::only_event_based())]);
+        let rewriter =
+            Rewriter::try_new(vec![Box::new(EvaluateGuards::only_event_based())]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -0,0 +1,469 @@
+//! A pass manager validating pipelines of rewriting rules before running them.
+//!
+//! Rewriting rules declare which [Property]s of the StreamIR they require, produce and invalidate.
+//! Invalidated properties that can be re-established by a rewriting rule are repaired automatically by
+//! running that rule directly afterwards, all other requirements are checked before the pipeline is run.
+
+use std::{collections::HashSet, fmt::Display};
+
+use thiserror::Error;
+
+use crate::ir::{Guard, IfStmt, Stmt, StreamIr};
+
+use super::{
+    CombineSeq, RemoveIfs, RemoveSkip, RewriteError, RewriteRule, Rewriter, SimplifyGuard,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A property of the StreamIR that rewriting rules can require, produce or invalidate.
+pub enum Property {
+    /// Sequences and parallel statements do not contain skip statements
+    NoSkip,
+    /// Sequences are not directly nested in sequences
+    FlatSeqs,
+    /// All guards are simplified as far as possible by [SimplifyGuard]
+    SimplifiedGuards,
+    /// No if statement has a constant guard
+    NoConstantIfs,
+    /// No guard contains a [Guard::FastAnd] or [Guard::FastOr]
+    NoFastGuards,
+}
+
+impl Property {
+    /// All properties
+    pub const ALL: [Property; 5] = [
+        Property::NoSkip,
+        Property::FlatSeqs,
+        Property::SimplifiedGuards,
+        Property::NoConstantIfs,
+        Property::NoFastGuards,
+    ];
+
+    /// Returns the rewriting rule that establishes the property, if there is one.
+    pub fn establishing_rule(self) -> Option<Box<dyn RewriteRule>> {
+        match self {
+            Property::NoSkip => Some(Box::new(RemoveSkip)),
+            Property::FlatSeqs => Some(Box::new(CombineSeq)),
+            Property::SimplifiedGuards => Some(Box::new(SimplifyGuard)),
+            Property::NoConstantIfs => Some(Box::new(RemoveIfs)),
+            Property::NoFastGuards => None,
+        }
+    }
+
+    /// Returns whether the property holds for the given StreamIR.
+    pub fn holds(self, ir: &StreamIr) -> bool {
+        match self.establishing_rule() {
+            Some(rule) => rule
+                .apply_stmt(ir.stmt.clone(), &ir.sr2memory, &ir.livetime_equivalences)
+                .is_ok_and(|(_, cs)| cs.is_empty()),
+            None => match self {
+                Property::NoFastGuards => !contains_fast_guard(&ir.stmt),
+                Property::NoSkip
+                | Property::FlatSeqs
+                | Property::SimplifiedGuards
+                | Property::NoConstantIfs => unreachable!("property has an establishing rule"),
+            },
+        }
+    }
+}
+
+impl Display for Property {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Property::NoSkip => write!(f, "there are no skip statements"),
+            Property::FlatSeqs => write!(f, "sequences are not nested"),
+            Property::SimplifiedGuards => write!(f, "guards are simplified"),
+            Property::NoConstantIfs => {
+                write!(f, "there are no if statements with a constant guard")
+            }
+            Property::NoFastGuards => write!(f, "guards contain no FastAnd/FastOr"),
+        }
+    }
+}
+
+fn contains_fast_guard(stmt: &Stmt) -> bool {
+    fn is_fast(guard: &Guard) -> bool {
+        match guard {
+            Guard::FastAnd(_) | Guard::FastOr(_) => true,
+            Guard::And { lhs, rhs } | Guard::Or { lhs, rhs } => is_fast(lhs) || is_fast(rhs),
+            Guard::Stream(_)
+            | Guard::Alive(_)
+            | Guard::Dynamic(_)
+            | Guard::GlobalFreq(_)
+            | Guard::LocalFreq(_)
+            | Guard::Constant(_) => false,
+        }
+    }
+    match stmt {
+        Stmt::Skip
+        | Stmt::Shift(_)
+        | Stmt::Input(_)
+        | Stmt::Spawn { .. }
+        | Stmt::Eval { .. }
+        | Stmt::Close { .. }
+        | Stmt::Let { .. } => false,
+        Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts.iter().any(contains_fast_guard),
+        Stmt::If(IfStmt { guard, cons, alt }) => {
+            is_fast(guard) || contains_fast_guard(cons) || contains_fast_guard(alt)
+        }
+        Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => contains_fast_guard(stmt),
+    }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+/// An error in the construction of a pipeline of rewriting rules
+pub enum PipelineError {
+    #[error("rewriting rule {rule} requires that {property}, which does not hold at this point of the pipeline")]
+    /// A rule requires a property that is not guaranteed to hold when the rule is applied
+    UnsatisfiedRequirement {
+        /// The name of the rewriting rule
+        rule: &'static str,
+        /// The required property
+        property: Property,
+    },
+    #[error("the rules repairing the properties invalidated by {rule} do not terminate")]
+    /// Repairing the invalidated properties of a rule leads to an infinite chain of rules
+    NonTerminatingRepair {
+        /// The name of the rewriting rule
+        rule: &'static str,
+    },
+}
+
+/// Appends the rules re-establishing the properties that are invalidated by each rule directly after the rule.
+pub(super) fn expand(
+    rules: Vec<Box<dyn RewriteRule>>,
+) -> Result<Vec<Box<dyn RewriteRule>>, PipelineError> {
+    fn expand_rule(
+        rule: Box<dyn RewriteRule>,
+        origin: &'static str,
+        depth: usize,
+        rules: &mut Vec<Box<dyn RewriteRule>>,
+    ) -> Result<(), PipelineError> {
+        // every rule in a chain of repairs establishes a property, so a longer chain must be cyclic
+        if depth > Property::ALL.len() {
+            return Err(PipelineError::NonTerminatingRepair { rule: origin });
+        }
+        let repairs = rule
+            .invalidates()
+            .into_iter()
+            .filter_map(Property::establishing_rule)
+            .collect::<Vec<_>>();
+        rules.push(rule);
+        repairs
+            .into_iter()
+            .try_for_each(|repair| expand_rule(repair, origin, depth + 1, rules))
+    }
+
+    let mut expanded = Vec::new();
+    for rule in rules {
+        let origin = rule.name();
+        let mut chain = Vec::new();
+        expand_rule(rule, origin, 0, &mut chain)?;
+        // a repair needed several times only has to run after the last rule invalidating its property
+        let repairs = chain.split_off(1);
+        let last = (0..repairs.len())
+            .map(|i| {
+                repairs[i + 1..]
+                    .iter()
+                    .all(|r| r.name() != repairs[i].name())
+            })
+            .collect::<Vec<_>>();
+        expanded.extend(chain);
+        expanded.extend(
+            repairs
+                .into_iter()
+                .zip(last)
+                .filter_map(|(repair, last)| last.then_some(repair)),
+        );
+    }
+    Ok(expanded)
+}
+
+/// Updates the properties that hold after applying the rule once, as declared by the rule.
+fn declare(rule: &dyn RewriteRule, properties: &mut HashSet<Property>) {
+    rule.invalidates().iter().for_each(|p| {
+        properties.remove(p);
+    });
+    properties.extend(rule.produces());
+}
+
+#[derive(Debug, Default)]
+/// A pipeline of rewriting stages that are run one after another.
+///
+/// Each stage applies its rewriting rules until a fixed point is reached (see [Rewriter]).
+pub struct PassManager {
+    stages: Vec<Rewriter>,
+    /// The first error that occurred while adding the stages
+    error: Option<PipelineError>,
+}
+
+impl PassManager {
+    /// Constructs an empty pipeline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage with the given rules to the pipeline.
+    ///
+    /// The rules re-establishing invalidated properties are inserted automatically.
+    pub fn stage(mut self, rules: Vec<Box<dyn RewriteRule>>) -> Self {
+        match Rewriter::try_new(rules) {
+            Ok(rewriter) => self.stages.push(rewriter),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+        self
+    }
+
+    /// Checks that the requirements of all rules are met, starting from a StreamIR for which the given
+    /// properties hold. Returns the properties that hold after running the pipeline.
+    pub fn validate(
+        &self,
+        mut properties: HashSet<Property>,
+    ) -> Result<HashSet<Property>, PipelineError> {
+        if let Some(e) = &self.error {
+            return Err(*e);
+        }
+        for stage in &self.stages {
+            // The rules of a stage are applied repeatedly, so only the properties that are preserved
+            // by a whole iteration of the stage hold at the start of every iteration.
+            let mut invariant = properties;
+            loop {
+                let mut after = invariant.clone();
+                stage
+                    .rules
+                    .iter()
+                    .for_each(|rule| declare(rule.as_ref(), &mut after));
+                let preserved = invariant.intersection(&after).copied().collect();
+                if preserved == invariant {
+                    break;
+                }
+                invariant = preserved;
+            }
+            properties = invariant;
+            for rule in &stage.rules {
+                if let Some(property) = rule
+                    .requires()
+                    .into_iter()
+                    .find(|p| !properties.contains(p))
+                {
+                    return Err(PipelineError::UnsatisfiedRequirement {
+                        rule: rule.name(),
+                        property,
+                    });
+                }
+                declare(rule.as_ref(), &mut properties);
+            }
+            // in the fixed point of the stage, every rule reached its fixed point
+            properties.extend(stage.rules.iter().flat_map(|rule| rule.produces()));
+        }
+        Ok(properties)
+    }
+
+    /// Validates the pipeline for the given StreamIR and runs all stages on it.
+    pub fn run(&self, mut ir: StreamIr) -> Result<StreamIr, RewriteError> {
+        let required = self
+            .stages
+            .iter()
+            .flat_map(|stage| &stage.rules)
+            .flat_map(|rule| rule.requires())
+            .collect::<HashSet<_>>();
+        let properties = required.into_iter().filter(|p| p.holds(&ir)).collect();
+        self.validate(properties)
+            .map_err(RewriteError::InvalidPipeline)?;
+        for stage in &self.stages {
+            ir = stage.run(ir)?;
+        }
+        Ok(ir)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::PathBuf};
+
+    use crate::{
+        general_optimizations,
+        ir::{
+            parse::{parse_ir, Random},
+            Guard, IfStmt, Stmt, StreamIr,
+        },
+        rewrite_rules::{
+            CombineIf, CombineIterate, CombineNestedIf, CombineSeq, FastGuards, ImpliedGuards,
+            IterateAssign, MemoryOptimizations, MoveCommonGuardsOutside, MoveIfOutside,
+            RemoveClose, RemoveIfs, RemoveShift, RemoveSkip, RemoveSpawn, RewriteError,
+            RewriteRule, Rewriter, SimplifyGuard,
+        },
+        ParserConfig,
+    };
+
+    use super::{PassManager, PipelineError, Property};
+
+    fn fresh() -> HashSet<Property> {
+        HashSet::from([Property::NoFastGuards])
+    }
+
+    #[test]
+    fn repairs_are_inserted() {
+        let rewriter = Rewriter::try_new(vec![Box::new(CombineIf), Box::new(FastGuards)]).unwrap();
+        let names = rewriter.rules.iter().map(|r| r.name()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "CombineIf",
+                "CombineSeq",
+                "RemoveSkip",
+                "FastGuards",
+                "SimplifyGuard"
+            ]
+        );
+    }
+
+    #[test]
+    fn implied_after_fast_guards() {
+        let pipeline = PassManager::new()
+            .stage(vec![Box::new(FastGuards)])
+            .stage(vec![Box::new(ImpliedGuards)]);
+        assert_eq!(
+            pipeline.validate(fresh()),
+            Err(PipelineError::UnsatisfiedRequirement {
+                rule: "ImpliedGuards",
+                property: Property::NoFastGuards
+            })
+        );
+    }
+
+    #[test]
+    fn implied_in_stage_with_fast_guards() {
+        // ImpliedGuards is applied again after FastGuards in the next iteration of the stage
+        let rules: Vec<Box<dyn RewriteRule>> = vec![Box::new(ImpliedGuards), Box::new(FastGuards)];
+        let pipeline = PassManager::new().stage(rules);
+        assert!(pipeline.validate(fresh()).is_err());
+    }
+
+    #[test]
+    fn fast_guards_in_input() {
+        let ir = parse_ir("if FastAnd(0, 1) then input 0 fi");
+        let pipeline = PassManager::new().stage(vec![Box::new(ImpliedGuards)]);
+        assert!(matches!(
+            pipeline.run(ir),
+            Err(RewriteError::InvalidPipeline(
+                PipelineError::UnsatisfiedRequirement { .. }
+            ))
+        ));
+    }
+
+    #[test]
+    fn optimize_all() {
+        let pipeline = PassManager::new()
+            .stage(general_optimizations())
+            .stage(vec![Box::new(FastGuards)]);
+        let properties = pipeline.validate(fresh()).unwrap();
+        assert!(!properties.contains(&Property::NoFastGuards));
+        assert!(properties.contains(&Property::SimplifiedGuards));
+    }
+
+    /// The unmodified rule lists of `optimize_all` before the rules declared their properties.
+    fn baseline_stages() -> [Vec<Box<dyn RewriteRule>>; 2] {
+        [
+            vec![
+                Box::new(CombineIf),
+                Box::new(SimplifyGuard),
+                Box::new(MoveCommonGuardsOutside),
+                Box::new(ImpliedGuards),
+                Box::new(SimplifyGuard),
+                Box::new(RemoveIfs),
+                Box::new(CombineSeq),
+                Box::new(MoveIfOutside),
+                Box::new(IterateAssign),
+                Box::new(CombineNestedIf),
+                Box::new(CombineIterate),
+                Box::new(RemoveIfs),
+                Box::new(RemoveShift),
+                Box::new(MemoryOptimizations),
+                Box::new(RemoveSpawn),
+                Box::new(RemoveClose),
+            ],
+            vec![Box::new(FastGuards)],
+        ]
+    }
+
+    /// The repairs `optimize_all` applies at the end of each baseline stage, as documented on it:
+    /// the skips left by RemoveShift are removed and the fast guards are simplified.
+    fn documented_repairs() -> [Vec<Box<dyn RewriteRule>>; 2] {
+        [
+            vec![Box::new(RemoveSkip), Box::new(CombineSeq)],
+            vec![Box::new(SimplifyGuard)],
+        ]
+    }
+
+    /// Rewrites all conjunctions into a right-nested chain, as the association of the conjunctions
+    /// built by CombineNestedIf depends on the order in which the rules are applied.
+    fn normalize(stmt: &mut Stmt) {
+        fn conjuncts(guard: Guard, acc: &mut Vec<Guard>) {
+            match guard {
+                Guard::And { lhs, rhs } => {
+                    conjuncts(*lhs, acc);
+                    conjuncts(*rhs, acc);
+                }
+                other => acc.push(other),
+            }
+        }
+        match stmt {
+            Stmt::Seq(inner) | Stmt::Parallel(inner) => inner.iter_mut().for_each(normalize),
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => normalize(stmt),
+            Stmt::If(IfStmt { guard, cons, alt }) => {
+                let mut acc = Vec::new();
+                conjuncts(guard.clone(), &mut acc);
+                *guard = acc
+                    .into_iter()
+                    .rev()
+                    .reduce(|rhs, lhs| Guard::And {
+                        lhs: Box::new(lhs),
+                        rhs: Box::new(rhs),
+                    })
+                    .unwrap();
+                normalize(cons);
+                normalize(alt);
+            }
+            _ => {}
+        }
+    }
+
+    /// Checks that `optimize_all` only differs from the baseline stages in the documented repairs
+    /// and the association of conjunctions.
+    fn assert_same_as_baseline(ir: StreamIr) {
+        let mut old = ir.clone();
+        for (mut rules, repairs) in baseline_stages().into_iter().zip(documented_repairs()) {
+            // the baseline rewriter applied the rules as given, without inserting repairs
+            rules.extend(repairs);
+            old = Rewriter { rules }.run(old).unwrap();
+        }
+        let mut new = crate::optimize_all(ir).unwrap();
+        normalize(&mut old.stmt);
+        normalize(&mut new.stmt);
+        assert_eq!(old.stmt, new.stmt);
+    }
+
+    #[test]
+    fn same_as_baseline_on_randomized_irs() {
+        let mut random = Random(11);
+        for _ in 0..200 {
+            let stmt = random.stmt(5);
+            assert_same_as_baseline(parse_ir(&stmt));
+        }
+    }
+
+    #[test]
+    fn same_as_baseline_on_repo_specs() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "lola") {
+                let config = ParserConfig::from_path(path).unwrap();
+                assert_same_as_baseline(crate::parse(&config).unwrap());
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use crate::ir::{memory::Memory, ranges::Ranges, Guard, LivetimeEquivalences, StreamReference};
+
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
+
+#[derive(Debug, Clone)]
+/// A rewriting rule replacing guards with a constant, if the [Ranges] of the streams determine the value of their expression.
+pub struct RangeGuards {
+    ranges: Ranges,
+}
+
+impl RangeGuards {
+    /// Creates the rule from the ranges computed for the StreamIR it is applied to.
+    pub fn new(ranges: Ranges) -> Self {
+        Self { ranges }
+    }
+}
+
+impl RewriteRule for RangeGuards {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
+    fn invalidates(&self) -> Vec<Property> {
+        vec![Property::SimplifiedGuards, Property::NoConstantIfs]
+    }
+
+    fn rewrite_guard(
+        &self,
+        guard: Guard,
+        _memory: &HashMap<StreamReference, Memory>,
+        _liveness_equivalences: &LivetimeEquivalences,
+    ) -> Result<(Guard, ChangeSet), RewriteError> {
+        match guard {
+            Guard::Dynamic(expr) => match self.ranges.expr(&expr).as_bool() {
+                Some(b) => Ok((Guard::Constant(b), ChangeSet::local_change())),
+                None => Ok((Guard::Dynamic(expr), ChangeSet::default())),
+            },
+            other => Ok((other, ChangeSet::default())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::{
+        ir::{parse::parse_ir, ranges::Ranges},
+        rewrite_rules::Rewriter,
+    };
+
+    use super::RangeGuards;
+
+    #[test]
+    fn fold_dynamic() {
+        let ir = parse_ir(
+            "seq { eval 0 true; if Expr(o0) then eval 1 s0 fi; if Expr(s1) then eval 2 s1 fi }",
+        );
+        let reference = parse_ir("seq { eval 0 true; eval 1 s0; if Expr(s1) then eval 2 s1 fi }");
+        let ranges = Ranges::new(&ir, &HashMap::new());
+        let rewriter = Rewriter::try_new(vec![Box::new(RangeGuards::new(ranges))]).unwrap();
+        let res = rewriter.run(ir).unwrap();
+        assert!(res.stmt.eq(&reference.stmt));
+    }
+}
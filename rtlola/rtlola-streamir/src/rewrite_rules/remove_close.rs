@@ -5,13 +5,17 @@ use crate::ir::{
     LivetimeEquivalences, Stmt, StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Copy, Clone, Debug)]
 /// Remove spawn statements when the stream lives for the whole runtime of the monitor
 pub struct RemoveClose;
 
 impl RewriteRule for RemoveClose {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::CLOSE)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
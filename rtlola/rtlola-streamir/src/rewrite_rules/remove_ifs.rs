@@ -2,13 +2,21 @@ use std::collections::HashMap;
 
 use crate::ir::{memory::Memory, Guard, IfStmt, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that removes if statements with a constant guard condition.
 pub struct RemoveIfs;
 
 impl RewriteRule for RemoveIfs {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
+    fn produces(&self) -> Vec<Property> {
+        vec![Property::NoConstantIfs]
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
@@ -50,7 +58,7 @@ mod tests {
 		",
         );
         let reference = parse_ir("input 0");
-        let rewriter = Rewriter::new(vec![Box::new(RemoveIfs {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(RemoveIfs {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -68,7 +76,7 @@ mod tests {
 		",
         );
         let reference = parse_ir("input 1");
-        let rewriter = Rewriter::new(vec![Box::new(RemoveIfs {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(RemoveIfs {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -85,7 +93,7 @@ mod tests {
 		fi	
 		",
         );
-        let rewriter = Rewriter::new(vec![Box::new(RemoveIfs {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(RemoveIfs {})]).unwrap();
         let (res, changed) = rewriter.apply(ir.clone()).unwrap();
         assert!(!changed);
         assert!(res.stmt.eq(&ir.stmt));
@@ -5,13 +5,17 @@ use crate::ir::{
     LivetimeEquivalences, Stmt, StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule removing shift statements if the memory is SingleValue or NoMemory.
 pub struct RemoveShift;
 
 impl RewriteRule for RemoveShift {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::SHIFT)
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
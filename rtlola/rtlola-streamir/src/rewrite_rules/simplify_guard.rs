@@ -8,13 +8,21 @@ use crate::ir::{
     Guard, LivetimeEquivalences, StreamReference,
 };
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule simplifying guard conditions by moving conjunctions/disjunctions and constants from expression level to guard level.
 pub struct SimplifyGuard;
 
 impl RewriteRule for SimplifyGuard {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(StmtKinds::IF)
+    }
+
+    fn produces(&self) -> Vec<Property> {
+        vec![Property::SimplifiedGuards]
+    }
+
     fn rewrite_guard(
         &self,
         guard: Guard,
@@ -26,21 +34,21 @@ impl RewriteRule for SimplifyGuard {
                 Ok((guard, ChangeSet::default()))
             }
             Guard::FastAnd(inner) => {
-                let inner_len = inner.len();
+                let normalized = inner.iter().tuple_windows().all(|(a, b)| a < b);
                 let new_inner: Vec<_> = inner.into_iter().sorted().unique().collect();
-                if inner_len != new_inner.len() {
-                    Ok((Guard::FastAnd(new_inner), ChangeSet::local_change()))
-                } else {
+                if normalized {
                     Ok((Guard::FastAnd(new_inner), ChangeSet::default()))
+                } else {
+                    Ok((Guard::FastAnd(new_inner), ChangeSet::local_change()))
                 }
             }
             Guard::FastOr(inner) => {
-                let inner_len = inner.len();
+                let normalized = inner.iter().tuple_windows().all(|(a, b)| a < b);
                 let new_inner: Vec<_> = inner.into_iter().sorted().unique().collect();
-                if inner_len != new_inner.len() {
-                    Ok((Guard::FastOr(new_inner), ChangeSet::local_change()))
-                } else {
+                if normalized {
                     Ok((Guard::FastOr(new_inner), ChangeSet::default()))
+                } else {
+                    Ok((Guard::FastOr(new_inner), ChangeSet::local_change()))
                 }
             }
             Guard::Dynamic(expr) => {
@@ -146,7 +154,7 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(SimplifyGuard {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(SimplifyGuard {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
@@ -167,7 +175,7 @@ mod tests {
             fi
         ",
         );
-        let rewriter = Rewriter::new(vec![Box::new(SimplifyGuard {})]);
+        let rewriter = Rewriter::try_new(vec![Box::new(SimplifyGuard {})]).unwrap();
         let (res, changed) = rewriter.apply(ir).unwrap();
         assert!(changed);
         assert!(res.stmt.eq(&reference.stmt));
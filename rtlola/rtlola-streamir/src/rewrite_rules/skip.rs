@@ -2,13 +2,27 @@ use std::collections::HashMap;
 
 use crate::ir::{memory::Memory, IfStmt, LivetimeEquivalences, Stmt, StreamReference};
 
-use super::{ChangeSet, RewriteError, RewriteRule};
+use super::{ChangeSet, Property, RewriteError, RewriteRule, StmtKinds};
 
 #[derive(Debug, Clone, Copy)]
 /// A rewriting rule that removes Skip instructions.
 pub struct RemoveSkip;
 
 impl RewriteRule for RemoveSkip {
+    fn matched_kinds(&self) -> Option<StmtKinds> {
+        Some(
+            StmtKinds::SEQ
+                | StmtKinds::PARALLEL
+                | StmtKinds::IF
+                | StmtKinds::ITERATE
+                | StmtKinds::ASSIGN,
+        )
+    }
+
+    fn produces(&self) -> Vec<Property> {
+        vec![Property::NoSkip]
+    }
+
     fn rewrite_stmt(
         &self,
         stmt: Stmt,
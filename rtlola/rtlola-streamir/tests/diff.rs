@@ -0,0 +1,56 @@
+//! Tests for the exit status of the `diff` subcommand, which follows diff(1):
+//! 0 if the specifications are equal, 1 if they differ and 2 on errors.
+
+use std::{
+    path::PathBuf,
+    process::{Command, Output},
+};
+
+fn spec(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("..")
+        .join(name)
+}
+
+fn diff(old: &PathBuf, new: &PathBuf) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_rtlola-streamir"))
+        .arg("diff")
+        .arg(old)
+        .arg(new)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn equal() {
+    let output = diff(
+        &spec("waypoint_spec_v1.lola"),
+        &spec("waypoint_spec_v1.lola"),
+    );
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn different() {
+    let output = diff(
+        &spec("waypoint_spec_v1.lola"),
+        &spec("waypoint_spec_v2.lola"),
+    );
+    assert_eq!(output.status.code(), Some(1));
+    assert!(!output.stdout.is_empty());
+}
+
+#[test]
+fn missing_file() {
+    let output = diff(&spec("missing.lola"), &spec("waypoint_spec_v1.lola"));
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn invalid_specification() {
+    let invalid = std::env::temp_dir().join("rtlola-streamir-diff-invalid.lola");
+    std::fs::write(&invalid, "output a := b\n").unwrap();
+    let output = diff(&spec("waypoint_spec_v1.lola"), &invalid);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!output.stderr.is_empty());
+}
@@ -1,17 +1,23 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use clap::{Parser, ValueEnum};
 use itertools::Itertools;
 use rtlola2c::main_function::MainFunction;
-use rtlola2c::CFormatter;
+use rtlola2c::unbounded::{OverflowPolicy, UnboundedMemory};
+use rtlola2c::{CFormatter, FormatError};
 use rtlola_streamir::formatter::StreamIrFormatter;
-use rtlola_streamir::ir::StreamReference;
+use rtlola_streamir::ir::cost::{self, TargetSizes};
+use rtlola_streamir::ir::ranges::{self, Interval, Ranges};
+use rtlola_streamir::ir::{json, InputReference, StreamIr, StreamReference};
+use rtlola_streamir::rewrite_rules::{CommonSubexpressions, InlineStreams, RangeGuards};
 use rtlola_streamir::{optimize_all, parse, ParserConfig};
 
 #[derive(Parser)]
 struct Args {
-    /// The path to the specification
+    /// The path to the specification or to a StreamIR serialized as JSON (with the extension `.json`)
     spec: PathBuf,
     #[clap(long)]
     /// Whether to overwrite existing files
@@ -28,6 +34,54 @@ struct Args {
     output_streams: Vec<String>,
     #[clap(short, long, default_value_t=Verbosity::Outputs, value_enum)]
     verbosity: Verbosity,
+    /// The range of the values of an input stream as `name=lo..hi`, can be given multiple times
+    #[clap(long)]
+    input_range: Vec<String>,
+    #[clap(flatten)]
+    budget: Budget,
+    #[clap(flatten)]
+    unbounded: Unbounded,
+}
+
+#[derive(clap::Args)]
+struct Unbounded {
+    /// The number of values stored for streams with unbounded memory, which are rejected if not given
+    #[clap(long)]
+    unbounded_capacity: Option<NonZeroUsize>,
+    /// What happens when a stream with unbounded memory exceeds the capacity
+    #[clap(long, value_enum, default_value_t = OverflowPolicy::DropOldest)]
+    overflow_policy: OverflowPolicy,
+}
+
+impl Unbounded {
+    fn memory(&self) -> Option<UnboundedMemory> {
+        self.unbounded_capacity.map(|capacity| UnboundedMemory {
+            capacity: capacity.get(),
+            policy: self.overflow_policy,
+        })
+    }
+}
+
+#[derive(clap::Args)]
+struct Budget {
+    /// Print the memory footprint and the operations per evaluation cycle of the monitor
+    #[clap(long, value_enum)]
+    cost_report: Option<ReportFormat>,
+    /// Fail if the monitor requires more than the given number of bytes of memory
+    #[clap(long)]
+    max_ram: Option<usize>,
+    /// Fail if an evaluation cycle of the monitor requires more than the given number of operations
+    #[clap(long)]
+    max_ops: Option<usize>,
+    /// The size of a pointer on the target platform in bytes
+    #[clap(long, default_value_t = 8)]
+    pointer_size: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ReportFormat {
+    Text,
+    Json,
 }
 
 #[derive(Clone, Copy, ValueEnum)]
@@ -38,18 +92,38 @@ enum Verbosity {
     Silent,
 }
 
-fn generate(
-    config: &ParserConfig,
-    overwrite: bool,
+fn load(spec: &Path) -> anyhow::Result<StreamIr> {
+    if spec.extension().is_some_and(|ext| ext == "json") {
+        let json = std::fs::read_to_string(spec).context("reading StreamIR file")?;
+        return json::from_json(&json).context("deserializing StreamIR from JSON");
+    }
+    let config =
+        ParserConfig::from_path(spec.to_path_buf()).context("loading specification file")?;
+    parse(&config).context("parsing specification to StreamIR")
+}
+
+fn prepare(
+    ir: StreamIr,
     optimize: bool,
-    main: MainFunction,
     output_streams: Vec<String>,
     verbosity: Verbosity,
-    output_dir: PathBuf,
-) -> anyhow::Result<()> {
-    let ir = parse(config).context("parsing specification to StreamIR")?;
+    input_range: Vec<String>,
+) -> anyhow::Result<(StreamIr, Vec<StreamReference>)> {
+    let inputs: HashMap<InputReference, Interval> = input_range
+        .iter()
+        .map(|annotation| ranges::parse_annotation(&ir, annotation))
+        .collect::<Result<_, _>>()
+        .map_err(anyhow::Error::msg)
+        .context("parsing input ranges")?;
+    for warning in Ranges::new(&ir, &inputs).trigger_warnings(&ir) {
+        eprintln!("warning: {warning}");
+    }
+
     let ir = if optimize {
-        optimize_all(ir).context("optimizing StreamIR")?
+        let ir = optimize_all(ir).context("optimizing StreamIR")?;
+        let guards = RangeGuards::new(Ranges::new(&ir, &inputs));
+        rtlola_streamir::optimize(ir, vec![Box::new(guards)])
+            .context("folding guards by the ranges of streams")?
     } else {
         ir
     };
@@ -78,9 +152,62 @@ fn generate(
         }
     };
 
-    let formatter = CFormatter::new(&ir, overwrite, main, verdict_streams, output_dir);
+    let ir = if optimize {
+        let inline = InlineStreams::new(&ir, &verdict_streams);
+        let ir =
+            rtlola_streamir::optimize(ir, vec![Box::new(inline)]).context("inlining streams")?;
+        let cse = CommonSubexpressions::new(&ir);
+        rtlola_streamir::optimize(ir, vec![Box::new(cse)])
+            .context("eliminating common subexpressions")?
+    } else {
+        ir
+    };
+    Ok((ir, verdict_streams))
+}
+
+fn check_budget(
+    ir: &StreamIr,
+    budget: Budget,
+    unbounded: Option<UnboundedMemory>,
+) -> anyhow::Result<()> {
+    let Budget {
+        cost_report,
+        max_ram,
+        max_ops,
+        pointer_size,
+    } = budget;
+    let sizes = TargetSizes {
+        pointer: pointer_size,
+        unbounded: unbounded.map(|u| u.capacity),
+        ..Default::default()
+    };
+    let report = cost::analyze(ir, &sizes);
+    match cost_report {
+        Some(ReportFormat::Text) => print!("{report}"),
+        Some(ReportFormat::Json) => println!("{}", report.to_json()),
+        None => {}
+    }
+    Ok(report.check_budget(max_ram, max_ops)?)
+}
+
+fn generate(
+    ir: StreamIr,
+    verdict_streams: Vec<StreamReference>,
+    overwrite: bool,
+    main: MainFunction,
+    output_dir: PathBuf,
+    unbounded: Option<UnboundedMemory>,
+    source: Option<String>,
+) -> anyhow::Result<()> {
+    let formatter = CFormatter::new(&ir, overwrite, main, verdict_streams, output_dir, unbounded);
     formatter
         .format(ir)
+        .map_err(|e| match (e, source) {
+            (FormatError::Unsupported(features), Some(source)) => {
+                FormatError::Unsupported(features.locate(&source))
+            }
+            (e, _) => e,
+        })
         .context("formatting StreamIR as C code")?;
     Ok(())
 }
@@ -94,16 +221,29 @@ fn main() -> anyhow::Result<()> {
         output_dir,
         output_streams,
         verbosity,
+        input_range,
+        budget,
+        unbounded,
     } = Args::parse();
-    let config = ParserConfig::from_path(spec).context("loading specification file")?;
+    let unbounded = unbounded.memory();
+    let ir = load(&spec)?;
+    // the source of a specification, used to locate unsupported features
+    let source = spec
+        .extension()
+        .is_none_or(|ext| ext != "json")
+        .then(|| std::fs::read_to_string(&spec).ok())
+        .flatten();
+    let (ir, verdict_streams) = prepare(ir, optimize, output_streams, verbosity, input_range)
+        .context("generating C code")?;
+    check_budget(&ir, budget, unbounded).context("checking the resource budget")?;
     generate(
-        &config,
+        ir,
+        verdict_streams,
         overwrite,
-        optimize,
         main,
-        output_streams,
-        verbosity,
         output_dir,
+        unbounded,
+        source,
     )
     .context("generating C code")
 }
@@ -0,0 +1,299 @@
+//! The capabilities of the C backend, which are checked with the shared check of the StreamIR
+//! (see [rtlola_streamir::capabilities]) before the formatting.
+
+use rtlola_streamir::{
+    capabilities::Capabilities,
+    ir::{
+        expressions::{Function, Operator},
+        StreamIr, Type,
+    },
+};
+
+pub use rtlola_streamir::capabilities::{
+    Feature, FeatureLocation, UnsupportedFeature, UnsupportedFeatures,
+};
+
+use crate::{main_function::MainFunction, unbounded::UnboundedMemory};
+
+/// The features supported by the C backend, which depend on the main function and the storage of unbounded memory
+struct CCapabilities {
+    main: MainFunction,
+    unbounded: Option<UnboundedMemory>,
+}
+
+impl Capabilities for CCapabilities {
+    fn backend(&self) -> &'static str {
+        "C"
+    }
+
+    fn supports_type(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Int(_)
+            | Type::UInt(_)
+            | Type::Bool
+            | Type::String
+            | Type::Float32
+            | Type::Float64 => true,
+            Type::Tuple(inner) => !inner.iter().any(|ty| matches!(ty, Type::Option(_))),
+            Type::Option(inner) => !matches!(**inner, Type::Option(_) | Type::Tuple(_)),
+            Type::Fixed(_) | Type::UFixed(_) | Type::Bytes => false,
+        }
+    }
+
+    fn supports_function(&self, function: Function, ty: &Type) -> bool {
+        match function {
+            Function::Sqrt
+            | Function::Sin
+            | Function::Arcsin
+            | Function::Cos
+            | Function::Arccos
+            | Function::Tan
+            | Function::Arctan => *ty == Type::Float64,
+            Function::Abs => matches!(ty, Type::Float64 | Type::Float32 | Type::Int(64)),
+            Function::Min | Function::Max => matches!(ty, Type::Float64 | Type::Float32),
+        }
+    }
+
+    fn supports_operator(&self, op: Operator, lhs: &Type, rhs: &Type) -> bool {
+        op != Operator::Pow || (matches!(lhs, Type::Float64 | Type::Float32) && lhs == rhs)
+    }
+
+    fn supports_input(&self, ty: &Type) -> bool {
+        // the generated main function reads the inputs from CSV
+        !matches!(self.main, MainFunction::CsvOffline)
+            || matches!(
+                ty,
+                Type::Int(_) | Type::UInt(_) | Type::Float32 | Type::Float64 | Type::Bool
+            )
+    }
+
+    fn supports_unbounded_memory(&self) -> bool {
+        self.unbounded.is_some()
+    }
+
+    fn supports_delayed_evaluation(&self) -> bool {
+        true
+    }
+
+    fn supports_global_frequency(&self) -> bool {
+        false
+    }
+
+    fn supports_memoryless_access(&self) -> bool {
+        false
+    }
+}
+
+/// Checks that the C backend can compile the StreamIR with the given main function and storage of unbounded memory.
+pub fn check(
+    ir: &StreamIr,
+    main: MainFunction,
+    unbounded: Option<UnboundedMemory>,
+) -> Result<(), UnsupportedFeatures> {
+    rtlola_streamir::capabilities::check(ir, &CCapabilities { main, unbounded })
+}
+
+#[cfg(test)]
+mod tests {
+    use rtlola_streamir::{
+        ir::{
+            expressions::{Function, Operator},
+            text::parse,
+            Type,
+        },
+        ParserConfig,
+    };
+
+    use super::{check, Feature, UnsupportedFeatures};
+    use crate::{
+        main_function::MainFunction,
+        tests::generate,
+        unbounded::{OverflowPolicy, UnboundedMemory},
+        FormatError,
+    };
+
+    /// Returns the unsupported features of a StreamIR with the input `a` and the given declarations and statement
+    fn features(declarations: &str, stmt: &str) -> Vec<Feature> {
+        features_with(declarations, stmt, MainFunction::NoMain, None)
+    }
+
+    fn features_with(
+        declarations: &str,
+        stmt: &str,
+        main: MainFunction,
+        unbounded: Option<UnboundedMemory>,
+    ) -> Vec<Feature> {
+        let ir = parse(&format!(
+            "streamir 1\nstream in[0] \"a\": Int64 = static bounded(2)\n{declarations}\nstmt\n{stmt}"
+        ))
+        .unwrap_or_else(|e| panic!("{e}"));
+        match check(&ir, main, unbounded) {
+            Ok(()) => Vec::new(),
+            Err(UnsupportedFeatures(found)) => found.into_iter().map(|f| f.feature).collect(),
+        }
+    }
+
+    const OUTPUT: &str = "stream out[0] \"b\": Int64 = static single";
+
+    #[test]
+    fn supported() {
+        let stmt = "if @in[0] then seq { shift in[0]; input in[0]; eval(0) out[0] = (offset(in[0], 1, i64(0)) + in[0]); } fi";
+        assert_eq!(features(OUTPUT, stmt), []);
+    }
+
+    #[test]
+    fn parameterization() {
+        let declarations = "stream pout[0] \"p\": Int64 = instances(\"x\": Int64) single";
+        let stmt = "if @in[0] then spawn pout[0] with (in[0]) fi";
+        assert_eq!(features(declarations, stmt), [Feature::Parameterization]);
+    }
+
+    #[test]
+    fn parameterized_access() {
+        // the formatter relies on the check instead of panicking on the parameters of the accesses
+        let declarations =
+            format!("{OUTPUT}\nstream pout[0] \"p\": Int64 = instances(\"x\": Int64) bounded(2)");
+        for access in [
+            "offset(pout[0](in[0]), 1, i64(0))",
+            "get(pout[0](in[0]), i64(0))",
+            "hold(pout[0](in[0]), i64(0))",
+            "fresh(pout[0](in[0]))",
+        ] {
+            let ir = format!(
+                "streamir 1\nstream in[0] \"a\": Int64 = static bounded(2)\n{declarations}\nstmt\nif @in[0] then eval(0) out[0] = {access} fi"
+            );
+            let Err(FormatError::Unsupported(UnsupportedFeatures(found))) = generate(&ir, None)
+            else {
+                panic!("parameterized access {access} is rejected")
+            };
+            let features = found
+                .into_iter()
+                .map(|f| (f.location.stream, f.feature))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                features,
+                [
+                    ("b".into(), Feature::Parameterization),
+                    ("p".into(), Feature::Parameterization)
+                ],
+                "{access}"
+            );
+        }
+    }
+
+    #[test]
+    fn global_frequency() {
+        let stmt = "if global(1s) then eval(0) out[0] = i64(1) fi";
+        assert_eq!(features(OUTPUT, stmt), [Feature::GlobalFrequency]);
+    }
+
+    #[test]
+    fn local_frequency() {
+        let declarations = format!("{OUTPUT}\nlocal_freq 0 = 500ms for out[0]");
+        let stmt = "if local(0) then eval(0) out[0] = i64(1) fi";
+        assert_eq!(features(&declarations, stmt), [Feature::LocalFrequency]);
+    }
+
+    #[test]
+    fn sliding_window() {
+        let declarations = format!("{OUTPUT}\nwindow sliding[0]: Int64 = sum(in[0]) over sliding(1s, 10, 100ms, wait) caller out[0] eval_with(0) pacing @in[0]");
+        let stmt = "if @in[0] then eval(0) out[0] = (window(in[0], sliding[0]) :: Int64) fi";
+        assert_eq!(features(&declarations, stmt), [Feature::SlidingWindow]);
+    }
+
+    #[test]
+    fn discrete_window() {
+        let declarations = format!("{OUTPUT}\nwindow discrete[0]: Int64 = sum(in[0]) over discrete(5, nowait) caller out[0] eval_with(0) pacing @in[0]");
+        let stmt = "if @in[0] then eval(0) out[0] = (window(in[0], discrete[0]) :: Int64) fi";
+        assert_eq!(features(&declarations, stmt), [Feature::DiscreteWindow]);
+    }
+
+    #[test]
+    fn instance_aggregation() {
+        let declarations = format!("{OUTPUT}\nstream pout[0] \"p\": Int64 = instances(\"x\": Int64) single\nwindow instance[0]: UInt64 = count(pout[0]) over instances(fresh) caller out[0] eval_with(0) pacing @in[0]");
+        let found = features(&declarations, "skip");
+        assert!(found.contains(&Feature::InstanceAggregation), "{found:?}");
+    }
+
+    #[test]
+    fn unbounded_memory() {
+        let declarations = "stream out[0] \"b\": Int64 = static unbounded";
+        let stmt = "if @in[0] then seq { shift out[0]; eval(0) out[0] = in[0]; } fi";
+        assert_eq!(features(declarations, stmt), [Feature::UnboundedMemory]);
+        let unbounded = UnboundedMemory {
+            capacity: 4,
+            policy: OverflowPolicy::DropOldest,
+        };
+        assert_eq!(
+            features_with(declarations, stmt, MainFunction::NoMain, Some(unbounded)),
+            []
+        );
+    }
+
+    #[test]
+    fn types() {
+        let declarations = "stream out[0] \"b\": Fixed32 = static single\nstream out[1] \"c\": Option<Option<Bool>> = static single";
+        assert_eq!(
+            features(declarations, "skip"),
+            [
+                Feature::Type(Type::Fixed(32)),
+                Feature::Type(Type::Option(Box::new(Type::Option(Box::new(Type::Bool)))))
+            ]
+        );
+    }
+
+    #[test]
+    fn function() {
+        let declarations = "stream out[0] \"b\": Float32 = static single";
+        let stmt = "if @in[0] then eval(0) out[0] = sqrt(cast(Float32, in[0])) fi";
+        assert_eq!(
+            features(declarations, stmt),
+            [Feature::Function(Function::Sqrt, Type::Float32)]
+        );
+    }
+
+    #[test]
+    fn operator() {
+        let stmt = "if @in[0] then eval(0) out[0] = (in[0] ** i64(2)) fi";
+        assert_eq!(
+            features(OUTPUT, stmt),
+            [Feature::Operator(Operator::Pow, Type::Int(64))]
+        );
+    }
+
+    #[test]
+    fn csv_input() {
+        let declarations = "stream in[1] \"s\": String = static single";
+        assert_eq!(
+            features_with(declarations, "skip", MainFunction::CsvOffline, None),
+            [Feature::InputType(Type::String)]
+        );
+        assert_eq!(features(declarations, "skip"), []);
+    }
+
+    #[test]
+    fn memoryless_access() {
+        let declarations =
+            "stream out[0] \"b\": Int64 = none\nstream out[1] \"c\": Int64 = static single";
+        let stmt = "if @in[0] then eval(0) out[1] = (out[0] + i64(1)) fi";
+        assert_eq!(
+            features(declarations, stmt),
+            [Feature::MemorylessAccess("b".into())]
+        );
+    }
+
+    #[test]
+    fn located() {
+        let source = "input a: Int64\noutput b := a ** 2";
+        let ir = rtlola_streamir::parse(&ParserConfig::for_string(source.into())).unwrap();
+        let error = check(&ir, MainFunction::NoMain, None).unwrap_err();
+        assert_eq!(error.0[0].location.span, None);
+        let error = error.locate(source);
+        let span = error.0[0].location.span.clone().unwrap();
+        assert_eq!((span.line, span.column), (2, 1));
+        assert_eq!(
+            error.to_string(),
+            "2:1: stream `b` uses the operator Pow on Int(64), which is not supported by the C backend"
+        );
+    }
+}
@@ -6,7 +6,7 @@ use rtlola_streamir::{
     ir::{InputReference, OutputReference, StreamReference, Type},
 };
 
-use crate::{types::CType, CFormatter};
+use crate::{expressions::option::OptionOperation, types::CType, CFormatter};
 
 impl FilesFormatter for CFormatter {
     type Key = RequirementKey;
@@ -234,9 +234,12 @@ pub enum RequirementKey {
     Import(&'static str),
 
     // Monitor File
+    OptionFunction(OptionOperation, Type),
     SyncAccess(StreamReference),
     OffsetAccess(StreamReference),
     GetAccess(StreamReference),
+    OptionalOffsetAccess(StreamReference),
+    OptionalGetAccess(StreamReference),
     IsFreshAccess(StreamReference),
     Input(InputReference),
     Eval(OutputReference, usize),
@@ -251,19 +254,51 @@ pub enum RequirementKey {
     ReadEvent,
     InitMemory,
     PrintVerdict,
+    IntrospectionTables,
+    GetStreamValue,
+    SetInputValue,
+    AbiVersion,
+    SpecFingerprint,
+    BeginEvent,
+    SetInput(InputReference),
+    CommitEvent,
+    SnapshotHelpers,
+    SnapshotStrings,
+    SnapshotSize,
+    Snapshot,
+    Restore,
+    Reset,
     Main,
 
     // Header File
+    UnboundedMemoryDefines,
     StaticString(usize),
     TupleStruct(Vec<Type>),
+    OptionStruct(Type),
     StaticStreamMemory(StreamReference),
     DynamicStreamMemory(StreamReference),
+    InternalEventStruct,
     MemoryStruct,
     InitMemoryHeader,
-    InternalEventStruct,
     VerdictStruct,
     PrintVerdictHeader,
     CycleHeader,
+    IntrospectionDefines,
+    StreamInfoStruct,
+    TriggerInfoStruct,
+    IntrospectionTablesHeader,
+    GetStreamValueHeader,
+    SetInputValueHeader,
+    AbiVersionHeader,
+    SpecFingerprintHeader,
+    BeginEventHeader,
+    SetInputHeader(InputReference),
+    CommitEventHeader,
+    SnapshotDefines,
+    SnapshotSizeHeader,
+    SnapshotHeader,
+    RestoreHeader,
+    ResetHeader,
 }
 
 impl CFormatter {
@@ -295,6 +330,13 @@ impl CFormatter {
         self.add_requirement(Import(s, file, true));
     }
 
+    /// Adds the function as a requirement to the store and returns its name
+    pub(crate) fn require_function<F: FunctionDefinition>(&self, f: F) -> String {
+        let name = f.name(self);
+        self.add_requirement(FD(f));
+        name
+    }
+
     pub(crate) fn require_struct<S: StructDefinition>(&self, s: S) {
         self.add_requirement(SD(s));
     }
@@ -1,14 +1,16 @@
 pub(crate) mod get;
 pub(crate) mod is_fresh;
 pub(crate) mod offset;
+pub(crate) mod option;
 pub(crate) mod sync;
 
 use std::path::PathBuf;
 
-use get::GetAccess;
+use get::{GetAccess, OptionalGetAccess};
 use is_fresh::IsFresh;
 use itertools::Itertools;
-use offset::OffsetAccess;
+use offset::{OffsetAccess, OptionalOffsetAccess};
+use option::{OptionFunction, OptionOperation};
 use rtlola_streamir::{
     formatter::{
         expressions::{
@@ -20,14 +22,14 @@ use rtlola_streamir::{
     },
     ir::{
         expressions::{Expr, Operator},
-        StreamReference, Type, WindowReference,
+        StreamReference, TempRef, Type, WindowReference,
     },
 };
 use sync::SyncAccess;
 
 use crate::{
     constructs::{Argument, RequirementKey, StructDefinition},
-    types::TupleType,
+    types::{OptionType, TupleType},
     CFormatter, CType, MemoryStruct,
 };
 
@@ -48,60 +50,89 @@ impl DefaultExprFormatter for CFormatter {
         default: Expr,
         parameters: Vec<Expr>,
     ) -> String {
-        assert!(parameters.is_empty());
-        self.call_function(
-            OffsetAccess(sr),
-            &[
-                MemoryStruct.argument_name(self),
-                offset.to_string(),
-                self.expr(default),
-            ],
-        )
+        assert!(
+            parameters.is_empty(),
+            "parameterized streams are rejected by the capability check"
+        );
+        let optional = self.is_optional_access(sr, &default);
+        let args = [
+            MemoryStruct.argument_name(self),
+            offset.to_string(),
+            self.expr(default),
+        ];
+        if optional {
+            self.call_function(OptionalOffsetAccess(sr), &args)
+        } else {
+            self.call_function(OffsetAccess(sr), &args)
+        }
     }
 
     fn hold_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
-        assert!(parameters.is_empty());
-        self.call_function(
-            OffsetAccess(sr),
-            &[
-                MemoryStruct.argument_name(self),
-                "0".into(),
-                self.expr(default),
-            ],
-        )
+        <Self as DefaultExprFormatter>::offset_access(self, sr, 0, default, parameters)
     }
 
     fn get_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
-        assert!(parameters.is_empty());
+        assert!(
+            parameters.is_empty(),
+            "parameterized streams are rejected by the capability check"
+        );
+        let optional = self.is_optional_access(sr, &default);
+        let args = [MemoryStruct.argument_name(self), self.expr(default)];
+        if optional {
+            self.call_function(OptionalGetAccess(sr), &args)
+        } else {
+            self.call_function(GetAccess(sr), &args)
+        }
+    }
+
+    fn none(&self, ty: &Type) -> String {
+        match ty {
+            Type::Option(inner) => OptionType(*inner.clone()).none(self),
+            ty => unreachable!("absent value of non-optional type {ty:?}"),
+        }
+    }
+
+    fn some(&self, inner: Expr, _ty: &Type) -> String {
+        OptionType(inner.ty.clone()).some(self.expr(inner), self)
+    }
+
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> String {
         self.call_function(
-            GetAccess(sr),
-            &[MemoryStruct.argument_name(self), self.expr(default)],
+            OptionFunction(OptionOperation::UnwrapOr, default.ty.clone()),
+            &[self.expr(expr), self.expr(default)],
         )
     }
 
     fn is_fresh(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
-        assert!(parameters.is_empty());
+        assert!(
+            parameters.is_empty(),
+            "parameterized streams are rejected by the capability check"
+        );
         self.call_function(IsFresh(sr), &[MemoryStruct.argument_name(self)])
     }
 
     fn sliding_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
-        unimplemented!()
+        unreachable!("sliding windows are rejected by the capability check")
     }
 
     fn discrete_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
-        unimplemented!()
+        unreachable!("discrete windows are rejected by the capability check")
     }
 
     fn instance_aggregation(&self, _wref: usize, _default: Option<Expr>) -> String {
-        unimplemented!()
+        unreachable!("instance aggregations are rejected by the capability check")
     }
 
     fn parameter_access(&self, _sr: StreamReference, _p: usize) -> String {
-        unimplemented!()
+        unreachable!("parameterized streams are rejected by the capability check")
     }
 
     fn lambda_parameter_access(&self, _wref: WindowReference, _idx: usize) -> String {
-        unimplemented!()
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn temporary(&self, temp: TempRef, _ty: &Type) -> String {
+        self.temporary_name(temp)
     }
 
     fn cast(&self, ty: Type, expr: Expr) -> String {
@@ -117,14 +148,27 @@ impl DefaultExprFormatter for CFormatter {
     }
 
     fn binary(&self, op: Operator, lhs: Expr, rhs: Expr) -> String {
-        match op {
-            Operator::Pow => match (&lhs.ty, &rhs.ty) {
-                (Type::Float64, Type::Float64) => {
-                    self.import(self.monitor_file(), "math");
-                    format!("pow({}, {})", self.expr(lhs), self.expr(rhs))
+        match (op, &lhs.ty) {
+            (Operator::Pow, _) => {
+                let pow = self.float_function("pow", &lhs.ty);
+                format!("{pow}({}, {})", self.expr(lhs), self.expr(rhs))
+            }
+            (Operator::Eq | Operator::Ne, Type::Option(inner)) => {
+                let eq = OptionFunction(OptionOperation::Eq, *inner.clone());
+                let eq = self.call_function(eq, &[self.expr(lhs), self.expr(rhs)]);
+                match op {
+                    Operator::Ne => format!("(!{eq})"),
+                    _ => eq,
+                }
+            }
+            (Operator::Eq | Operator::Ne, _) => {
+                let ty = lhs.ty.clone();
+                let eq = self.equal(&ty, self.expr(lhs), self.expr(rhs));
+                match op {
+                    Operator::Ne => format!("(!{eq})"),
+                    _ => eq,
                 }
-                _ => panic!("unsupported by current compiler"),
-            },
+            }
             _ => format!("({} {} {})", self.expr(lhs), self.op(op), self.expr(rhs)),
         }
     }
@@ -144,7 +188,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "sqrt".into(),
-            ty => panic!("unsupported function: sqrt({ty:?})"),
+            ty => unreachable!("sqrt({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -154,7 +198,7 @@ impl DefaultFunctionFormatter for CFormatter {
             Type::Float64 => "fabs".into(),
             Type::Float32 => "fabsf".into(),
             Type::Int(64) => "abs".into(),
-            ty => panic!("unsupported function: abs({ty:?})"),
+            ty => unreachable!("abs({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -162,7 +206,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "sin".into(),
-            ty => panic!("unsupported function: sin({ty:?})"),
+            ty => unreachable!("sin({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -170,7 +214,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "asin".into(),
-            ty => panic!("unsupported function: asin({ty:?})"),
+            ty => unreachable!("asin({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -178,7 +222,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "cos".into(),
-            ty => panic!("unsupported function: cos({ty:?})"),
+            ty => unreachable!("cos({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -186,7 +230,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "acos".into(),
-            ty => panic!("unsupported function: acos({ty:?})"),
+            ty => unreachable!("acos({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -194,7 +238,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "tan".into(),
-            ty => panic!("unsupported function: tan({ty:?})"),
+            ty => unreachable!("tan({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -202,7 +246,7 @@ impl DefaultFunctionFormatter for CFormatter {
         self.import(self.monitor_file(), "math");
         match return_ty {
             Type::Float64 => "atan".into(),
-            ty => panic!("unsupported function: atan({ty:?})"),
+            ty => unreachable!("atan({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -211,7 +255,7 @@ impl DefaultFunctionFormatter for CFormatter {
         match return_ty {
             Type::Float64 => "fmin".into(),
             Type::Float32 => "fminf".into(),
-            ty => panic!("unsupported function: min({ty:?})"),
+            ty => unreachable!("min({ty:?}) is rejected by the capability check"),
         }
     }
 
@@ -220,7 +264,35 @@ impl DefaultFunctionFormatter for CFormatter {
         match return_ty {
             Type::Float64 => "fmax".into(),
             Type::Float32 => "fmaxf".into(),
-            ty => panic!("unsupported function: max({ty:?})"),
+            ty => unreachable!("max({ty:?}) is rejected by the capability check"),
+        }
+    }
+}
+
+impl CFormatter {
+    /// Compares two values of the given type for equality, where strings are compared by their content
+    pub(crate) fn equal(&self, ty: &Type, lhs: String, rhs: String) -> String {
+        match ty {
+            Type::String => {
+                self.import(self.monitor_file(), "string");
+                format!("(strcmp({lhs}, {rhs}) == 0)")
+            }
+            _ => format!("({lhs} == {rhs})"),
+        }
+    }
+
+    /// Returns whether an access to the stream wraps its value into an optional value
+    fn is_optional_access(&self, sr: StreamReference, default: &Expr) -> bool {
+        matches!(default.ty, Type::Option(_)) && !matches!(self.sr2memory[&sr].ty, Type::Option(_))
+    }
+
+    /// Returns the name of the function of the C math library operating on values of the given type
+    fn float_function(&self, name: &str, ty: &Type) -> String {
+        self.import(self.monitor_file(), "math");
+        match ty {
+            Type::Float64 => name.into(),
+            Type::Float32 => format!("{name}f"),
+            ty => unreachable!("{name}({ty:?}) is rejected by the capability check"),
         }
     }
 }
@@ -2,9 +2,10 @@ use std::path::PathBuf;
 
 use crate::{
     constructs::{Argument, FunctionDefinition, RequirementKey},
+    types::OptionType,
     CFormatter, CType, MemoryStruct, StructDefinition,
 };
-use rtlola_streamir::ir::StreamReference;
+use rtlola_streamir::ir::{StreamReference, Type};
 
 pub(crate) struct GetAccess(pub(crate) StreamReference);
 
@@ -16,8 +17,8 @@ impl FunctionDefinition for GetAccess {
     fn body(self, f: &CFormatter) -> String {
         format!(
             "if ({}) return {}; else return {};",
-            f.static_buffer(self.0).unwrap().is_fresh(f),
-            f.static_buffer(self.0).unwrap().sync_access(f),
+            f.accessed_buffer(self.0).is_fresh(f),
+            f.accessed_buffer(self.0).sync_access(f),
             f.default_argument_name()
         )
     }
@@ -41,3 +42,47 @@ impl FunctionDefinition for GetAccess {
         f.monitor_file()
     }
 }
+
+/// The get access returning the value of the stream as an optional value
+pub(crate) struct OptionalGetAccess(pub(crate) StreamReference);
+
+impl OptionalGetAccess {
+    fn option_ty(&self, f: &CFormatter) -> Type {
+        Type::Option(Box::new(f.sr2memory[&self.0].ty.clone()))
+    }
+}
+
+impl FunctionDefinition for OptionalGetAccess {
+    fn name(&self, f: &CFormatter) -> String {
+        f.optional_get_access_function_name(self.0)
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let value = f.accessed_buffer(self.0).sync_access(f);
+        format!(
+            "if ({}) return {}; else return {};",
+            f.accessed_buffer(self.0).is_fresh(f),
+            OptionType(f.sr2memory[&self.0].ty.clone()).some(value, f),
+            f.default_argument_name()
+        )
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::OptionalGetAccess(self.0)
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            Argument::Normal(f.default_argument_name(), CType::Lola(self.option_ty(f))),
+        ]
+    }
+
+    fn returns(&self, f: &CFormatter) -> Option<CType> {
+        Some(CType::Lola(self.option_ty(f)))
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+}
@@ -15,7 +15,7 @@ impl FunctionDefinition for IsFresh {
     }
 
     fn body(self, f: &crate::CFormatter) -> String {
-        format!("return {};", f.static_buffer(self.0).unwrap().is_fresh(f))
+        format!("return {};", f.accessed_buffer(self.0).is_fresh(f))
     }
 
     fn key(&self) -> RequirementKey {
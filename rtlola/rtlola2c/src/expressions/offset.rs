@@ -1,9 +1,10 @@
 use std::path::PathBuf;
 
-use rtlola_streamir::ir::StreamReference;
+use rtlola_streamir::ir::{StreamReference, Type};
 
 use crate::{
     constructs::{Argument, FunctionDefinition, RequirementKey},
+    types::OptionType,
     CFormatter, CType, MemoryStruct, StructDefinition,
 };
 
@@ -15,9 +16,10 @@ impl FunctionDefinition for OffsetAccess {
     }
 
     fn body(self, f: &CFormatter) -> String {
-        f.static_buffer(self.0).unwrap().get_value(
+        f.accessed_buffer(self.0).get_value(
             f.offset_argument_name(),
             f.default_argument_name(),
+            &|value| value,
             f,
         )
     }
@@ -42,3 +44,48 @@ impl FunctionDefinition for OffsetAccess {
         f.monitor_file()
     }
 }
+
+/// The offset access returning the value of the stream as an optional value
+pub(crate) struct OptionalOffsetAccess(pub(crate) StreamReference);
+
+impl OptionalOffsetAccess {
+    fn option_ty(&self, f: &CFormatter) -> Type {
+        Type::Option(Box::new(f.sr2memory[&self.0].ty.clone()))
+    }
+}
+
+impl FunctionDefinition for OptionalOffsetAccess {
+    fn name(&self, f: &CFormatter) -> String {
+        f.optional_offset_access_function_name(self.0)
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let inner = f.sr2memory[&self.0].ty.clone();
+        f.accessed_buffer(self.0).get_value(
+            f.offset_argument_name(),
+            f.default_argument_name(),
+            &|value| OptionType(inner.clone()).some(value, f),
+            f,
+        )
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            Argument::Normal(f.offset_argument_name(), CType::Int),
+            Argument::Normal(f.default_argument_name(), CType::Lola(self.option_ty(f))),
+        ]
+    }
+
+    fn returns(&self, f: &CFormatter) -> Option<CType> {
+        Some(CType::Lola(self.option_ty(f)))
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::OptionalOffsetAccess(self.0)
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+}
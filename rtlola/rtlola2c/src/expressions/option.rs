@@ -0,0 +1,165 @@
+use std::path::PathBuf;
+
+use rtlola_streamir::ir::Type;
+
+use crate::{
+    constructs::{Argument, FunctionDefinition, RequirementKey},
+    CFormatter, CType,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+/// An operation on optional values that is implemented by a helper function
+pub enum OptionOperation {
+    /// Returns the inner value if present and the default otherwise
+    UnwrapOr,
+    /// Compares two optional values for equality
+    Eq,
+}
+
+/// A helper function on optional values with the given inner type
+pub(crate) struct OptionFunction(pub(crate) OptionOperation, pub(crate) Type);
+
+impl OptionFunction {
+    fn option_ty(&self) -> CType {
+        CType::Lola(Type::Option(Box::new(self.1.clone())))
+    }
+}
+
+impl FunctionDefinition for OptionFunction {
+    fn name(&self, f: &CFormatter) -> String {
+        let name = match self.0 {
+            OptionOperation::UnwrapOr => "unwrap_or",
+            OptionOperation::Eq => "eq",
+        };
+        format!("{}_{name}", f.option_struct_name(&self.1).to_lowercase())
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let present = f.option_present_field_name();
+        let value = f.option_value_field_name();
+        match self.0 {
+            OptionOperation::UnwrapOr => {
+                format!("return x.{present} ? x.{value} : d;")
+            }
+            OptionOperation::Eq => {
+                let equal = f.equal(&self.1, format!("x.{value}"), format!("y.{value}"));
+                format!("return x.{present} == y.{present} && (!x.{present} || {equal});")
+            }
+        }
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::OptionFunction(self.0, self.1.clone())
+    }
+
+    fn arguments(&self, _f: &CFormatter) -> Vec<Argument> {
+        match self.0 {
+            OptionOperation::UnwrapOr => vec![
+                Argument::Normal("x".into(), self.option_ty()),
+                Argument::Normal("d".into(), CType::Lola(self.1.clone())),
+            ],
+            OptionOperation::Eq => vec![
+                Argument::Normal("x".into(), self.option_ty()),
+                Argument::Normal("y".into(), self.option_ty()),
+            ],
+        }
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        match self.0 {
+            OptionOperation::UnwrapOr => Some(CType::Lola(self.1.clone())),
+            OptionOperation::Eq => Some(CType::Bool),
+        }
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::generate;
+
+    const OPTIONS: &str = r#"streamir 1
+stream in[0] "a": Int64 = static bounded(2)
+stream in[1] "s": String = static bounded(2)
+stream out[0] "prev": Option<Int64> = static single
+stream out[1] "value": Int64 = static single
+stream out[2] "same": Bool = static single
+stream out[3] "prev_s": Option<String> = static single
+stream out[4] "same_s": Bool = static single
+stream out[5] "wrapped": Option<Int64> = static single
+stmt
+seq {
+    if @in[0] then
+        seq {
+            shift in[0];
+            input in[0];
+            shift in[1];
+            input in[1];
+            eval(0) out[0] = offset(in[0], 1, (none :: Option<Int64>));
+            eval(0) out[1] = unwrap_or(out[0], i64(-1));
+            eval(0) out[2] = (out[0] == some(in[0]));
+            eval(0) out[3] = offset(in[1], 1, (none :: Option<String>));
+            eval(0) out[4] = (out[3] == some(in[1]));
+            eval(0) out[5] = some(in[0]);
+        }
+    fi;
+}"#;
+
+    #[test]
+    fn option_structs() {
+        let monitor = generate(OPTIONS, None).unwrap();
+        for option in [
+            "typedef struct {\nbool is_present;\nint64_t value;\n} OPTION_INT64_T;",
+            "typedef struct {\nbool is_present;\nchar* value;\n} OPTION_CHAR_PTR;",
+            "OPTION_INT64_T prev;",
+        ] {
+            assert!(monitor.header.contains(option), "{option}");
+        }
+    }
+
+    #[test]
+    fn option_functions() {
+        let monitor = generate(OPTIONS, None).unwrap();
+        for function in [
+            "int64_t option_int64_t_unwrap_or(OPTION_INT64_T x, int64_t d){\nreturn x.is_present ? x.value : d;\n}",
+            "bool option_int64_t_eq(OPTION_INT64_T x, OPTION_INT64_T y){\nreturn x.is_present == y.is_present && (!x.is_present || (x.value == y.value));\n}",
+            "bool option_char_ptr_eq(OPTION_CHAR_PTR x, OPTION_CHAR_PTR y){\nreturn x.is_present == y.is_present && (!x.is_present || (strcmp(x.value, y.value) == 0));\n}",
+            "OPTION_INT64_T a_offset_optional(Memory* memory, int offset, OPTION_INT64_T def){",
+            "OPTION_INT64_T new_value = (OPTION_INT64_T){.is_present=true, .value=a_sync(memory)};",
+        ] {
+            assert!(monitor.source.contains(function), "{function}");
+        }
+        assert!(monitor.source.contains("#include <string.h>"));
+    }
+
+    #[test]
+    fn values() {
+        let monitor = generate(OPTIONS, None).unwrap();
+        // the strings of consecutive events have the same content but are stored at different addresses
+        let driver = r#"
+#include <stdio.h>
+#include "monitor.c"
+int main(void) {
+    Memory m;
+    init_memory(&m);
+    int64_t a[] = {1, 1, 2};
+    char s[3][2] = {"x", "x", "y"};
+    for (int k = 0; k < 3; k++) {
+        begin_event(&m, k);
+        set_a(&m, a[k]);
+        set_s(&m, s[k]);
+        Verdict v = commit_event(&m);
+        printf("%d %lld %d %d %d %d\n", v.prev.is_present, (long long) v.value, v.same, v.prev_s.is_present,
+            v.same_s, v.wrapped.is_present);
+    }
+    return 0;
+}"#;
+        assert_eq!(
+            monitor.stdout(driver, &[]),
+            "0 -1 0 0 0 1\n1 1 1 1 1 1\n1 1 0 1 0 1\n"
+        );
+    }
+}
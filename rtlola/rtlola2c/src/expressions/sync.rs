@@ -13,10 +13,7 @@ impl FunctionDefinition for SyncAccess {
     }
 
     fn body(self, f: &crate::CFormatter) -> String {
-        format!(
-            "return {};",
-            f.static_buffer(self.0).unwrap().sync_access(f)
-        )
+        format!("return {};", f.accessed_buffer(self.0).sync_access(f))
     }
 
     fn key(&self) -> RequirementKey {
@@ -37,11 +37,11 @@ impl DefaultGuardFormatter for CFormatter {
     }
 
     fn global_freq(&self, _duration: Duration) -> String {
-        unimplemented!()
+        unreachable!("periodic streams are rejected by the capability check")
     }
 
     fn local_freq(&self, _freq_ref: LocalFreqRef) -> String {
-        unimplemented!()
+        unreachable!("local frequencies are rejected by the capability check")
     }
 
     fn constant(&self, b: bool) -> String {
@@ -57,14 +57,15 @@ struct DynamicGuard(Expr, usize);
 
 impl DynamicGuard {
     fn new(expr: Expr, f: &CFormatter) -> Self {
+        let id = f.expr_interner.lock().unwrap().intern(&expr);
         let mut expr_counter = f.expr_counter.lock().unwrap();
         let params = expr.contains_parameter_access();
-        if let Some(c) = expr_counter.get(&(expr.clone(), params)) {
+        if let Some(c) = expr_counter.get(&(id, params)) {
             Self(expr, *c)
         } else {
             let mut num_exprs = f.num_exprs.lock().unwrap();
             let c = *num_exprs;
-            expr_counter.insert((expr.clone(), params), c);
+            expr_counter.insert((id, params), c);
             *num_exprs += 1;
             Self(expr, c)
         }
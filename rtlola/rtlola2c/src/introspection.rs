@@ -0,0 +1,635 @@
+//! Constant metadata describing the streams and triggers of the compiled specification.
+//!
+//! The tables allow external tools to discover the contents of a compiled monitor without parsing the header.
+//! Streams are identified by their index in the sorted list of all streams, so inputs come before outputs.
+//! Each table is terminated by an entry whose name is `NULL`, such that it can be iterated after loading
+//! it from a shared library.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{files::FilesFormatter, names::GetStreamName},
+    ir::{
+        expressions::{Constant, ExprKind},
+        memory::StreamBuffer,
+        Guard, OutputReference, Stmt, StreamIr, StreamReference, Type,
+    },
+};
+
+use crate::{
+    constructs::{Argument, FunctionDefinition, RequirementKey, StructDefinition},
+    io::InternalEvent,
+    CFormatter, CType, MemoryStruct,
+};
+
+/// The version of the introspection interface, which is increased whenever its layout changes
+const ABI_VERSION: u32 = 1;
+
+/// The metadata of an input or output stream
+pub(crate) struct StreamInfo;
+
+impl StructDefinition for StreamInfo {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::StreamInfoStruct
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.header_file()
+    }
+
+    fn struct_name(&self, _f: &CFormatter) -> String {
+        "StreamInfo".into()
+    }
+
+    fn fields(&self, _f: &CFormatter) -> Vec<Argument> {
+        vec![
+            Argument::Normal("id".into(), CType::Int),
+            Argument::Normal("name".into(), CType::Char.reference()),
+            Argument::Normal("type".into(), CType::Char.reference()),
+            Argument::Normal("memory_bound".into(), CType::Int),
+            Argument::Normal("pacing".into(), CType::Char.reference()),
+        ]
+    }
+}
+
+/// The metadata of a trigger
+pub(crate) struct TriggerInfo;
+
+impl StructDefinition for TriggerInfo {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::TriggerInfoStruct
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.header_file()
+    }
+
+    fn struct_name(&self, _f: &CFormatter) -> String {
+        "TriggerInfo".into()
+    }
+
+    fn fields(&self, _f: &CFormatter) -> Vec<Argument> {
+        vec![
+            Argument::Normal("id".into(), CType::Int),
+            Argument::Normal("stream".into(), CType::Int),
+            Argument::Normal("name".into(), CType::Char.reference()),
+            Argument::Normal("message".into(), CType::Char.reference()),
+        ]
+    }
+}
+
+impl CFormatter {
+    /// Adds the metadata tables, the accessors by stream id, the spec fingerprint and the ABI version to the output
+    pub(crate) fn require_introspection(&self, ir: &StreamIr) {
+        let pacing = self.pacing_descriptions(&ir.stmt);
+        let messages = trigger_messages(&ir.stmt);
+        let triggers = ir
+            .triggers
+            .iter()
+            .sorted_by_key(|(_, idx)| **idx)
+            .map(|(o, _)| *o)
+            .collect::<Vec<_>>();
+
+        let stream_info = StreamInfo.struct_name(self);
+        let trigger_info = TriggerInfo.struct_name(self);
+        self.require_struct(StreamInfo);
+        self.require_struct(TriggerInfo);
+
+        let inputs = self
+            .inputs()
+            .map(|sr| self.stream_info_entry(sr, "input"))
+            .collect::<Vec<_>>();
+        let outputs = self
+            .outputs()
+            .map(|sr| {
+                let pacing = pacing.get(&sr).map(String::as_str).unwrap_or("never");
+                self.stream_info_entry(sr, pacing)
+            })
+            .collect::<Vec<_>>();
+        let trigger_entries = triggers
+            .iter()
+            .enumerate()
+            .map(|(idx, o)| {
+                let sr = StreamReference::Out(*o);
+                let message = messages
+                    .get(o)
+                    .and_then(Option::as_deref)
+                    .map(string_literal)
+                    .unwrap_or("NULL".into());
+                format!(
+                    "{{{idx}, {}, {}, {message}}}",
+                    self.stream_id(sr),
+                    string_literal(&self.stream_name(sr))
+                )
+            })
+            .collect::<Vec<_>>();
+
+        self.add_requirement_string(
+            self.header_file(),
+            RequirementKey::IntrospectionDefines,
+            format!(
+                "#define MONITOR_ABI_VERSION {ABI_VERSION}\n\
+                #define MONITOR_SPEC_FINGERPRINT 0x{:016x}ULL\n\
+                #define MONITOR_NUM_INPUTS {}\n\
+                #define MONITOR_NUM_OUTPUTS {}\n\
+                #define MONITOR_NUM_TRIGGERS {}",
+                fingerprint(&ir.to_text()),
+                inputs.len(),
+                outputs.len(),
+                trigger_entries.len()
+            ),
+        );
+        let tables = [
+            (self.input_table_name(), &stream_info, inputs),
+            (self.output_table_name(), &stream_info, outputs),
+            (self.trigger_table_name(), &trigger_info, trigger_entries),
+        ];
+        self.add_requirement_string(
+            self.header_file(),
+            RequirementKey::IntrospectionTablesHeader,
+            tables
+                .iter()
+                .map(|(name, ty, _)| format!("extern const {ty} {name}[];"))
+                .join("\n"),
+        );
+        self.add_requirement_string(
+            self.monitor_file(),
+            RequirementKey::IntrospectionTables,
+            tables
+                .into_iter()
+                .map(|(name, ty, entries)| {
+                    // the terminating entry has a NULL name
+                    let entries = entries.into_iter().chain(Some("{0}".into())).join(",\n");
+                    format!("const {ty} {name}[] = {{\n{entries}\n}};")
+                })
+                .join("\n"),
+        );
+
+        self.require_function(GetStreamValue);
+        self.require_function(SetInputValue);
+        self.require_function(AbiVersion);
+        self.require_function(SpecFingerprint);
+    }
+
+    /// Returns the id of the stream in the metadata tables
+    pub(crate) fn stream_id(&self, sr: StreamReference) -> usize {
+        self.streams().position(|s| s == sr).unwrap()
+    }
+
+    fn stream_info_entry(&self, sr: StreamReference, pacing: &str) -> String {
+        let memory = &self.sr2memory[&sr];
+        let bound = match memory.buffer.buffer() {
+            None => 0,
+            Some(StreamBuffer::SingleValue) => 1,
+            Some(StreamBuffer::Bounded(size)) => *size,
+            Some(StreamBuffer::UnBounded) => self.unbounded.map_or(0, |u| u.capacity),
+        };
+        format!(
+            "{{{}, {}, {}, {bound}, {}}}",
+            self.stream_id(sr),
+            string_literal(&memory.name),
+            string_literal(&memory.ty.to_string()),
+            string_literal(pacing)
+        )
+    }
+
+    /// Returns the description of the pacing of each output stream, derived from the guards of its evaluations
+    fn pacing_descriptions(&self, stmt: &Stmt) -> BTreeMap<StreamReference, String> {
+        let mut clauses: BTreeMap<StreamReference, Vec<String>> = BTreeMap::new();
+        self.collect_pacing(stmt, &mut Vec::new(), &mut clauses);
+        clauses
+            .into_iter()
+            .map(|(sr, clauses)| {
+                let clauses = clauses.into_iter().unique().collect::<Vec<_>>();
+                let description = if clauses.len() == 1 {
+                    clauses.into_iter().next().unwrap()
+                } else {
+                    clauses.into_iter().map(|c| format!("({c})")).join(" || ")
+                };
+                (sr, description)
+            })
+            .collect()
+    }
+
+    fn collect_pacing(
+        &self,
+        stmt: &Stmt,
+        guards: &mut Vec<String>,
+        clauses: &mut BTreeMap<StreamReference, Vec<String>>,
+    ) {
+        match stmt {
+            Stmt::Eval { sr, .. } => {
+                let pacing = if guards.is_empty() {
+                    "constant".into()
+                } else {
+                    guards.join(" && ")
+                };
+                clauses
+                    .entry(StreamReference::Out(*sr))
+                    .or_default()
+                    .push(pacing);
+            }
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => stmts
+                .iter()
+                .for_each(|stmt| self.collect_pacing(stmt, guards, clauses)),
+            Stmt::If(if_stmt) => {
+                // nested conditionals may repeat the pacing of an enclosing one
+                let pacing = self
+                    .pacing(if_stmt.guard())
+                    .filter(|pacing| !guards.contains(pacing));
+                let pushed = pacing.is_some();
+                guards.extend(pacing);
+                self.collect_pacing(if_stmt.cons(), guards, clauses);
+                if pushed {
+                    guards.pop();
+                }
+                if let Some(alt) = if_stmt.alt() {
+                    self.collect_pacing(alt, guards, clauses);
+                }
+            }
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => {
+                self.collect_pacing(stmt, guards, clauses)
+            }
+            Stmt::Skip
+            | Stmt::Shift(_)
+            | Stmt::Input(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Close { .. }
+            | Stmt::Let { .. } => {}
+        }
+    }
+
+    /// Returns the description of the part of the guard that stems from the pacing of a stream
+    ///
+    /// Filters and the liveness of streams are not part of the pacing and yield `None`.
+    fn pacing(&self, guard: &Guard) -> Option<String> {
+        match guard {
+            Guard::Stream(sr) => Some(format!("@{}", self.stream_name(*sr))),
+            Guard::FastAnd(srs) => Some(
+                srs.iter()
+                    .map(|sr| format!("@{}", self.stream_name(*sr)))
+                    .join(" && "),
+            ),
+            Guard::FastOr(srs) => Some(format!(
+                "({})",
+                srs.iter()
+                    .map(|sr| format!("@{}", self.stream_name(*sr)))
+                    .join(" || ")
+            )),
+            Guard::GlobalFreq(duration) => Some(format!("every {duration:?}")),
+            Guard::LocalFreq(lref) => Some(format!("local frequency {lref}")),
+            Guard::And { lhs, rhs } => match (self.pacing(lhs), self.pacing(rhs)) {
+                (Some(lhs), Some(rhs)) => Some(format!("{lhs} && {rhs}")),
+                (lhs, rhs) => lhs.or(rhs),
+            },
+            Guard::Or { lhs, rhs } => {
+                Some(format!("({} || {})", self.pacing(lhs)?, self.pacing(rhs)?))
+            }
+            Guard::Constant(true) => None,
+            Guard::Constant(false) => Some("never".into()),
+            Guard::Alive(_) | Guard::Dynamic(_) => None,
+        }
+    }
+}
+
+/// Returns the message of each trigger whose evaluations all produce the same constant string
+fn trigger_messages(stmt: &Stmt) -> BTreeMap<OutputReference, Option<String>> {
+    fn collect(stmt: &Stmt, messages: &mut BTreeMap<OutputReference, Option<String>>) {
+        match stmt {
+            Stmt::Eval { sr, with, .. } => {
+                let message = match &with.kind {
+                    ExprKind::Constant(Constant::Str(s)) => Some(s.clone()),
+                    _ => None,
+                };
+                messages
+                    .entry(*sr)
+                    .and_modify(|m| {
+                        if *m != message {
+                            *m = None
+                        }
+                    })
+                    .or_insert(message);
+            }
+            Stmt::Seq(stmts) | Stmt::Parallel(stmts) => {
+                stmts.iter().for_each(|stmt| collect(stmt, messages))
+            }
+            Stmt::If(if_stmt) => {
+                collect(if_stmt.cons(), messages);
+                if let Some(alt) = if_stmt.alt() {
+                    collect(alt, messages);
+                }
+            }
+            Stmt::Iterate { stmt, .. } | Stmt::Assign { stmt, .. } => collect(stmt, messages),
+            Stmt::Skip
+            | Stmt::Shift(_)
+            | Stmt::Input(_)
+            | Stmt::Spawn { .. }
+            | Stmt::Close { .. }
+            | Stmt::Let { .. } => {}
+        }
+    }
+    let mut messages = BTreeMap::new();
+    collect(stmt, &mut messages);
+    messages
+}
+
+/// Returns the 64-bit FNV-1a hash of the textual representation of the StreamIR
+fn fingerprint(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Returns the C string literal of the string, where all bytes besides printable ASCII characters are given in octal
+fn string_literal(s: &str) -> String {
+    let escaped: String = s
+        .bytes()
+        .map(|b| match b {
+            b'"' => "\\\"".into(),
+            b'\\' => "\\\\".into(),
+            b' '..=b'~' => char::from(b).to_string(),
+            b => format!("\\{b:03o}"),
+        })
+        .collect();
+    format!("\"{escaped}\"")
+}
+
+/// Copies the current value of a stream into the given pointer
+pub(crate) struct GetStreamValue;
+
+impl FunctionDefinition for GetStreamValue {
+    fn name(&self, f: &CFormatter) -> String {
+        f.get_stream_value_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            Argument::Normal("id".into(), CType::Int),
+            Argument::Normal("value".into(), CType::Other("void".into()).reference()),
+        ]
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Bool)
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let cases = f
+            .streams()
+            .filter_map(|sr| Some((sr, f.static_buffer(sr)?)))
+            .map(|(sr, buffer)| {
+                format!(
+                    "case {id}:\nif (!({valid})) return 0;\n*({ty}*) value = {value};\nreturn 1;",
+                    id = f.stream_id(sr),
+                    valid = buffer.is_valid(f),
+                    ty = f.c_ty(f.stream_ty(sr)),
+                    value = buffer.sync_access(f),
+                )
+            })
+            .join("\n");
+        format!("switch (id) {{\n{cases}\ndefault:\nreturn 0;\n}}")
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::GetStreamValue
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::GetStreamValueHeader, f.header_file()))
+    }
+}
+
+/// Sets the value of an input stream in an event from the given pointer
+pub(crate) struct SetInputValue;
+
+impl FunctionDefinition for SetInputValue {
+    fn name(&self, f: &CFormatter) -> String {
+        f.set_input_value_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            InternalEvent.into_argument(f).reference(),
+            Argument::Normal("id".into(), CType::Int),
+            Argument::Normal("value".into(), CType::Other("void".into()).reference()),
+        ]
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Bool)
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let event = InternalEvent.argument_name(f);
+        let cases = f
+            .inputs()
+            .map(|sr| {
+                format!(
+                    "case {id}:\n{event}->{field} = *({ty}*) value;\n{event}->{present} = 1;\nreturn 1;",
+                    id = f.stream_id(sr),
+                    field = f.stream_name(sr),
+                    ty = f.c_ty(f.stream_ty(sr)),
+                    present = f.internal_event_present_flag(sr),
+                )
+            })
+            .join("\n");
+        format!("switch (id) {{\n{cases}\ndefault:\nreturn 0;\n}}")
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::SetInputValue
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::SetInputValueHeader, f.header_file()))
+    }
+}
+
+/// Returns the version of the introspection interface the monitor was compiled with
+pub(crate) struct AbiVersion;
+
+impl FunctionDefinition for AbiVersion {
+    fn name(&self, f: &CFormatter) -> String {
+        f.abi_version_function_name()
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Int)
+    }
+
+    fn body(self, _f: &CFormatter) -> String {
+        "return MONITOR_ABI_VERSION;".into()
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::AbiVersion
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::AbiVersionHeader, f.header_file()))
+    }
+}
+
+/// Returns the fingerprint of the specification the monitor was compiled from
+pub(crate) struct SpecFingerprint;
+
+impl FunctionDefinition for SpecFingerprint {
+    fn name(&self, f: &CFormatter) -> String {
+        f.spec_fingerprint_function_name()
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Lola(Type::UInt(64)))
+    }
+
+    fn body(self, _f: &CFormatter) -> String {
+        "return MONITOR_SPEC_FINGERPRINT;".into()
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::SpecFingerprint
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::SpecFingerprintHeader, f.header_file()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::generate;
+
+    /// `x` is evaluated in a conditional nested in one with the same guard, `z` in two conditionals
+    /// and the trigger `t` under a filter
+    const STREAMS: &str = r#"streamir 1
+stream in[0] "a": Int64 = static bounded(2)
+stream in[1] "b": Int64 = static single
+stream out[0] "x": Int64 = static single
+stream out[1] "y": Int64 = static single
+stream out[2] "t": String = static single
+stream out[3] "z": Bool = static single
+trigger out[2] = 0
+stmt
+seq {
+    if @in[0] then
+        seq {
+            shift in[0];
+            input in[0];
+        }
+    fi;
+    if @in[1] then
+        input in[1]
+    fi;
+    if @in[0] then
+        seq {
+            if @in[0] then
+                eval(0) out[0] = in[0]
+            fi;
+            if @in[1] then
+                eval(0) out[1] = (in[0] + in[1])
+            fi;
+            if dynamic((in[0] > i64(3))) then
+                eval(0) out[2] = "a is large"
+            fi;
+            eval(0) out[3] = true;
+        }
+    fi;
+    if (@in[0] || @in[1]) then
+        eval(0) out[3] = false
+    fi;
+}"#;
+
+    #[test]
+    fn stream_tables() {
+        let monitor = generate(STREAMS, None).unwrap();
+        assert!(monitor.header.contains(
+            "#define MONITOR_NUM_INPUTS 2\n#define MONITOR_NUM_OUTPUTS 4\n#define MONITOR_NUM_TRIGGERS 1"
+        ));
+        assert!(monitor.source.contains(
+            "const StreamInfo INPUT_INFOS[] = {\n\
+            {0, \"a\", \"Int(64)\", 2, \"input\"},\n\
+            {1, \"b\", \"Int(64)\", 1, \"input\"},\n\
+            {0}\n\
+            };"
+        ));
+        assert!(monitor.source.contains(
+            "const StreamInfo OUTPUT_INFOS[] = {\n\
+            {2, \"x\", \"Int(64)\", 1, \"@a\"},\n\
+            {3, \"y\", \"Int(64)\", 1, \"@a && @b\"},\n\
+            {4, \"t\", \"String\", 1, \"@a\"},\n\
+            {5, \"z\", \"Bool\", 1, \"(@a) || ((@a || @b))\"},\n\
+            {0}\n\
+            };"
+        ));
+    }
+
+    #[test]
+    fn trigger_table() {
+        let monitor = generate(STREAMS, None).unwrap();
+        assert!(monitor.source.contains(
+            "const TriggerInfo TRIGGER_INFOS[] = {\n\
+            {0, 4, \"t\", \"a is large\"},\n\
+            {0}\n\
+            };"
+        ));
+    }
+
+    #[test]
+    fn nested_pacing() {
+        // the guard of the nested conditional repeats the enclosing one and is not part of the pacing twice
+        let monitor = generate(STREAMS, None).unwrap();
+        assert!(monitor
+            .source
+            .contains("{2, \"x\", \"Int(64)\", 1, \"@a\"}"));
+        assert!(!monitor.source.contains("@a && @a"));
+    }
+
+    #[test]
+    fn accessors() {
+        let monitor = generate(STREAMS, None).unwrap();
+        let driver = r#"
+#include <stdio.h>
+#include "monitor.c"
+int main(void) {
+    for (const StreamInfo* info = OUTPUT_INFOS; info->name != NULL; info++)
+        printf("%d %s %s\n", info->id, info->name, info->pacing);
+    Memory m;
+    init_memory(&m);
+    begin_event(&m, 0);
+    int64_t a = 5;
+    printf("%d %d\n", set_input_value(&m.staged_event, 0, &a), set_input_value(&m.staged_event, 2, &a));
+    commit_event(&m);
+    int64_t x = 0;
+    int64_t y = 0;
+    bool x_valid = get_stream_value(&m, 2, &x);
+    bool y_valid = get_stream_value(&m, 3, &y);
+    printf("%d %lld %d\n", x_valid, (long long) x, y_valid);
+    printf("%d %llx\n", abi_version(), (unsigned long long) spec_fingerprint());
+    return 0;
+}"#;
+        let fingerprint =
+            super::fingerprint(&rtlola_streamir::ir::text::parse(STREAMS).unwrap().to_text());
+        assert_eq!(
+            monitor.stdout(driver, &[]),
+            format!("2 x @a\n3 y @a && @b\n4 t @a\n5 z (@a) || ((@a || @b))\n1 0\n1 5 0\n1 {fingerprint:x}\n")
+        );
+    }
+}
@@ -61,7 +61,12 @@ impl StructDefinition for VerdictStruct {
                     Argument::Normal(f.stream_name(o).to_owned(), f.stream_ty(o)),
                     Argument::Normal(f.verdict_present_flag(o), CType::Bool),
                 ]
+                .into_iter()
+                .chain((f.delay(o) > 0).then(|| {
+                    Argument::Normal(f.verdict_time_field_name(o), CType::Lola(Type::Float64))
+                }))
             })
+            .chain(f.reports_overflow().then(|| f.overflow_argument()))
             .chain(Some(f.time_argument()))
             .collect()
     }
@@ -85,8 +90,14 @@ impl FunctionDefinition for NewVerdict {
             ),
             f.verdict_streams.iter().copied()
                 .map(|o| {
+                    // delayed streams report the time of the activation their value refers to
+                    let time = if f.delay(o) > 0 {
+                        format!("\n{}.{} = {};", VerdictStruct.argument_name(f), f.verdict_time_field_name(o), f.delayed_time(o))
+                    } else {
+                        String::new()
+                    };
                     format!(
-                        "if ({is_fresh}) {{\n{struct}.{value} = {sync};{struct}.{is_present} = 1;\n}}",
+                        "if ({is_fresh}) {{\n{struct}.{value} = {sync};{struct}.{is_present} = 1;{time}\n}}",
                         is_fresh=f.call_function(IsFresh(o), &[MemoryStruct.argument_name(f)]),
                         sync=f.call_function(SyncAccess(o), &[MemoryStruct.argument_name(f)]),
                         struct=VerdictStruct.argument_name(f),
@@ -95,6 +106,11 @@ impl FunctionDefinition for NewVerdict {
                     )
                 })
                 .join("\n"),
+            if f.reports_overflow() {
+                format!("{}.{} = {}->{};", VerdictStruct.argument_name(f), f.overflow_field_name(), MemoryStruct.argument_name(f), f.overflow_field_name())
+            } else {
+                String::new()
+            },
             format!("{}.{} = {}->{};", VerdictStruct.argument_name(f), f.time_argument_name(), MemoryStruct.argument_name(f), f.time_argument_name()),
             format!("return {};", VerdictStruct.argument_name(f)),
         ]
@@ -126,12 +142,14 @@ impl CFormatter {
             Type::String => "\\\"%s\\\"".into(),
             Type::Float32 => "%f".into(),
             Type::Float64 => "%lf".into(),
-            Type::Fixed(_) | Type::UFixed(_) => unimplemented!(),
+            Type::Fixed(_) | Type::UFixed(_) => {
+                unreachable!("fixed point types are rejected by the capability check")
+            }
             Type::Tuple(inner) => {
                 format!("({})", inner.into_iter().map(Self::format_string).join(","))
             }
-            Type::Bytes => unimplemented!(),
-            Type::Option(_) => unreachable!(),
+            Type::Bytes => unreachable!("bytes are rejected by the capability check"),
+            Type::Option(_) => unreachable!("option types are rejected by the capability check"),
         }
     }
 
@@ -169,27 +187,58 @@ impl FunctionDefinition for PrintVerdict {
             .iter()
             .copied()
             .fold(String::new(), |mut s, o| {
-                let values = f
-                    .unroll_tuple_elements(
-                        f.stream_ty(o).lola(),
-                        format!("{verdict}->{}", f.stream_name(o),),
-                    )
-                    .join(", ");
+                let value = format!("{verdict}->{}", f.stream_name(o));
+                let mut has_value = format!("{verdict}->{}", f.verdict_present_flag(o));
+                // absent optional values are printed like missing verdicts
+                let (ty, value) = match f.stream_ty(o).lola() {
+                    Type::Option(inner) => {
+                        write!(
+                            &mut has_value,
+                            " && {value}.{}",
+                            f.option_present_field_name()
+                        )
+                        .unwrap();
+                        (*inner, format!("{value}.{}", f.option_value_field_name()))
+                    }
+                    ty => (ty, value),
+                };
+                let values = f.unroll_tuple_elements(ty.clone(), value).join(", ");
                 write!(
                     &mut s,
-                    "if ({verdict}->{has_value}) {{\n\
+                    "if ({has_value}) {{\n\
     printf(\"{format_string},\", {values});\n\
     }} else {{\n\
      printf(\"#,\");\n\
     }}",
-                    has_value = f.verdict_present_flag(o),
-                    format_string = CFormatter::format_string(f.stream_ty(o).lola()),
+                    format_string = CFormatter::format_string(ty),
                 )
                 .unwrap();
+                if f.delay(o) > 0 {
+                    // the time column of a delayed stream follows its value
+                    write!(
+                        &mut s,
+                        "\nif ({verdict}->{present}) {{\n\
+    printf(\"%lf,\", {verdict}->{time});\n\
+    }} else {{\n\
+     printf(\"#,\");\n\
+    }}",
+                        present = f.verdict_present_flag(o),
+                        time = f.verdict_time_field_name(o)
+                    )
+                    .unwrap();
+                }
                 s
             });
+        let overflow = if f.reports_overflow() {
+            format!(
+                "\nprintf(\"%s,\", {verdict}->{overflow} ? \"true\" : \"false\");",
+                overflow = f.overflow_field_name()
+            )
+        } else {
+            String::new()
+        };
         format!(
-            "{i}\nprintf(\"%f\\n\", {verdict}->{time});",
+            "{i}{overflow}\nprintf(\"%f\\n\", {verdict}->{time});",
             time = f.time_argument_name()
         )
     }
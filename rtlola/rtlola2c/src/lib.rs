@@ -1,15 +1,21 @@
+pub mod capabilities;
 pub(crate) mod constructs;
 pub(crate) mod expressions;
 mod guards;
+mod introspection;
 pub(crate) mod io;
 pub mod main_function;
 mod memory;
 mod names;
+mod snapshot;
+mod staged_event;
 mod statements;
 mod types;
+pub mod unbounded;
 
 use std::{collections::HashMap, path::PathBuf, sync::Mutex};
 
+use capabilities::UnsupportedFeatures;
 use constructs::StructDefinition;
 use itertools::Itertools;
 use main_function::MainFunction;
@@ -19,15 +25,33 @@ use rtlola_streamir::{
         files::{ConstructStore, ConstructWriteError, FilesFormatter},
         StreamIrFormatter,
     },
-    ir::{expressions::Expr, memory::Memory, StreamIr, StreamReference},
+    ir::{
+        interner::{ExprId, ExprInterner},
+        memory::Memory,
+        StreamIr, StreamReference,
+    },
 };
 use statements::CycleFunction;
+use thiserror::Error;
 use types::CType;
+use unbounded::UnboundedMemory;
+
+#[derive(Debug, Error)]
+/// An error that can happen when compiling the StreamIR to C
+pub enum FormatError {
+    #[error("the specification uses features unsupported by the C backend:\n{0}")]
+    /// The StreamIR uses features the backend cannot compile
+    Unsupported(#[from] UnsupportedFeatures),
+    #[error(transparent)]
+    /// The generated files could not be written
+    Write(#[from] ConstructWriteError),
+}
 
 pub struct CFormatter {
     construct_store: ConstructStore<Self>,
     sr2memory: HashMap<StreamReference, Memory>,
-    expr_counter: Mutex<HashMap<(Expr, Option<StreamReference>), usize>>,
+    expr_interner: Mutex<ExprInterner>,
+    expr_counter: Mutex<HashMap<(ExprId, Option<StreamReference>), usize>>,
     num_exprs: Mutex<usize>,
     overwrite: bool,
     main: MainFunction,
@@ -35,6 +59,7 @@ pub struct CFormatter {
     next_static_string: Mutex<usize>,
     verdict_streams: Vec<StreamReference>,
     output_dir: PathBuf,
+    unbounded: Option<UnboundedMemory>,
 }
 
 impl CFormatter {
@@ -44,10 +69,12 @@ impl CFormatter {
         main: MainFunction,
         verdict_streams: Vec<StreamReference>,
         output_dir: PathBuf,
+        unbounded: Option<UnboundedMemory>,
     ) -> Self {
         Self {
             construct_store: ConstructStore::default(),
             sr2memory: ir.sr2memory.clone(),
+            expr_interner: Mutex::default(),
             expr_counter: Mutex::default(),
             num_exprs: Mutex::new(0),
             overwrite,
@@ -56,25 +83,31 @@ impl CFormatter {
             next_static_string: Mutex::new(0),
             verdict_streams,
             output_dir,
+            unbounded,
         }
     }
 }
 
 impl StreamIrFormatter for CFormatter {
-    type Return = Result<(), ConstructWriteError>;
+    type Return = Result<(), FormatError>;
 
     fn id(&self) -> String {
         "c-formatter".into()
     }
 
     fn format(self, ir: StreamIr) -> Self::Return {
+        capabilities::check(&ir, self.main, self.unbounded)?;
+        self.require_introspection(&ir);
+        self.require_staged_event();
         let StreamIr { stmt, .. } = ir;
         self.import_own(self.monitor_file(), "monitor");
         let _ = self.call_function(CycleFunction(stmt), &[MemoryStruct.argument_name(&self)]);
         // let _ = self.call_function(AcceptEventFunction, &[]);
         self.require_struct(MemoryStruct);
+        self.require_unbounded_defines();
+        self.require_snapshot();
         self.main.insert_requirement(&self);
-        self.generate_files()
+        Ok(self.generate_files()?)
     }
 }
 
@@ -95,7 +128,6 @@ impl CFormatter {
             .copied()
     }
 
-    #[allow(dead_code)]
     fn outputs(&self) -> impl Iterator<Item = StreamReference> + '_ {
         self.sr2memory
             .keys()
@@ -104,3 +136,113 @@ impl CFormatter {
             .copied()
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        path::PathBuf,
+        process::{Command, Output},
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use itertools::Itertools;
+    use rtlola_streamir::{formatter::StreamIrFormatter, ir::text::parse};
+
+    use super::{CFormatter, FormatError};
+    use crate::{main_function::MainFunction, unbounded::UnboundedMemory};
+
+    pub(crate) const EXAMPLE: &str = include_str!("tests/example.sir");
+
+    /// The C files generated for a StreamIR, which are removed when dropped
+    pub(crate) struct Monitor {
+        dir: PathBuf,
+        /// The content of `monitor.h`
+        pub(crate) header: String,
+        /// The content of `monitor.c`
+        pub(crate) source: String,
+    }
+
+    /// Generates the monitor for the StreamIR in its textual representation, with all streams as verdict streams
+    pub(crate) fn generate(
+        ir: &str,
+        unbounded: Option<UnboundedMemory>,
+    ) -> Result<Monitor, FormatError> {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let ir = parse(ir).unwrap_or_else(|e| panic!("{e}"));
+        let dir = std::env::temp_dir().join(format!(
+            "rtlola2c-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let verdict_streams = ir.streams().sorted().collect();
+        let formatter = CFormatter::new(
+            &ir,
+            true,
+            MainFunction::NoMain,
+            verdict_streams,
+            dir.clone(),
+            unbounded,
+        );
+        let monitor = formatter.format(ir).map(|()| Monitor {
+            header: std::fs::read_to_string(dir.join("monitor.h")).unwrap(),
+            source: std::fs::read_to_string(dir.join("monitor.c")).unwrap(),
+            dir: dir.clone(),
+        });
+        if monitor.is_err() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        monitor
+    }
+
+    impl Monitor {
+        /// Compiles the driver, which includes `monitor.c`, with the given macro definitions and runs it
+        pub(crate) fn run(&self, driver: &str, defines: &[&str]) -> Output {
+            std::fs::write(self.dir.join("driver.c"), driver).unwrap();
+            let binary = self.dir.join("driver");
+            let compile = Command::new("cc")
+                .args(["-std=c11", "-Wall", "-o"])
+                .arg(&binary)
+                .args(defines.iter().map(|define| format!("-D{define}")))
+                .arg(self.dir.join("driver.c"))
+                .arg("-lm")
+                .output()
+                .expect("a C compiler is available as `cc`");
+            assert!(
+                compile.status.success(),
+                "{}",
+                String::from_utf8_lossy(&compile.stderr)
+            );
+            Command::new(binary).output().unwrap()
+        }
+
+        /// Compiles and runs the driver, returning its output, which must be successful
+        pub(crate) fn stdout(&self, driver: &str, defines: &[&str]) -> String {
+            let output = self.run(driver, defines);
+            assert!(
+                output.status.success(),
+                "{}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            String::from_utf8(output.stdout).unwrap()
+        }
+    }
+
+    impl Drop for Monitor {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn example_compiles() {
+        let monitor = generate(EXAMPLE, None).unwrap();
+        assert!(monitor.source.contains("#include \"monitor.h\""));
+        assert!(monitor.header.contains("void init_memory(Memory* memory);"));
+        let output = monitor.stdout(
+            "#include \"monitor.c\"\nint main(void) { Memory m; init_memory(&m); return 0; }",
+            &[],
+        );
+        assert_eq!(output, "");
+    }
+}
@@ -48,7 +48,7 @@ impl FunctionDefinition for ReadField {
             Type::Float32 => "atof(token)".into(),
             Type::Float64 => "atof(token)".into(),
             Type::Bool => "strcmp(token, \"true\") == 0 ? 1 : 0".into(),
-            _ => unreachable!(),
+            _ => unreachable!("the input type is rejected by the capability check"),
         };
         format!(
             "if (token[0] == \'#\') {{
@@ -249,7 +249,18 @@ impl CFormatter {
         let headers = self
             .verdict_streams
             .iter()
-            .map(|s| self.stream_name(*s))
+            .map(|s| {
+                if self.delay(*s) > 0 {
+                    format!(
+                        "{},{}",
+                        self.stream_name(*s),
+                        self.verdict_time_field_name(*s)
+                    )
+                } else {
+                    self.stream_name(*s)
+                }
+            })
+            .chain(self.reports_overflow().then(|| self.overflow_field_name()))
             .join(",");
         format!("printf(\"{},time\\n\");", headers)
     }
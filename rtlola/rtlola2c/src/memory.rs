@@ -3,12 +3,13 @@ use rtlola_streamir::{
     formatter::names::GetStreamName,
     ir::{
         memory::{StreamBuffer, StreamMemory},
-        StreamReference,
+        StreamReference, Type,
     },
 };
 
 use crate::{
     constructs::{Argument, FunctionDefinition, RequirementKey, StructDefinition},
+    unbounded::UnboundedBuffer,
     CFormatter, CType,
 };
 
@@ -37,8 +38,26 @@ impl StructDefinition for MemoryStruct {
                 StreamMemory::Dynamic { .. } => {
                     Some(f.dynamic_memory_struct(sr).unwrap().into_argument(f))
                 }
-                StreamMemory::Instances { .. } => todo!(),
+                StreamMemory::Instances { .. } => {
+                    unreachable!("parameterized streams are rejected by the capability check")
+                }
             })
+            .chain(f.streams().filter(|sr| f.delay(*sr) > 0).flat_map(|sr| {
+                // the times of the activations a delayed stream has not yet been evaluated for
+                [
+                    Argument::Array(
+                        f.delay_times_field_name(sr),
+                        CType::Lola(Type::Float64),
+                        f.delay(sr) as usize + 1,
+                    ),
+                    Argument::Normal(
+                        f.delay_evaluations_field_name(sr),
+                        CType::Lola(Type::UInt(64)),
+                    ),
+                ]
+            }))
+            .chain(f.reports_overflow().then(|| f.overflow_argument()))
+            .chain(Some(f.staged_event_argument()))
             .chain(Some(f.time_argument()))
             .collect()
     }
@@ -51,10 +70,18 @@ impl CFormatter {
             StreamMemory::Static(buffer) | StreamMemory::Dynamic { buffer, .. } => {
                 Some(self.static_stream_memory_struct(sr, buffer))
             }
-            StreamMemory::Instances { .. } => unimplemented!(),
+            StreamMemory::Instances { .. } => {
+                unreachable!("parameterized streams are rejected by the capability check")
+            }
         }
     }
 
+    /// Returns the buffer of a stream that is accessed by an expression
+    pub(crate) fn accessed_buffer(&self, sr: StreamReference) -> Box<dyn StaticBufferTrait> {
+        self.static_buffer(sr)
+            .expect("accesses to streams without memory are rejected by the capability check")
+    }
+
     pub(crate) fn dynamic_memory_struct(&self, sr: StreamReference) -> Option<DynamicBuffer> {
         match &self.sr2memory[&sr].buffer {
             &StreamMemory::Dynamic { buffer, .. } => Some(DynamicBuffer(sr, buffer)),
@@ -70,7 +97,12 @@ impl CFormatter {
         match buffer {
             StreamBuffer::SingleValue => Box::new(SingleValueBuffer(sr)),
             StreamBuffer::Bounded(b) => Box::new(BoundedBuffer(sr, *b)),
-            StreamBuffer::UnBounded => unimplemented!("not supported in RTLola"),
+            StreamBuffer::UnBounded => Box::new(UnboundedBuffer(
+                sr,
+                self.unbounded.expect(
+                    "unbounded memory without a capacity is rejected by the capability check",
+                ),
+            )),
         }
     }
 }
@@ -78,15 +110,35 @@ impl CFormatter {
 pub(crate) trait StaticBufferTrait {
     fn shift_code(&self, f: &CFormatter) -> String;
     fn push_value(&self, v: String, f: &CFormatter) -> String;
-    fn get_value(&self, offset: String, default: String, f: &CFormatter) -> String;
+    /// Returns the code returning the value at the offset, which is passed to `wrap`, or the default if it is invalid
+    fn get_value(
+        &self,
+        offset: String,
+        default: String,
+        wrap: &dyn Fn(String) -> String,
+        f: &CFormatter,
+    ) -> String;
     fn is_fresh(&self, f: &CFormatter) -> String;
     fn reset_fresh(&self, f: &CFormatter) -> String;
     fn sync_access(&self, f: &CFormatter) -> String;
+    /// Returns whether the current value of the buffer is valid
+    fn is_valid(&self, f: &CFormatter) -> String;
 
     fn as_argument(&self, f: &CFormatter) -> Argument;
 }
 
 impl CFormatter {
+    /// Returns the time of the activation the current value of a delayed stream refers to
+    pub(crate) fn delayed_time(&self, sr: StreamReference) -> String {
+        format!(
+            "{memory}->{times}[{memory}->{evaluations} % {size}]",
+            memory = MemoryStruct.argument_name(self),
+            times = self.delay_times_field_name(sr),
+            evaluations = self.delay_evaluations_field_name(sr),
+            size = self.delay(sr) + 1
+        )
+    }
+
     pub(crate) fn buffer(&self, sr: StreamReference) -> Option<String> {
         match &self.sr2memory[&sr].buffer {
             StreamMemory::NoMemory => None,
@@ -106,7 +158,9 @@ impl CFormatter {
                     .name(),
                 self.static_buffer(sr).unwrap().as_argument(self).name()
             )),
-            StreamMemory::Instances { .. } => todo!(),
+            StreamMemory::Instances { .. } => {
+                unreachable!("parameterized streams are rejected by the capability check")
+            }
         }
     }
 }
@@ -138,24 +192,32 @@ impl StructDefinition for SingleValueBuffer {
 
 impl StaticBufferTrait for SingleValueBuffer {
     fn shift_code(&self, _f: &CFormatter) -> String {
-        unreachable!("shifts should be optimized away for single value buffers")
+        // the new value overwrites the single value, so nothing needs to be shifted
+        String::new()
     }
 
     fn push_value(&self, v: String, f: &CFormatter) -> String {
         format!(
-            "{buffer}.{value} = {v};\n{buffer}.{valid} = true;",
+            "{buffer}.{value} = {v};\n{buffer}.{valid} = true;\n{buffer}.{fresh} = 1;",
             value = f.values_argument_name(),
             valid = f.valid_argument_name(),
-            buffer = f.buffer(self.0).unwrap()
+            buffer = f.buffer(self.0).unwrap(),
+            fresh = f.is_fresh_argument_name()
         )
     }
 
-    fn get_value(&self, offset: String, default: String, f: &CFormatter) -> String {
+    fn get_value(
+        &self,
+        offset: String,
+        default: String,
+        wrap: &dyn Fn(String) -> String,
+        f: &CFormatter,
+    ) -> String {
+        let buffer = f.buffer(self.0).unwrap();
         format!(
-            "assert({offset} == 0);\nif ({buffer}.{valid})\nreturn {buffer}.{value};\nelse\nreturn {default};",
-            buffer=f.buffer(self.0).unwrap(),
+            "assert({offset} == 0);\nif ({buffer}.{valid})\nreturn {value};\nelse\nreturn {default};",
             valid = f.valid_argument_name(),
-            value = f.values_argument_name()
+            value = wrap(format!("{buffer}.{}", f.values_argument_name()))
         )
     }
 
@@ -175,8 +237,20 @@ impl StaticBufferTrait for SingleValueBuffer {
         )
     }
 
-    fn sync_access(&self, _f: &CFormatter) -> String {
-        format!("{buffer}.value", buffer = _f.buffer(self.0).unwrap())
+    fn sync_access(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{value}",
+            buffer = f.buffer(self.0).unwrap(),
+            value = f.values_argument_name()
+        )
+    }
+
+    fn is_valid(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{valid}",
+            buffer = f.buffer(self.0).unwrap(),
+            valid = f.valid_argument_name()
+        )
     }
 
     fn as_argument(&self, f: &CFormatter) -> Argument {
@@ -233,14 +307,20 @@ impl StaticBufferTrait for BoundedBuffer {
         )
     }
 
-    fn get_value(&self, offset: String, default: String, f: &CFormatter) -> String {
+    fn get_value(
+        &self,
+        offset: String,
+        default: String,
+        wrap: &dyn Fn(String) -> String,
+        f: &CFormatter,
+    ) -> String {
+        let buffer = f.buffer(self.0).unwrap();
         format!(
-            "int i = ({buffer}.{current} - {offset} + {size}) % {size};\nif ({buffer}.{valid}[i])\nreturn {buffer}.{values}[i];\nelse\nreturn {default};",
+            "int i = ({buffer}.{current} - {offset} + {size}) % {size};\nif ({buffer}.{valid}[i])\nreturn {value};\nelse\nreturn {default};",
             size=self.1,
             current = f.current_argument_name(),
-            values = f.values_argument_name(),
             valid = f.valid_argument_name(),
-            buffer = f.buffer(self.0).unwrap()
+            value = wrap(format!("{buffer}.{}[i]", f.values_argument_name()))
         )
     }
 
@@ -269,6 +349,15 @@ impl StaticBufferTrait for BoundedBuffer {
         )
     }
 
+    fn is_valid(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{valid}[{buffer}.{current}]",
+            buffer = f.buffer(self.0).unwrap(),
+            current = f.current_argument_name(),
+            valid = f.valid_argument_name()
+        )
+    }
+
     fn as_argument(&self, f: &CFormatter) -> Argument {
         <Self as StructDefinition>::into_argument(self.to_owned(), f)
     }
@@ -342,6 +431,13 @@ impl FunctionDefinition for ClearActivation {
         f.streams()
             .filter_map(|s| f.static_buffer(s))
             .map(|b| b.reset_fresh(f))
+            .chain(f.reports_overflow().then(|| {
+                format!(
+                    "{}->{} = 0;",
+                    MemoryStruct.argument_name(f),
+                    f.overflow_field_name()
+                )
+            }))
             .join("\n")
     }
 
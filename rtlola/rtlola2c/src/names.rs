@@ -15,6 +15,8 @@ function_names! {CFormatter,
     sync_access_function_name(stream): "{stream}_sync",
     offset_access_function_name(stream): "{stream}_offset",
     get_access_function_name(stream): "{stream}_get",
+    optional_offset_access_function_name(stream): "{stream}_offset_optional",
+    optional_get_access_function_name(stream): "{stream}_get_optional",
     is_fresh_access_function_name(stream): "{stream}_is_fresh",
     spawn_function_name(stream): "spawn_{stream}",
     close_function_name(stream): "close_{stream}",
@@ -24,16 +26,39 @@ function_names! {CFormatter,
     valid_argument_name(): "valid",
     current_argument_name(): "current",
     is_fresh_argument_name(): "is_fresh",
+    dropped_argument_name(): "dropped",
     new_value_argument_name(): "new_value",
     default_argument_name(): "def",
     offset_argument_name(): "offset",
+    option_present_field_name(): "is_present",
+    option_value_field_name(): "value",
+    delay_times_field_name(stream): "{stream}_times",
+    delay_evaluations_field_name(stream): "{stream}_evaluations",
+    verdict_time_field_name(stream): "{stream}_time",
 
     internal_event_struct_name(): "InternalEvent",
     verdict_struct_name(): "Verdict",
     time_argument_name(): "time",
+    overflow_field_name(): "memory_overflow",
 
     build_verdict_function_name(): "build_verdict",
-    static_str_constant_name(num): "STR_CONSTANT_{num}"
+    get_stream_value_function_name(): "get_stream_value",
+    set_input_value_function_name(): "set_input_value",
+    abi_version_function_name(): "abi_version",
+    spec_fingerprint_function_name(): "spec_fingerprint",
+    input_table_name(): "INPUT_INFOS",
+    output_table_name(): "OUTPUT_INFOS",
+    trigger_table_name(): "TRIGGER_INFOS",
+    begin_event_function_name(): "begin_event",
+    set_input_function_name(stream): "set_{stream}",
+    commit_event_function_name(): "commit_event",
+    staged_event_field_name(): "staged_event",
+    snapshot_size_function_name(): "snapshot_size",
+    snapshot_function_name(): "snapshot",
+    restore_function_name(): "restore",
+    reset_function_name(): "reset",
+    static_str_constant_name(num): "STR_CONSTANT_{num}",
+    temporary_name(num): "tmp_{num}"
 }
 
 impl GetStreamName for CFormatter {
@@ -59,6 +84,11 @@ impl CFormatter {
         Argument::Normal(self.time_argument_name(), CType::Lola(Type::Float64))
     }
 
+    /// Returns the number of activations the evaluation of the stream lags behind
+    pub(crate) fn delay(&self, sr: StreamReference) -> u32 {
+        self.sr2memory[&sr].delay
+    }
+
     pub(crate) fn tuple_struct_name(&self, ty: &[Type]) -> String {
         format!(
             "TUPLE{}_{}",
@@ -69,6 +99,15 @@ impl CFormatter {
         )
     }
 
+    pub(crate) fn option_struct_name(&self, inner: &Type) -> String {
+        format!(
+            "OPTION_{}",
+            self.ty(inner.to_owned())
+                .to_ascii_uppercase()
+                .replace('*', "_PTR")
+        )
+    }
+
     pub(crate) fn tuple_argument_name(&self, i: usize) -> String {
         format!("_{i}")
     }
@@ -0,0 +1,711 @@
+//! Saving, restoring and resetting the state of the monitor.
+//!
+//! A snapshot serializes the memory of all streams into a byte buffer of a fixed size that is independent of the
+//! layout of the [MemoryStruct] on the target platform: integers are stored in little endian with their
+//! width, floating point numbers by their IEEE 754 representation, and strings by the index of their constant.
+//! The snapshot starts with the version of the format and the fingerprint of the specification,
+//! such that a snapshot is only restored into a monitor compiled from the same specification.
+
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{files::FilesFormatter, guards::DefaultGuardFormatter, types::TypeFormatter},
+    ir::{memory::StreamBuffer, StreamReference, Type},
+};
+
+use crate::{
+    constructs::{Argument, FunctionDefinition, RequirementKey, StructDefinition},
+    memory::InitMemory,
+    unbounded::OverflowPolicy,
+    CFormatter, CType, MemoryStruct,
+};
+
+/// The version of the snapshot format, which is increased whenever the serialization changes
+const SNAPSHOT_VERSION: u32 = 1;
+
+/// The number of bytes of the version and the fingerprint at the start of a snapshot
+const HEADER_SIZE: usize = 4 + 8;
+
+/// The functions converting values to and from their serialized representation
+const HELPERS: &str = "void snapshot_write(uint8_t** buffer, uint64_t value, int bytes){
+for (int i = 0; i < bytes; i++) {
+*(*buffer)++ = (uint8_t) (value >> (8 * i));
+}
+}
+uint64_t snapshot_read(uint8_t** buffer, int bytes){
+uint64_t value = 0;
+for (int i = 0; i < bytes; i++) {
+value |= ((uint64_t) *(*buffer)++) << (8 * i);
+}
+return value;
+}
+uint64_t float64_bits(double value){
+uint64_t bits;
+memcpy(&bits, &value, sizeof(bits));
+return bits;
+}
+double float64_from_bits(uint64_t bits){
+double value;
+memcpy(&value, &bits, sizeof(value));
+return value;
+}
+uint64_t float32_bits(float value){
+uint32_t bits;
+memcpy(&bits, &value, sizeof(bits));
+return bits;
+}
+float float32_from_bits(uint64_t bits){
+uint32_t narrow = (uint32_t) bits;
+float value;
+memcpy(&value, &narrow, sizeof(value));
+return value;
+}";
+
+/// A value of the memory that is part of the snapshot
+struct Field {
+    /// The expression of the value, in which `i` is the index of array fields
+    lvalue: String,
+    ty: Type,
+    /// The number of elements, if the field is an array
+    len: Option<usize>,
+}
+
+impl Field {
+    fn new(lvalue: String, ty: Type) -> Self {
+        Self {
+            lvalue,
+            ty,
+            len: None,
+        }
+    }
+
+    fn array(lvalue: String, ty: Type, len: usize) -> Self {
+        Self {
+            lvalue,
+            ty,
+            len: Some(len),
+        }
+    }
+
+    fn size(&self) -> usize {
+        serialized_size(&self.ty) * self.len.unwrap_or(1)
+    }
+}
+
+/// Returns the number of bytes of the serialized value of the type
+fn serialized_size(ty: &Type) -> usize {
+    match ty {
+        Type::Int(bits) | Type::UInt(bits) => *bits as usize / 8,
+        Type::Bool => 1,
+        Type::Float32 => 4,
+        Type::Float64 => 8,
+        // strings are stored by the index of their constant
+        Type::String => 4,
+        Type::Option(inner) => 1 + serialized_size(inner),
+        Type::Tuple(inner) => inner.iter().map(serialized_size).sum(),
+        Type::Fixed(_) | Type::UFixed(_) => {
+            unreachable!("fixed point types are rejected by the capability check")
+        }
+        Type::Bytes => unreachable!("bytes are rejected by the capability check"),
+    }
+}
+
+impl CFormatter {
+    /// Adds the functions saving, restoring and resetting the state of the monitor to the output
+    ///
+    /// Needs to be called after the code of the cycle is generated, such that all string constants are known.
+    pub(crate) fn require_snapshot(&self) {
+        self.import(self.monitor_file(), "string");
+        self.add_requirement_string(
+            self.monitor_file(),
+            RequirementKey::SnapshotHelpers,
+            HELPERS.into(),
+        );
+        self.add_requirement_string(
+            self.monitor_file(),
+            RequirementKey::SnapshotStrings,
+            self.string_table(),
+        );
+        self.add_requirement_string(
+            self.header_file(),
+            RequirementKey::SnapshotDefines,
+            format!(
+                "#define SNAPSHOT_VERSION {SNAPSHOT_VERSION}\n#define SNAPSHOT_SIZE {}",
+                HEADER_SIZE
+                    + self
+                        .snapshot_fields()
+                        .iter()
+                        .map(Field::size)
+                        .sum::<usize>()
+            ),
+        );
+        self.require_function(SnapshotSize);
+        self.require_function(Snapshot);
+        self.require_function(Restore);
+        self.require_function(Reset);
+    }
+
+    /// Returns the values of the memory in the order they are serialized
+    fn snapshot_fields(&self) -> Vec<Field> {
+        let memory = MemoryStruct.argument_name(self);
+        let buffers = self.streams().flat_map(|sr| {
+            let Some(buffer) = self.buffer(sr) else {
+                return Vec::new();
+            };
+            let ty = self.stream_ty(sr).lola();
+            let values = format!("{buffer}.{}", self.values_argument_name());
+            let valid = format!("{buffer}.{}", self.valid_argument_name());
+            let mut fields = match self.sr2memory[&sr].buffer.buffer() {
+                Some(StreamBuffer::SingleValue) => {
+                    vec![Field::new(values, ty), Field::new(valid, Type::Bool)]
+                }
+                Some(StreamBuffer::Bounded(size)) => self.ring_buffer_fields(&buffer, ty, *size),
+                Some(StreamBuffer::UnBounded) => {
+                    let unbounded = self.unbounded.unwrap();
+                    let mut fields = self.ring_buffer_fields(&buffer, ty, unbounded.capacity);
+                    if unbounded.policy == OverflowPolicy::Error {
+                        fields.push(Field::new(
+                            format!("{buffer}.{}", self.dropped_argument_name()),
+                            Type::Int(32),
+                        ));
+                    }
+                    fields
+                }
+                None => unreachable!("streams with a buffer have memory"),
+            };
+            fields.push(Field::new(
+                format!("{buffer}.{}", self.is_fresh_argument_name()),
+                Type::Bool,
+            ));
+            if self.is_dynamic(sr) {
+                fields.push(Field::new(self.alive(sr), Type::Bool));
+            }
+            fields
+        });
+        let delays = self
+            .streams()
+            .filter(|sr| self.delay(*sr) > 0)
+            .flat_map(|sr| {
+                [
+                    Field::array(
+                        format!("{memory}->{}[i]", self.delay_times_field_name(sr)),
+                        Type::Float64,
+                        self.delay(sr) as usize + 1,
+                    ),
+                    Field::new(
+                        format!("{memory}->{}", self.delay_evaluations_field_name(sr)),
+                        Type::UInt(64),
+                    ),
+                ]
+            });
+        let overflow = self.reports_overflow().then(|| {
+            Field::new(
+                format!("{memory}->{}", self.overflow_field_name()),
+                Type::Bool,
+            )
+        });
+        let time = Field::new(
+            format!("{memory}->{}", self.time_argument_name()),
+            Type::Float64,
+        );
+        buffers
+            .chain(delays)
+            .chain(overflow)
+            .chain(Some(time))
+            .collect()
+    }
+
+    fn ring_buffer_fields(&self, buffer: &str, ty: Type, size: usize) -> Vec<Field> {
+        vec![
+            Field::array(
+                format!("{buffer}.{}[i]", self.values_argument_name()),
+                ty,
+                size,
+            ),
+            Field::array(
+                format!("{buffer}.{}[i]", self.valid_argument_name()),
+                Type::Bool,
+                size,
+            ),
+            Field::new(
+                format!("{buffer}.{}", self.current_argument_name()),
+                Type::UInt(32),
+            ),
+        ]
+    }
+
+    fn is_dynamic(&self, sr: StreamReference) -> bool {
+        self.dynamic_memory_struct(sr).is_some()
+    }
+
+    /// Returns the functions mapping the string constants to their index in a snapshot and back
+    ///
+    /// The index 0 represents a string that is not initialized.
+    fn string_table(&self) -> String {
+        let num_strings = *self.next_static_string.lock().unwrap();
+        let (to_index, from_index): (Vec<_>, Vec<_>) = (0..num_strings)
+            .map(|idx| {
+                let name = self.static_str_constant_name(idx);
+                (
+                    format!("if (value == {name}) return {};", idx + 1),
+                    format!("case {}: return {name};", idx + 1),
+                )
+            })
+            .unzip();
+        let string = self.c_ty(CType::Char.reference());
+        let index = self.ty(Type::UInt(64));
+        format!(
+            "{index} string_index({string} value){{\n{}\nreturn 0;\n}}\n\
+            {string} string_at({index} index){{\nswitch (index) {{\n{}\ndefault:\nreturn NULL;\n}}\n}}",
+            to_index.join("\n"),
+            from_index.join("\n")
+        )
+    }
+
+    fn write_value(&self, ty: &Type, lvalue: &str) -> String {
+        match ty {
+            Type::Int(bits) | Type::UInt(bits) => format!(
+                "snapshot_write(&buffer, (uint64_t) {lvalue}, {});",
+                bits / 8
+            ),
+            Type::Bool => format!("snapshot_write(&buffer, {lvalue}, 1);"),
+            Type::Float32 => format!("snapshot_write(&buffer, float32_bits({lvalue}), 4);"),
+            Type::Float64 => format!("snapshot_write(&buffer, float64_bits({lvalue}), 8);"),
+            Type::String => format!("snapshot_write(&buffer, string_index({lvalue}), 4);"),
+            Type::Option(inner) => [
+                self.write_value(
+                    &Type::Bool,
+                    &format!("{lvalue}.{}", self.option_present_field_name()),
+                ),
+                self.write_value(
+                    inner,
+                    &format!("{lvalue}.{}", self.option_value_field_name()),
+                ),
+            ]
+            .join("\n"),
+            Type::Tuple(inner) => inner
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| {
+                    self.write_value(ty, &format!("{lvalue}.{}", self.tuple_argument_name(i)))
+                })
+                .join("\n"),
+            Type::Fixed(_) | Type::UFixed(_) | Type::Bytes => {
+                unreachable!("the type is rejected by the capability check")
+            }
+        }
+    }
+
+    fn read_value(&self, ty: &Type, lvalue: &str) -> String {
+        match ty {
+            Type::Int(bits) | Type::UInt(bits) => format!(
+                "{lvalue} = ({}) snapshot_read(&buffer, {});",
+                self.ty(ty.clone()),
+                bits / 8
+            ),
+            Type::Bool => format!("{lvalue} = snapshot_read(&buffer, 1) != 0;"),
+            Type::Float32 => {
+                format!("{lvalue} = float32_from_bits(snapshot_read(&buffer, 4));")
+            }
+            Type::Float64 => {
+                format!("{lvalue} = float64_from_bits(snapshot_read(&buffer, 8));")
+            }
+            Type::String => format!("{lvalue} = string_at(snapshot_read(&buffer, 4));"),
+            Type::Option(inner) => [
+                self.read_value(
+                    &Type::Bool,
+                    &format!("{lvalue}.{}", self.option_present_field_name()),
+                ),
+                self.read_value(
+                    inner,
+                    &format!("{lvalue}.{}", self.option_value_field_name()),
+                ),
+            ]
+            .join("\n"),
+            Type::Tuple(inner) => inner
+                .iter()
+                .enumerate()
+                .map(|(i, ty)| {
+                    self.read_value(ty, &format!("{lvalue}.{}", self.tuple_argument_name(i)))
+                })
+                .join("\n"),
+            Type::Fixed(_) | Type::UFixed(_) | Type::Bytes => {
+                unreachable!("the type is rejected by the capability check")
+            }
+        }
+    }
+
+    /// Returns the code applying `code` to each field, looping over the elements of arrays
+    fn for_each_field(&self, code: impl Fn(&Type, &str) -> String) -> String {
+        self.snapshot_fields()
+            .into_iter()
+            .map(|field| match field.len {
+                Some(len) => format!(
+                    "for (int i = 0; i < {len}; i++) {{\n{}\n}}",
+                    code(&field.ty, &field.lvalue)
+                ),
+                None => code(&field.ty, &field.lvalue),
+            })
+            .join("\n")
+    }
+
+    fn snapshot_buffer_argument(&self) -> Argument {
+        Argument::Normal("buffer".into(), CType::Lola(Type::UInt(8)).reference())
+    }
+}
+
+/// Returns the number of bytes of a snapshot
+struct SnapshotSize;
+
+impl FunctionDefinition for SnapshotSize {
+    fn name(&self, f: &CFormatter) -> String {
+        f.snapshot_size_function_name()
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Int)
+    }
+
+    fn body(self, _f: &CFormatter) -> String {
+        "return SNAPSHOT_SIZE;".into()
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::SnapshotSize
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::SnapshotSizeHeader, f.header_file()))
+    }
+}
+
+/// Writes the state of the monitor into a buffer of `SNAPSHOT_SIZE` bytes
+struct Snapshot;
+
+impl FunctionDefinition for Snapshot {
+    fn name(&self, f: &CFormatter) -> String {
+        f.snapshot_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            f.snapshot_buffer_argument(),
+        ]
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        [
+            "snapshot_write(&buffer, SNAPSHOT_VERSION, 4);".into(),
+            "snapshot_write(&buffer, MONITOR_SPEC_FINGERPRINT, 8);".into(),
+            f.for_each_field(|ty, lvalue| f.write_value(ty, lvalue)),
+        ]
+        .join("\n")
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Snapshot
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::SnapshotHeader, f.header_file()))
+    }
+}
+
+/// Restores the state of the monitor from a snapshot
+///
+/// Returns false and leaves the memory unchanged if the snapshot has a different size or version,
+/// or was taken from a monitor of a different specification.
+struct Restore;
+
+impl FunctionDefinition for Restore {
+    fn name(&self, f: &CFormatter) -> String {
+        f.restore_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            f.snapshot_buffer_argument(),
+            Argument::Normal("size".into(), CType::Int),
+        ]
+    }
+
+    fn returns(&self, _f: &CFormatter) -> Option<CType> {
+        Some(CType::Bool)
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        [
+            "if (size != SNAPSHOT_SIZE) return 0;".into(),
+            "if (snapshot_read(&buffer, 4) != SNAPSHOT_VERSION) return 0;".into(),
+            "if (snapshot_read(&buffer, 8) != MONITOR_SPEC_FINGERPRINT) return 0;".into(),
+            f.for_each_field(|ty, lvalue| f.read_value(ty, lvalue)),
+            "return 1;".into(),
+        ]
+        .join("\n")
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Restore
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::RestoreHeader, f.header_file()))
+    }
+}
+
+/// Resets the monitor to its initial state
+///
+/// The capacity and the overflow policy of unbounded memory are part of the generated code and are kept.
+struct Reset;
+
+impl FunctionDefinition for Reset {
+    fn name(&self, f: &CFormatter) -> String {
+        f.reset_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![MemoryStruct.into_argument(f).reference()]
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        f.call_function_stmt(InitMemory, &[MemoryStruct.argument_name(f)])
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Reset
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::ResetHeader, f.header_file()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        tests::generate,
+        unbounded::{OverflowPolicy, UnboundedMemory},
+    };
+
+    /// A monitor with bounded, floating point, string and optional memory
+    const STATE: &str = r#"streamir 1
+stream in[0] "a": Int64 = static bounded(2)
+stream out[0] "sum": Int64 = static bounded(2)
+stream out[1] "half": Float64 = static single
+stream out[2] "msg": String = static single
+stream out[3] "prev": Option<Int64> = static single
+stmt
+seq {
+    if @in[0] then
+        seq {
+            shift in[0];
+            input in[0];
+            shift out[0];
+            eval(0) out[0] = (in[0] + offset(out[0], 1, i64(0)));
+            eval(0) out[1] = (cast(Float64, in[0]) / f64(2));
+            eval(0) out[2] = if((in[0] > i64(3)), "large", "small");
+            eval(0) out[3] = offset(in[0], 1, (none :: Option<Int64>));
+        }
+    fi;
+}"#;
+
+    /// Prints the verdict of the monitor for an event of `a`
+    const PRINT: &str = r#"
+#include <stdio.h>
+#include "monitor.c"
+void step(Memory* m, double time, int64_t a) {
+    begin_event(m, time);
+    set_a(m, a);
+    Verdict v = commit_event(m);
+    printf("%lld %.1f %s %d %lld\n", (long long) v.sum, v.half, v.msg, v.prev.is_present, (long long) v.prev.value);
+}
+"#;
+
+    #[test]
+    fn size() {
+        let monitor = generate(STATE, None).unwrap();
+        // the header, a and sum with two values, valid flags and the current index, the single
+        // values with their valid flag, a fresh flag for each stream, and the time
+        let ring_buffer = 2 * 8 + 2 + 4;
+        let size = 12 + 2 * ring_buffer + (8 + 1) + (4 + 1) + (9 + 1) + 5 + 8;
+        assert!(monitor.header.contains(&format!(
+            "#define SNAPSHOT_VERSION 1\n#define SNAPSHOT_SIZE {size}"
+        )));
+    }
+
+    #[test]
+    fn string_index() {
+        let monitor = generate(STATE, None).unwrap();
+        assert!(monitor.source.contains(
+            "uint64_t string_index(char* value){\n\
+            if (value == STR_CONSTANT_0) return 1;\n\
+            if (value == STR_CONSTANT_1) return 2;\n\
+            return 0;\n}"
+        ));
+        assert!(monitor.source.contains(
+            "char* string_at(uint64_t index){\nswitch (index) {\n\
+            case 1: return STR_CONSTANT_0;\n\
+            case 2: return STR_CONSTANT_1;\n\
+            default:\nreturn NULL;\n}\n}"
+        ));
+    }
+
+    #[test]
+    fn restore() {
+        let monitor = generate(STATE, None).unwrap();
+        // the restored monitor continues where the original one was snapshotted, with the same string constants
+        let driver = format!(
+            r#"{PRINT}
+int main(void) {{
+    Memory m, restored;
+    init_memory(&m);
+    init_memory(&restored);
+    step(&m, 1, 5);
+    uint8_t buffer[SNAPSHOT_SIZE];
+    snapshot(&m, buffer);
+    printf("%d\n", restore(&restored, buffer, snapshot_size()));
+    step(&m, 2, 1);
+    step(&restored, 2, 1);
+    printf("%d\n", restored.singlevaluebuffer_msg.values == m.singlevaluebuffer_msg.values);
+    return 0;
+}}"#
+        );
+        assert_eq!(
+            monitor.stdout(&driver, &[]),
+            "5 2.5 large 0 0\n1\n6 0.5 small 1 5\n6 0.5 small 1 5\n1\n"
+        );
+    }
+
+    #[test]
+    fn restore_rejects() {
+        let monitor = generate(STATE, None).unwrap();
+        // a snapshot of a wrong size, version or fingerprint is rejected and leaves the memory unchanged
+        let driver = format!(
+            r#"{PRINT}
+int main(void) {{
+    Memory m;
+    init_memory(&m);
+    step(&m, 1, 5);
+    uint8_t buffer[SNAPSHOT_SIZE];
+    uint8_t before[SNAPSHOT_SIZE];
+    uint8_t after[SNAPSHOT_SIZE];
+    snapshot(&m, buffer);
+    Memory other;
+    init_memory(&other);
+    printf("%d ", restore(&other, buffer, SNAPSHOT_SIZE - 1));
+    buffer[0] ^= 1;
+    printf("%d ", restore(&other, buffer, SNAPSHOT_SIZE));
+    buffer[0] ^= 1;
+    buffer[4] ^= 1;
+    printf("%d\n", restore(&other, buffer, SNAPSHOT_SIZE));
+    snapshot(&other, after);
+    init_memory(&m);
+    snapshot(&m, before);
+    printf("%d\n", memcmp(before, after, SNAPSHOT_SIZE) == 0);
+    return 0;
+}}"#
+        );
+        assert_eq!(monitor.stdout(&driver, &[]), "5 2.5 large 0 0\n0 0 0\n1\n");
+    }
+
+    #[test]
+    fn reset() {
+        let monitor = generate(STATE, None).unwrap();
+        // after a reset, the monitor behaves and serializes as a new one
+        let driver = format!(
+            r#"{PRINT}
+int main(void) {{
+    Memory m, fresh;
+    init_memory(&m);
+    init_memory(&fresh);
+    step(&m, 1, 5);
+    step(&m, 2, 1);
+    reset(&m);
+    uint8_t reset_state[SNAPSHOT_SIZE];
+    uint8_t fresh_state[SNAPSHOT_SIZE];
+    snapshot(&m, reset_state);
+    snapshot(&fresh, fresh_state);
+    printf("%d\n", memcmp(reset_state, fresh_state, SNAPSHOT_SIZE) == 0);
+    step(&m, 3, 4);
+    return 0;
+}}"#
+        );
+        assert_eq!(
+            monitor.stdout(&driver, &[]),
+            "5 2.5 large 0 0\n6 0.5 small 1 5\n1\n4 2.0 large 0 0\n"
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let ir = r#"streamir 1
+stream in[0] "a": Int64 = static unbounded
+stream in[1] "b": Bool = static single
+stream out[0] "old": Int64 = static single
+stmt
+seq {
+    if @in[0] then
+        seq {
+            shift in[0];
+            input in[0];
+        }
+    fi;
+    if @in[1] then
+        eval(0) out[0] = offset(in[0], 2, i64(-1))
+    fi;
+}"#;
+        let unbounded = UnboundedMemory {
+            capacity: 2,
+            policy: OverflowPolicy::Error,
+        };
+        let monitor = generate(ir, Some(unbounded)).unwrap();
+        // the overflow flag and the number of dropped values are part of the snapshot
+        assert!(monitor
+            .source
+            .contains("snapshot_write(&buffer, (uint64_t) memory->unboundedbuffer_a.dropped, 4);"));
+        assert!(monitor
+            .source
+            .contains("snapshot_write(&buffer, memory->memory_overflow, 1);"));
+        let driver = r#"
+#include <stdio.h>
+#include "monitor.c"
+int main(void) {
+    Memory m, restored;
+    init_memory(&m);
+    init_memory(&restored);
+    for (int k = 0; k < 3; k++) {
+        begin_event(&m, k);
+        set_a(&m, k + 1);
+        commit_event(&m);
+    }
+    uint8_t buffer[SNAPSHOT_SIZE];
+    snapshot(&m, buffer);
+    printf("%d\n", restore(&restored, buffer, SNAPSHOT_SIZE));
+    begin_event(&restored, 3);
+    set_b(&restored, 1);
+    Verdict v = commit_event(&restored);
+    printf("%d %lld\n", v.memory_overflow, (long long) v.old);
+    return 0;
+}"#;
+        assert_eq!(monitor.stdout(driver, &[]), "1\n1 -1\n");
+    }
+}
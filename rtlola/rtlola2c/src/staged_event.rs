@@ -0,0 +1,243 @@
+//! An API for building the event of a cycle one input at a time.
+//!
+//! Instead of constructing an [InternalEvent] with a value and a present flag for each input,
+//! callers start an event with `begin_event`, set the inputs that received a new value with `set_<input>`,
+//! and evaluate the event with `commit_event`. The event is staged in the memory of the monitor,
+//! such that callers do not need to know the layout of the [InternalEvent] struct.
+
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::names::GetStreamName,
+    ir::{InputReference, StreamReference},
+};
+
+use crate::{
+    constructs::{Argument, FunctionDefinition, RequirementKey, StructDefinition},
+    io::{InternalEvent, VerdictStruct},
+    CFormatter, CType, MemoryStruct,
+};
+
+impl CFormatter {
+    /// Adds the functions staging and committing an event to the output
+    pub(crate) fn require_staged_event(&self) {
+        self.require_function(BeginEvent);
+        self.inputs().for_each(|sr| {
+            self.require_function(SetInput(sr.in_idx()));
+        });
+        self.require_function(CommitEvent);
+    }
+
+    /// The field of the memory holding the staged event
+    pub(crate) fn staged_event_argument(&self) -> Argument {
+        Argument::Normal(self.staged_event_field_name(), InternalEvent.as_ty(self))
+    }
+
+    fn staged_event(&self) -> String {
+        format!(
+            "{}->{}",
+            MemoryStruct.argument_name(self),
+            self.staged_event_field_name()
+        )
+    }
+}
+
+/// Starts a new event at the given time without any new input values
+struct BeginEvent;
+
+impl FunctionDefinition for BeginEvent {
+    fn name(&self, f: &CFormatter) -> String {
+        f.begin_event_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![MemoryStruct.into_argument(f).reference(), f.time_argument()]
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let event = f.staged_event();
+        f.inputs()
+            .map(|sr| format!("{event}.{} = 0;", f.internal_event_present_flag(sr)))
+            .chain(Some(format!(
+                "{event}.{time} = {time};",
+                time = f.time_argument_name()
+            )))
+            .join("\n")
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::BeginEvent
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::BeginEventHeader, f.header_file()))
+    }
+}
+
+/// Sets the new value of an input stream in the staged event
+struct SetInput(InputReference);
+
+impl FunctionDefinition for SetInput {
+    fn name(&self, f: &CFormatter) -> String {
+        f.set_input_function_name(StreamReference::In(self.0))
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            MemoryStruct.into_argument(f).reference(),
+            Argument::Normal(
+                f.new_value_argument_name(),
+                f.stream_ty(StreamReference::In(self.0)),
+            ),
+        ]
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        let sr = StreamReference::In(self.0);
+        format!(
+            "{event}.{value} = {new_value};\n{event}.{present} = 1;",
+            event = f.staged_event(),
+            value = f.stream_name(sr),
+            new_value = f.new_value_argument_name(),
+            present = f.internal_event_present_flag(sr)
+        )
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::SetInput(self.0)
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::SetInputHeader(self.0), f.header_file()))
+    }
+}
+
+/// Evaluates the monitor for the staged event and returns the verdict
+struct CommitEvent;
+
+impl FunctionDefinition for CommitEvent {
+    fn name(&self, f: &CFormatter) -> String {
+        f.commit_event_function_name()
+    }
+
+    fn arguments(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![MemoryStruct.into_argument(f).reference()]
+    }
+
+    fn returns(&self, f: &CFormatter) -> Option<CType> {
+        Some(VerdictStruct.as_ty(f))
+    }
+
+    fn body(self, f: &CFormatter) -> String {
+        format!(
+            "return cycle({}, {});",
+            MemoryStruct.argument_name(f),
+            f.staged_event()
+        )
+    }
+
+    fn key(&self) -> RequirementKey {
+        RequirementKey::CommitEvent
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn header_file(&self, f: &CFormatter) -> Option<(RequirementKey, PathBuf)> {
+        Some((RequirementKey::CommitEventHeader, f.header_file()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::generate;
+
+    const INPUTS: &str = r#"streamir 1
+stream in[0] "a": Int64 = static single
+stream in[1] "b": Float64 = static single
+stream out[0] "x": Int64 = static single
+stream out[1] "y": Float64 = static single
+stmt
+seq {
+    if @in[0] then
+        input in[0]
+    fi;
+    if @in[1] then
+        input in[1]
+    fi;
+    if @in[0] then
+        eval(0) out[0] = (in[0] * i64(2))
+    fi;
+    if (@in[0] && @in[1]) then
+        eval(0) out[1] = (cast(Float64, in[0]) + in[1])
+    fi;
+}"#;
+
+    #[test]
+    fn functions() {
+        let monitor = generate(INPUTS, None).unwrap();
+        for declaration in [
+            "void begin_event(Memory* memory, double time);",
+            "void set_a(Memory* memory, int64_t new_value);",
+            "void set_b(Memory* memory, double new_value);",
+            "Verdict commit_event(Memory* memory);",
+        ] {
+            assert!(monitor.header.contains(declaration), "{declaration}");
+        }
+        for definition in [
+            "void begin_event(Memory* memory, double time){\nmemory->staged_event.a_is_present = 0;\nmemory->staged_event.b_is_present = 0;\nmemory->staged_event.time = time;\n}",
+            "void set_a(Memory* memory, int64_t new_value){\nmemory->staged_event.a = new_value;\nmemory->staged_event.a_is_present = 1;\n}",
+            "Verdict commit_event(Memory* memory){\nreturn cycle(memory, memory->staged_event);\n}",
+        ] {
+            assert!(monitor.source.contains(definition), "{definition}");
+        }
+    }
+
+    #[test]
+    fn partial_events() {
+        let monitor = generate(INPUTS, None).unwrap();
+        // each event only contains the inputs set since it began, setting an input twice keeps the last value
+        let driver = r#"
+#include <stdio.h>
+#include "monitor.c"
+void print(Verdict v) {
+    printf("%.1f %d", v.time, v.x_is_present);
+    if (v.x_is_present) printf(" x=%lld", (long long) v.x);
+    printf(" %d", v.y_is_present);
+    if (v.y_is_present) printf(" y=%.1f", v.y);
+    printf("\n");
+}
+int main(void) {
+    Memory m;
+    init_memory(&m);
+    begin_event(&m, 1.0);
+    set_a(&m, 1);
+    print(commit_event(&m));
+    begin_event(&m, 2.0);
+    set_b(&m, 0.5);
+    set_a(&m, 2);
+    print(commit_event(&m));
+    begin_event(&m, 3.0);
+    set_a(&m, 3);
+    set_a(&m, 4);
+    print(commit_event(&m));
+    begin_event(&m, 4.0);
+    print(commit_event(&m));
+    return 0;
+}"#;
+        assert_eq!(
+            monitor.stdout(driver, &[]),
+            "1.0 1 x=2 0\n2.0 1 x=4 1 y=2.5\n3.0 1 x=8 0\n4.0 0 0\n"
+        );
+    }
+}
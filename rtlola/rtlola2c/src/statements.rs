@@ -11,12 +11,13 @@ use eval::EvalFunction;
 use input::InputFunction;
 use rtlola_streamir::{
     formatter::{
+        expressions::ExprFormatter,
         guards::GuardFormatter,
         statements::{DefaultStmtFormatter, StmtFormatter},
     },
     ir::{
         expressions::Expr, Guard, InputReference, LocalFreqRef, OutputReference, Stmt,
-        StreamReference, WindowReference,
+        StreamReference, TempRef, WindowReference,
     },
 };
 use shift::ShiftFunction;
@@ -26,7 +27,7 @@ use crate::{
     constructs::{Argument, FunctionDefinition, RequirementKey, StructDefinition},
     io::{InternalEvent, NewVerdict, VerdictStruct},
     memory::ClearActivation,
-    CFormatter, MemoryStruct,
+    CFormatter, CType, MemoryStruct,
 };
 
 pub(crate) struct CycleFunction(pub Stmt);
@@ -105,10 +106,12 @@ impl DefaultStmtFormatter for CFormatter {
         local_frequencies: Vec<LocalFreqRef>,
         windows: Vec<WindowReference>,
     ) -> String {
-        assert!(local_frequencies.is_empty());
-        assert!(windows.is_empty());
+        assert!(
+            local_frequencies.is_empty() && windows.is_empty(),
+            "local frequencies and windows are rejected by the capability check"
+        );
         if let Some(_with) = with {
-            unimplemented!()
+            unreachable!("parameterized streams are rejected by the capability check")
         } else {
             format!(
                 "if (!{}) {}",
@@ -124,7 +127,22 @@ impl DefaultStmtFormatter for CFormatter {
             expr: with,
             i: idx,
         };
-        self.call_function_stmt(eval, &[MemoryStruct.argument_name(self)])
+        let arguments = std::iter::once(MemoryStruct.argument_name(self))
+            .chain(
+                eval.expr
+                    .temporaries()
+                    .into_iter()
+                    .map(|(temp, _)| self.temporary_name(temp)),
+            )
+            .collect::<Vec<_>>();
+        self.call_function_stmt(eval, &arguments)
+    }
+
+    fn r#let(&self, temp: TempRef, expr: Expr) -> String {
+        self.variable_declaration_with_initialization(
+            Argument::Normal(self.temporary_name(temp), CType::Lola(expr.ty.clone())),
+            self.expr(expr),
+        )
     }
 
     fn close(
@@ -148,7 +166,7 @@ impl DefaultStmtFormatter for CFormatter {
     }
 
     fn iterate(&self, _sr: Vec<OutputReference>, _inner: Stmt) -> String {
-        todo!()
+        unreachable!("parameterized streams are rejected by the capability check")
     }
 
     fn assign(
@@ -157,6 +175,6 @@ impl DefaultStmtFormatter for CFormatter {
         _parameter_expr: Vec<Expr>,
         _inner: Stmt,
     ) -> String {
-        todo!()
+        unreachable!("parameterized streams are rejected by the capability check")
     }
 }
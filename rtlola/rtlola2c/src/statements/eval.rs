@@ -1,3 +1,4 @@
+use itertools::Itertools;
 use rtlola_streamir::{
     formatter::{expressions::ExprFormatter, names::GetStreamName},
     ir::{expressions::Expr, OutputReference},
@@ -20,16 +21,33 @@ impl FunctionDefinition for EvalFunction {
     }
 
     fn body(self, f: &crate::CFormatter) -> String {
-        [
-            f.variable_declaration_with_initialization(
-                Argument::Normal("new_value".into(), CType::Lola(self.expr.ty.clone())),
-                f.expr(self.expr.clone()),
-            ),
-            f.static_buffer(self.sr.sr())
-                .unwrap()
-                .push_value("new_value".into(), f),
-        ]
-        .join("\n")
+        let sr = self.sr.sr();
+        let delay = (f.delay(sr) > 0).then(|| {
+            // remember the time of the activation and skip the evaluations before the first one with a value
+            let memory = MemoryStruct.argument_name(f);
+            let evaluations = f.delay_evaluations_field_name(sr);
+            format!(
+                "{memory}->{times}[{memory}->{evaluations} % {size}] = {memory}->{time};\n\
+                {memory}->{evaluations}++;\n\
+                if ({memory}->{evaluations} <= {delay}) return;",
+                times = f.delay_times_field_name(sr),
+                size = f.delay(sr) + 1,
+                time = f.time_argument_name(),
+                delay = f.delay(sr)
+            )
+        });
+        delay
+            .into_iter()
+            .chain([
+                f.variable_declaration_with_initialization(
+                    Argument::Normal("new_value".into(), CType::Lola(self.expr.ty.clone())),
+                    f.expr(self.expr.clone()),
+                ),
+                f.static_buffer(sr)
+                    .unwrap()
+                    .push_value("new_value".into(), f),
+            ])
+            .join("\n")
     }
 
     fn key(&self) -> RequirementKey {
@@ -37,7 +55,14 @@ impl FunctionDefinition for EvalFunction {
     }
 
     fn arguments(&self, f: &crate::CFormatter) -> Vec<crate::constructs::Argument> {
-        vec![MemoryStruct.into_argument(f).reference()]
+        std::iter::once(MemoryStruct.into_argument(f).reference())
+            .chain(
+                self.expr
+                    .temporaries()
+                    .into_iter()
+                    .map(|(temp, ty)| Argument::Normal(f.temporary_name(temp), CType::Lola(ty))),
+            )
+            .collect()
     }
 
     fn file(&self, f: &crate::CFormatter) -> std::path::PathBuf {
@@ -13,7 +13,9 @@ impl FunctionDefinition for ShiftFunction {
     }
 
     fn body(self, f: &crate::CFormatter) -> String {
-        f.static_buffer(self.0).unwrap().shift_code(f)
+        f.static_buffer(self.0)
+            .map(|buffer| buffer.shift_code(f))
+            .unwrap_or_default()
     }
 
     fn key(&self) -> crate::constructs::RequirementKey {
@@ -24,7 +24,9 @@ impl CType {
     pub(crate) fn lola(self) -> Type {
         match self {
             CType::Lola(ty) => ty,
-            _ => unreachable!(),
+            _ => unreachable!(
+                "only the C types of streams are converted back to types of the StreamIR"
+            ),
         }
     }
 }
@@ -75,8 +77,11 @@ impl TypeFormatter for CFormatter {
         "double".into()
     }
 
-    fn type_option(&self, _inner: Type) -> Self::Return {
-        unimplemented!()
+    fn type_option(&self, inner: Type) -> Self::Return {
+        let option_struct = OptionType(inner);
+        let name = option_struct.struct_name(self);
+        self.require_struct(option_struct);
+        name
     }
 
     fn type_tuple(&self, inner: Vec<Type>) -> Self::Return {
@@ -87,15 +92,15 @@ impl TypeFormatter for CFormatter {
     }
 
     fn type_fixed(&self, _bits: u16) -> Self::Return {
-        unimplemented!()
+        unreachable!("fixed point types are rejected by the capability check")
     }
 
     fn type_ufixed(&self, _bits: u16) -> Self::Return {
-        unimplemented!()
+        unreachable!("fixed point types are rejected by the capability check")
     }
 
     fn type_bytes(&self) -> Self::Return {
-        unimplemented!()
+        unreachable!("bytes are rejected by the capability check")
     }
 }
 
@@ -122,3 +127,48 @@ impl StructDefinition for TupleType {
             .collect()
     }
 }
+
+/// The struct representing optional values of the inner type
+pub(crate) struct OptionType(pub(crate) Type);
+
+impl StructDefinition for OptionType {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::OptionStruct(self.0.clone())
+    }
+
+    fn file(&self, f: &CFormatter) -> PathBuf {
+        f.header_file()
+    }
+
+    fn struct_name(&self, f: &CFormatter) -> String {
+        f.option_struct_name(&self.0)
+    }
+
+    fn fields(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            Argument::Normal(f.option_present_field_name(), CType::Bool),
+            Argument::Normal(f.option_value_field_name(), CType::Lola(self.0.to_owned())),
+        ]
+    }
+}
+
+impl OptionType {
+    /// Returns the expression constructing the present optional value
+    pub(crate) fn some(self, value: String, f: &CFormatter) -> String {
+        format!(
+            "({}){{.{}=true, .{}={value}}}",
+            self.struct_name(f),
+            f.option_present_field_name(),
+            f.option_value_field_name()
+        )
+    }
+
+    /// Returns the expression constructing the absent optional value
+    pub(crate) fn none(self, f: &CFormatter) -> String {
+        format!(
+            "({}){{.{}=false}}",
+            self.struct_name(f),
+            f.option_present_field_name()
+        )
+    }
+}
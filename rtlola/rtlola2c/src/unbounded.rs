@@ -0,0 +1,391 @@
+//! The memory of streams whose values are required without a bound.
+//!
+//! As the generated code does not allocate memory dynamically, unbounded memory is stored in a ring buffer
+//! with a fixed capacity. Once the buffer is full, each new value drops the oldest one,
+//! which is handled according to the [OverflowPolicy].
+//! Reading a value beyond the capacity returns the default of the access.
+//! Under [OverflowPolicy::Error], such a read also sets the `memory_overflow` flag if the value existed and was dropped.
+
+use clap::ValueEnum;
+use rtlola_streamir::{
+    formatter::{files::FilesFormatter, names::GetStreamName},
+    ir::{memory::StreamBuffer, StreamReference},
+};
+
+use crate::{
+    constructs::{Argument, RequirementKey, StructDefinition},
+    memory::StaticBufferTrait,
+    CFormatter, CType, MemoryStruct,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+/// What happens when a value is dropped from the memory of a stream with unbounded memory
+pub enum OverflowPolicy {
+    /// The oldest value is silently dropped
+    DropOldest,
+    /// The oldest value is dropped and the `memory_overflow` flag of the verdict is set,
+    /// both when the value is dropped and when it is read afterwards
+    Error,
+    /// The monitor prints an error and aborts
+    Abort,
+}
+
+impl OverflowPolicy {
+    fn define(&self) -> &'static str {
+        match self {
+            OverflowPolicy::DropOldest => "OVERFLOW_POLICY_DROP_OLDEST",
+            OverflowPolicy::Error => "OVERFLOW_POLICY_ERROR",
+            OverflowPolicy::Abort => "OVERFLOW_POLICY_ABORT",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+/// How the memory of streams with unbounded memory is stored
+pub struct UnboundedMemory {
+    /// The number of values stored for each stream
+    pub capacity: usize,
+    /// What happens when the capacity is exceeded
+    pub policy: OverflowPolicy,
+}
+
+impl CFormatter {
+    /// Whether the memory of one of the streams is unbounded
+    pub(crate) fn has_unbounded_memory(&self) -> bool {
+        self.sr2memory
+            .values()
+            .any(|m| matches!(m.buffer.buffer(), Some(StreamBuffer::UnBounded)))
+    }
+
+    /// Whether the verdict reports that values were dropped from unbounded memory
+    pub(crate) fn reports_overflow(&self) -> bool {
+        self.has_unbounded_memory()
+            && self
+                .unbounded
+                .is_some_and(|u| u.policy == OverflowPolicy::Error)
+    }
+
+    pub(crate) fn overflow_argument(&self) -> Argument {
+        Argument::Normal(self.overflow_field_name(), CType::Bool)
+    }
+
+    /// Adds the capacity and the overflow policy of unbounded memory to the header
+    pub(crate) fn require_unbounded_defines(&self) {
+        let Some(unbounded) = self.unbounded else {
+            return;
+        };
+        if self.has_unbounded_memory() {
+            self.add_requirement_string(
+                self.header_file(),
+                RequirementKey::UnboundedMemoryDefines,
+                format!(
+                    "#define UNBOUNDED_CAPACITY {}\n#define {}",
+                    unbounded.capacity,
+                    unbounded.policy.define()
+                ),
+            );
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct UnboundedBuffer(pub(crate) StreamReference, pub(crate) UnboundedMemory);
+
+impl StructDefinition for UnboundedBuffer {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::StaticStreamMemory(self.0)
+    }
+
+    fn file(&self, f: &CFormatter) -> std::path::PathBuf {
+        f.header_file()
+    }
+
+    fn struct_name(&self, f: &CFormatter) -> String {
+        format!("UnboundedBuffer_{}", f.stream_name(self.0))
+    }
+
+    fn fields(&self, f: &CFormatter) -> Vec<Argument> {
+        vec![
+            Argument::Array(
+                f.values_argument_name(),
+                f.stream_ty(self.0),
+                self.1.capacity,
+            ),
+            Argument::Array(f.valid_argument_name(), CType::Bool, self.1.capacity),
+            Argument::Normal(f.current_argument_name(), CType::Int),
+            Argument::Normal(f.is_fresh_argument_name(), CType::Bool),
+        ]
+        .into_iter()
+        // the number of dropped values, to recognize reads of dropped values
+        .chain(
+            (self.1.policy == OverflowPolicy::Error)
+                .then(|| Argument::Normal(f.dropped_argument_name(), CType::Int)),
+        )
+        .collect()
+    }
+}
+
+impl UnboundedBuffer {
+    /// Returns the code handling that the value at the current position is dropped
+    fn overflow(&self, f: &CFormatter) -> String {
+        let buffer = f.buffer(self.0).unwrap();
+        let valid = format!(
+            "{buffer}.{}[{buffer}.{}]",
+            f.valid_argument_name(),
+            f.current_argument_name()
+        );
+        match self.1.policy {
+            OverflowPolicy::DropOldest => String::new(),
+            OverflowPolicy::Error => {
+                f.import(f.monitor_file(), "limits");
+                format!(
+                    "if ({valid}) {{\n{memory}->{overflow} = 1;\nif ({buffer}.{dropped} < INT_MAX)\n{buffer}.{dropped}++;\n}}\n",
+                    memory = MemoryStruct.argument_name(f),
+                    overflow = f.overflow_field_name(),
+                    dropped = f.dropped_argument_name()
+                )
+            }
+            OverflowPolicy::Abort => {
+                f.import(f.monitor_file(), "stdio");
+                f.import(f.monitor_file(), "stdlib");
+                format!(
+                    "if ({valid}) {{\nfprintf(stderr, \"the memory of stream {name} exceeds its capacity of %d values\\n\", UNBOUNDED_CAPACITY);\nabort();\n}}\n",
+                    name = f.stream_name(self.0)
+                )
+            }
+        }
+    }
+}
+
+impl StaticBufferTrait for UnboundedBuffer {
+    fn shift_code(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{current} = ({buffer}.{current} + 1) % {size};",
+            size = self.1.capacity,
+            current = f.current_argument_name(),
+            buffer = f.buffer(self.0).unwrap()
+        )
+    }
+
+    fn push_value(&self, v: String, f: &CFormatter) -> String {
+        format!(
+            "{overflow}{buffer}.{values}[{buffer}.{current}] = {v};\n\
+            {buffer}.{valid}[{buffer}.{current}] = 1;\n\
+            {buffer}.{fresh} = 1;",
+            overflow = self.overflow(f),
+            valid = f.valid_argument_name(),
+            current = f.current_argument_name(),
+            values = f.values_argument_name(),
+            buffer = f.buffer(self.0).unwrap(),
+            fresh = f.is_fresh_argument_name()
+        )
+    }
+
+    fn get_value(
+        &self,
+        offset: String,
+        default: String,
+        wrap: &dyn Fn(String) -> String,
+        f: &CFormatter,
+    ) -> String {
+        let buffer = f.buffer(self.0).unwrap();
+        // values beyond the capacity are not stored, they have been dropped if the buffer has dropped more values than they are beyond the capacity
+        let dropped = match self.1.policy {
+            OverflowPolicy::Error => format!(
+                "if ({offset} - {size} < {buffer}.{dropped})\n{memory}->{overflow} = 1;\n",
+                size = self.1.capacity,
+                dropped = f.dropped_argument_name(),
+                memory = MemoryStruct.argument_name(f),
+                overflow = f.overflow_field_name()
+            ),
+            // under Abort, the monitor aborts before a value is dropped
+            OverflowPolicy::DropOldest | OverflowPolicy::Abort => String::new(),
+        };
+        format!(
+            "if ({offset} >= {size}) {{\n{dropped}return {default};\n}}\nint i = ({buffer}.{current} - {offset} + {size}) % {size};\nif ({buffer}.{valid}[i])\nreturn {value};\nelse\nreturn {default};",
+            size = self.1.capacity,
+            current = f.current_argument_name(),
+            valid = f.valid_argument_name(),
+            value = wrap(format!("{buffer}.{}[i]", f.values_argument_name()))
+        )
+    }
+
+    fn is_fresh(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{is_fresh}",
+            buffer = f.buffer(self.0).unwrap(),
+            is_fresh = f.is_fresh_argument_name()
+        )
+    }
+
+    fn reset_fresh(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{is_fresh} = 0;",
+            buffer = f.buffer(self.0).unwrap(),
+            is_fresh = f.is_fresh_argument_name()
+        )
+    }
+
+    fn sync_access(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{values}[{buffer}.{current}]",
+            buffer = f.buffer(self.0).unwrap(),
+            current = f.current_argument_name(),
+            values = f.values_argument_name()
+        )
+    }
+
+    fn is_valid(&self, f: &CFormatter) -> String {
+        format!(
+            "{buffer}.{valid}[{buffer}.{current}]",
+            buffer = f.buffer(self.0).unwrap(),
+            current = f.current_argument_name(),
+            valid = f.valid_argument_name()
+        )
+    }
+
+    fn as_argument(&self, f: &CFormatter) -> Argument {
+        <Self as StructDefinition>::into_argument(self.to_owned(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OverflowPolicy, UnboundedMemory};
+    use crate::{
+        capabilities::Feature,
+        tests::{generate, Monitor},
+        FormatError,
+    };
+
+    /// The stream `a` has unbounded memory, `old` reads a value that was dropped after three values of `a`
+    /// and `older` reads a value that did not exist after three values of `a`
+    const UNBOUNDED: &str = r#"streamir 1
+stream in[0] "a": Int64 = static unbounded
+stream in[1] "b": Bool = static single
+stream in[2] "c": Bool = static single
+stream out[0] "old": Int64 = static single
+stream out[1] "older": Int64 = static single
+stmt
+seq {
+    if @in[0] then
+        seq {
+            shift in[0];
+            input in[0];
+        }
+    fi;
+    if @in[1] then
+        eval(0) out[0] = offset(in[0], 2, i64(-1))
+    fi;
+    if @in[2] then
+        eval(0) out[1] = offset(in[0], 3, i64(-1))
+    fi;
+}"#;
+
+    fn generate_with(policy: OverflowPolicy) -> Monitor {
+        generate(
+            UNBOUNDED,
+            Some(UnboundedMemory {
+                capacity: 2,
+                policy,
+            }),
+        )
+        .unwrap()
+    }
+
+    /// Drives the monitor with three values of `a` and then `b` and `c` separately,
+    /// printing the overflow flag of each verdict
+    const DRIVER: &str = r#"
+#include <stdio.h>
+#include "monitor.c"
+int main(void) {
+    Memory m;
+    init_memory(&m);
+    for (int k = 0; k < 3; k++) {
+        begin_event(&m, k);
+        set_a(&m, k + 1);
+        Verdict v = commit_event(&m);
+        printf("%d\n", v.memory_overflow);
+    }
+    begin_event(&m, 3);
+    set_b(&m, 1);
+    Verdict v = commit_event(&m);
+    printf("%d %lld\n", v.memory_overflow, (long long) v.old);
+    begin_event(&m, 4);
+    set_c(&m, 1);
+    v = commit_event(&m);
+    printf("%d %lld\n", v.memory_overflow, (long long) v.older);
+    return 0;
+}"#;
+
+    #[test]
+    fn rejected_without_capacity() {
+        let Err(FormatError::Unsupported(unsupported)) = generate(UNBOUNDED, None) else {
+            panic!("unbounded memory without a capacity is rejected")
+        };
+        let features = unsupported
+            .0
+            .into_iter()
+            .map(|u| u.feature)
+            .collect::<Vec<_>>();
+        assert_eq!(features, [Feature::UnboundedMemory]);
+    }
+
+    #[test]
+    fn defines() {
+        let monitor = generate_with(OverflowPolicy::Error);
+        assert!(monitor
+            .header
+            .contains("#define UNBOUNDED_CAPACITY 2\n#define OVERFLOW_POLICY_ERROR"));
+        assert!(monitor.header.contains(
+            "typedef struct {\nint64_t values[2];\nbool valid[2];\nint current;\nbool is_fresh;\nint dropped;\n} UnboundedBuffer_a;"
+        ));
+    }
+
+    #[test]
+    fn overflow_field() {
+        let monitor = generate_with(OverflowPolicy::Error);
+        for name in ["Memory", "Verdict"] {
+            let end = monitor.header.find(&format!("}} {name};")).unwrap();
+            let start = monitor.header[..end].rfind("typedef struct {").unwrap();
+            assert!(
+                monitor.header[start..end].contains("bool memory_overflow;"),
+                "{name}"
+            );
+        }
+        for policy in [OverflowPolicy::DropOldest, OverflowPolicy::Abort] {
+            let monitor = generate_with(policy);
+            assert!(!monitor.header.contains("memory_overflow"), "{policy:?}");
+            assert!(!monitor.header.contains("dropped"), "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn error() {
+        // the third value drops the first one, which is read afterwards by `old`,
+        // while `older` reads a value that never existed
+        let monitor = generate_with(OverflowPolicy::Error);
+        assert_eq!(monitor.stdout(DRIVER, &[]), "0\n0\n1\n1 -1\n0 -1\n");
+    }
+
+    #[test]
+    fn drop_oldest() {
+        let monitor = generate_with(OverflowPolicy::DropOldest);
+        let driver = DRIVER.replace("v.memory_overflow", "0");
+        assert_eq!(monitor.stdout(&driver, &[]), "0\n0\n0\n0 -1\n0 -1\n");
+    }
+
+    #[test]
+    fn abort() {
+        let monitor = generate_with(OverflowPolicy::Abort);
+        assert!(monitor
+            .header
+            .contains("#define UNBOUNDED_CAPACITY 2\n#define OVERFLOW_POLICY_ABORT"));
+        let output = monitor.run(&DRIVER.replace("v.memory_overflow", "0"), &[]);
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stderr).unwrap(),
+            "the memory of stream a exceeds its capacity of 2 values\n"
+        );
+    }
+}
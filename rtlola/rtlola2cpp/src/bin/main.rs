@@ -0,0 +1,122 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use clap::{Parser, ValueEnum};
+use itertools::Itertools;
+use rtlola2cpp::CppFormatter;
+use rtlola_streamir::formatter::StreamIrFormatter;
+use rtlola_streamir::ir::{json, StreamIr, StreamReference};
+use rtlola_streamir::rewrite_rules::{CommonSubexpressions, InlineStreams};
+use rtlola_streamir::{optimize_all, parse, ParserConfig};
+
+#[derive(Parser)]
+struct Args {
+    /// The path to the specification or to a StreamIR serialized as JSON (with the extension `.json`)
+    spec: PathBuf,
+    #[clap(long)]
+    /// Whether to overwrite existing files
+    overwrite: bool,
+    /// Whether to optimize the StreamIR
+    #[clap(short, long)]
+    optimize: bool,
+    #[clap(long, default_value = ".")]
+    output_dir: PathBuf,
+    #[clap(long)]
+    output_streams: Vec<String>,
+    #[clap(short, long, default_value_t=Verbosity::Outputs, value_enum)]
+    verbosity: Verbosity,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Verbosity {
+    Streams,
+    Outputs,
+    Trigger,
+    Silent,
+}
+
+fn load(spec: &Path) -> anyhow::Result<StreamIr> {
+    if spec.extension().is_some_and(|ext| ext == "json") {
+        let json = std::fs::read_to_string(spec).context("reading StreamIR file")?;
+        return json::from_json(&json).context("deserializing StreamIR from JSON");
+    }
+    let config =
+        ParserConfig::from_path(spec.to_path_buf()).context("loading specification file")?;
+    parse(&config).context("parsing specification to StreamIR")
+}
+
+fn prepare(
+    ir: StreamIr,
+    optimize: bool,
+    output_streams: Vec<String>,
+    verbosity: Verbosity,
+) -> anyhow::Result<(StreamIr, Vec<StreamReference>)> {
+    let ir = if optimize {
+        optimize_all(ir).context("optimizing StreamIR")?
+    } else {
+        ir
+    };
+
+    let verdict_streams: Vec<StreamReference> = if !output_streams.is_empty() {
+        output_streams
+            .iter()
+            .flat_map(|s| s.split(','))
+            .map(|s| s.trim())
+            .map(|stream_name| {
+                ir.sr2memory
+                    .iter()
+                    .find_map(|(sr, m)| (m.name == stream_name).then_some(*sr))
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("stream {stream_name} does not exist in the specification")
+                    })
+            })
+            .collect::<anyhow::Result<_>>()
+            .context("finding output streams")?
+    } else {
+        match verbosity {
+            Verbosity::Silent => Vec::new(),
+            Verbosity::Trigger => ir.triggers().sorted().map(StreamReference::Out).collect(),
+            Verbosity::Outputs => ir.outputs().sorted().map(StreamReference::Out).collect(),
+            Verbosity::Streams => ir.streams().sorted().collect(),
+        }
+    };
+
+    let ir = if optimize {
+        let inline = InlineStreams::new(&ir, &verdict_streams);
+        let ir =
+            rtlola_streamir::optimize(ir, vec![Box::new(inline)]).context("inlining streams")?;
+        let cse = CommonSubexpressions::new(&ir);
+        rtlola_streamir::optimize(ir, vec![Box::new(cse)])
+            .context("eliminating common subexpressions")?
+    } else {
+        ir
+    };
+    Ok((ir, verdict_streams))
+}
+
+fn generate(
+    ir: StreamIr,
+    verdict_streams: Vec<StreamReference>,
+    overwrite: bool,
+    output_dir: PathBuf,
+) -> anyhow::Result<()> {
+    let formatter = CppFormatter::new(&ir, overwrite, verdict_streams, output_dir);
+    formatter
+        .format(ir)
+        .context("formatting StreamIR as C++ code")
+}
+
+fn main() -> anyhow::Result<()> {
+    let Args {
+        spec,
+        overwrite,
+        optimize,
+        output_dir,
+        output_streams,
+        verbosity,
+    } = Args::parse();
+    let ir = load(&spec)?;
+    let (ir, verdict_streams) =
+        prepare(ir, optimize, output_streams, verbosity).context("generating C++ code")?;
+    generate(ir, verdict_streams, overwrite, output_dir).context("generating C++ code")
+}
@@ -0,0 +1,53 @@
+//! The capabilities of the C++ backend, which are checked with the shared check of the StreamIR
+//! (see [rtlola_streamir::capabilities]) before the formatting.
+
+use rtlola_streamir::{
+    capabilities::Capabilities,
+    ir::{
+        expressions::{Function, Operator},
+        StreamIr, Type,
+    },
+};
+
+pub use rtlola_streamir::capabilities::{
+    Feature, FeatureLocation, UnsupportedFeature, UnsupportedFeatures,
+};
+
+/// The features supported by the C++ backend
+struct CppCapabilities;
+
+impl Capabilities for CppCapabilities {
+    fn backend(&self) -> &'static str {
+        "C++"
+    }
+
+    fn supports_type(&self, ty: &Type) -> bool {
+        !matches!(ty, Type::Fixed(_) | Type::UFixed(_) | Type::Bytes)
+    }
+
+    fn supports_function(&self, function: Function, ty: &Type) -> bool {
+        match function {
+            Function::Sqrt
+            | Function::Sin
+            | Function::Arcsin
+            | Function::Cos
+            | Function::Arccos
+            | Function::Tan
+            | Function::Arctan => matches!(ty, Type::Float64 | Type::Float32),
+            Function::Abs => matches!(ty, Type::Float64 | Type::Float32 | Type::Int(_)),
+            Function::Min | Function::Max => matches!(
+                ty,
+                Type::Float64 | Type::Float32 | Type::Int(_) | Type::UInt(_)
+            ),
+        }
+    }
+
+    fn supports_operator(&self, op: Operator, lhs: &Type, rhs: &Type) -> bool {
+        op != Operator::Pow || (matches!(lhs, Type::Float64 | Type::Float32) && lhs == rhs)
+    }
+}
+
+/// Checks that the C++ backend can compile the StreamIR.
+pub fn check(ir: &StreamIr) -> Result<(), UnsupportedFeatures> {
+    rtlola_streamir::capabilities::check(ir, &CppCapabilities)
+}
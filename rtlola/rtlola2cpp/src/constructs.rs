@@ -0,0 +1,115 @@
+use std::path::PathBuf;
+
+use rtlola_streamir::formatter::files::{ConstructStore, FilesFormatter};
+
+use crate::CppFormatter;
+
+impl FilesFormatter for CppFormatter {
+    type Key = RequirementKey;
+
+    fn get_construct_store(&self) -> &ConstructStore<Self> {
+        &self.construct_store
+    }
+
+    fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum RequirementKey {
+    Header,
+
+    // Internals
+    DetailStart,
+    Buffer,
+    Deadlines,
+    DetailEnd,
+
+    // Public API
+    EventStruct,
+    TriggerEnum,
+    VerdictStruct,
+    MonitorClass,
+    Cycle,
+
+    Footer,
+}
+
+impl CppFormatter {
+    pub(crate) fn header_file(&self) -> PathBuf {
+        self.output_dir.join("monitor.hpp")
+    }
+
+    /// Adds a construct of the header, indented according to the nesting of its braces
+    pub(crate) fn add_construct(&self, key: RequirementKey, construct: &str) {
+        self.add_requirement_string(self.header_file(), key, indent(construct));
+    }
+}
+
+/// Indents each line of the code by the number of braces that are open at its start,
+/// ignoring the braces in literals and comments.
+///
+/// Namespaces do not indent their contents and access specifiers are indented one level less than the members.
+pub(crate) fn indent(code: &str) -> String {
+    let mut depth = 0usize;
+    let mut result = String::new();
+    for line in code.lines().map(str::trim) {
+        if line.is_empty() {
+            result.push('\n');
+            continue;
+        }
+        let namespace = line.starts_with("namespace ") || line.starts_with("} // namespace");
+        let mut opened = 0usize;
+        let mut closed_first = 0usize;
+        let mut closed = 0usize;
+        let mut chars = line.chars().peekable();
+        let mut leading = true;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' | '\'' => {
+                    leading = false;
+                    while let Some(next) = chars.next() {
+                        match next {
+                            '\\' => {
+                                chars.next();
+                            }
+                            next if next == c => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '/' if chars.peek() == Some(&'/') => break,
+                '{' => {
+                    leading = false;
+                    opened += 1;
+                }
+                '}' => {
+                    if opened > 0 {
+                        opened -= 1;
+                    } else {
+                        closed += 1;
+                        if leading {
+                            closed_first += 1;
+                        }
+                    }
+                }
+                _ => leading = false,
+            }
+        }
+        if namespace {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        }
+        let mut line_depth = depth.saturating_sub(closed_first);
+        if matches!(line, "public:" | "private:" | "protected:") {
+            line_depth = line_depth.saturating_sub(1);
+        }
+        result.push_str(&"    ".repeat(line_depth));
+        result.push_str(line);
+        result.push('\n');
+        depth = depth.saturating_sub(closed) + opened;
+    }
+    result
+}
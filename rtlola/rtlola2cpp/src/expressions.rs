@@ -0,0 +1,260 @@
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{
+        expressions::{
+            DefaultConstantFormatter, DefaultExprFormatter, DefaultFunctionFormatter,
+            DefaultOperatorFormatter, ExprFormatter, OperatorFormatter,
+        },
+        types::TypeFormatter,
+    },
+    ir::{
+        expressions::{Constant, Expr, Operator},
+        StreamReference, TempRef, Type, WindowReference,
+    },
+};
+
+use crate::{types::integer_bits, CppFormatter};
+
+impl DefaultExprFormatter for CppFormatter {
+    fn sync_access(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        format!("{}.sync()", self.buffer(sr).unwrap())
+    }
+
+    fn offset_access(
+        &self,
+        sr: StreamReference,
+        offset: u32,
+        default: Expr,
+        parameters: Vec<Expr>,
+    ) -> String {
+        assert!(parameters.is_empty());
+        let value = format!("{}.offset({offset})", self.buffer(sr).unwrap());
+        self.or_default(sr, value, default)
+    }
+
+    fn hold_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        <Self as DefaultExprFormatter>::offset_access(self, sr, 0, default, parameters)
+    }
+
+    fn get_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        let value = format!("{}.get()", self.buffer(sr).unwrap());
+        self.or_default(sr, value, default)
+    }
+
+    fn is_fresh(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        format!("{}.is_fresh()", self.buffer(sr).unwrap())
+    }
+
+    fn sliding_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("sliding windows are rejected by the capability check")
+    }
+
+    fn discrete_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("discrete windows are rejected by the capability check")
+    }
+
+    fn instance_aggregation(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn parameter_access(&self, _sr: StreamReference, _p: usize) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+
+    fn lambda_parameter_access(&self, _wref: WindowReference, _idx: usize) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn temporary(&self, temp: TempRef, _ty: &Type) -> String {
+        self.temporary_name(temp)
+    }
+
+    fn cast(&self, ty: Type, expr: Expr) -> String {
+        format!("static_cast<{}>({})", self.ty(ty), self.expr(expr))
+    }
+
+    fn tuple(&self, inner: Vec<Expr>) -> String {
+        let ty = self.ty(Type::Tuple(inner.iter().map(|e| e.ty.clone()).collect()));
+        format!(
+            "{ty}{{{}}}",
+            inner.into_iter().map(|e| self.expr(e)).join(", ")
+        )
+    }
+
+    fn tuple_access(&self, expr: Expr, i: usize) -> String {
+        format!("std::get<{i}>({})", self.expr(expr))
+    }
+
+    fn if_then_else(&self, condition: Expr, consequence: Expr, alternative: Expr) -> String {
+        format!(
+            "({} ? {} : {})",
+            self.expr(condition),
+            self.expr(consequence),
+            self.expr(alternative)
+        )
+    }
+
+    fn none(&self, ty: &Type) -> String {
+        format!("{}{{}}", self.ty(ty.clone()))
+    }
+
+    fn some(&self, inner: Expr, ty: &Type) -> String {
+        format!("{}{{{}}}", self.ty(ty.clone()), self.expr(inner))
+    }
+
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> String {
+        format!("({}).value_or({})", self.expr(expr), self.expr(default))
+    }
+
+    fn binary(&self, op: Operator, lhs: Expr, rhs: Expr) -> String {
+        let float = matches!(lhs.ty, Type::Float64 | Type::Float32);
+        match op {
+            Operator::Pow => format!("std::pow({}, {})", self.expr(lhs), self.expr(rhs)),
+            Operator::Rem if float => format!("std::fmod({}, {})", self.expr(lhs), self.expr(rhs)),
+            _ => format!("({} {} {})", self.expr(lhs), self.op(op), self.expr(rhs)),
+        }
+    }
+}
+
+impl CppFormatter {
+    /// Returns the value of an access returning a `std::optional`, or the default if the value is absent
+    fn or_default(&self, sr: StreamReference, value: String, default: Expr) -> String {
+        // an access with an optional default wraps the value of a non-optional stream
+        let optional = matches!(default.ty, Type::Option(_))
+            && !matches!(self.sr2memory[&sr].ty, Type::Option(_));
+        if optional {
+            format!("detail::or_else({value}, {})", self.expr(default))
+        } else {
+            format!("{value}.value_or({})", self.expr(default))
+        }
+    }
+
+    /// Returns the function of the standard library operating on floating point values
+    fn float_function(&self, name: &str, ty: &Type) -> String {
+        match ty {
+            Type::Float64 | Type::Float32 => format!("std::{name}"),
+            ty => unreachable!("{name}({ty:?}) is rejected by the capability check"),
+        }
+    }
+}
+
+impl DefaultConstantFormatter for CppFormatter {
+    fn constant_string(&self, s: String) -> String {
+        // the length is given explicitly, as the string may contain null characters
+        format!("std::string_view{{\"{}\", {}}}", escape(&s), s.len())
+    }
+
+    fn constant_uint(&self, i: u64, bits: u16) -> String {
+        format!("std::uint{}_t{{{i}u}}", integer_bits(bits))
+    }
+
+    fn constant_int(&self, i: i64, bits: u16) -> String {
+        let ty = format!("std::int{}_t", integer_bits(bits));
+        if i == i64::MIN {
+            // the literal of the absolute value does not fit into a signed integer
+            format!("std::numeric_limits<{ty}>::min()")
+        } else {
+            format!("{ty}{{{i}}}")
+        }
+    }
+
+    fn constant_float32(&self, f: f64) -> String {
+        float_constant(f, "float", "f")
+    }
+
+    fn constant_float64(&self, f: f64) -> String {
+        float_constant(f, "double", "")
+    }
+
+    fn constant_tuple(&self, f: Vec<Constant>) -> String {
+        format!(
+            "std::make_tuple({})",
+            f.into_iter()
+                .map(|c| <Self as DefaultExprFormatter>::constant(self, c))
+                .join(", ")
+        )
+    }
+
+    fn constant_none(&self) -> String {
+        "std::nullopt".into()
+    }
+}
+
+fn float_constant(f: f64, ty: &str, suffix: &str) -> String {
+    if f.is_nan() {
+        format!("std::numeric_limits<{ty}>::quiet_NaN()")
+    } else if f.is_infinite() {
+        let sign = if f < 0.0 { "-" } else { "" };
+        format!("({sign}std::numeric_limits<{ty}>::infinity())")
+    } else if f < 0.0 {
+        format!("({f:?}{suffix})")
+    } else {
+        format!("{f:?}{suffix}")
+    }
+}
+
+/// Escapes the string for a string literal, where all bytes besides printable ASCII characters are given in octal
+fn escape(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'"' => "\\\"".into(),
+            b'\\' => "\\\\".into(),
+            b' '..=b'~' => char::from(b).to_string(),
+            b => format!("\\{b:03o}"),
+        })
+        .collect()
+}
+
+impl DefaultOperatorFormatter for CppFormatter {}
+
+impl DefaultFunctionFormatter for CppFormatter {
+    fn function_sqrt(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("sqrt", return_ty)
+    }
+
+    fn function_abs(&self, _arg_tys: &[Type], _return_ty: &Type) -> String {
+        "std::abs".into()
+    }
+
+    fn function_sin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("sin", return_ty)
+    }
+
+    fn function_arcsin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("asin", return_ty)
+    }
+
+    fn function_cos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("cos", return_ty)
+    }
+
+    fn function_arccos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("acos", return_ty)
+    }
+
+    fn function_tan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("tan", return_ty)
+    }
+
+    fn function_arctan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("atan", return_ty)
+    }
+
+    fn function_min(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => self.float_function("fmin", return_ty),
+            // the type is given explicitly, as the arguments may be promoted to different types
+            ty => format!("std::min<{}>", self.ty(ty.clone())),
+        }
+    }
+
+    fn function_max(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => self.float_function("fmax", return_ty),
+            ty => format!("std::max<{}>", self.ty(ty.clone())),
+        }
+    }
+}
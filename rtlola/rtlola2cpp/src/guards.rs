@@ -0,0 +1,42 @@
+use std::time::Duration;
+
+use rtlola_streamir::{
+    formatter::{expressions::ExprFormatter, guards::DefaultGuardFormatter, names::GetStreamName},
+    ir::{expressions::Expr, LocalFreqRef, StreamReference},
+};
+
+use crate::CppFormatter;
+
+impl DefaultGuardFormatter for CppFormatter {
+    fn stream(&self, sr: StreamReference) -> String {
+        format!(
+            "{}.{}.has_value()",
+            self.event_argument_name(),
+            self.stream_name(sr)
+        )
+    }
+
+    fn alive(&self, sr: StreamReference) -> String {
+        self.alive_member_name(sr)
+    }
+
+    fn dynamic(&self, expr: Expr) -> String {
+        self.expr(expr)
+    }
+
+    fn global_freq(&self, duration: Duration) -> String {
+        format!("due(std::chrono::nanoseconds{{{}}})", duration.as_nanos())
+    }
+
+    fn local_freq(&self, _freq_ref: LocalFreqRef) -> String {
+        unreachable!("local frequencies are rejected by the capability check")
+    }
+
+    fn constant(&self, b: bool) -> String {
+        match b {
+            true => "true",
+            false => "false",
+        }
+        .into()
+    }
+}
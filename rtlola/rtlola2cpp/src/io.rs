@@ -0,0 +1,126 @@
+use itertools::Itertools;
+use rtlola_streamir::formatter::names::GetStreamName;
+
+use crate::{constructs::RequirementKey, CppFormatter};
+
+impl CppFormatter {
+    pub(crate) fn require_io(&self) {
+        self.add_construct(RequirementKey::EventStruct, &self.event_struct());
+        self.add_construct(RequirementKey::TriggerEnum, &self.trigger_enum());
+        self.add_construct(RequirementKey::VerdictStruct, &self.verdict_struct());
+    }
+
+    fn event_struct(&self) -> String {
+        let fields = self
+            .inputs()
+            .map(|i| {
+                format!(
+                    "/// The new value of the input stream `{}`\nstd::optional<{}> {};",
+                    self.sr2memory[&i].name,
+                    self.stream_ty(i),
+                    self.stream_name(i)
+                )
+            })
+            .join("\n");
+        format!(
+            "/// An event given to the monitor, in which each input stream is empty if it did not receive a new value\n\
+            struct {} {{\n{fields}\n}};\n",
+            self.event_struct_name()
+        )
+    }
+
+    fn trigger_enum(&self) -> String {
+        let triggers = self.triggers().map(|o| o.sr()).collect::<Vec<_>>();
+        let enumerators = triggers
+            .iter()
+            .map(|sr| {
+                format!(
+                    "/// The trigger `{}`\n{},",
+                    self.sr2memory[sr].name,
+                    self.trigger_enumerator(*sr)
+                )
+            })
+            .join("\n");
+        let names = triggers
+            .iter()
+            .map(|sr| {
+                format!(
+                    "case {}::{}: return \"{}\";",
+                    self.trigger_enum_name(),
+                    self.trigger_enumerator(*sr),
+                    self.sr2memory[sr].name
+                )
+            })
+            .join("\n");
+        format!(
+            "/// The triggers of the specification\n\
+            enum class {trigger} {{\n{enumerators}\n}};\n\n\
+            /// Returns the name of the trigger in the specification\n\
+            constexpr std::string_view name({trigger} trigger) {{\n\
+            switch (trigger) {{\n{names}\n}}\n\
+            return {{}};\n\
+            }}\n",
+            trigger = self.trigger_enum_name()
+        )
+    }
+
+    fn verdict_struct(&self) -> String {
+        let fields = self
+            .verdict_streams
+            .iter()
+            .map(|sr| {
+                format!(
+                    "/// The new value of the stream `{}`, if it was evaluated in the cycle\nstd::optional<{}> {};",
+                    self.sr2memory[sr].name,
+                    self.stream_ty(*sr),
+                    self.stream_name(*sr)
+                )
+            })
+            .chain(Some(format!(
+                "/// The time of the cycle\nstd::chrono::nanoseconds {}{{}};",
+                self.time_argument_name()
+            )))
+            .join("\n");
+        // only the triggers included in the verdict can be reported as fired
+        let fired = self
+            .triggers()
+            .map(|o| o.sr())
+            .filter(|sr| self.verdict_streams.contains(sr))
+            .map(|sr| {
+                format!(
+                    "if ({}) {{\nfired.push_back({}::{});\n}}",
+                    self.stream_name(sr),
+                    self.trigger_enum_name(),
+                    self.trigger_enumerator(sr)
+                )
+            })
+            .join("\n");
+        format!(
+            "/// The verdict of the monitor for a cycle\n\
+            struct {verdict} {{\n{fields}\n\n\
+            /// Returns the triggers that fired in the cycle\n\
+            std::vector<{trigger}> triggers() const {{\n\
+            std::vector<{trigger}> fired;\n\
+            {fired}\n\
+            return fired;\n\
+            }}\n\
+            }};\n",
+            verdict = self.verdict_struct_name(),
+            trigger = self.trigger_enum_name(),
+        )
+    }
+
+    /// Returns the code setting the fields of the verdict for the streams evaluated in the cycle
+    pub(crate) fn build_verdict(&self, verdict: &str) -> String {
+        self.verdict_streams
+            .iter()
+            .map(|sr| {
+                let buffer = self.buffer(*sr).expect("verdict streams have memory");
+                format!(
+                    "if ({buffer}.is_fresh()) {{\n{verdict}.{} = {buffer}.sync();\n}}",
+                    self.stream_name(*sr)
+                )
+            })
+            .join("\n")
+    }
+}
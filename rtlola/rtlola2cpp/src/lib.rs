@@ -0,0 +1,432 @@
+//! A backend compiling the StreamIR into a header-only C++ library.
+//!
+//! The generated header only depends on the C++17 standard library.
+//! It contains a typed `Event` struct given to the `Monitor` class, which keeps the memory of the streams in its members
+//! and returns a typed `Verdict` for each event and each deadline of the periodic streams.
+
+pub mod capabilities;
+pub(crate) mod constructs;
+mod expressions;
+mod guards;
+mod io;
+mod memory;
+mod monitor;
+mod names;
+mod statements;
+mod types;
+
+use std::{collections::HashMap, path::PathBuf};
+
+use capabilities::UnsupportedFeatures;
+use constructs::RequirementKey;
+use itertools::Itertools;
+use monitor::{Cycle, MonitorClass};
+use rtlola_streamir::{
+    formatter::{
+        files::{ConstructStore, ConstructWriteError, FilesFormatter},
+        StreamIrFormatter,
+    },
+    ir::{memory::Memory, OutputReference, StaticSchedule, StreamIr, StreamReference},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// An error that can happen when compiling the StreamIR to C++
+pub enum FormatError {
+    #[error("the specification uses features unsupported by the C++ backend:\n{0}")]
+    /// The StreamIR uses features the backend cannot compile
+    Unsupported(#[from] UnsupportedFeatures),
+    #[error(transparent)]
+    /// The generated files could not be written
+    Write(#[from] ConstructWriteError),
+}
+
+pub struct CppFormatter {
+    construct_store: ConstructStore<Self>,
+    sr2memory: HashMap<StreamReference, Memory>,
+    triggers: HashMap<OutputReference, usize>,
+    static_schedule: Option<StaticSchedule>,
+    overwrite: bool,
+    verdict_streams: Vec<StreamReference>,
+    output_dir: PathBuf,
+}
+
+impl CppFormatter {
+    pub fn new(
+        ir: &StreamIr,
+        overwrite: bool,
+        verdict_streams: Vec<StreamReference>,
+        output_dir: PathBuf,
+    ) -> Self {
+        Self {
+            construct_store: ConstructStore::default(),
+            sr2memory: ir.sr2memory.clone(),
+            triggers: ir.triggers.clone(),
+            static_schedule: ir.static_schedule.clone(),
+            overwrite,
+            verdict_streams,
+            output_dir,
+        }
+    }
+}
+
+impl StreamIrFormatter for CppFormatter {
+    type Return = Result<(), FormatError>;
+
+    fn id(&self) -> String {
+        "cpp-formatter".into()
+    }
+
+    fn format(self, ir: StreamIr) -> Self::Return {
+        capabilities::check(&ir)?;
+        let StreamIr { stmt, .. } = ir;
+        self.require_header();
+        self.require_buffer();
+        self.require_io();
+        self.add_requirement(MonitorClass);
+        self.add_requirement(Cycle(stmt));
+        Ok(self.generate_files()?)
+    }
+}
+
+impl CppFormatter {
+    fn streams(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory.keys().sorted().copied()
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory
+            .keys()
+            .filter(|o| matches!(o, StreamReference::In(_)))
+            .sorted()
+            .copied()
+    }
+
+    /// Returns the triggers ordered by their index
+    fn triggers(&self) -> impl Iterator<Item = OutputReference> + '_ {
+        self.triggers
+            .iter()
+            .sorted_by_key(|(_, idx)| **idx)
+            .map(|(o, _)| *o)
+    }
+
+    fn require_header(&self) {
+        let namespace = self.namespace_name();
+        self.add_construct(
+            RequirementKey::Header,
+            &format!(
+                "/// A monitor generated by rtlola2cpp.\n\
+                ///\n\
+                /// The header only depends on the C++17 standard library.\n\
+                #pragma once\n\n\
+                #include <algorithm>\n\
+                #include <array>\n\
+                #include <chrono>\n\
+                #include <cmath>\n\
+                #include <cstddef>\n\
+                #include <cstdint>\n\
+                #include <limits>\n\
+                #include <optional>\n\
+                #include <string_view>\n\
+                #include <tuple>\n\
+                #include <vector>\n\n\
+                namespace {namespace} {{\n"
+            ),
+        );
+        self.add_construct(
+            RequirementKey::DetailStart,
+            "/// The internals of the monitor\nnamespace detail {\n",
+        );
+        self.add_construct(RequirementKey::DetailEnd, "} // namespace detail\n");
+        self.add_construct(
+            RequirementKey::Footer,
+            &format!("}} // namespace {namespace}\n"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Write,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use itertools::Itertools;
+    use rtlola2c::{main_function::MainFunction, CFormatter};
+    use rtlola_streamir::{
+        capabilities::Feature,
+        formatter::StreamIrFormatter,
+        ir::{text::parse, StreamIr, StreamReference, Type},
+        optimize_all, ParserConfig,
+    };
+
+    use super::{CppFormatter, FormatError};
+
+    const EXAMPLE: &str = include_str!("tests/example.sir");
+
+    /// The specifications of the repository, whose monitors are compared with the monitors of the C backend
+    const SPECS: [&str; 2] = ["waypoint_spec_v2.lola", "waypoint_spec_dlr.lola"];
+
+    /// An event of a trace with its time in seconds and the textual values of the inputs, ordered by their reference
+    type Event = (f64, Vec<Option<String>>);
+
+    /// Returns a pseudo-random trace of 50 events, where each input is present with a probability of 3/4
+    fn trace(ir: &StreamIr) -> Vec<Event> {
+        let mut state = 42u64;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        (1..=50)
+            .map(|i| {
+                let values = ir
+                    .inputs()
+                    .sorted()
+                    .map(|i| {
+                        let value = match &ir.stream_memory(StreamReference::In(i)).ty {
+                            Type::Bool => (next(2) == 0).to_string(),
+                            Type::Int(_) => (next(101) as i64 - 50).to_string(),
+                            Type::UInt(_) => next(101).to_string(),
+                            Type::Float32 | Type::Float64 => {
+                                format!("{:.3}", (next(2001) as f64 - 1000.0) / 1000.0)
+                            }
+                            ty => unreachable!("no input of the specifications has type {ty}"),
+                        };
+                        (next(4) != 0).then_some(value)
+                    })
+                    .collect();
+                (i as f64 / 4.0, values)
+            })
+            .collect()
+    }
+
+    /// Returns the verdicts of the outputs for the trace computed by the monitor of the C backend,
+    /// in the CSV format of its main function without the header
+    fn c_verdicts(ir: &StreamIr, trace: &[Event], dir: &Path) -> String {
+        let verdict_streams = ir.outputs().sorted().map(StreamReference::Out).collect();
+        let formatter = CFormatter::new(
+            ir,
+            true,
+            MainFunction::CsvOffline,
+            verdict_streams,
+            dir.to_path_buf(),
+            None,
+        );
+        formatter.format(ir.clone()).unwrap();
+        let compile = Command::new("cc")
+            .current_dir(dir)
+            .args(["-std=c11", "-o", "monitor", "monitor.c", "-lm"])
+            .output()
+            .expect("cc is available");
+        assert!(
+            compile.status.success(),
+            "{}",
+            String::from_utf8_lossy(&compile.stderr)
+        );
+        let csv = trace.iter().fold(
+            ir.inputs()
+                .sorted()
+                .map(|i| ir.name(StreamReference::In(i)))
+                .chain(["time"])
+                .join(",")
+                + "\n",
+            |mut csv, (time, values)| {
+                let values = values.iter().map(|v| v.as_deref().unwrap_or("#"));
+                writeln!(&mut csv, "{},{time}", values.format(",")).unwrap();
+                csv
+            },
+        );
+        std::fs::write(dir.join("trace.csv"), csv).unwrap();
+        let output = Command::new(dir.join("monitor"))
+            .arg(dir.join("trace.csv"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .skip(1)
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    /// Returns a program giving the trace to the monitor and printing the verdicts as the main function of the C backend
+    fn driver(ir: &StreamIr, trace: &[Event]) -> String {
+        let inputs = ir
+            .inputs()
+            .sorted()
+            .map(|i| ir.name(StreamReference::In(i)))
+            .collect::<Vec<_>>();
+        let cells = ir
+            .outputs()
+            .sorted()
+            .map(|o| format!("cell(verdict.{}) + \",\"", ir.name(StreamReference::Out(o))))
+            .join(" + ");
+        let events = trace
+            .iter()
+            .map(|(time, values)| {
+                let fields = inputs
+                    .iter()
+                    .zip(values)
+                    .filter_map(|(name, value)| {
+                        Some(format!("event.{name} = {};", value.as_ref()?))
+                    })
+                    .join(" ");
+                format!(
+                    "    {{\n\
+                    monitor::Event event{{}};\n\
+                    {fields}\n\
+                    print(monitor.accept_event(event, std::chrono::nanoseconds{{{}}}));\n\
+                    }}",
+                    (time * 1e9) as u64
+                )
+            })
+            .join("\n");
+        format!(
+            r##"#include <cstdio>
+#include <string>
+#include <type_traits>
+#include "monitor.hpp"
+std::string cell(double value) {{
+    char buffer[512];
+    std::snprintf(buffer, sizeof(buffer), "%lf", value);
+    return buffer;
+}}
+std::string cell(float value) {{
+    return cell(static_cast<double>(value));
+}}
+std::string cell(bool value) {{
+    return value ? "true" : "false";
+}}
+std::string cell(std::string_view value) {{
+    return "\"" + std::string(value) + "\"";
+}}
+template <typename T, typename = std::enable_if_t<std::is_integral_v<T>>>
+std::string cell(T value) {{
+    return std::to_string(value);
+}}
+template <typename... T>
+std::string cell(const std::tuple<T...>& value);
+template <typename T>
+std::string cell(const std::optional<T>& value) {{
+    return value ? cell(*value) : "#";
+}}
+template <typename... T>
+std::string cell(const std::tuple<T...>& value) {{
+    std::string elements;
+    std::apply([&](const auto&... element) {{ ((elements += (elements.empty() ? "" : ",") + cell(element)), ...); }}, value);
+    return "(" + elements + ")";
+}}
+void print(const monitor::Verdict& verdict) {{
+    std::printf("%s%lf\n", ({cells}).c_str(), verdict.time.count() / 1e9);
+}}
+int main() {{
+    monitor::Monitor monitor{{std::chrono::nanoseconds::zero()}};
+{events}
+}}
+"##
+        )
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rtlola2cpp-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Compiles `main.cpp` in the directory into the executable `main`
+    fn gxx(dir: &Path) {
+        let compile = Command::new("g++")
+            .current_dir(dir)
+            .args(["-std=c++17", "-Wall", "-Werror", "-o", "main", "main.cpp"])
+            .output()
+            .expect("g++ is available");
+        assert!(
+            compile.status.success(),
+            "{}",
+            String::from_utf8_lossy(&compile.stderr)
+        );
+    }
+
+    #[test]
+    fn compiles() {
+        let ir = parse(EXAMPLE).unwrap_or_else(|e| panic!("{e}"));
+        let verdict_streams = ir.sr2memory.keys().copied().collect();
+        let dir = output_dir("compiles");
+        let formatter = CppFormatter::new(&ir, true, verdict_streams, dir.clone());
+        formatter.format(ir).unwrap();
+
+        std::fs::write(
+            dir.join("main.cpp"),
+            r#"#include <iostream>
+#include "monitor.hpp"
+using namespace std::chrono_literals;
+int main() {
+    monitor::Monitor monitor{0ns};
+    monitor::Verdict verdict = monitor.accept_event(monitor::Event{1, std::nullopt}, 500ms);
+    std::cout << *verdict.d << " " << int{*verdict.g} << " " << *verdict.t << " " << verdict.triggers().size() << "\n";
+    verdict = *monitor.step(1s);
+    std::cout << *verdict.p << " " << verdict.time.count() << "\n";
+}
+"#,
+        )
+        .unwrap();
+        gxx(&dir);
+        let output = Command::new(dir.join("main")).output().unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "2 100 a is \"large\" 1\n1 1000000000\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn repo_specs() {
+        for spec in SPECS {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join(spec);
+            let ir = rtlola_streamir::parse(&ParserConfig::from_path(path).unwrap()).unwrap();
+            let trace = trace(&ir);
+            let dir = output_dir(&format!("repo-{spec}"));
+            let expected = c_verdicts(&ir, &trace, &dir);
+            std::fs::write(dir.join("main.cpp"), driver(&ir, &trace)).unwrap();
+            for ir in [ir.clone(), optimize_all(ir).unwrap()] {
+                let verdict_streams = ir.streams().collect();
+                let formatter = CppFormatter::new(&ir, true, verdict_streams, dir.clone());
+                formatter.format(ir).unwrap();
+                gxx(&dir);
+                let output = Command::new(dir.join("main")).output().unwrap();
+                assert_eq!(
+                    String::from_utf8(output.stdout).unwrap(),
+                    expected,
+                    "{spec}"
+                );
+            }
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn unsupported_features() {
+        let ir = parse(&EXAMPLE.replace("\"b\": Float64", "\"b\": Fixed32"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let dir = output_dir("unsupported");
+        let formatter = CppFormatter::new(&ir, true, Vec::new(), dir.clone());
+        let Err(FormatError::Unsupported(unsupported)) = formatter.format(ir) else {
+            panic!("values of type Fixed32 are rejected")
+        };
+        assert!(unsupported
+            .features()
+            .any(|f| *f == Feature::Type(Type::Fixed(32))));
+        assert!(unsupported.to_string().contains(
+            "stream `b` uses values of type Fixed32, which is not supported by the C++ backend"
+        ));
+        assert!(!dir.join("monitor.hpp").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
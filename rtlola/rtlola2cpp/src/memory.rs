@@ -0,0 +1,133 @@
+use itertools::Itertools;
+use rtlola_streamir::ir::{
+    memory::{StreamBuffer, StreamMemory},
+    StreamReference,
+};
+
+use crate::{constructs::RequirementKey, CppFormatter};
+
+/// The ring buffer storing the last values of a stream, shared by all streams
+const BUFFER: &str = "/// The last `N` values of a stream
+template <typename T, std::size_t N>
+class Buffer {
+static_assert(N > 0, \"a buffer stores at least the current value\");
+
+public:
+/// The number of values stored by the buffer
+static constexpr std::size_t size = N;
+
+constexpr void shift() {
+current_ = (current_ + 1) % N;
+}
+
+constexpr void push(T value) {
+values_[current_] = value;
+valid_[current_] = true;
+fresh_ = true;
+}
+
+constexpr T sync() const {
+return values_[current_];
+}
+
+constexpr std::optional<T> offset(std::size_t offset) const {
+const std::size_t i = (current_ + N - offset) % N;
+if (!valid_[i]) {
+return std::nullopt;
+}
+return values_[i];
+}
+
+constexpr std::optional<T> get() const {
+if (!fresh_) {
+return std::nullopt;
+}
+return values_[current_];
+}
+
+constexpr bool is_fresh() const {
+return fresh_;
+}
+
+constexpr void reset_fresh() {
+fresh_ = false;
+}
+
+constexpr void clear() {
+for (bool& valid : valid_) {
+valid = false;
+}
+}
+
+private:
+std::array<T, N> values_{};
+std::array<bool, N> valid_{};
+std::size_t current_ = 0;
+bool fresh_ = false;
+};
+
+/// Returns the value if it is present and the alternative otherwise
+template <typename T>
+constexpr std::optional<T> or_else(const std::optional<T>& value, const std::optional<T>& alternative) {
+return value ? value : alternative;
+}
+";
+
+impl CppFormatter {
+    /// Returns the member holding the buffer of the stream, if the stream has memory
+    pub(crate) fn buffer(&self, sr: StreamReference) -> Option<String> {
+        match &self.sr2memory[&sr].buffer {
+            StreamMemory::NoMemory => None,
+            StreamMemory::Static(_) | StreamMemory::Dynamic { .. } => Some(self.member_name(sr)),
+            StreamMemory::Instances { .. } => {
+                unreachable!("parameterized streams are rejected by the capability check")
+            }
+        }
+    }
+
+    /// Returns the type of the buffer of the stream, if the stream has memory
+    pub(crate) fn buffer_ty(&self, sr: StreamReference) -> Option<String> {
+        let size = match self.sr2memory[&sr].buffer.buffer()? {
+            StreamBuffer::SingleValue => 1,
+            StreamBuffer::Bounded(size) => *size,
+            StreamBuffer::UnBounded => {
+                unreachable!("unbounded memory is rejected by the capability check")
+            }
+        };
+        Some(format!(
+            "{}<{}, {size}>",
+            self.buffer_class_name(),
+            self.stream_ty(sr)
+        ))
+    }
+
+    /// Whether the stream is spawned and closed dynamically
+    pub(crate) fn is_dynamic(&self, sr: StreamReference) -> bool {
+        matches!(self.sr2memory[&sr].buffer, StreamMemory::Dynamic { .. })
+    }
+
+    pub(crate) fn require_buffer(&self) {
+        self.add_construct(RequirementKey::Buffer, BUFFER);
+    }
+
+    /// Returns the declarations of the members holding the memory of the streams
+    pub(crate) fn memory_members(&self) -> String {
+        self.streams()
+            .filter_map(|sr| Some((sr, self.buffer_ty(sr)?)))
+            .map(|(sr, ty)| format!("{ty} {}{{}};", self.member_name(sr)))
+            .chain(
+                self.streams()
+                    .filter(|sr| self.is_dynamic(*sr))
+                    .map(|sr| format!("bool {} = false;", self.alive_member_name(sr))),
+            )
+            .join("\n")
+    }
+
+    /// Returns the code resetting the fresh values of the streams at the end of a cycle
+    pub(crate) fn reset_fresh(&self) -> String {
+        self.streams()
+            .filter_map(|sr| self.buffer(sr))
+            .map(|buffer| format!("{buffer}.reset_fresh();"))
+            .join("\n")
+    }
+}
@@ -0,0 +1,163 @@
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{files::Requirement, statements::StmtFormatter},
+    ir::Stmt,
+};
+
+use crate::{
+    constructs::{indent, RequirementKey},
+    CppFormatter,
+};
+
+/// The monitor class with its public interface, evaluating events and the deadlines of the periodic streams
+pub(crate) struct MonitorClass;
+
+impl Requirement<CppFormatter> for MonitorClass {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::MonitorClass
+    }
+
+    fn file(&self, f: &CppFormatter) -> PathBuf {
+        f.header_file()
+    }
+
+    fn format(self, f: &CppFormatter) -> String {
+        let monitor = f.monitor_class_name();
+        let event = f.event_struct_name();
+        let verdict = f.verdict_struct_name();
+        let cycle = f.cycle_function_name();
+        let deadlines = f.deadlines_name();
+        let start = f.start_member_name();
+        let time = f.time_member_name();
+        let timed = f.timed_member_name();
+        let deadline = f.deadline_member_name();
+        let next_deadline = f.next_deadline_member_name();
+        let schedule = f
+            .static_schedule
+            .as_ref()
+            .filter(|schedule| !schedule.deadlines.is_empty());
+
+        let (members, init, next_deadline_body, step) = if let Some(schedule) = schedule {
+            f.add_construct(
+                RequirementKey::Deadlines,
+                &format!(
+                    "/// The pauses between the deadlines of the periodic streams, repeated after the last deadline\n\
+                    inline constexpr std::array<std::chrono::nanoseconds, {}> deadlines{{{}}};\n",
+                    schedule.deadlines.len(),
+                    schedule
+                        .deadlines
+                        .iter()
+                        .map(|deadline| format!("std::chrono::nanoseconds{{{}}}", deadline.pause.as_nanos()))
+                        .join(", ")
+                ),
+            );
+            (
+                format!(
+                    "std::size_t {deadline} = 0;\n\
+                    std::chrono::nanoseconds {next_deadline};"
+                ),
+                format!(", {next_deadline}{{start + {deadlines}[0]}}"),
+                format!("return {next_deadline};"),
+                format!(
+                    "if ({next_deadline} > time) {{\n\
+                    return std::nullopt;\n\
+                    }}\n\
+                    const std::chrono::nanoseconds deadline = {next_deadline};\n\
+                    {deadline} = ({deadline} + 1) % {deadlines}.size();\n\
+                    {next_deadline} += {deadlines}[{deadline}];\n\
+                    return {cycle}({event}{{}}, deadline, true);"
+                ),
+            )
+        } else {
+            (
+                String::new(),
+                String::new(),
+                "return std::nullopt;".into(),
+                "return std::nullopt;".into(),
+            )
+        };
+
+        indent(&format!(
+            "/// The monitor for the specification, which keeps the memory of all streams\n\
+            class {monitor} {{\n\
+            public:\n\
+            /// Creates a new monitor that starts monitoring at the given time\n\
+            explicit {monitor}(std::chrono::nanoseconds start) : {start}{{start}}, {time}{{start}}{init} {{}}\n\n\
+            /// Evaluates the monitor for an event at the given time\n\
+            ///\n\
+            /// All deadlines up to the time of the event need to be evaluated before with `step`.\n\
+            {verdict} accept_event(const {event}& event, std::chrono::nanoseconds time) {{\n\
+            return {cycle}(event, time, false);\n\
+            }}\n\n\
+            /// Returns the time of the next deadline of the periodic streams, if there are any\n\
+            std::optional<std::chrono::nanoseconds> next_deadline() const {{\n\
+            {next_deadline_body}\n\
+            }}\n\n\
+            /// Evaluates the periodic streams at the next deadline if it is not after the given time\n\
+            ///\n\
+            /// Returns an empty optional if no deadline is due, such that all deadlines up to the given time are evaluated\n\
+            /// by calling this function until it returns an empty optional.\n\
+            std::optional<{verdict}> step([[maybe_unused]] std::chrono::nanoseconds time) {{\n\
+            {step}\n\
+            }}\n\n\
+            private:\n\
+            /// Evaluates the streams for the event at the given time, where the event is empty at a deadline\n\
+            {verdict} {cycle}(const {event}& event, std::chrono::nanoseconds time, bool timed);\n\n\
+            /// Whether a periodic stream with the given period is due in the current cycle\n\
+            bool due(std::chrono::nanoseconds period) const {{\n\
+            return {timed} && ({time} - {start}) % period == std::chrono::nanoseconds::zero();\n\
+            }}\n\n\
+            /// Resets the fresh values of the streams at the end of a cycle\n\
+            void reset_fresh() {{\n{reset}\n}}\n\n\
+            {memory}\n\
+            std::chrono::nanoseconds {start};\n\
+            std::chrono::nanoseconds {time};\n\
+            bool {timed} = false;\n\
+            {members}\n\
+            }};\n",
+            reset = f.reset_fresh(),
+            memory = f.memory_members(),
+        ))
+    }
+}
+
+/// The evaluation of the statement for an event or a deadline, returning the verdict
+pub(crate) struct Cycle(pub(crate) Stmt);
+
+impl Requirement<CppFormatter> for Cycle {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Cycle
+    }
+
+    fn file(&self, f: &CppFormatter) -> PathBuf {
+        f.header_file()
+    }
+
+    fn format(self, f: &CppFormatter) -> String {
+        let verdict = f.verdict_struct_name();
+        let time = f.time_argument_name();
+        let timed = f.timed_argument_name();
+        indent(&format!(
+            "inline {verdict} {monitor}::{cycle}(const {event_struct}& {event}, std::chrono::nanoseconds {time}, bool {timed}) {{\n\
+            {time_member} = {time};\n\
+            {timed_member} = {timed};\n\
+            {stmt}\n\
+            {verdict} verdict;\n\
+            verdict.{time} = {time};\n\
+            {build_verdict}\n\
+            reset_fresh();\n\
+            return verdict;\n\
+            }}\n",
+            monitor = f.monitor_class_name(),
+            cycle = f.cycle_function_name(),
+            event = f.event_argument_name(),
+            event_struct = f.event_struct_name(),
+            time_member = f.time_member_name(),
+            timed_member = f.timed_member_name(),
+            stmt = f.stmt(self.0),
+            build_verdict = f.build_verdict("verdict"),
+        ))
+    }
+}
@@ -0,0 +1,170 @@
+use rtlola_streamir::{
+    formatter::names::GetStreamName,
+    function_names,
+    ir::{StreamReference, WindowReference},
+};
+
+use crate::CppFormatter;
+
+function_names! {CppFormatter,
+    temporary_name(num): "tmp_{num}",
+
+    event_argument_name(): "event",
+    time_argument_name(): "time",
+    timed_argument_name(): "timed",
+    start_member_name(): "start_",
+    time_member_name(): "time_",
+    timed_member_name(): "timed_",
+    deadline_member_name(): "deadline_",
+    next_deadline_member_name(): "next_deadline_",
+
+    namespace_name(): "monitor",
+    event_struct_name(): "Event",
+    verdict_struct_name(): "Verdict",
+    trigger_enum_name(): "Trigger",
+    monitor_class_name(): "Monitor",
+    buffer_class_name(): "detail::Buffer",
+    deadlines_name(): "detail::deadlines",
+    cycle_function_name(): "cycle"
+}
+
+/// The keywords of C++ that are suffixed by an underscore when used as the name of a stream
+const KEYWORDS: [&str; 92] = [
+    "alignas",
+    "alignof",
+    "and",
+    "and_eq",
+    "asm",
+    "auto",
+    "bitand",
+    "bitor",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "char8_t",
+    "char16_t",
+    "char32_t",
+    "class",
+    "co_await",
+    "co_return",
+    "co_yield",
+    "compl",
+    "concept",
+    "const",
+    "const_cast",
+    "consteval",
+    "constexpr",
+    "constinit",
+    "continue",
+    "decltype",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "dynamic_cast",
+    "else",
+    "enum",
+    "explicit",
+    "export",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "not",
+    "not_eq",
+    "nullptr",
+    "operator",
+    "or",
+    "or_eq",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "reinterpret_cast",
+    "requires",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "static_assert",
+    "static_cast",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "thread_local",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typeid",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "wchar_t",
+    "while",
+    "xor",
+    "xor_eq",
+];
+
+impl GetStreamName for CppFormatter {
+    fn stream_name(&self, sr: StreamReference) -> String {
+        let name = &self.sr2memory[&sr].name;
+        if KEYWORDS.contains(&name.as_str()) {
+            format!("{name}_")
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn window_name(&self, _sref: WindowReference) -> String {
+        unreachable!("windows are rejected by the capability check")
+    }
+}
+
+impl CppFormatter {
+    /// Returns the member of the monitor holding the memory of the stream
+    ///
+    /// As the name is suffixed by an underscore, it cannot collide with a keyword.
+    pub(crate) fn member_name(&self, sr: StreamReference) -> String {
+        format!("{}_", self.sr2memory[&sr].name)
+    }
+
+    /// Returns the member of the monitor holding whether the stream is alive
+    pub(crate) fn alive_member_name(&self, sr: StreamReference) -> String {
+        format!("{}_alive_", self.sr2memory[&sr].name)
+    }
+
+    /// Returns the enumerator of the trigger enum representing the trigger
+    pub(crate) fn trigger_enumerator(&self, sr: StreamReference) -> String {
+        self.sr2memory[&sr]
+            .name
+            .split('_')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,110 @@
+use rtlola_streamir::{
+    formatter::{
+        expressions::ExprFormatter,
+        guards::GuardFormatter,
+        names::GetStreamName,
+        statements::{DefaultStmtFormatter, StmtFormatter},
+        types::TypeFormatter,
+    },
+    ir::{
+        expressions::Expr, Guard, InputReference, LocalFreqRef, OutputReference, Stmt,
+        StreamReference, TempRef, WindowReference,
+    },
+};
+
+use crate::CppFormatter;
+
+impl DefaultStmtFormatter for CppFormatter {
+    fn shift(&self, sr: StreamReference) -> String {
+        self.buffer(sr)
+            .map(|buffer| format!("{buffer}.shift();"))
+            .unwrap_or_default()
+    }
+
+    fn input(&self, sr: InputReference) -> String {
+        let sr = StreamReference::In(sr);
+        self.buffer(sr)
+            .map(|buffer| {
+                let value = format!("{}.{}", self.event_argument_name(), self.stream_name(sr));
+                format!("if ({value}) {{\n{buffer}.push(*{value});\n}}")
+            })
+            .unwrap_or_default()
+    }
+
+    fn spawn(
+        &self,
+        sr: OutputReference,
+        with: Option<Vec<Expr>>,
+        local_frequencies: Vec<LocalFreqRef>,
+        windows: Vec<WindowReference>,
+    ) -> String {
+        assert!(local_frequencies.is_empty());
+        assert!(windows.is_empty());
+        if with.is_some() {
+            unreachable!("parameterized streams are rejected by the capability check")
+        }
+        let sr = sr.sr();
+        let alive = self.alive_member_name(sr);
+        format!(
+            "if (!{alive}) {{\n{} = {}{{}};\n{alive} = true;\n}}",
+            self.buffer(sr).unwrap(),
+            self.buffer_ty(sr).unwrap()
+        )
+    }
+
+    fn eval(&self, sr: OutputReference, with: Expr, _idx: usize) -> String {
+        format!(
+            "{}.push({});",
+            self.buffer(sr.sr()).unwrap(),
+            self.expr(with)
+        )
+    }
+
+    fn r#let(&self, temp: TempRef, expr: Expr) -> String {
+        format!(
+            "const {} {} = {};",
+            self.ty(expr.ty.clone()),
+            self.temporary_name(temp),
+            self.expr(expr)
+        )
+    }
+
+    fn close(
+        &self,
+        sr: OutputReference,
+        _local_frequencies: Vec<LocalFreqRef>,
+        _windows: Vec<WindowReference>,
+    ) -> String {
+        // for hold and offset accesses to return the default, the values are invalidated
+        let sr = sr.sr();
+        format!(
+            "{}.clear();\n{} = false;",
+            self.buffer(sr).unwrap(),
+            self.alive_member_name(sr)
+        )
+    }
+
+    fn r#if(&self, guard: Guard, cons: Stmt, alt: Option<Stmt>) -> String {
+        let guard = self.guard(guard);
+        let cons = self.stmt(cons);
+        if let Some(alt) = alt {
+            let alt = self.stmt(alt);
+            format!("if ({guard}) {{\n{cons}\n}} else {{\n{alt}\n}}")
+        } else {
+            format!("if ({guard}) {{\n{cons}\n}}")
+        }
+    }
+
+    fn iterate(&self, _sr: Vec<OutputReference>, _inner: Stmt) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+
+    fn assign(
+        &self,
+        _sr: Vec<OutputReference>,
+        _parameter_expr: Vec<Expr>,
+        _inner: Stmt,
+    ) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+}
@@ -0,0 +1,70 @@
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::types::TypeFormatter,
+    ir::{StreamReference, Type},
+};
+
+use crate::CppFormatter;
+
+/// Returns the number of bits of the smallest fixed width integer type holding values with the given number of bits
+pub(crate) fn integer_bits(bits: u16) -> u16 {
+    bits.next_power_of_two().max(8)
+}
+
+impl TypeFormatter for CppFormatter {
+    type Return = String;
+
+    fn type_int(&self, bits: u16) -> Self::Return {
+        format!("std::int{}_t", integer_bits(bits))
+    }
+
+    fn type_uint(&self, bits: u16) -> Self::Return {
+        format!("std::uint{}_t", integer_bits(bits))
+    }
+
+    fn type_bool(&self) -> Self::Return {
+        "bool".into()
+    }
+
+    fn type_string(&self) -> Self::Return {
+        // strings only originate from literals, which have static storage duration
+        "std::string_view".into()
+    }
+
+    fn type_float32(&self) -> Self::Return {
+        "float".into()
+    }
+
+    fn type_float64(&self) -> Self::Return {
+        "double".into()
+    }
+
+    fn type_option(&self, inner: Type) -> Self::Return {
+        format!("std::optional<{}>", self.ty(inner))
+    }
+
+    fn type_tuple(&self, inner: Vec<Type>) -> Self::Return {
+        format!(
+            "std::tuple<{}>",
+            inner.into_iter().map(|ty| self.ty(ty)).join(", ")
+        )
+    }
+
+    fn type_fixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_ufixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_bytes(&self) -> Self::Return {
+        unreachable!("bytes are rejected by the capability check")
+    }
+}
+
+impl CppFormatter {
+    pub(crate) fn stream_ty(&self, sr: StreamReference) -> String {
+        self.ty(self.sr2memory[&sr].ty.clone())
+    }
+}
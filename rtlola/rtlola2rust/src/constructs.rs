@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+
+use rtlola_streamir::formatter::files::{ConstructStore, FilesFormatter};
+
+use crate::RustFormatter;
+
+impl FilesFormatter for RustFormatter {
+    type Key = RequirementKey;
+
+    fn get_construct_store(&self) -> &ConstructStore<Self> {
+        &self.construct_store
+    }
+
+    fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum RequirementKey {
+    Header,
+
+    // Public API
+    EventStruct,
+    TriggerEnum,
+    VerdictStruct,
+    MonitorStruct,
+    MonitorImpl,
+
+    // Internals
+    Deadlines,
+    Buffer,
+    MemoryStruct,
+    Cycle,
+}
+
+impl RustFormatter {
+    pub(crate) fn monitor_file(&self) -> PathBuf {
+        self.output_dir.join("monitor.rs")
+    }
+}
@@ -0,0 +1,250 @@
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{
+        expressions::{
+            DefaultConstantFormatter, DefaultExprFormatter, DefaultFunctionFormatter,
+            DefaultOperatorFormatter, ExprFormatter, OperatorFormatter,
+        },
+        types::TypeFormatter,
+    },
+    ir::{
+        expressions::{Constant, Expr, Operator},
+        StreamReference, TempRef, Type, WindowReference,
+    },
+};
+
+use crate::{types::integer_bits, RustFormatter};
+
+impl DefaultExprFormatter for RustFormatter {
+    fn sync_access(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        format!("{}.sync()", self.buffer(sr).unwrap())
+    }
+
+    fn offset_access(
+        &self,
+        sr: StreamReference,
+        offset: u32,
+        default: Expr,
+        parameters: Vec<Expr>,
+    ) -> String {
+        assert!(parameters.is_empty());
+        let value = format!("{}.offset({offset})", self.buffer(sr).unwrap());
+        self.or_default(sr, value, default)
+    }
+
+    fn hold_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        <Self as DefaultExprFormatter>::offset_access(self, sr, 0, default, parameters)
+    }
+
+    fn get_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        let value = format!("{}.get()", self.buffer(sr).unwrap());
+        self.or_default(sr, value, default)
+    }
+
+    fn is_fresh(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        format!("{}.is_fresh()", self.buffer(sr).unwrap())
+    }
+
+    fn sliding_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("sliding windows are rejected by the capability check")
+    }
+
+    fn discrete_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("discrete windows are rejected by the capability check")
+    }
+
+    fn instance_aggregation(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn parameter_access(&self, _sr: StreamReference, _p: usize) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+
+    fn lambda_parameter_access(&self, _wref: WindowReference, _idx: usize) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn temporary(&self, temp: TempRef, _ty: &Type) -> String {
+        self.temporary_name(temp)
+    }
+
+    fn cast(&self, ty: Type, expr: Expr) -> String {
+        format!("(({}) as {})", self.expr(expr), self.ty(ty))
+    }
+
+    fn tuple(&self, inner: Vec<Expr>) -> String {
+        let trailing = if inner.len() == 1 { "," } else { "" };
+        format!(
+            "({}{trailing})",
+            inner.into_iter().map(|e| self.expr(e)).join(", ")
+        )
+    }
+
+    fn tuple_access(&self, expr: Expr, i: usize) -> String {
+        format!("({}).{i}", self.expr(expr))
+    }
+
+    fn if_then_else(&self, condition: Expr, consequence: Expr, alternative: Expr) -> String {
+        format!(
+            "(if {} {{ {} }} else {{ {} }})",
+            self.expr(condition),
+            self.expr(consequence),
+            self.expr(alternative)
+        )
+    }
+
+    fn some(&self, inner: Expr, _ty: &Type) -> String {
+        format!("Some({})", self.expr(inner))
+    }
+
+    fn unwrap_or(&self, expr: Expr, default: Expr) -> String {
+        format!("({}).unwrap_or({})", self.expr(expr), self.expr(default))
+    }
+
+    fn binary(&self, op: Operator, lhs: Expr, rhs: Expr) -> String {
+        match op {
+            Operator::Pow => {
+                let pow = self.float_function("pow", &lhs.ty);
+                format!("{pow}({}, {})", self.expr(lhs), self.expr(rhs))
+            }
+            _ => format!("({} {} {})", self.expr(lhs), self.op(op), self.expr(rhs)),
+        }
+    }
+}
+
+impl RustFormatter {
+    /// Returns the value of an access returning an `Option`, or the default if the value is absent
+    fn or_default(&self, sr: StreamReference, value: String, default: Expr) -> String {
+        // an access with an optional default wraps the value of a non-optional stream
+        let optional = matches!(default.ty, Type::Option(_))
+            && !matches!(self.sr2memory[&sr].ty, Type::Option(_));
+        if optional {
+            format!("{value}.or({})", self.expr(default))
+        } else {
+            format!("{value}.unwrap_or({})", self.expr(default))
+        }
+    }
+
+    /// Returns the path of the function of the `libm` crate operating on values of the given type
+    fn float_function(&self, name: &str, ty: &Type) -> String {
+        match ty {
+            Type::Float64 => format!("libm::{name}"),
+            Type::Float32 => format!("libm::{name}f"),
+            ty => unreachable!("{name}({ty:?}) is rejected by the capability check"),
+        }
+    }
+}
+
+impl DefaultConstantFormatter for RustFormatter {
+    fn constant_string(&self, s: String) -> String {
+        format!("{s:?}")
+    }
+
+    fn constant_uint(&self, i: u64, bits: u16) -> String {
+        format!("{i}u{}", integer_bits(bits))
+    }
+
+    fn constant_int(&self, i: i64, bits: u16) -> String {
+        if i < 0 {
+            format!("({i}i{})", integer_bits(bits))
+        } else {
+            format!("{i}i{}", integer_bits(bits))
+        }
+    }
+
+    fn constant_float32(&self, f: f64) -> String {
+        float_constant(f, "f32")
+    }
+
+    fn constant_float64(&self, f: f64) -> String {
+        float_constant(f, "f64")
+    }
+
+    fn constant_tuple(&self, f: Vec<Constant>) -> String {
+        let trailing = if f.len() == 1 { "," } else { "" };
+        format!(
+            "({}{trailing})",
+            f.into_iter()
+                .map(|c| <Self as DefaultExprFormatter>::constant(self, c))
+                .join(", ")
+        )
+    }
+
+    fn constant_none(&self) -> String {
+        "None".into()
+    }
+}
+
+fn float_constant(f: f64, ty: &str) -> String {
+    if f.is_nan() {
+        format!("{ty}::NAN")
+    } else if f.is_infinite() {
+        let sign = if f < 0.0 { "NEG_" } else { "" };
+        format!("{ty}::{sign}INFINITY")
+    } else if f < 0.0 {
+        format!("({f:?}{ty})")
+    } else {
+        format!("{f:?}{ty}")
+    }
+}
+
+impl DefaultOperatorFormatter for RustFormatter {
+    fn op_bitnot(&self) -> String {
+        "!".into()
+    }
+}
+
+impl DefaultFunctionFormatter for RustFormatter {
+    fn function_sqrt(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("sqrt", return_ty)
+    }
+
+    fn function_abs(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => self.float_function("fabs", return_ty),
+            ty => format!("{}::abs", self.ty(ty.clone())),
+        }
+    }
+
+    fn function_sin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("sin", return_ty)
+    }
+
+    fn function_arcsin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("asin", return_ty)
+    }
+
+    fn function_cos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("cos", return_ty)
+    }
+
+    fn function_arccos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("acos", return_ty)
+    }
+
+    fn function_tan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("tan", return_ty)
+    }
+
+    fn function_arctan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("atan", return_ty)
+    }
+
+    fn function_min(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => self.float_function("fmin", return_ty),
+            _ => "core::cmp::min".into(),
+        }
+    }
+
+    fn function_max(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => self.float_function("fmax", return_ty),
+            _ => "core::cmp::max".into(),
+        }
+    }
+}
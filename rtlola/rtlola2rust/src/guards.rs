@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+use rtlola_streamir::{
+    formatter::{expressions::ExprFormatter, guards::DefaultGuardFormatter, names::GetStreamName},
+    ir::{expressions::Expr, LocalFreqRef, StreamReference},
+};
+
+use crate::RustFormatter;
+
+impl DefaultGuardFormatter for RustFormatter {
+    fn stream(&self, sr: StreamReference) -> String {
+        format!(
+            "{}.{}.is_some()",
+            self.event_argument_name(),
+            self.stream_name(sr)
+        )
+    }
+
+    fn alive(&self, sr: StreamReference) -> String {
+        self.alive_flag(sr)
+    }
+
+    fn dynamic(&self, expr: Expr) -> String {
+        self.expr(expr)
+    }
+
+    fn global_freq(&self, duration: Duration) -> String {
+        format!(
+            "{}.due(Duration::from_nanos({}))",
+            self.memory_argument_name(),
+            duration.as_nanos()
+        )
+    }
+
+    fn local_freq(&self, _freq_ref: LocalFreqRef) -> String {
+        unreachable!("local frequencies are rejected by the capability check")
+    }
+
+    fn constant(&self, b: bool) -> String {
+        match b {
+            true => "true",
+            false => "false",
+        }
+        .into()
+    }
+}
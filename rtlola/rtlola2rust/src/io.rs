@@ -0,0 +1,132 @@
+use itertools::Itertools;
+use rtlola_streamir::formatter::{files::FilesFormatter, names::GetStreamName};
+
+use crate::{constructs::RequirementKey, RustFormatter};
+
+impl RustFormatter {
+    pub(crate) fn require_io(&self) {
+        let file = self.monitor_file();
+        self.add_requirement_string(&file, RequirementKey::EventStruct, self.event_struct());
+        self.add_requirement_string(&file, RequirementKey::TriggerEnum, self.trigger_enum());
+        self.add_requirement_string(&file, RequirementKey::VerdictStruct, self.verdict_struct());
+    }
+
+    fn event_struct(&self) -> String {
+        let fields = self
+            .inputs()
+            .map(|i| {
+                format!(
+                    "/// The new value of the input stream `{}`\npub {}: Option<{}>,",
+                    self.sr2memory[&i].name,
+                    self.stream_name(i),
+                    self.stream_ty(i)
+                )
+            })
+            .join("\n");
+        format!(
+            "/// An event given to the monitor, in which each input stream is `None` if it did not receive a new value\n\
+            #[derive(Clone, Copy, Debug, Default, PartialEq)]\n\
+            pub struct {} {{\n{fields}\n}}\n",
+            self.event_struct_name()
+        )
+    }
+
+    fn trigger_enum(&self) -> String {
+        let triggers = self.triggers().map(|o| o.sr()).collect::<Vec<_>>();
+        let variants = triggers
+            .iter()
+            .map(|sr| {
+                format!(
+                    "/// The trigger `{}`\n{},",
+                    self.sr2memory[sr].name,
+                    self.trigger_variant(*sr)
+                )
+            })
+            .join("\n");
+        let names = triggers
+            .iter()
+            .map(|sr| {
+                format!(
+                    "Self::{} => \"{}\",",
+                    self.trigger_variant(*sr),
+                    self.sr2memory[sr].name
+                )
+            })
+            .join("\n");
+        format!(
+            "/// The triggers of the specification\n\
+            #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\n\
+            pub enum {trigger} {{\n{variants}\n}}\n\n\
+            impl {trigger} {{\n\
+            /// Returns the name of the trigger in the specification\n\
+            pub fn name(&self) -> &'static str {{\n\
+            match *self {{\n{names}\n}}\n\
+            }}\n\
+            }}\n",
+            trigger = self.trigger_enum_name()
+        )
+    }
+
+    fn verdict_struct(&self) -> String {
+        let fields = self
+            .verdict_streams
+            .iter()
+            .map(|sr| {
+                format!(
+                    "/// The new value of the stream `{}`, if it was evaluated in the cycle\npub {}: Option<{}>,",
+                    self.sr2memory[sr].name,
+                    self.stream_name(*sr),
+                    self.stream_ty(*sr)
+                )
+            })
+            .chain(Some(format!(
+                "/// The time of the cycle\npub {}: Duration,",
+                self.time_argument_name()
+            )))
+            .join("\n");
+        // only the triggers included in the verdict can be reported as fired
+        let fired = self
+            .triggers()
+            .map(|o| o.sr())
+            .filter(|sr| self.verdict_streams.contains(sr))
+            .collect::<Vec<_>>();
+        format!(
+            "/// The verdict of the monitor for a cycle\n\
+            #[derive(Clone, Copy, Debug, Default, PartialEq)]\n\
+            pub struct {verdict} {{\n{fields}\n}}\n\n\
+            impl {verdict} {{\n\
+            /// Returns the triggers that fired in the cycle\n\
+            pub fn triggers(&self) -> impl Iterator<Item = {trigger}> {{\n\
+            let fired: [({trigger}, bool); {len}] = [{values}];\n\
+            fired.into_iter().filter_map(|(trigger, fired)| fired.then_some(trigger))\n\
+            }}\n\
+            }}\n",
+            verdict = self.verdict_struct_name(),
+            trigger = self.trigger_enum_name(),
+            len = fired.len(),
+            values = fired
+                .iter()
+                .map(|sr| format!(
+                    "({}::{}, self.{}.is_some())",
+                    self.trigger_enum_name(),
+                    self.trigger_variant(*sr),
+                    self.stream_name(*sr)
+                ))
+                .join(", ")
+        )
+    }
+
+    /// Returns the code setting the fields of the verdict for the streams evaluated in the cycle
+    pub(crate) fn build_verdict(&self, verdict: &str) -> String {
+        self.verdict_streams
+            .iter()
+            .map(|sr| {
+                let buffer = self.buffer(*sr).expect("verdict streams have memory");
+                format!(
+                    "if {buffer}.is_fresh() {{\n{verdict}.{} = Some({buffer}.sync());\n}}",
+                    self.stream_name(*sr)
+                )
+            })
+            .join("\n")
+    }
+}
@@ -0,0 +1,407 @@
+//! A backend compiling the StreamIR into a self-contained Rust module.
+//!
+//! The generated module only depends on `core` (and the `libm` crate for floating point functions),
+//! such that it can be included into `no_std` crates.
+//! It contains a typed `Event` struct given to the `Monitor`, which returns a typed `Verdict`
+//! for each event and each deadline of the periodic streams.
+
+pub mod capabilities;
+pub(crate) mod constructs;
+mod expressions;
+mod guards;
+mod io;
+mod memory;
+mod monitor;
+mod names;
+mod statements;
+mod types;
+
+use std::{collections::HashMap, path::PathBuf};
+
+use capabilities::UnsupportedFeatures;
+use constructs::RequirementKey;
+use itertools::Itertools;
+use monitor::{Cycle, MonitorImpl};
+use rtlola_streamir::{
+    formatter::{
+        files::{ConstructStore, ConstructWriteError, FilesFormatter},
+        StreamIrFormatter,
+    },
+    ir::{memory::Memory, OutputReference, StaticSchedule, StreamIr, StreamReference},
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// An error that can happen when compiling the StreamIR to Rust
+pub enum FormatError {
+    #[error("the specification uses features unsupported by the Rust backend:\n{0}")]
+    /// The StreamIR uses features the backend cannot compile
+    Unsupported(#[from] UnsupportedFeatures),
+    #[error(transparent)]
+    /// The generated files could not be written
+    Write(#[from] ConstructWriteError),
+}
+
+pub struct RustFormatter {
+    construct_store: ConstructStore<Self>,
+    sr2memory: HashMap<StreamReference, Memory>,
+    triggers: HashMap<OutputReference, usize>,
+    static_schedule: Option<StaticSchedule>,
+    overwrite: bool,
+    verdict_streams: Vec<StreamReference>,
+    output_dir: PathBuf,
+}
+
+impl RustFormatter {
+    pub fn new(
+        ir: &StreamIr,
+        overwrite: bool,
+        verdict_streams: Vec<StreamReference>,
+        output_dir: PathBuf,
+    ) -> Self {
+        Self {
+            construct_store: ConstructStore::default(),
+            sr2memory: ir.sr2memory.clone(),
+            triggers: ir.triggers.clone(),
+            static_schedule: ir.static_schedule.clone(),
+            overwrite,
+            verdict_streams,
+            output_dir,
+        }
+    }
+}
+
+impl StreamIrFormatter for RustFormatter {
+    type Return = Result<(), FormatError>;
+
+    fn id(&self) -> String {
+        "rust-formatter".into()
+    }
+
+    fn format(self, ir: StreamIr) -> Self::Return {
+        capabilities::check(&ir)?;
+        let StreamIr { stmt, .. } = ir;
+        self.require_header();
+        self.require_memory();
+        self.require_io();
+        self.add_requirement(MonitorImpl);
+        self.add_requirement(Cycle(stmt));
+        Ok(self.generate_files()?)
+    }
+}
+
+impl RustFormatter {
+    fn streams(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory.keys().sorted().copied()
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory
+            .keys()
+            .filter(|o| matches!(o, StreamReference::In(_)))
+            .sorted()
+            .copied()
+    }
+
+    /// Returns the triggers ordered by their index
+    fn triggers(&self) -> impl Iterator<Item = OutputReference> + '_ {
+        self.triggers
+            .iter()
+            .sorted_by_key(|(_, idx)| **idx)
+            .map(|(o, _)| *o)
+    }
+
+    fn require_header(&self) {
+        self.add_requirement_string(
+            self.monitor_file(),
+            RequirementKey::Header,
+            "//! A monitor generated by rtlola2rust.\n\
+            //!\n\
+            //! The module only depends on `core` and the `libm` crate, such that it can be used in `no_std` crates.\n\
+            #![allow(dead_code, non_snake_case, unused_comparisons, unused_mut, unused_parens, unused_variables, clippy::all)]\n\n\
+            use core::time::Duration;\n"
+                .into(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Write,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use itertools::Itertools;
+    use rtlola2c::{main_function::MainFunction, CFormatter};
+    use rtlola_streamir::{
+        capabilities::Feature,
+        formatter::StreamIrFormatter,
+        ir::{text::parse, StreamIr, StreamReference, Type},
+        optimize_all, ParserConfig,
+    };
+
+    use super::{FormatError, RustFormatter};
+
+    const EXAMPLE: &str = include_str!("tests/example.sir");
+
+    /// The functions of the `libm` crate called by the monitors of the tests
+    const LIBM: &str = "pub fn sinf(x: f32) -> f32 { x.sin() }
+pub fn pow(x: f64, y: f64) -> f64 { x.powf(y) }
+pub fn round(x: f64) -> f64 { x.round() }
+pub fn fmax(x: f64, y: f64) -> f64 { x.max(y) }
+pub fn sqrt(x: f64) -> f64 { x.sqrt() }
+pub fn cos(x: f64) -> f64 { x.cos() }
+pub fn acos(x: f64) -> f64 { x.acos() }";
+
+    /// The specifications of the repository, whose monitors are compared with the monitors of the C backend
+    const SPECS: [&str; 2] = ["waypoint_spec_v2.lola", "waypoint_spec_dlr.lola"];
+
+    /// An event of a trace with its time in seconds and the textual values of the inputs, ordered by their reference
+    type Event = (f64, Vec<Option<String>>);
+
+    /// Returns a pseudo-random trace of 50 events, where each input is present with a probability of 3/4
+    fn trace(ir: &StreamIr) -> Vec<Event> {
+        let mut state = 42u64;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        (1..=50)
+            .map(|i| {
+                let values = ir
+                    .inputs()
+                    .sorted()
+                    .map(|i| {
+                        let value = match &ir.stream_memory(StreamReference::In(i)).ty {
+                            Type::Bool => (next(2) == 0).to_string(),
+                            Type::Int(_) => (next(101) as i64 - 50).to_string(),
+                            Type::UInt(_) => next(101).to_string(),
+                            Type::Float32 | Type::Float64 => {
+                                format!("{:.3}", (next(2001) as f64 - 1000.0) / 1000.0)
+                            }
+                            ty => unreachable!("no input of the specifications has type {ty}"),
+                        };
+                        (next(4) != 0).then_some(value)
+                    })
+                    .collect();
+                (i as f64 / 4.0, values)
+            })
+            .collect()
+    }
+
+    /// Returns the verdicts of the outputs for the trace computed by the monitor of the C backend,
+    /// in the CSV format of its main function without the header
+    fn c_verdicts(ir: &StreamIr, trace: &[Event], dir: &Path) -> String {
+        let verdict_streams = ir.outputs().sorted().map(StreamReference::Out).collect();
+        let formatter = CFormatter::new(
+            ir,
+            true,
+            MainFunction::CsvOffline,
+            verdict_streams,
+            dir.to_path_buf(),
+            None,
+        );
+        formatter.format(ir.clone()).unwrap();
+        let compile = Command::new("cc")
+            .current_dir(dir)
+            .args(["-std=c11", "-o", "monitor", "monitor.c", "-lm"])
+            .output()
+            .expect("cc is available");
+        assert!(
+            compile.status.success(),
+            "{}",
+            String::from_utf8_lossy(&compile.stderr)
+        );
+        let csv = trace.iter().fold(
+            ir.inputs()
+                .sorted()
+                .map(|i| ir.name(StreamReference::In(i)))
+                .chain(["time"])
+                .join(",")
+                + "\n",
+            |mut csv, (time, values)| {
+                let values = values.iter().map(|v| v.as_deref().unwrap_or("#"));
+                writeln!(&mut csv, "{},{time}", values.format(",")).unwrap();
+                csv
+            },
+        );
+        std::fs::write(dir.join("trace.csv"), csv).unwrap();
+        let output = Command::new(dir.join("monitor"))
+            .arg(dir.join("trace.csv"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .skip(1)
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    /// Returns a program giving the trace to the monitor and printing the verdicts as the main function of the C backend
+    fn driver(ir: &StreamIr, trace: &[Event]) -> String {
+        let inputs = ir
+            .inputs()
+            .sorted()
+            .map(|i| ir.name(StreamReference::In(i)))
+            .collect::<Vec<_>>();
+        let cells = ir
+            .outputs()
+            .sorted()
+            .map(|o| format!("verdict.{}.cell()", ir.name(StreamReference::Out(o))))
+            .join(", ");
+        let events = trace
+            .iter()
+            .map(|(time, values)| {
+                let fields = inputs
+                    .iter()
+                    .zip(values)
+                    .map(|(name, value)| match value {
+                        Some(value) => format!("{name}: Some({value})"),
+                        None => format!("{name}: None"),
+                    })
+                    .join(", ");
+                format!(
+                    "let verdict = monitor.accept_event(monitor::Event {{ {fields} }}, Duration::from_secs_f64({time:?}));\n\
+                    println!(\"{{}},{{:.6}}\", [{cells}].join(\",\"), verdict.time.as_secs_f64());"
+                )
+            })
+            .join("\n");
+        format!(
+            "mod monitor;
+use core::time::Duration;
+trait Cell {{
+    fn cell(&self) -> String;
+}}
+impl Cell for f64 {{
+    fn cell(&self) -> String {{ format!(\"{{self:.6}}\") }}
+}}
+impl Cell for f32 {{
+    fn cell(&self) -> String {{ format!(\"{{self:.6}}\") }}
+}}
+impl Cell for &str {{
+    fn cell(&self) -> String {{ format!(\"\\\"{{self}}\\\"\") }}
+}}
+macro_rules! display {{
+    ($($ty:ty),*) => {{ $(impl Cell for $ty {{ fn cell(&self) -> String {{ self.to_string() }} }})* }};
+}}
+display!(bool, i8, i16, i32, i64, u8, u16, u32, u64);
+impl<A: Cell, B: Cell> Cell for (A, B) {{
+    fn cell(&self) -> String {{ format!(\"({{}},{{}})\", self.0.cell(), self.1.cell()) }}
+}}
+impl<T: Cell> Cell for Option<T> {{
+    fn cell(&self) -> String {{ self.as_ref().map_or(\"#\".into(), Cell::cell) }}
+}}
+fn main() {{
+    let mut monitor = monitor::Monitor::new(Duration::ZERO);
+{events}
+}}"
+        )
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rtlola2rust-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn rustc(dir: &Path, args: &[&str]) {
+        let output = Command::new("rustc")
+            .current_dir(dir)
+            .args(["--edition", "2021"])
+            .args(args)
+            .output()
+            .expect("rustc is available");
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    #[test]
+    fn compiles() {
+        let ir = parse(EXAMPLE).unwrap_or_else(|e| panic!("{e}"));
+        let verdict_streams = ir.sr2memory.keys().copied().collect();
+        let dir = output_dir("compiles");
+        let formatter = RustFormatter::new(&ir, true, verdict_streams, dir.clone());
+        formatter.format(ir).unwrap();
+
+        std::fs::write(dir.join("libm.rs"), LIBM).unwrap();
+        rustc(&dir, &["--crate-type", "rlib", "libm.rs"]);
+        std::fs::write(
+            dir.join("main.rs"),
+            "mod monitor;
+use core::time::Duration;
+fn main() {
+    let mut monitor = monitor::Monitor::new(Duration::ZERO);
+    let event = monitor::Event { a: Some(1), b: None };
+    let verdict = monitor.accept_event(event, Duration::from_millis(500));
+    println!(\"{:?} {:?} {:?} {:?}\", verdict.d, verdict.g, verdict.t, verdict.triggers().collect::<Vec<_>>());
+    let verdict = monitor.accept_time(Duration::from_secs(1)).unwrap();
+    println!(\"{:?} {:?}\", verdict.p, verdict.time);
+}",
+        )
+        .unwrap();
+        rustc(&dir, &["--extern", "libm=liblibm.rlib", "main.rs"]);
+        let output = Command::new(dir.join("main")).output().unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "Some(2) Some(100) Some(\"a is \\\"large\\\"\") [T]\nSome(1) 1s\n"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn repo_specs() {
+        for spec in SPECS {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join(spec);
+            let ir = rtlola_streamir::parse(&ParserConfig::from_path(path).unwrap()).unwrap();
+            let trace = trace(&ir);
+            let dir = output_dir(&format!("repo-{spec}"));
+            let expected = c_verdicts(&ir, &trace, &dir);
+            std::fs::write(dir.join("libm.rs"), LIBM).unwrap();
+            rustc(&dir, &["--crate-type", "rlib", "libm.rs"]);
+            std::fs::write(dir.join("main.rs"), driver(&ir, &trace)).unwrap();
+            for ir in [ir.clone(), optimize_all(ir).unwrap()] {
+                let verdict_streams = ir.streams().collect();
+                let formatter = RustFormatter::new(&ir, true, verdict_streams, dir.clone());
+                formatter.format(ir).unwrap();
+                rustc(&dir, &["--extern", "libm=liblibm.rlib", "main.rs"]);
+                let output = Command::new(dir.join("main")).output().unwrap();
+                assert_eq!(
+                    String::from_utf8(output.stdout).unwrap(),
+                    expected,
+                    "{spec}"
+                );
+            }
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn unsupported_features() {
+        let ir = parse(&EXAMPLE.replace("\"b\": Float64", "\"b\": Fixed32"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let dir = output_dir("unsupported");
+        let formatter = RustFormatter::new(&ir, true, Vec::new(), dir.clone());
+        let Err(FormatError::Unsupported(unsupported)) = formatter.format(ir) else {
+            panic!("values of type Fixed32 are rejected")
+        };
+        assert!(unsupported
+            .features()
+            .any(|f| *f == Feature::Type(Type::Fixed(32))));
+        assert!(unsupported.to_string().contains(
+            "stream `b` uses values of type Fixed32, which is not supported by the Rust backend"
+        ));
+        assert!(!dir.join("monitor.rs").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,191 @@
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{files::FilesFormatter, names::GetStreamName},
+    ir::{
+        memory::{StreamBuffer, StreamMemory},
+        StreamReference,
+    },
+};
+
+use crate::{constructs::RequirementKey, RustFormatter};
+
+/// The ring buffer storing the last values of a stream, shared by all streams
+const BUFFER: &str = "/// The last `N` values of a stream
+#[derive(Clone, Copy, Debug)]
+struct Buffer<T: Copy + Default, const N: usize> {
+    values: [T; N],
+    valid: [bool; N],
+    current: usize,
+    fresh: bool,
+}
+
+impl<T: Copy + Default, const N: usize> Buffer<T, N> {
+    fn new() -> Self {
+        Self {
+            values: [T::default(); N],
+            valid: [false; N],
+            current: 0,
+            fresh: false,
+        }
+    }
+
+    fn shift(&mut self) {
+        self.current = (self.current + 1) % N;
+    }
+
+    fn push(&mut self, value: T) {
+        self.values[self.current] = value;
+        self.valid[self.current] = true;
+        self.fresh = true;
+    }
+
+    fn sync(&self) -> T {
+        self.values[self.current]
+    }
+
+    fn offset(&self, offset: usize) -> Option<T> {
+        debug_assert!(offset < N);
+        let i = (self.current + N - offset) % N;
+        self.valid[i].then(|| self.values[i])
+    }
+
+    fn get(&self) -> Option<T> {
+        self.fresh.then(|| self.values[self.current])
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.fresh
+    }
+
+    fn reset_fresh(&mut self) {
+        self.fresh = false;
+    }
+
+    fn clear(&mut self) {
+        self.valid = [false; N];
+    }
+}
+";
+
+impl RustFormatter {
+    /// Returns the expression referring to the buffer of the stream, if the stream has memory
+    pub(crate) fn buffer(&self, sr: StreamReference) -> Option<String> {
+        match &self.sr2memory[&sr].buffer {
+            StreamMemory::NoMemory => None,
+            StreamMemory::Static(_) | StreamMemory::Dynamic { .. } => Some(format!(
+                "{}.{}",
+                self.memory_argument_name(),
+                self.stream_name(sr)
+            )),
+            StreamMemory::Instances { .. } => {
+                unreachable!("parameterized streams are rejected by the capability check")
+            }
+        }
+    }
+
+    /// Returns the type of the buffer of the stream, if the stream has memory
+    fn buffer_ty(&self, sr: StreamReference) -> Option<String> {
+        let size = match self.sr2memory[&sr].buffer.buffer()? {
+            StreamBuffer::SingleValue => 1,
+            StreamBuffer::Bounded(size) => *size,
+            StreamBuffer::UnBounded => {
+                unreachable!("unbounded memory is rejected by the capability check")
+            }
+        };
+        Some(format!(
+            "{}<{}, {size}>",
+            self.buffer_struct_name(),
+            self.stream_ty(sr)
+        ))
+    }
+
+    /// Whether the stream is spawned and closed dynamically
+    pub(crate) fn is_dynamic(&self, sr: StreamReference) -> bool {
+        matches!(self.sr2memory[&sr].buffer, StreamMemory::Dynamic { .. })
+    }
+
+    pub(crate) fn alive_flag(&self, sr: StreamReference) -> String {
+        format!(
+            "{}.{}",
+            self.memory_argument_name(),
+            self.alive_field_name(sr)
+        )
+    }
+
+    pub(crate) fn require_memory(&self) {
+        let file = self.monitor_file();
+        self.add_requirement_string(&file, RequirementKey::Buffer, BUFFER.into());
+
+        let buffers = self
+            .streams()
+            .filter_map(|sr| Some((sr, self.buffer_ty(sr)?)))
+            .collect::<Vec<_>>();
+        let dynamic = self
+            .streams()
+            .filter(|sr| self.is_dynamic(*sr))
+            .collect::<Vec<_>>();
+        let start = self.start_field_name();
+        let time = self.time_argument_name();
+        let timed = self.timed_argument_name();
+
+        let fields = buffers
+            .iter()
+            .map(|(sr, ty)| format!("{}: {ty},", self.stream_name(*sr)))
+            .chain(
+                dynamic
+                    .iter()
+                    .map(|sr| format!("{}: bool,", self.alive_field_name(*sr))),
+            )
+            .chain([
+                format!("{start}: Duration,"),
+                format!("{time}: Duration,"),
+                format!("{timed}: bool,"),
+            ])
+            .join("\n");
+        let init = buffers
+            .iter()
+            .map(|(sr, _)| {
+                format!(
+                    "{}: {}::new(),",
+                    self.stream_name(*sr),
+                    self.buffer_struct_name()
+                )
+            })
+            .chain(
+                dynamic
+                    .iter()
+                    .map(|sr| format!("{}: false,", self.alive_field_name(*sr))),
+            )
+            .chain([
+                format!("{start},"),
+                format!("{time}: {start},"),
+                format!("{timed}: false,"),
+            ])
+            .join("\n");
+        self.add_requirement_string(
+            &file,
+            RequirementKey::MemoryStruct,
+            format!(
+                "/// The memory of all streams\n\
+                #[derive(Clone, Debug)]\n\
+                struct {memory} {{\n{fields}\n}}\n\n\
+                impl {memory} {{\n\
+                fn new({start}: Duration) -> Self {{\n\
+                Self {{\n{init}\n}}\n\
+                }}\n\n\
+                /// Whether a periodic stream with the given period is due in the current cycle\n\
+                fn due(&self, period: Duration) -> bool {{\n\
+                self.{timed} && (self.{time} - self.{start}).as_nanos() % period.as_nanos() == 0\n\
+                }}\n\n\
+                /// Resets the fresh values of the streams at the end of a cycle\n\
+                fn reset_fresh(&mut self) {{\n{reset}\n}}\n\
+                }}\n",
+                memory = self.memory_struct_name(),
+                reset = buffers
+                    .iter()
+                    .map(|(sr, _)| format!("self.{}.reset_fresh();", self.stream_name(*sr)))
+                    .join("\n"),
+            ),
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{
+        files::{FilesFormatter, Requirement},
+        statements::StmtFormatter,
+    },
+    ir::Stmt,
+};
+
+use crate::{constructs::RequirementKey, RustFormatter};
+
+/// The public interface of the monitor, evaluating events and the deadlines of the periodic streams
+pub(crate) struct MonitorImpl;
+
+impl Requirement<RustFormatter> for MonitorImpl {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::MonitorImpl
+    }
+
+    fn file(&self, f: &RustFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn format(self, f: &RustFormatter) -> String {
+        let monitor = f.monitor_struct_name();
+        let memory = f.memory_struct_name();
+        let event = f.event_struct_name();
+        let verdict = f.verdict_struct_name();
+        let cycle = f.cycle_function_name();
+        let schedule = f
+            .static_schedule
+            .as_ref()
+            .filter(|schedule| !schedule.deadlines.is_empty());
+
+        let (fields, init, next_deadline, accept_time) = if let Some(schedule) = schedule {
+            f.add_requirement_string(
+                f.monitor_file(),
+                RequirementKey::Deadlines,
+                format!(
+                    "/// The pauses between the deadlines of the periodic streams, repeated after the last deadline\n\
+                    const DEADLINES: [Duration; {}] = [{}];\n",
+                    schedule.deadlines.len(),
+                    schedule
+                        .deadlines
+                        .iter()
+                        .map(|deadline| format!("Duration::from_nanos({})", deadline.pause.as_nanos()))
+                        .join(", ")
+                ),
+            );
+            (
+                "deadline: usize,\nnext_deadline: Duration,",
+                "deadline: 0,\nnext_deadline: start + DEADLINES[0],",
+                "Some(self.next_deadline)",
+                format!(
+                    "if self.next_deadline > time {{\n\
+                    return None;\n\
+                    }}\n\
+                    let deadline = self.next_deadline;\n\
+                    self.deadline = (self.deadline + 1) % DEADLINES.len();\n\
+                    self.next_deadline += DEADLINES[self.deadline];\n\
+                    Some({cycle}(&mut self.memory, &{event}::default(), deadline, true))"
+                ),
+            )
+        } else {
+            ("", "", "None", "None".into())
+        };
+
+        f.add_requirement_string(
+            f.monitor_file(),
+            RequirementKey::MonitorStruct,
+            format!(
+                "/// The monitor for the specification\n\
+                #[derive(Clone, Debug)]\n\
+                pub struct {monitor} {{\n\
+                memory: {memory},\n\
+                {fields}\n\
+                }}\n"
+            ),
+        );
+
+        format!(
+            "impl {monitor} {{\n\
+            /// Creates a new monitor that starts monitoring at the given time\n\
+            pub fn new(start: Duration) -> Self {{\n\
+            Self {{\n\
+            memory: {memory}::new(start),\n\
+            {init}\n\
+            }}\n\
+            }}\n\n\
+            /// Evaluates the monitor for an event at the given time\n\
+            ///\n\
+            /// All deadlines up to the time of the event need to be evaluated before with [{monitor}::accept_time].\n\
+            pub fn accept_event(&mut self, event: {event}, time: Duration) -> {verdict} {{\n\
+            {cycle}(&mut self.memory, &event, time, false)\n\
+            }}\n\n\
+            /// Returns the time of the next deadline of the periodic streams, if there are any\n\
+            pub fn next_deadline(&self) -> Option<Duration> {{\n\
+            {next_deadline}\n\
+            }}\n\n\
+            /// Evaluates the periodic streams at the next deadline if it is not after the given time\n\
+            ///\n\
+            /// Returns `None` if no deadline is due, such that all deadlines up to the given time are evaluated\n\
+            /// by calling this function until it returns `None`.\n\
+            pub fn accept_time(&mut self, time: Duration) -> Option<{verdict}> {{\n\
+            {accept_time}\n\
+            }}\n\
+            }}\n"
+        )
+    }
+}
+
+/// The evaluation of the statement for an event or a deadline, returning the verdict
+pub(crate) struct Cycle(pub(crate) Stmt);
+
+impl Requirement<RustFormatter> for Cycle {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Cycle
+    }
+
+    fn file(&self, f: &RustFormatter) -> PathBuf {
+        f.monitor_file()
+    }
+
+    fn format(self, f: &RustFormatter) -> String {
+        let memory = f.memory_argument_name();
+        let time = f.time_argument_name();
+        let timed = f.timed_argument_name();
+        format!(
+            "fn {cycle}({memory}: &mut {memory_struct}, {event}: &{event_struct}, {time}: Duration, {timed}: bool) -> {verdict_struct} {{\n\
+            {memory}.{time} = {time};\n\
+            {memory}.{timed} = {timed};\n\
+            {stmt}\n\
+            let mut verdict = {verdict_struct} {{\n\
+            {time},\n\
+            ..{verdict_struct}::default()\n\
+            }};\n\
+            {build_verdict}\n\
+            {memory}.reset_fresh();\n\
+            verdict\n\
+            }}\n",
+            cycle = f.cycle_function_name(),
+            memory_struct = f.memory_struct_name(),
+            event = f.event_argument_name(),
+            event_struct = f.event_struct_name(),
+            verdict_struct = f.verdict_struct_name(),
+            stmt = f.stmt(self.0),
+            build_verdict = f.build_verdict("verdict"),
+        )
+    }
+}
@@ -0,0 +1,67 @@
+use rtlola_streamir::{
+    formatter::names::GetStreamName,
+    function_names,
+    ir::{StreamReference, WindowReference},
+};
+
+use crate::RustFormatter;
+
+function_names! {RustFormatter,
+    alive_field_name(stream): "{stream}_alive",
+    temporary_name(num): "tmp_{num}",
+
+    memory_argument_name(): "memory",
+    event_argument_name(): "event",
+    time_argument_name(): "time",
+    timed_argument_name(): "timed",
+    start_field_name(): "start",
+
+    event_struct_name(): "Event",
+    verdict_struct_name(): "Verdict",
+    trigger_enum_name(): "Trigger",
+    memory_struct_name(): "Memory",
+    monitor_struct_name(): "Monitor",
+    buffer_struct_name(): "Buffer",
+    cycle_function_name(): "cycle"
+}
+
+/// The keywords of Rust that are escaped as raw identifiers when used as the name of a stream
+const KEYWORDS: [&str; 38] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "static", "struct",
+    "trait", "true", "type", "unsafe",
+];
+
+impl GetStreamName for RustFormatter {
+    fn stream_name(&self, sr: StreamReference) -> String {
+        let name = &self.sr2memory[&sr].name;
+        if KEYWORDS.contains(&name.as_str()) {
+            format!("r#{name}")
+        } else {
+            name.to_owned()
+        }
+    }
+
+    fn window_name(&self, _sref: WindowReference) -> String {
+        unreachable!("windows are rejected by the capability check")
+    }
+}
+
+impl RustFormatter {
+    /// Returns the variant of the trigger enum representing the trigger
+    pub(crate) fn trigger_variant(&self, sr: StreamReference) -> String {
+        self.sr2memory[&sr]
+            .name
+            .split('_')
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                let mut chars = part.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect::<String>())
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+}
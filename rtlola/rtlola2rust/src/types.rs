@@ -0,0 +1,71 @@
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::types::TypeFormatter,
+    ir::{StreamReference, Type},
+};
+
+use crate::RustFormatter;
+
+/// Returns the number of bits of the smallest Rust integer type holding values with the given number of bits
+pub(crate) fn integer_bits(bits: u16) -> u16 {
+    bits.next_power_of_two().max(8)
+}
+
+impl TypeFormatter for RustFormatter {
+    type Return = String;
+
+    fn type_int(&self, bits: u16) -> Self::Return {
+        format!("i{}", integer_bits(bits))
+    }
+
+    fn type_uint(&self, bits: u16) -> Self::Return {
+        format!("u{}", integer_bits(bits))
+    }
+
+    fn type_bool(&self) -> Self::Return {
+        "bool".into()
+    }
+
+    fn type_string(&self) -> Self::Return {
+        // strings only originate from constants, so they can be borrowed statically
+        "&'static str".into()
+    }
+
+    fn type_float32(&self) -> Self::Return {
+        "f32".into()
+    }
+
+    fn type_float64(&self) -> Self::Return {
+        "f64".into()
+    }
+
+    fn type_option(&self, inner: Type) -> Self::Return {
+        format!("Option<{}>", self.ty(inner))
+    }
+
+    fn type_tuple(&self, inner: Vec<Type>) -> Self::Return {
+        let trailing = if inner.len() == 1 { "," } else { "" };
+        format!(
+            "({}{trailing})",
+            inner.into_iter().map(|ty| self.ty(ty)).join(", ")
+        )
+    }
+
+    fn type_fixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_ufixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_bytes(&self) -> Self::Return {
+        unreachable!("bytes are rejected by the capability check")
+    }
+}
+
+impl RustFormatter {
+    pub(crate) fn stream_ty(&self, sr: StreamReference) -> String {
+        self.ty(self.sr2memory[&sr].ty.clone())
+    }
+}
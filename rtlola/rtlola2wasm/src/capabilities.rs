@@ -0,0 +1,74 @@
+//! The capabilities of the WebAssembly backend, which are checked with the shared check of the StreamIR
+//! (see [rtlola_streamir::capabilities]) before the formatting.
+
+use rtlola_streamir::{
+    capabilities::Capabilities,
+    ir::{
+        expressions::{Function, Operator},
+        StreamIr, Type,
+    },
+};
+
+pub use rtlola_streamir::capabilities::{
+    Feature, FeatureLocation, UnsupportedFeature, UnsupportedFeatures,
+};
+
+/// The features supported by the WebAssembly backend
+struct WasmCapabilities;
+
+impl Capabilities for WasmCapabilities {
+    fn backend(&self) -> &'static str {
+        "WebAssembly"
+    }
+
+    fn supports_type(&self, ty: &Type) -> bool {
+        matches!(
+            ty,
+            Type::Int(_)
+                | Type::UInt(_)
+                | Type::Bool
+                | Type::String
+                | Type::Float32
+                | Type::Float64
+        )
+    }
+
+    fn supports_function(&self, function: Function, ty: &Type) -> bool {
+        match function {
+            Function::Sqrt
+            | Function::Sin
+            | Function::Arcsin
+            | Function::Cos
+            | Function::Arccos
+            | Function::Tan
+            | Function::Arctan => matches!(ty, Type::Float64 | Type::Float32),
+            Function::Abs => matches!(ty, Type::Float64 | Type::Float32 | Type::Int(_)),
+            Function::Min | Function::Max => matches!(
+                ty,
+                Type::Float64 | Type::Float32 | Type::Int(_) | Type::UInt(_)
+            ),
+        }
+    }
+
+    fn supports_operator(&self, op: Operator, lhs: &Type, rhs: &Type) -> bool {
+        match (op, lhs) {
+            (Operator::Pow, Type::Float64 | Type::Float32) => lhs == rhs,
+            (Operator::Pow, _) => false,
+            (Operator::Rem, Type::Float64 | Type::Float32) => false,
+            // strings are compared by their address, which is unique for each constant
+            (Operator::Eq | Operator::Ne, Type::String) => true,
+            (_, Type::String) => false,
+            _ => true,
+        }
+    }
+
+    fn supports_input(&self, ty: &Type) -> bool {
+        // strings only exist as constants in the memory of the module, which the host cannot extend
+        *ty != Type::String
+    }
+}
+
+/// Checks that the WebAssembly backend can compile the StreamIR.
+pub fn check(ir: &StreamIr) -> Result<(), UnsupportedFeatures> {
+    rtlola_streamir::capabilities::check(ir, &WasmCapabilities)
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+
+use rtlola_streamir::formatter::files::{ConstructStore, FilesFormatter};
+
+use crate::WasmFormatter;
+
+impl FilesFormatter for WasmFormatter {
+    type Key = RequirementKey;
+
+    fn get_construct_store(&self) -> &ConstructStore<Self> {
+        &self.construct_store
+    }
+
+    fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum RequirementKey {
+    // The module, where imports need to precede all definitions
+    ModuleStart,
+    Import(&'static str),
+    Memory,
+    Deadlines,
+    Strings,
+    Globals,
+
+    // Internals
+    Buffer,
+    MathFunction(String),
+    Cycle,
+
+    // Exports
+    MonitorFunctions,
+    InputSetters,
+    VerdictAccessors,
+    ModuleEnd,
+
+    // The JSON descriptor
+    Layout,
+}
+
+impl WasmFormatter {
+    pub(crate) fn module_file(&self) -> PathBuf {
+        self.output_dir.join("monitor.wat")
+    }
+
+    pub(crate) fn layout_file(&self) -> PathBuf {
+        self.output_dir.join("layout.json")
+    }
+
+    /// Adds a construct of the module, indented according to the nesting of its parentheses
+    pub(crate) fn add_module_construct(&self, key: RequirementKey, construct: &str) {
+        self.add_requirement_string(self.module_file(), key, indent(construct, 1));
+    }
+}
+
+/// Indents each line of the code by the number of parentheses that are open at its start,
+/// ignoring the parentheses in strings and comments.
+///
+/// Empty lines are only kept between the definitions at the given depth.
+pub(crate) fn indent(code: &str, base: usize) -> String {
+    let mut depth = base;
+    let mut result = String::new();
+    for line in code.lines().map(str::trim) {
+        if line.is_empty() {
+            if depth == base && !result.is_empty() && !result.ends_with("\n\n") {
+                result.push('\n');
+            }
+            continue;
+        }
+        let mut opened = 0usize;
+        let mut closed_first = 0usize;
+        let mut closed = 0usize;
+        let mut chars = line.chars().peekable();
+        let mut leading = true;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    leading = false;
+                    while let Some(c) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                ';' if chars.peek() == Some(&';') => break,
+                '(' => {
+                    leading = false;
+                    opened += 1;
+                }
+                ')' => {
+                    if opened > 0 {
+                        opened -= 1;
+                    } else {
+                        closed += 1;
+                        if leading {
+                            closed_first += 1;
+                        }
+                    }
+                }
+                _ => leading = false,
+            }
+        }
+        let line_depth = depth.saturating_sub(closed_first);
+        result.push_str(&"  ".repeat(line_depth));
+        result.push_str(line);
+        result.push('\n');
+        depth = depth.saturating_sub(closed) + opened;
+    }
+    if result.ends_with("\n\n") {
+        result.pop();
+    }
+    result
+}
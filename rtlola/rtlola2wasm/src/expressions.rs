@@ -0,0 +1,435 @@
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{
+        expressions::{
+            DefaultConstantFormatter, DefaultExprFormatter, DefaultFunctionFormatter,
+            DefaultOperatorFormatter, ExprFormatter, FunctionFormatter,
+        },
+        files::{FilesFormatter, Requirement},
+        types::TypeFormatter,
+    },
+    ir::{
+        expressions::{Constant, Expr, Function, Operator},
+        StreamReference, TempRef, Type, WindowReference,
+    },
+};
+
+use crate::{
+    constructs::{indent, RequirementKey},
+    guards::{and, or},
+    WasmFormatter,
+};
+
+impl DefaultExprFormatter for WasmFormatter {
+    fn sync_access(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        self.load_value(sr, 0)
+    }
+
+    fn offset_access(
+        &self,
+        sr: StreamReference,
+        offset: u32,
+        default: Expr,
+        parameters: Vec<Expr>,
+    ) -> String {
+        assert!(parameters.is_empty());
+        let buffer = self.buffer(sr).expect("accessed streams have memory");
+        format!(
+            "(if (result {}) (i32.load8_u (call $valid {} (i32.const {offset}))) (then {}) (else {}))",
+            self.stream_ty(sr),
+            self.buffer_arguments(buffer),
+            self.load_value(sr, offset),
+            self.expr(default)
+        )
+    }
+
+    fn hold_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        <Self as DefaultExprFormatter>::offset_access(self, sr, 0, default, parameters)
+    }
+
+    fn get_access(&self, sr: StreamReference, default: Expr, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        format!(
+            "(if (result {}) {} (then {}) (else {}))",
+            self.stream_ty(sr),
+            <Self as DefaultExprFormatter>::is_fresh(self, sr, Vec::new()),
+            self.load_value(sr, 0),
+            self.expr(default)
+        )
+    }
+
+    fn is_fresh(&self, sr: StreamReference, parameters: Vec<Expr>) -> String {
+        assert!(parameters.is_empty());
+        let buffer = self.buffer(sr).expect("accessed streams have memory");
+        format!("(i32.load (i32.const {}))", buffer.fresh)
+    }
+
+    fn sliding_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("sliding windows are rejected by the capability check")
+    }
+
+    fn discrete_window_access(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("discrete windows are rejected by the capability check")
+    }
+
+    fn instance_aggregation(&self, _wref: usize, _default: Option<Expr>) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn parameter_access(&self, _sr: StreamReference, _p: usize) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+
+    fn lambda_parameter_access(&self, _wref: WindowReference, _idx: usize) -> String {
+        unreachable!("instance aggregations are rejected by the capability check")
+    }
+
+    fn temporary(&self, temp: TempRef, _ty: &Type) -> String {
+        format!("(local.get {})", self.temporary_name(temp))
+    }
+
+    fn cast(&self, ty: Type, expr: Expr) -> String {
+        let from = expr.ty.clone();
+        let (from_ty, to_ty) = (self.ty(from.clone()), self.ty(ty.clone()));
+        let value = self.expr(expr);
+        let signed = |ty: &Type| if matches!(ty, Type::Int(_)) { "s" } else { "u" };
+        let converted = match (from_ty.as_str(), to_ty.as_str()) {
+            _ if ty == Type::Bool && from != Type::Bool => {
+                format!("({from_ty}.ne {value} ({from_ty}.const 0))")
+            }
+            (from_ty, to_ty) if from_ty == to_ty => value,
+            ("i32", "i64") => format!("(i64.extend_i32_{} {value})", signed(&from)),
+            ("i64", "i32") => format!("(i32.wrap_i64 {value})"),
+            ("f32", "f64") => format!("(f64.promote_f32 {value})"),
+            ("f64", "f32") => format!("(f32.demote_f64 {value})"),
+            ("i32" | "i64", _) => format!("({to_ty}.convert_{from_ty}_{} {value})", signed(&from)),
+            (_, _) => format!("({to_ty}.trunc_sat_{from_ty}_{} {value})", signed(&ty)),
+        };
+        self.wrap(&ty, converted)
+    }
+
+    fn if_then_else(&self, condition: Expr, consequence: Expr, alternative: Expr) -> String {
+        format!(
+            "(if (result {}) {} (then {}) (else {}))",
+            self.ty(consequence.ty.clone()),
+            self.expr(condition),
+            self.expr(consequence),
+            self.expr(alternative)
+        )
+    }
+
+    fn unary(&self, op: Operator, operand: Expr) -> String {
+        let ty = operand.ty.clone();
+        let wasm_ty = self.ty(ty.clone());
+        let operand = self.expr(operand);
+        match (op, &ty) {
+            (Operator::Not, _) => format!("(i32.eqz {operand})"),
+            (Operator::Neg, Type::Float32 | Type::Float64) => format!("({wasm_ty}.neg {operand})"),
+            (Operator::Neg, _) => self.wrap(
+                &ty,
+                format!("({wasm_ty}.sub ({wasm_ty}.const 0) {operand})"),
+            ),
+            (Operator::BitNot, _) => self.wrap(
+                &ty,
+                format!("({wasm_ty}.xor {operand} ({wasm_ty}.const -1))"),
+            ),
+            (op, _) => unreachable!("{op:?} is not an unary operator"),
+        }
+    }
+
+    fn binary(&self, op: Operator, lhs: Expr, rhs: Expr) -> String {
+        let ty = lhs.ty.clone();
+        let wasm_ty = self.ty(ty.clone());
+        let float = matches!(ty, Type::Float32 | Type::Float64);
+        let instruction = match op {
+            Operator::And => return and(self.expr(lhs), self.expr(rhs)),
+            Operator::Or => return or(self.expr(lhs), self.expr(rhs)),
+            Operator::Pow => {
+                let pow = self.float_function("pow", &ty);
+                return format!("({pow} {} {})", self.expr(lhs), self.expr(rhs));
+            }
+            Operator::Shl | Operator::Shr => {
+                // the shifted value and the number of bits need to have the same type
+                let (lhs, rhs) = (self.expr(lhs), self.shift_amount(rhs, &wasm_ty));
+                let shift = match (op, &ty) {
+                    (Operator::Shl, _) => "shl",
+                    (_, Type::Int(_)) => "shr_s",
+                    _ => "shr_u",
+                };
+                return self.wrap(&ty, format!("({wasm_ty}.{shift} {lhs} {rhs})"));
+            }
+            Operator::Add => format!("{wasm_ty}.add"),
+            Operator::Sub => format!("{wasm_ty}.sub"),
+            Operator::Mul => format!("{wasm_ty}.mul"),
+            Operator::Div if float => format!("{wasm_ty}.div"),
+            Operator::Div => self.signed_op("div", &ty),
+            Operator::Rem => self.signed_op("rem", &ty),
+            Operator::BitXor => format!("{wasm_ty}.xor"),
+            Operator::BitAnd => format!("{wasm_ty}.and"),
+            Operator::BitOr => format!("{wasm_ty}.or"),
+            Operator::Eq => format!("{wasm_ty}.eq"),
+            Operator::Ne => format!("{wasm_ty}.ne"),
+            Operator::Lt => self.signed_op("lt", &ty),
+            Operator::Le => self.signed_op("le", &ty),
+            Operator::Gt => self.signed_op("gt", &ty),
+            Operator::Ge => self.signed_op("ge", &ty),
+            Operator::Not | Operator::Neg | Operator::BitNot => {
+                unreachable!("{op:?} is not a binary operator")
+            }
+        };
+        let code = format!("({instruction} {} {})", self.expr(lhs), self.expr(rhs));
+        match op {
+            Operator::Add | Operator::Sub | Operator::Mul | Operator::Div => self.wrap(&ty, code),
+            _ => code,
+        }
+    }
+
+    fn function_call(&self, function: Function, args: Vec<Expr>, ty: &Type) -> String {
+        let arg_tys = args.iter().map(|e| e.ty.clone()).collect::<Vec<_>>();
+        let args = args.into_iter().map(|e| self.expr(e)).join(" ");
+        let function = self.function(function, &arg_tys, ty);
+        self.wrap(ty, format!("({function} {args})"))
+    }
+
+    fn tuple(&self, _inner: Vec<Expr>) -> String {
+        unreachable!("tuples are rejected by the capability check")
+    }
+
+    fn tuple_access(&self, _expr: Expr, _i: usize) -> String {
+        unreachable!("tuples are rejected by the capability check")
+    }
+
+    fn none(&self, _ty: &Type) -> String {
+        unreachable!("optional types are rejected by the capability check")
+    }
+
+    fn some(&self, _inner: Expr, _ty: &Type) -> String {
+        unreachable!("optional types are rejected by the capability check")
+    }
+
+    fn unwrap_or(&self, _expr: Expr, _default: Expr) -> String {
+        unreachable!("optional types are rejected by the capability check")
+    }
+}
+
+impl WasmFormatter {
+    /// Returns the number of bits of a shift converted to the type of the shifted value
+    fn shift_amount(&self, amount: Expr, ty: &str) -> String {
+        let amount_ty = self.ty(amount.ty.clone());
+        let amount = self.expr(amount);
+        match (amount_ty.as_str(), ty) {
+            ("i32", "i64") => format!("(i64.extend_i32_u {amount})"),
+            ("i64", "i32") => format!("(i32.wrap_i64 {amount})"),
+            _ => amount,
+        }
+    }
+
+    /// Returns the function computing the function of the `Math` object of JavaScript on values of the given type
+    fn float_function(&self, name: &'static str, ty: &Type) -> String {
+        match ty {
+            Type::Float64 => self.require_import(name),
+            Type::Float32 => self.require_math_function(name, ty),
+            ty => unreachable!("{name}({ty:?}) is rejected by the capability check"),
+        }
+    }
+
+    /// Imports the function of the `Math` object of JavaScript and returns the instruction calling it
+    fn require_import(&self, name: &'static str) -> String {
+        let parameters = if arity(name) == 2 { "f64 f64" } else { "f64" };
+        self.add_module_construct(
+            RequirementKey::Import(name),
+            &format!(
+                "(import \"Math\" \"{name}\" (func $Math.{name} (param {parameters}) (result f64)))"
+            ),
+        );
+        format!("call $Math.{name}")
+    }
+
+    /// Adds the function as a requirement and returns the instruction calling it
+    fn require_math_function(&self, name: &'static str, ty: &Type) -> String {
+        let function = MathFunction(name, ty.clone());
+        let call = format!("call ${}", function.name(self));
+        self.add_requirement(function);
+        call
+    }
+}
+
+/// Returns the number of arguments of the function of the `Math` object of JavaScript
+fn arity(name: &str) -> usize {
+    match name {
+        "pow" => 2,
+        _ => 1,
+    }
+}
+
+impl DefaultConstantFormatter for WasmFormatter {
+    fn constant_bool(&self, b: bool) -> String {
+        format!("(i32.const {})", b as u8)
+    }
+
+    fn constant_string(&self, s: String) -> String {
+        format!("(i32.const {})", self.string_address(s))
+    }
+
+    fn constant_uint(&self, i: u64, bits: u16) -> String {
+        format!("({}.const {i})", self.type_uint(bits))
+    }
+
+    fn constant_int(&self, i: i64, bits: u16) -> String {
+        format!("({}.const {i})", self.type_int(bits))
+    }
+
+    fn constant_float32(&self, f: f64) -> String {
+        format!("(f32.const {})", float_constant(f))
+    }
+
+    fn constant_float64(&self, f: f64) -> String {
+        format!("(f64.const {})", float_constant(f))
+    }
+
+    fn constant_tuple(&self, _f: Vec<Constant>) -> String {
+        unreachable!("tuples are rejected by the capability check")
+    }
+
+    fn constant_none(&self) -> String {
+        unreachable!("optional types are rejected by the capability check")
+    }
+}
+
+fn float_constant(f: f64) -> String {
+    if f.is_nan() {
+        "nan".into()
+    } else if f.is_infinite() {
+        let sign = if f < 0.0 { "-" } else { "" };
+        format!("{sign}inf")
+    } else {
+        format!("{f:?}")
+    }
+}
+
+impl DefaultOperatorFormatter for WasmFormatter {}
+
+impl DefaultFunctionFormatter for WasmFormatter {
+    fn function_sqrt(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        format!("{}.sqrt", self.ty(return_ty.clone()))
+    }
+
+    fn function_abs(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => format!("{}.abs", self.ty(return_ty.clone())),
+            ty => self.require_math_function("abs", ty),
+        }
+    }
+
+    fn function_sin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("sin", return_ty)
+    }
+
+    fn function_arcsin(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("asin", return_ty)
+    }
+
+    fn function_cos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("cos", return_ty)
+    }
+
+    fn function_arccos(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("acos", return_ty)
+    }
+
+    fn function_tan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("tan", return_ty)
+    }
+
+    fn function_arctan(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        self.float_function("atan", return_ty)
+    }
+
+    fn function_min(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => format!("{}.min", self.ty(return_ty.clone())),
+            ty => self.require_math_function("min", ty),
+        }
+    }
+
+    fn function_max(&self, _arg_tys: &[Type], return_ty: &Type) -> String {
+        match return_ty {
+            Type::Float64 | Type::Float32 => format!("{}.max", self.ty(return_ty.clone())),
+            ty => self.require_math_function("max", ty),
+        }
+    }
+}
+
+/// A function of the module computing a mathematical function on values of the given type
+/// for which WebAssembly has no instruction
+struct MathFunction(&'static str, Type);
+
+impl MathFunction {
+    fn name(&self, f: &WasmFormatter) -> String {
+        let signedness = match self.1 {
+            Type::Int(_) => "_s",
+            Type::UInt(_) => "_u",
+            _ => "",
+        };
+        format!("{}_{}{signedness}", self.0, f.ty(self.1.clone()))
+    }
+}
+
+impl Requirement<WasmFormatter> for MathFunction {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::MathFunction(format!("{}_{:?}", self.0, self.1))
+    }
+
+    fn file(&self, f: &WasmFormatter) -> PathBuf {
+        f.module_file()
+    }
+
+    fn format(self, f: &WasmFormatter) -> String {
+        let name = self.name(f);
+        let ty = f.ty(self.1.clone());
+        let lt = f.signed_op("lt", &self.1);
+        let gt = f.signed_op("gt", &self.1);
+        let (parameters, locals, body) = match self.0 {
+            "abs" => (
+                vec!["x"],
+                String::new(),
+                format!("(select ({ty}.sub ({ty}.const 0) (local.get $x)) (local.get $x) ({lt} (local.get $x) ({ty}.const 0)))"),
+            ),
+            "min" => (
+                vec!["x", "y"],
+                String::new(),
+                format!("(select (local.get $x) (local.get $y) ({lt} (local.get $x) (local.get $y)))"),
+            ),
+            "max" => (
+                vec!["x", "y"],
+                String::new(),
+                format!("(select (local.get $x) (local.get $y) ({gt} (local.get $x) (local.get $y)))"),
+            ),
+            // the functions of the `Math` object of JavaScript operate on 64-bit floats
+            name => {
+                let parameters = ["x", "y"][..arity(name)].to_vec();
+                let call = f.require_import(name);
+                let args = parameters
+                    .iter()
+                    .map(|p| format!("(f64.promote_f32 (local.get ${p}))"))
+                    .join(" ");
+                (
+                    parameters,
+                    String::new(),
+                    format!("(f32.demote_f64 ({call} {args}))"),
+                )
+            }
+        };
+        let parameters = parameters
+            .iter()
+            .map(|p| format!("(param ${p} {ty})"))
+            .join(" ");
+        indent(
+            &format!("(func ${name} {parameters} (result {ty})\n{locals}\n{body})"),
+            1,
+        )
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use rtlola_streamir::{
+    formatter::{
+        expressions::ExprFormatter,
+        guards::{DefaultGuardFormatter, GuardFormatter},
+    },
+    ir::{expressions::Expr, Guard, LocalFreqRef, StreamReference},
+};
+
+use crate::WasmFormatter;
+
+impl DefaultGuardFormatter for WasmFormatter {
+    fn stream(&self, sr: StreamReference) -> String {
+        format!(
+            "(call $present (i32.const {}))",
+            self.layout.input(sr).present
+        )
+    }
+
+    fn alive(&self, sr: StreamReference) -> String {
+        format!("(i32.load (i32.const {}))", self.alive_flag(sr))
+    }
+
+    fn dynamic(&self, expr: Expr) -> String {
+        self.expr(expr)
+    }
+
+    fn global_freq(&self, duration: Duration) -> String {
+        format!("(call $due (i64.const {}))", duration.as_nanos())
+    }
+
+    fn local_freq(&self, _freq_ref: LocalFreqRef) -> String {
+        unreachable!("local frequencies are rejected by the capability check")
+    }
+
+    fn constant(&self, b: bool) -> String {
+        format!("(i32.const {})", b as u8)
+    }
+
+    fn and(&self, lhs: Guard, rhs: Guard) -> String {
+        and(self.guard(lhs), self.guard(rhs))
+    }
+
+    fn or(&self, lhs: Guard, rhs: Guard) -> String {
+        or(self.guard(lhs), self.guard(rhs))
+    }
+}
+
+/// Returns the short-circuiting conjunction of the conditions
+pub(crate) fn and(lhs: String, rhs: String) -> String {
+    format!("(if (result i32) {lhs} (then {rhs}) (else (i32.const 0)))")
+}
+
+/// Returns the short-circuiting disjunction of the conditions
+pub(crate) fn or(lhs: String, rhs: String) -> String {
+    format!("(if (result i32) {lhs} (then (i32.const 1)) (else {rhs}))")
+}
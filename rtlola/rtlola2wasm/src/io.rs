@@ -0,0 +1,59 @@
+use itertools::Itertools;
+
+use crate::{constructs::RequirementKey, WasmFormatter};
+
+impl WasmFormatter {
+    pub(crate) fn require_io(&self) {
+        self.add_module_construct(RequirementKey::InputSetters, &self.input_setters());
+        self.add_module_construct(RequirementKey::VerdictAccessors, &self.verdict_accessors());
+    }
+
+    fn input_setters(&self) -> String {
+        self.layout
+            .inputs
+            .iter()
+            .map(|input| {
+                let ty = &self.sr2memory[&input.id].ty;
+                format!(
+                    ";; Stages the value of the input stream `{}` for the next event\n\
+                    (func (export \"{}\") (param $value {})\n\
+                    ({} (i32.const {}) (local.get $value))\n\
+                    (i32.store (i32.const {}) (i32.const 1)))",
+                    input.name,
+                    input.setter,
+                    input.wasm_type,
+                    self.store(ty),
+                    input.value,
+                    input.present
+                )
+            })
+            .join("\n\n")
+    }
+
+    fn verdict_accessors(&self) -> String {
+        self.verdict_streams
+            .iter()
+            .map(|sr| {
+                let buffer = self.buffer(*sr).expect("verdict streams have memory");
+                let verdict = buffer
+                    .verdict
+                    .as_ref()
+                    .expect("the stream is part of the verdict");
+                format!(
+                    ";; Returns the current value of the stream `{name}`\n\
+                    (func (export \"{}\") (result {})\n\
+                    {})\n\n\
+                    ;; Returns whether the stream `{name}` was evaluated in the last cycle\n\
+                    (func (export \"{}\") (result i32)\n\
+                    (i32.load (i32.const {})))",
+                    verdict.value,
+                    buffer.wasm_type,
+                    self.load_value(*sr, 0),
+                    verdict.fresh,
+                    buffer.fresh,
+                    name = buffer.name,
+                )
+            })
+            .join("\n\n")
+    }
+}
@@ -0,0 +1,324 @@
+//! The layout of the linear memory of the generated module, written as a JSON descriptor next to the module.
+//!
+//! The memory starts with the staged event, containing the value of each input stream and whether it is present.
+//! It is followed by the memory of each stream, the pauses between the deadlines of the periodic streams and the string constants.
+//! All addresses are absolute byte addresses into the exported memory, where values are stored in little endian.
+
+use rtlola_streamir::ir::{memory::StreamBuffer, StreamReference};
+use serde::Serialize;
+
+use crate::WasmFormatter;
+
+/// The version of the JSON descriptor, incremented on breaking changes
+const VERSION: u32 = 1;
+/// The number of bytes of the staged value of an input stream, followed by the flag whether the value is present
+const EVENT_VALUE_SIZE: u32 = 8;
+/// The number of bytes of the staged value of an input stream and the flag whether it is present
+const EVENT_SLOT_SIZE: u32 = 16;
+/// The number of bytes of the header of the memory of a stream, holding the index of the current value,
+/// whether the stream is fresh and whether it is alive
+pub(crate) const HEADER_SIZE: u32 = 16;
+/// The number of bytes of a page of the linear memory
+const PAGE_SIZE: u32 = 65536;
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The layout of the linear memory and the exported functions of the generated module
+pub struct Layout {
+    /// The version of the descriptor
+    pub version: u32,
+    /// The exported memory
+    pub memory: MemoryLayout,
+    /// The names of the exported functions driving the monitor
+    pub functions: Functions,
+    /// The staged values of the input streams
+    pub inputs: Vec<InputLayout>,
+    /// The memory of the streams, where streams without memory are omitted
+    pub streams: Vec<StreamLayout>,
+    /// The triggers ordered by their index
+    pub triggers: Vec<TriggerLayout>,
+    /// The pauses between the deadlines of the periodic streams, if there are any
+    pub deadlines: Option<DeadlinesLayout>,
+    /// The string constants, each stored as its length (a 32-bit integer) followed by its UTF-8 bytes
+    pub strings: Vec<StringLayout>,
+    #[serde(skip)]
+    /// The address after the staged event
+    pub(crate) event_end: u32,
+    #[serde(skip)]
+    /// The address after the memory of the streams
+    pub(crate) streams_end: u32,
+    #[serde(skip)]
+    /// The address of the first string constant
+    pub(crate) strings_start: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The exported linear memory
+pub struct MemoryLayout {
+    /// The name of the export
+    pub export: String,
+    /// The number of bytes used by the monitor
+    pub size: u32,
+    /// The number of pages of the memory
+    pub pages: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+/// The names of the exported functions driving the monitor, where time is given as a 64-bit integer in nanoseconds
+pub struct Functions {
+    /// Resets the monitor to start monitoring at the given time
+    pub init: String,
+    /// Evaluates the staged event at the given time
+    pub cycle: String,
+    /// Returns the time of the next deadline of the periodic streams, or -1 if there are none
+    pub next_deadline: String,
+    /// Evaluates the next deadline if it is not after the given time, returning whether it was evaluated
+    pub cycle_deadline: String,
+    /// Returns the time of the last cycle
+    pub time: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The staged value of an input stream
+pub struct InputLayout {
+    /// The reference of the input stream in the StreamIR
+    pub id: StreamReference,
+    /// The name of the input stream
+    pub name: String,
+    #[serde(rename = "type")]
+    /// The type of the input stream
+    pub ty: String,
+    /// The type of the values in WebAssembly
+    pub wasm_type: String,
+    /// The exported function staging a value of the input stream for the next event
+    pub setter: String,
+    /// The address of the staged value
+    pub value: u32,
+    /// The address of the flag (a 32-bit integer) whether a value is staged
+    pub present: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The memory of a stream, storing its last values in a ring buffer
+pub struct StreamLayout {
+    /// The reference of the stream in the StreamIR
+    pub id: StreamReference,
+    /// The name of the stream
+    pub name: String,
+    #[serde(rename = "type")]
+    /// The type of the stream
+    pub ty: String,
+    /// The type of the values in WebAssembly
+    pub wasm_type: String,
+    /// The address of the memory of the stream
+    pub address: u32,
+    /// The number of bytes of the memory of the stream
+    pub size: u32,
+    /// The number of values stored for the stream
+    pub length: u32,
+    /// The number of bytes of a single value
+    pub value_size: u32,
+    /// The address of the index (a 32-bit integer) of the current value
+    pub current: u32,
+    /// The address of the flag (a 32-bit integer) whether the stream was evaluated in the last cycle
+    pub fresh: u32,
+    /// The address of the flag (a 32-bit integer) whether the stream is alive, if it is spawned dynamically
+    pub alive: Option<u32>,
+    /// The address of the values, where the value with the index `i` is stored at `values + i * value_size`
+    pub values: u32,
+    /// The address of the flags (a byte each) whether the values are valid
+    pub valid: u32,
+    /// The exported functions returning the verdict of the stream, if the stream is part of the verdict
+    pub verdict: Option<VerdictAccessors>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The exported functions returning the verdict of a stream after a cycle
+pub struct VerdictAccessors {
+    /// Returns the current value of the stream
+    pub value: String,
+    /// Returns whether the stream was evaluated in the last cycle
+    pub fresh: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A trigger of the specification
+pub struct TriggerLayout {
+    /// The reference of the output stream of the trigger in the StreamIR
+    pub id: StreamReference,
+    /// The name of the output stream of the trigger
+    pub name: String,
+    /// The index of the trigger
+    pub index: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// The pauses between the deadlines of the periodic streams, repeated after the last deadline
+pub struct DeadlinesLayout {
+    /// The address of the pauses (64-bit integers in nanoseconds)
+    pub address: u32,
+    /// The number of deadlines
+    pub count: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+/// A string constant, represented by its address
+pub struct StringLayout {
+    /// The address of the length of the string, followed by its bytes
+    pub address: u32,
+    /// The string
+    pub value: String,
+}
+
+impl StringLayout {
+    /// Returns the address after the string
+    fn end(&self) -> u32 {
+        self.address + 4 + self.value.len() as u32
+    }
+}
+
+impl Layout {
+    pub(crate) fn new(f: &WasmFormatter) -> Self {
+        let mut address = 0;
+        let inputs = f
+            .inputs()
+            .map(|sr| {
+                let value = address;
+                address += EVENT_SLOT_SIZE;
+                InputLayout {
+                    id: sr,
+                    name: f.sr2memory[&sr].name.clone(),
+                    ty: f.sr2memory[&sr].ty.to_string(),
+                    wasm_type: f.stream_ty(sr),
+                    setter: f.setter_name(sr),
+                    value,
+                    present: value + EVENT_VALUE_SIZE,
+                }
+            })
+            .collect();
+        let event_end = address;
+
+        let streams = f
+            .streams()
+            .filter_map(|sr| {
+                let length = match f.sr2memory[&sr].buffer.buffer()? {
+                    StreamBuffer::SingleValue => 1,
+                    StreamBuffer::Bounded(length) => *length as u32,
+                    StreamBuffer::UnBounded => {
+                        unreachable!("unbounded memory is rejected by the capability check")
+                    }
+                };
+                let value_size = f.value_size(&f.sr2memory[&sr].ty);
+                let values = address + HEADER_SIZE;
+                let valid = values + length * value_size;
+                let stream = StreamLayout {
+                    id: sr,
+                    name: f.sr2memory[&sr].name.clone(),
+                    ty: f.sr2memory[&sr].ty.to_string(),
+                    wasm_type: f.stream_ty(sr),
+                    address,
+                    size: align(valid + length, 8) - address,
+                    length,
+                    value_size,
+                    current: address,
+                    fresh: address + 4,
+                    alive: f.is_dynamic(sr).then_some(address + 8),
+                    values,
+                    valid,
+                    verdict: f.verdict_streams.contains(&sr).then(|| VerdictAccessors {
+                        value: f.value_accessor_name(sr),
+                        fresh: f.fresh_accessor_name(sr),
+                    }),
+                };
+                address += stream.size;
+                Some(stream)
+            })
+            .collect();
+        let streams_end = address;
+
+        let deadlines = f.static_schedule.as_ref().map(|schedule| {
+            let deadlines = DeadlinesLayout {
+                address,
+                count: schedule.deadlines.len() as u32,
+            };
+            address += 8 * deadlines.count;
+            deadlines
+        });
+
+        Self {
+            version: VERSION,
+            memory: MemoryLayout {
+                export: f.memory_export_name(),
+                size: address,
+                pages: pages(address),
+            },
+            functions: Functions {
+                init: f.init_function_name(),
+                cycle: f.cycle_function_name(),
+                next_deadline: f.next_deadline_function_name(),
+                cycle_deadline: f.deadline_function_name(),
+                time: f.time_function_name(),
+            },
+            inputs,
+            streams,
+            triggers: f
+                .triggers()
+                .map(|o| TriggerLayout {
+                    id: o.sr(),
+                    name: f.sr2memory[&o.sr()].name.clone(),
+                    index: f.triggers[&o],
+                })
+                .collect(),
+            deadlines,
+            strings: Vec::new(),
+            event_end,
+            streams_end,
+            strings_start: address,
+        }
+    }
+
+    /// Returns the memory of the stream
+    pub(crate) fn stream(&self, sr: StreamReference) -> Option<&StreamLayout> {
+        self.streams.iter().find(|stream| stream.id == sr)
+    }
+
+    /// Returns the staged value of the input stream
+    pub(crate) fn input(&self, sr: StreamReference) -> &InputLayout {
+        self.inputs
+            .iter()
+            .find(|input| input.id == sr)
+            .expect("the stream is an input stream")
+    }
+
+    /// Adds the string constants at the end of the memory
+    pub(crate) fn with_strings(mut self, strings: Vec<StringLayout>) -> Self {
+        let size = strings.last().map_or(self.strings_start, StringLayout::end);
+        self.memory.size = size;
+        self.memory.pages = pages(size);
+        self.strings = strings;
+        self
+    }
+}
+
+impl WasmFormatter {
+    /// Returns the address of the string constant, adding it to the constants if it is new
+    pub(crate) fn string_address(&self, s: String) -> u32 {
+        let mut strings = self.static_strings.lock().unwrap();
+        if let Some(string) = strings.iter().find(|string| string.value == s) {
+            return string.address;
+        }
+        let address = strings
+            .last()
+            .map_or(self.layout.strings_start, |string| align(string.end(), 4));
+        strings.push(StringLayout { address, value: s });
+        address
+    }
+}
+
+fn align(address: u32, alignment: u32) -> u32 {
+    address.next_multiple_of(alignment)
+}
+
+/// Returns the number of pages holding the given number of bytes, which is at least one
+fn pages(size: u32) -> u32 {
+    size.div_ceil(PAGE_SIZE).max(1)
+}
@@ -0,0 +1,437 @@
+//! A backend compiling the StreamIR into a WebAssembly module in the text format.
+//!
+//! The memory of the streams is laid out in the linear memory of the module, which is exported such that a host
+//! (e.g., a browser) can read the values of the streams directly.
+//! Besides the module, the backend writes a JSON descriptor of this [layout::Layout], containing the addresses
+//! of the streams, their references in the StreamIR and the names of the exported functions.
+
+pub mod capabilities;
+pub(crate) mod constructs;
+mod expressions;
+mod guards;
+mod io;
+pub mod layout;
+mod memory;
+mod monitor;
+mod names;
+mod statements;
+mod types;
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Mutex,
+};
+
+use capabilities::UnsupportedFeatures;
+use constructs::RequirementKey;
+use itertools::Itertools;
+use layout::{Layout, StringLayout};
+use monitor::{Cycle, MonitorFunctions};
+use rtlola_streamir::{
+    formatter::{
+        files::{ConstructStore, ConstructWriteError, FilesFormatter},
+        StreamIrFormatter,
+    },
+    ir::{
+        memory::Memory, OutputReference, StaticSchedule, StreamIr, StreamReference, TempRef, Type,
+    },
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+/// An error that can happen when compiling the StreamIR to WebAssembly
+pub enum FormatError {
+    #[error("the specification uses features unsupported by the WebAssembly backend:\n{0}")]
+    /// The StreamIR uses features the backend cannot compile
+    Unsupported(#[from] UnsupportedFeatures),
+    #[error(transparent)]
+    /// The generated files could not be written
+    Write(#[from] ConstructWriteError),
+}
+
+pub struct WasmFormatter {
+    construct_store: ConstructStore<Self>,
+    sr2memory: HashMap<StreamReference, Memory>,
+    triggers: HashMap<OutputReference, usize>,
+    static_schedule: Option<StaticSchedule>,
+    layout: Layout,
+    static_strings: Mutex<Vec<StringLayout>>,
+    temporaries: Mutex<BTreeMap<TempRef, Type>>,
+    overwrite: bool,
+    verdict_streams: Vec<StreamReference>,
+    output_dir: PathBuf,
+}
+
+impl WasmFormatter {
+    /// Creates the formatter for the StreamIR and lays out the memory of its streams.
+    ///
+    /// As the layout depends on the types of the streams, the check for unsupported features
+    /// happens here instead of when formatting.
+    pub fn new(
+        ir: &StreamIr,
+        overwrite: bool,
+        verdict_streams: Vec<StreamReference>,
+        output_dir: PathBuf,
+    ) -> Result<Self, FormatError> {
+        capabilities::check(ir)?;
+        let mut formatter = Self {
+            construct_store: ConstructStore::default(),
+            sr2memory: ir.sr2memory.clone(),
+            triggers: ir.triggers.clone(),
+            static_schedule: ir
+                .static_schedule
+                .clone()
+                .filter(|schedule| !schedule.deadlines.is_empty()),
+            layout: Layout::default(),
+            static_strings: Mutex::new(Vec::new()),
+            temporaries: Mutex::new(BTreeMap::new()),
+            overwrite,
+            verdict_streams,
+            output_dir,
+        };
+        formatter.layout = Layout::new(&formatter);
+        Ok(formatter)
+    }
+
+    /// Returns the layout of the linear memory of the module
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+}
+
+impl StreamIrFormatter for WasmFormatter {
+    type Return = Result<(), FormatError>;
+
+    fn id(&self) -> String {
+        "wasm-formatter".into()
+    }
+
+    fn format(self, ir: StreamIr) -> Self::Return {
+        let StreamIr { stmt, .. } = ir;
+        self.require_module();
+        self.require_buffer();
+        self.require_io();
+        self.add_requirement(MonitorFunctions);
+        self.add_requirement(Cycle(stmt));
+        // the string constants are only known after formatting all expressions
+        let strings = std::mem::take(&mut *self.static_strings.lock().unwrap());
+        let layout = self.layout.clone().with_strings(strings);
+        self.require_data(&layout);
+        self.add_requirement_string(
+            self.layout_file(),
+            RequirementKey::Layout,
+            serde_json::to_string_pretty(&layout).expect("the layout is serializable") + "\n",
+        );
+        Ok(self.generate_files()?)
+    }
+}
+
+impl WasmFormatter {
+    fn streams(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory.keys().sorted().copied()
+    }
+
+    fn inputs(&self) -> impl Iterator<Item = StreamReference> + '_ {
+        self.sr2memory
+            .keys()
+            .filter(|o| matches!(o, StreamReference::In(_)))
+            .sorted()
+            .copied()
+    }
+
+    /// Returns the triggers ordered by their index
+    fn triggers(&self) -> impl Iterator<Item = OutputReference> + '_ {
+        self.triggers
+            .iter()
+            .sorted_by_key(|(_, idx)| **idx)
+            .map(|(o, _)| *o)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Write,
+        path::{Path, PathBuf},
+        process::Command,
+    };
+
+    use itertools::Itertools;
+    use rtlola2c::{main_function::MainFunction, CFormatter};
+    use rtlola_streamir::{
+        capabilities::Feature,
+        formatter::StreamIrFormatter,
+        ir::{text::parse, StreamIr, StreamReference, Type},
+        optimize_all, ParserConfig,
+    };
+    use wasmparser::Validator;
+
+    use super::{FormatError, WasmFormatter};
+
+    const EXAMPLE: &str = include_str!("tests/example.sir");
+
+    /// The specifications of the repository, whose monitors are compared with the monitors of the C backend
+    const SPECS: [&str; 2] = ["waypoint_spec_v1.lola", "waypoint_spec_v2.lola"];
+
+    /// An event of a trace with its time in seconds and the textual values of the inputs, ordered by their reference
+    type Event = (f64, Vec<Option<String>>);
+
+    /// Returns a pseudo-random trace of 50 events, where each input is present with a probability of 3/4
+    fn trace(ir: &StreamIr) -> Vec<Event> {
+        let mut state = 42u64;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        (1..=50)
+            .map(|i| {
+                let values = ir
+                    .inputs()
+                    .sorted()
+                    .map(|i| {
+                        let value = match &ir.stream_memory(StreamReference::In(i)).ty {
+                            Type::Bool => (next(2) == 0).to_string(),
+                            Type::Int(_) => (next(101) as i64 - 50).to_string(),
+                            Type::UInt(_) => next(101).to_string(),
+                            Type::Float32 | Type::Float64 => {
+                                format!("{:.3}", (next(2001) as f64 - 1000.0) / 1000.0)
+                            }
+                            ty => unreachable!("no input of the specifications has type {ty}"),
+                        };
+                        (next(4) != 0).then_some(value)
+                    })
+                    .collect();
+                (i as f64 / 4.0, values)
+            })
+            .collect()
+    }
+
+    /// Returns the verdicts of the outputs for the trace computed by the monitor of the C backend,
+    /// in the CSV format of its main function without the header
+    fn c_verdicts(ir: &StreamIr, trace: &[Event], dir: &Path) -> String {
+        let verdict_streams = ir.outputs().sorted().map(StreamReference::Out).collect();
+        let formatter = CFormatter::new(
+            ir,
+            true,
+            MainFunction::CsvOffline,
+            verdict_streams,
+            dir.to_path_buf(),
+            None,
+        );
+        formatter.format(ir.clone()).unwrap();
+        let compile = Command::new("cc")
+            .current_dir(dir)
+            .args(["-std=c11", "-o", "monitor", "monitor.c", "-lm"])
+            .output()
+            .expect("cc is available");
+        assert!(
+            compile.status.success(),
+            "{}",
+            String::from_utf8_lossy(&compile.stderr)
+        );
+        let csv = trace.iter().fold(
+            ir.inputs()
+                .sorted()
+                .map(|i| ir.name(StreamReference::In(i)))
+                .chain(["time"])
+                .join(",")
+                + "\n",
+            |mut csv, (time, values)| {
+                let values = values.iter().map(|v| v.as_deref().unwrap_or("#"));
+                writeln!(&mut csv, "{},{time}", values.format(",")).unwrap();
+                csv
+            },
+        );
+        std::fs::write(dir.join("trace.csv"), csv).unwrap();
+        let output = Command::new(dir.join("monitor"))
+            .arg(dir.join("trace.csv"))
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        stdout
+            .lines()
+            .skip(1)
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    /// Returns how the driver converts between the values of the given type and JavaScript values
+    fn kind(ty: &Type) -> &'static str {
+        match ty {
+            Type::Bool => "bool",
+            Type::String => "string",
+            Type::Int(64) | Type::UInt(64) => "bigint",
+            Type::Int(_) | Type::UInt(_) => "int",
+            Type::Float32 | Type::Float64 => "float",
+            ty => unreachable!("no stream of the specifications has type {ty}"),
+        }
+    }
+
+    /// Returns a Node.js script giving the trace to the monitor and printing the verdicts as the main function of the C backend
+    fn driver(ir: &StreamIr, trace: &[Event]) -> String {
+        let inputs = ir
+            .inputs()
+            .sorted()
+            .map(|i| {
+                let sr = StreamReference::In(i);
+                format!("[{:?}, {:?}]", ir.name(sr), kind(&ir.stream_memory(sr).ty))
+            })
+            .join(", ");
+        let outputs = ir
+            .outputs()
+            .sorted()
+            .map(|o| {
+                let sr = StreamReference::Out(o);
+                format!("[{:?}, {:?}]", ir.name(sr), kind(&ir.stream_memory(sr).ty))
+            })
+            .join(", ");
+        let events = trace
+            .iter()
+            .map(|(time, values)| {
+                let values = values
+                    .iter()
+                    .map(|v| v.as_ref().map_or("null".into(), |v| format!("{v:?}")))
+                    .join(", ");
+                format!("[{}n, [{values}]]", (time * 1e9) as u64)
+            })
+            .join(",\n    ");
+        format!(
+            r##"const fs = require("fs");
+const layout = JSON.parse(fs.readFileSync(__dirname + "/layout.json"));
+const strings = new Map(layout.strings.map((s) => [s.address, s.value]));
+const verdicts = new Map(layout.streams.map((s) => [s.name, s.verdict]));
+const inputs = [{inputs}];
+const outputs = [{outputs}];
+const events = [
+    {events}
+];
+const value = {{
+    bool: (v) => (v === "true" ? 1 : 0),
+    int: Number,
+    bigint: BigInt,
+    float: Number,
+}};
+const cell = {{
+    bool: (v) => (v ? "true" : "false"),
+    string: (v) => `"${{strings.get(v)}}"`,
+    int: String,
+    bigint: String,
+    float: (v) => (Object.is(v, -0) ? "-" : "") + v.toFixed(6),
+}};
+WebAssembly.instantiate(fs.readFileSync(__dirname + "/monitor.wasm"), {{ Math }}).then(({{ instance }}) => {{
+    const monitor = instance.exports;
+    monitor[layout.functions.init](0n);
+    for (const [time, values] of events) {{
+        inputs.forEach(([name, kind], i) => {{
+            if (values[i] !== null) monitor[`set_${{name}}`](value[kind](values[i]));
+        }});
+        monitor[layout.functions.cycle](time);
+        const cells = outputs.map(([name, kind]) => {{
+            const verdict = verdicts.get(name);
+            return monitor[verdict.fresh]() ? cell[kind](monitor[verdict.value]()) : "#";
+        }});
+        cells.push((Number(monitor[layout.functions.time]()) / 1e9).toFixed(6));
+        console.log(cells.join(","));
+    }}
+}});
+"##
+        )
+    }
+
+    fn output_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rtlola2wasm-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn valid_module() {
+        let ir = parse(EXAMPLE).unwrap_or_else(|e| panic!("{e}"));
+        let verdict_streams = ir.sr2memory.keys().copied().collect();
+        let dir = output_dir("valid");
+        let formatter = WasmFormatter::new(&ir, true, verdict_streams, dir.clone()).unwrap();
+        formatter.format(ir).unwrap();
+
+        let wasm = wat::parse_file(dir.join("monitor.wat")).unwrap();
+        Validator::new().validate_all(&wasm).unwrap();
+
+        let layout: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(dir.join("layout.json")).unwrap())
+                .unwrap();
+        assert_eq!(layout["inputs"].as_array().unwrap().len(), 2);
+        assert_eq!(layout["streams"].as_array().unwrap().len(), 10);
+        assert_eq!(layout["triggers"][0]["name"], "t");
+        assert_eq!(layout["deadlines"]["count"], 2);
+        assert_eq!(layout["strings"][0]["value"], "a is \"large\"");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn repo_specs() {
+        for spec in SPECS {
+            let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("..")
+                .join(spec);
+            let ir = rtlola_streamir::parse(&ParserConfig::from_path(path).unwrap()).unwrap();
+            let trace = trace(&ir);
+            let dir = output_dir(&format!("repo-{spec}"));
+            let expected = c_verdicts(&ir, &trace, &dir);
+            std::fs::write(dir.join("main.js"), driver(&ir, &trace)).unwrap();
+            for ir in [ir.clone(), optimize_all(ir).unwrap()] {
+                let verdict_streams = ir.streams().collect();
+                let formatter =
+                    WasmFormatter::new(&ir, true, verdict_streams, dir.clone()).unwrap();
+                formatter.format(ir).unwrap();
+                let wasm = wat::parse_file(dir.join("monitor.wat")).unwrap();
+                std::fs::write(dir.join("monitor.wasm"), wasm).unwrap();
+                let output = Command::new("node")
+                    .arg(dir.join("main.js"))
+                    .output()
+                    .expect("node is available");
+                assert!(
+                    output.status.success(),
+                    "{}",
+                    String::from_utf8_lossy(&output.stderr)
+                );
+                assert_eq!(
+                    String::from_utf8(output.stdout).unwrap(),
+                    expected,
+                    "{spec}"
+                );
+            }
+            std::fs::remove_dir_all(dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn unsupported_repo_spec() {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../waypoint_spec_dlr.lola");
+        let ir = rtlola_streamir::parse(&ParserConfig::from_path(path).unwrap()).unwrap();
+        let dir = output_dir("unsupported-repo-spec");
+        let Err(FormatError::Unsupported(unsupported)) =
+            WasmFormatter::new(&ir, true, Vec::new(), dir.clone())
+        else {
+            panic!("the tuples of waypoint_spec_dlr.lola are rejected")
+        };
+        assert!(unsupported
+            .features()
+            .all(|f| matches!(f, Feature::Type(Type::Tuple(_)))));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn unsupported_types() {
+        let ir = parse(&EXAMPLE.replace("\"b\": Float64", "\"b\": Option<Float64>"))
+            .unwrap_or_else(|e| panic!("{e}"));
+        let dir = output_dir("unsupported");
+        assert!(matches!(
+            WasmFormatter::new(&ir, true, Vec::new(), dir.clone()),
+            Err(FormatError::Unsupported(_))
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use itertools::Itertools;
+use rtlola_streamir::ir::{memory::StreamMemory, StreamReference};
+
+use crate::{
+    constructs::RequirementKey,
+    layout::{Layout, StreamLayout},
+    WasmFormatter,
+};
+
+/// The functions operating on the memory of a stream, shared by all streams.
+///
+/// The memory of a stream starts with the index of its current value (at offset 0), the flag whether it is fresh (at offset 4)
+/// and the flag whether it is alive (at offset 8), followed by its `length` values and a byte for each value whether it is valid.
+const BUFFER: &str = ";; Returns the index of the value with the given offset in the memory of a stream
+(func $index (param $stream i32) (param $length i32) (param $offset i32) (result i32)
+(i32.rem_u
+(i32.sub (i32.add (i32.load (local.get $stream)) (local.get $length)) (local.get $offset))
+(local.get $length)))
+
+;; Returns the address of the value with the given offset in the memory of a stream
+(func $value (param $stream i32) (param $length i32) (param $size i32) (param $offset i32) (result i32)
+(i32.add
+(i32.add (local.get $stream) (i32.const 16))
+(i32.mul (call $index (local.get $stream) (local.get $length) (local.get $offset)) (local.get $size))))
+
+;; Returns the address of the flag whether the value with the given offset in the memory of a stream is valid
+(func $valid (param $stream i32) (param $length i32) (param $size i32) (param $offset i32) (result i32)
+(i32.add
+(i32.add (local.get $stream) (i32.add (i32.const 16) (i32.mul (local.get $length) (local.get $size))))
+(call $index (local.get $stream) (local.get $length) (local.get $offset))))
+
+;; Moves the current value of a stream to the next slot of its memory
+(func $shift (param $stream i32) (param $length i32)
+(i32.store (local.get $stream)
+(i32.rem_u (i32.add (i32.load (local.get $stream)) (i32.const 1)) (local.get $length))))
+
+;; Marks the current value of a stream as valid and the stream as fresh, after the value was stored
+(func $push (param $stream i32) (param $length i32) (param $size i32)
+(i32.store8 (call $valid (local.get $stream) (local.get $length) (local.get $size) (i32.const 0)) (i32.const 1))
+(i32.store offset=4 (local.get $stream) (i32.const 1)))
+
+;; Invalidates all values of a stream
+(func $clear (param $stream i32) (param $length i32) (param $size i32)
+(memory.fill
+(i32.add (local.get $stream) (i32.add (i32.const 16) (i32.mul (local.get $length) (local.get $size))))
+(i32.const 0)
+(local.get $length)))
+
+;; Whether the staged value of an input is present, which is never the case at a deadline
+(func $present (param $input i32) (result i32)
+(i32.and (i32.eqz (global.get $timed)) (i32.load (local.get $input))))
+
+;; Whether a periodic stream with the given period in nanoseconds is due in the current cycle
+(func $due (param $period i64) (result i32)
+(i32.and
+(global.get $timed)
+(i64.eqz (i64.rem_u (i64.sub (global.get $time) (global.get $start)) (local.get $period)))))
+";
+
+impl WasmFormatter {
+    /// Returns the memory of the stream, if the stream has memory
+    pub(crate) fn buffer(&self, sr: StreamReference) -> Option<&StreamLayout> {
+        match &self.sr2memory[&sr].buffer {
+            StreamMemory::NoMemory => None,
+            StreamMemory::Static(_) | StreamMemory::Dynamic { .. } => self.layout.stream(sr),
+            StreamMemory::Instances { .. } => {
+                unreachable!("parameterized streams are rejected by the capability check")
+            }
+        }
+    }
+
+    /// Returns the arguments identifying the memory of the stream for the functions operating on it
+    pub(crate) fn buffer_arguments(&self, buffer: &StreamLayout) -> String {
+        format!(
+            "(i32.const {}) (i32.const {}) (i32.const {})",
+            buffer.address, buffer.length, buffer.value_size
+        )
+    }
+
+    /// Returns the code loading the value of the stream with the given offset
+    pub(crate) fn load_value(&self, sr: StreamReference, offset: u32) -> String {
+        let buffer = self.buffer(sr).expect("accessed streams have memory");
+        format!(
+            "({} (call $value {} (i32.const {offset})))",
+            self.load(&self.sr2memory[&sr].ty),
+            self.buffer_arguments(buffer)
+        )
+    }
+
+    /// Returns the code storing the value as the current value of the stream
+    pub(crate) fn push_value(&self, sr: StreamReference, value: String) -> String {
+        let buffer = self.buffer(sr).expect("evaluated streams have memory");
+        let arguments = self.buffer_arguments(buffer);
+        format!(
+            "({} (call $value {arguments} (i32.const 0)) {value})\n(call $push {arguments})",
+            self.store(&self.sr2memory[&sr].ty)
+        )
+    }
+
+    /// Whether the stream is spawned and closed dynamically
+    pub(crate) fn is_dynamic(&self, sr: StreamReference) -> bool {
+        matches!(self.sr2memory[&sr].buffer, StreamMemory::Dynamic { .. })
+    }
+
+    /// Returns the address of the flag whether the stream is alive
+    pub(crate) fn alive_flag(&self, sr: StreamReference) -> u32 {
+        self.buffer(sr)
+            .and_then(|buffer| buffer.alive)
+            .expect("dynamic streams have memory")
+    }
+
+    pub(crate) fn require_buffer(&self) {
+        self.add_module_construct(RequirementKey::Buffer, BUFFER);
+    }
+
+    /// Declares the exported memory and initializes the pauses between the deadlines and the string constants
+    pub(crate) fn require_data(&self, layout: &Layout) {
+        self.add_module_construct(
+            RequirementKey::Memory,
+            &format!(
+                "(memory (export \"{}\") {})",
+                layout.memory.export, layout.memory.pages
+            ),
+        );
+        if let (Some(deadlines), Some(schedule)) = (&layout.deadlines, &self.static_schedule) {
+            let pauses = schedule
+                .deadlines
+                .iter()
+                .flat_map(|deadline| (deadline.pause.as_nanos() as u64).to_le_bytes())
+                .collect::<Vec<_>>();
+            self.add_module_construct(
+                RequirementKey::Deadlines,
+                &format!(
+                    ";; The pauses between the deadlines of the periodic streams in nanoseconds\n\
+                    (data (i32.const {}) \"{}\")",
+                    deadlines.address,
+                    escape(&pauses)
+                ),
+            );
+        }
+        if !layout.strings.is_empty() {
+            let strings = layout
+                .strings
+                .iter()
+                .map(|string| {
+                    let mut bytes = (string.value.len() as u32).to_le_bytes().to_vec();
+                    bytes.extend(string.value.as_bytes());
+                    format!(
+                        "(data (i32.const {}) \"{}\")",
+                        string.address,
+                        escape(&bytes)
+                    )
+                })
+                .join("\n");
+            self.add_module_construct(
+                RequirementKey::Strings,
+                &format!(";; The string constants, prefixed by their length\n{strings}"),
+            );
+        }
+    }
+
+    /// Returns the code resetting the flags whether the streams are fresh
+    pub(crate) fn reset_fresh(&self) -> String {
+        self.layout
+            .streams
+            .iter()
+            .map(|stream| format!("(i32.store (i32.const {}) (i32.const 0))", stream.fresh))
+            .join("\n")
+    }
+}
+
+/// Escapes the bytes for a string in the text format, keeping printable ASCII characters
+fn escape(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| match b {
+            b'"' | b'\\' => format!("\\{b:02x}"),
+            b' '..=b'~' => (*b as char).to_string(),
+            b => format!("\\{b:02x}"),
+        })
+        .collect()
+}
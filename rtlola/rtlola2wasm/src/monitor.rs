@@ -0,0 +1,173 @@
+use std::path::PathBuf;
+
+use itertools::Itertools;
+use rtlola_streamir::{
+    formatter::{
+        files::{FilesFormatter, Requirement},
+        statements::StmtFormatter,
+        types::TypeFormatter,
+    },
+    ir::Stmt,
+};
+
+use crate::{
+    constructs::{indent, RequirementKey},
+    WasmFormatter,
+};
+
+/// The state of the monitor besides the memory of the streams
+const GLOBALS: &str =
+    ";; The time at which the monitor started, the time of the current cycle in nanoseconds
+;; and whether the current cycle evaluates a deadline
+(global $start (mut i64) (i64.const 0))
+(global $time (mut i64) (i64.const 0))
+(global $timed (mut i32) (i32.const 0))
+
+;; The index of the next deadline of the periodic streams and its time in nanoseconds
+(global $deadline (mut i32) (i32.const 0))
+(global $next_deadline (mut i64) (i64.const 0))
+";
+
+impl WasmFormatter {
+    pub(crate) fn require_module(&self) {
+        let file = self.module_file();
+        self.add_requirement_string(
+            &file,
+            RequirementKey::ModuleStart,
+            ";; A monitor generated by rtlola2wasm.\n\
+            ;;\n\
+            ;; The layout of the exported memory and the exported functions are described by the accompanying layout.json.\n\
+            ;; The functions of the `Math` object of JavaScript are imported from the module \"Math\".\n\
+            (module\n"
+                .into(),
+        );
+        self.add_module_construct(RequirementKey::Globals, GLOBALS);
+        self.add_requirement_string(&file, RequirementKey::ModuleEnd, ")\n".into());
+    }
+}
+
+/// The exported functions initializing the monitor and evaluating events and the deadlines of the periodic streams
+pub(crate) struct MonitorFunctions;
+
+impl Requirement<WasmFormatter> for MonitorFunctions {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::MonitorFunctions
+    }
+
+    fn file(&self, f: &WasmFormatter) -> PathBuf {
+        f.module_file()
+    }
+
+    fn format(self, f: &WasmFormatter) -> String {
+        let layout = f.layout();
+        let (init_deadline, next_deadline, cycle_deadline) = if let Some(deadlines) =
+            &layout.deadlines
+        {
+            (
+                format!(
+                    "(global.set $deadline (i32.const 0))\n\
+                    (global.set $next_deadline (i64.add (local.get $start) (i64.load (i32.const {}))))",
+                    deadlines.address
+                ),
+                "(global.get $next_deadline)".to_string(),
+                format!(
+                    "(local $deadline i64)\n\
+                    (if (i64.gt_s (global.get $next_deadline) (local.get $time))\n\
+                    (then (return (i32.const 0))))\n\
+                    (local.set $deadline (global.get $next_deadline))\n\
+                    (global.set $deadline (i32.rem_u (i32.add (global.get $deadline) (i32.const 1)) (i32.const {count})))\n\
+                    (global.set $next_deadline\n\
+                    (i64.add\n\
+                    (global.get $next_deadline)\n\
+                    (i64.load (i32.add (i32.const {address}) (i32.mul (global.get $deadline) (i32.const 8))))))\n\
+                    (call $cycle (local.get $deadline) (i32.const 1))\n\
+                    (i32.const 1)",
+                    count = deadlines.count,
+                    address = deadlines.address
+                ),
+            )
+        } else {
+            (
+                String::new(),
+                "(i64.const -1)".to_string(),
+                "(i32.const 0)".to_string(),
+            )
+        };
+        indent(
+            &format!(
+                ";; Resets the monitor, which starts monitoring at the given time in nanoseconds\n\
+                (func (export \"{init}\") (param $start i64)\n\
+                (memory.fill (i32.const 0) (i32.const 0) (i32.const {state}))\n\
+                (global.set $start (local.get $start))\n\
+                (global.set $time (local.get $start))\n\
+                (global.set $timed (i32.const 0))\n\
+                {init_deadline})\n\n\
+                ;; Evaluates the staged event at the given time in nanoseconds and discards the staged values\n\
+                ;;\n\
+                ;; All deadlines up to the time of the event need to be evaluated before with `{cycle_deadline_name}`.\n\
+                (func (export \"{cycle}\") (param $time i64)\n\
+                (call $cycle (local.get $time) (i32.const 0))\n\
+                (memory.fill (i32.const 0) (i32.const 0) (i32.const {event})))\n\n\
+                ;; Returns the time of the next deadline of the periodic streams in nanoseconds, or -1 if there are none\n\
+                (func (export \"{next_deadline_name}\") (result i64)\n\
+                {next_deadline})\n\n\
+                ;; Evaluates the periodic streams at the next deadline if it is not after the given time in nanoseconds\n\
+                ;;\n\
+                ;; Returns whether a deadline was evaluated, such that all deadlines up to the given time are evaluated\n\
+                ;; by calling this function until it returns 0.\n\
+                (func (export \"{cycle_deadline_name}\") (param $time i64) (result i32)\n\
+                {cycle_deadline})\n\n\
+                ;; Returns the time of the last cycle in nanoseconds\n\
+                (func (export \"{time}\") (result i64)\n\
+                (global.get $time))",
+                init = layout.functions.init,
+                cycle = layout.functions.cycle,
+                next_deadline_name = layout.functions.next_deadline,
+                cycle_deadline_name = layout.functions.cycle_deadline,
+                time = layout.functions.time,
+                state = layout.streams_end,
+                event = layout.event_end,
+            ),
+            1,
+        )
+    }
+}
+
+/// The evaluation of the statement for the staged event or a deadline
+pub(crate) struct Cycle(pub(crate) Stmt);
+
+impl Requirement<WasmFormatter> for Cycle {
+    fn key(&self) -> RequirementKey {
+        RequirementKey::Cycle
+    }
+
+    fn file(&self, f: &WasmFormatter) -> PathBuf {
+        f.module_file()
+    }
+
+    fn format(self, f: &WasmFormatter) -> String {
+        let stmt = f.stmt(self.0);
+        // the temporaries are known after formatting the statement
+        let locals = f
+            .temporaries
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(temp, ty)| format!("(local {} {})", f.temporary_name(*temp), f.ty(ty.clone())))
+            .join("\n");
+        indent(
+            &format!(
+                ";; Evaluates the streams at the given time in nanoseconds, where the fresh flags of the streams\n\
+                ;; remain set until the next cycle to form the verdict\n\
+                (func $cycle (param $time i64) (param $timed i32)\n\
+                {locals}\n\
+                (global.set $time (local.get $time))\n\
+                (global.set $timed (local.get $timed))\n\
+                {reset_fresh}\n\
+                {stmt})",
+                reset_fresh = f.reset_fresh(),
+            ),
+            1,
+        )
+    }
+}
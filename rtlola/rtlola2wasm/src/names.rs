@@ -0,0 +1,32 @@
+use rtlola_streamir::{
+    formatter::names::GetStreamName,
+    function_names,
+    ir::{StreamReference, WindowReference},
+};
+
+use crate::WasmFormatter;
+
+function_names! {WasmFormatter,
+    setter_name(stream): "set_{stream}",
+    value_accessor_name(stream): "value_{stream}",
+    fresh_accessor_name(stream): "fresh_{stream}",
+    temporary_name(num): "$tmp_{num}",
+
+    memory_export_name(): "memory",
+    init_function_name(): "init",
+    cycle_function_name(): "cycle",
+    next_deadline_function_name(): "next_deadline",
+    deadline_function_name(): "cycle_deadline",
+    time_function_name(): "time"
+}
+
+impl GetStreamName for WasmFormatter {
+    fn stream_name(&self, sr: StreamReference) -> String {
+        // the names of streams only contain characters allowed in identifiers and names of exports
+        self.sr2memory[&sr].name.clone()
+    }
+
+    fn window_name(&self, _sref: WindowReference) -> String {
+        unreachable!("windows are rejected by the capability check")
+    }
+}
@@ -0,0 +1,120 @@
+use rtlola_streamir::{
+    formatter::{
+        expressions::ExprFormatter,
+        guards::GuardFormatter,
+        statements::{DefaultStmtFormatter, StmtFormatter},
+    },
+    ir::{
+        expressions::Expr, Guard, InputReference, LocalFreqRef, OutputReference, Stmt,
+        StreamReference, TempRef, WindowReference,
+    },
+};
+
+use crate::WasmFormatter;
+
+impl DefaultStmtFormatter for WasmFormatter {
+    fn shift(&self, sr: StreamReference) -> String {
+        self.buffer(sr)
+            .map(|buffer| {
+                format!(
+                    "(call $shift (i32.const {}) (i32.const {}))",
+                    buffer.address, buffer.length
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    fn input(&self, sr: InputReference) -> String {
+        let sr = StreamReference::In(sr);
+        if self.buffer(sr).is_none() {
+            return String::new();
+        }
+        let value = format!(
+            "({} (i32.const {}))",
+            self.load(&self.sr2memory[&sr].ty),
+            self.layout.input(sr).value
+        );
+        self.push_value(sr, value)
+    }
+
+    fn spawn(
+        &self,
+        sr: OutputReference,
+        with: Option<Vec<Expr>>,
+        local_frequencies: Vec<LocalFreqRef>,
+        windows: Vec<WindowReference>,
+    ) -> String {
+        assert!(local_frequencies.is_empty());
+        assert!(windows.is_empty());
+        if with.is_some() {
+            unreachable!("parameterized streams are rejected by the capability check")
+        }
+        let sr = sr.sr();
+        let buffer = self.buffer(sr).expect("dynamic streams have memory");
+        let alive = self.alive_flag(sr);
+        format!(
+            "(if (i32.eqz (i32.load (i32.const {alive})))\n\
+            (then\n\
+            (memory.fill (i32.const {}) (i32.const 0) (i32.const {}))\n\
+            (i32.store (i32.const {alive}) (i32.const 1))))",
+            buffer.address, buffer.size
+        )
+    }
+
+    fn eval(&self, sr: OutputReference, with: Expr, _idx: usize) -> String {
+        self.push_value(sr.sr(), self.expr(with))
+    }
+
+    fn r#let(&self, temp: TempRef, expr: Expr) -> String {
+        // the locals are declared at the start of the cycle function
+        self.temporaries
+            .lock()
+            .unwrap()
+            .insert(temp, expr.ty.clone());
+        format!(
+            "(local.set {} {})",
+            self.temporary_name(temp),
+            self.expr(expr)
+        )
+    }
+
+    fn close(
+        &self,
+        sr: OutputReference,
+        _local_frequencies: Vec<LocalFreqRef>,
+        _windows: Vec<WindowReference>,
+    ) -> String {
+        // for hold and offset accesses to return the default, the values are invalidated
+        let sr = sr.sr();
+        let buffer = self.buffer(sr).expect("dynamic streams have memory");
+        format!(
+            "(call $clear {})\n(i32.store (i32.const {}) (i32.const 0))",
+            self.buffer_arguments(buffer),
+            self.alive_flag(sr)
+        )
+    }
+
+    fn r#if(&self, guard: Guard, cons: Stmt, alt: Option<Stmt>) -> String {
+        let guard = self.guard(guard);
+        let cons = self.stmt(cons);
+        if let Some(alt) = alt {
+            let alt = self.stmt(alt);
+            format!("(if {guard}\n(then\n{cons})\n(else\n{alt}))")
+        } else {
+            format!("(if {guard}\n(then\n{cons}))")
+        }
+    }
+
+    fn iterate(&self, _sr: Vec<OutputReference>, _inner: Stmt) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+
+    fn assign(
+        &self,
+        _sr: Vec<OutputReference>,
+        _parameter_expr: Vec<Expr>,
+        _inner: Stmt,
+    ) -> String {
+        unreachable!("parameterized streams are rejected by the capability check")
+    }
+}
@@ -0,0 +1,124 @@
+use rtlola_streamir::{
+    formatter::types::TypeFormatter,
+    ir::{StreamReference, Type},
+};
+
+use crate::WasmFormatter;
+
+/// Returns the number of bits of the smallest integer type holding values with the given number of bits
+fn integer_bits(bits: u16) -> u16 {
+    bits.next_power_of_two().max(8)
+}
+
+impl TypeFormatter for WasmFormatter {
+    type Return = String;
+
+    fn type_int(&self, bits: u16) -> Self::Return {
+        if integer_bits(bits) > 32 {
+            "i64"
+        } else {
+            "i32"
+        }
+        .into()
+    }
+
+    fn type_uint(&self, bits: u16) -> Self::Return {
+        self.type_int(bits)
+    }
+
+    fn type_bool(&self) -> Self::Return {
+        "i32".into()
+    }
+
+    fn type_string(&self) -> Self::Return {
+        // strings are represented by the address of the constant
+        "i32".into()
+    }
+
+    fn type_float32(&self) -> Self::Return {
+        "f32".into()
+    }
+
+    fn type_float64(&self) -> Self::Return {
+        "f64".into()
+    }
+
+    fn type_option(&self, _inner: Type) -> Self::Return {
+        unreachable!("optional types are rejected by the capability check")
+    }
+
+    fn type_tuple(&self, _inner: Vec<Type>) -> Self::Return {
+        unreachable!("tuples are rejected by the capability check")
+    }
+
+    fn type_fixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_ufixed(&self, _bits: u16) -> Self::Return {
+        unreachable!("fixed point types are rejected by the capability check")
+    }
+
+    fn type_bytes(&self) -> Self::Return {
+        unreachable!("bytes are rejected by the capability check")
+    }
+}
+
+impl WasmFormatter {
+    pub(crate) fn stream_ty(&self, sr: StreamReference) -> String {
+        self.ty(self.sr2memory[&sr].ty.clone())
+    }
+
+    /// Returns the number of bytes a value of the type occupies in the linear memory
+    pub(crate) fn value_size(&self, ty: &Type) -> u32 {
+        match ty {
+            Type::Bool => 1,
+            Type::Int(bits) | Type::UInt(bits) => u32::from(integer_bits(*bits)) / 8,
+            Type::String | Type::Float32 => 4,
+            Type::Float64 => 8,
+            ty => unreachable!("{ty} is rejected by the capability check"),
+        }
+    }
+
+    /// Returns the instruction loading a value of the type from the linear memory
+    pub(crate) fn load(&self, ty: &Type) -> String {
+        match (ty, self.value_size(ty)) {
+            (Type::Int(_), 1) => "i32.load8_s".into(),
+            (Type::Int(_), 2) => "i32.load16_s".into(),
+            (_, 1) => "i32.load8_u".into(),
+            (_, 2) => "i32.load16_u".into(),
+            _ => format!("{}.load", self.ty(ty.clone())),
+        }
+    }
+
+    /// Returns the instruction storing a value of the type into the linear memory
+    pub(crate) fn store(&self, ty: &Type) -> String {
+        match self.value_size(ty) {
+            1 => "i32.store8".into(),
+            2 => "i32.store16".into(),
+            _ => format!("{}.store", self.ty(ty.clone())),
+        }
+    }
+
+    /// Truncates the result of an integer operation to the number of bits of the type,
+    /// as integers with less than 32 bits are computed with 32-bit integers
+    pub(crate) fn wrap(&self, ty: &Type, code: String) -> String {
+        match (ty, self.value_size(ty)) {
+            (Type::Int(_), 1) => format!("(i32.extend8_s {code})"),
+            (Type::Int(_), 2) => format!("(i32.extend16_s {code})"),
+            (Type::UInt(_), 1) => format!("(i32.and {code} (i32.const 255))"),
+            (Type::UInt(_), 2) => format!("(i32.and {code} (i32.const 65535))"),
+            _ => code,
+        }
+    }
+
+    /// Returns the instruction of the operation on values of the type, with the suffix for the signedness of integers
+    pub(crate) fn signed_op(&self, op: &str, ty: &Type) -> String {
+        let suffix = match ty {
+            Type::Int(_) => "_s",
+            Type::UInt(_) | Type::Bool => "_u",
+            _ => "",
+        };
+        format!("{}.{op}{suffix}", self.ty(ty.clone()))
+    }
+}